@@ -0,0 +1,329 @@
+use chrono::{DateTime, Local};
+use regex::Regex;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::config::ActivityFilters;
+use crate::presence::filter_text;
+use crate::provider::PluginManifest;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SystemStats {
+    pub cpu_usage: f32,
+    pub memory_usage: f64,
+    pub memory_total: u64,
+    pub memory_used: u64,
+    pub uptime: u64,
+    pub process_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessInfo {
+    pub name: String,
+    pub pid: u32,
+    pub cpu_usage: f32,
+    pub memory_usage: u64,
+    pub start_time: u64,
+}
+
+/// A running process as listed in the process browser, before any filtering.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProcessEntry {
+    pub name: String,
+    pub pid: u32,
+    pub exe: Option<String>,
+    pub cpu_usage: f32,
+    pub memory_usage: u64,
+    /// Seconds since the Unix epoch.
+    pub start_time: u64,
+    pub user: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ActivityData {
+    pub system_stats: SystemStats,
+    pub current_time: DateTime<Local>,
+    pub top_processes: Vec<ProcessInfo>,
+    pub active_window: Option<String>,
+    /// Process that owns the focused window, when the window system tells.
+    pub active_window_pid: Option<u32>,
+    /// What a media player is playing, when the media provider is enabled.
+    pub media: Option<MediaInfo>,
+    /// The repository being worked in, when the git provider is enabled.
+    pub git: Option<GitInfo>,
+    /// The file being edited, when the editor provider is enabled.
+    pub editor: Option<EditorInfo>,
+    /// The foreground job of a terminal, when the terminal provider is enabled.
+    pub terminal: Option<TerminalInfo>,
+    /// Combined charge of all batteries, when the battery provider is enabled.
+    pub battery: Option<BatteryInfo>,
+    /// Temperature sensors, when the thermal provider is enabled.
+    pub temperatures: Vec<Temperature>,
+    /// Load average and swap, when the load provider is enabled.
+    pub load: Option<LoadInfo>,
+    /// Usage of the configured mount points, when the disk provider is enabled.
+    pub disks: Vec<DiskUsage>,
+    /// Throughput per interface, when the network provider is enabled.
+    pub networks: Vec<NetworkThroughput>,
+    /// Running containers, when the containers provider is enabled and the API answered.
+    pub containers: Option<Vec<ContainerInfo>>,
+    /// Fields and errors of the configured external commands.
+    pub commands: Vec<CommandStatus>,
+    /// Fields and errors of the configured WebAssembly plugins.
+    pub plugins: Vec<PluginStatus>,
+}
+
+impl ActivityData {
+    /// A copy fit to hand outside the app: every text field goes through `word_filter`, and
+    /// processes and terminal jobs that `filters` hides are left out.
+    pub fn filtered(&self, word_filter: &Regex, filters: &ActivityFilters) -> ActivityData {
+        let filter = |text: &str| filter_text(word_filter, text);
+        let filter_fields = |fields: &BTreeMap<String, String>| {
+            fields.iter().map(|(key, value)| (key.clone(), filter(value))).collect()
+        };
+        
+        ActivityData {
+            system_stats: self.system_stats.clone(),
+            current_time: self.current_time,
+            top_processes: self
+                .top_processes
+                .iter()
+                .filter(|process| !filters.hides(&process.name))
+                .map(|process| ProcessInfo {
+                    name: filter(&process.name),
+                    ..process.clone()
+                })
+                .collect(),
+            active_window: self.active_window.as_deref().map(filter),
+            active_window_pid: self.active_window_pid,
+            media: self.media.as_ref().map(|media| MediaInfo {
+                player: filter(&media.player),
+                title: filter(&media.title),
+                artist: media.artist.as_deref().map(filter),
+                album: media.album.as_deref().map(filter),
+                ..media.clone()
+            }),
+            git: self.git.as_ref().map(|git| GitInfo {
+                repo: filter(&git.repo),
+                branch: git.branch.as_deref().map(filter),
+                commit: git.commit.as_deref().map(filter),
+                ..git.clone()
+            }),
+            editor: self.editor.as_ref().map(|editor| EditorInfo {
+                editor: filter(&editor.editor),
+                file: filter(&editor.file),
+                project: editor.project.as_deref().map(filter),
+                language: editor.language.as_deref().map(filter),
+                ..editor.clone()
+            }),
+            terminal: self
+                .terminal
+                .as_ref()
+                .filter(|terminal| !filters.hides(&terminal.terminal) && !filters.hides(&terminal.command))
+                .map(|terminal| TerminalInfo {
+                    terminal: filter(&terminal.terminal),
+                    command: filter(&terminal.command),
+                    argv: terminal.argv.iter().map(|arg| filter(arg)).collect(),
+                }),
+            battery: self.battery.clone(),
+            temperatures: self
+                .temperatures
+                .iter()
+                .map(|temperature| Temperature {
+                    label: filter(&temperature.label),
+                    celsius: temperature.celsius,
+                })
+                .collect(),
+            load: self.load.clone(),
+            disks: self
+                .disks
+                .iter()
+                .map(|disk| DiskUsage {
+                    mount: filter(&disk.mount),
+                    ..disk.clone()
+                })
+                .collect(),
+            networks: self
+                .networks
+                .iter()
+                .map(|network| NetworkThroughput {
+                    interface: filter(&network.interface),
+                    ..network.clone()
+                })
+                .collect(),
+            containers: self.containers.as_ref().map(|containers| {
+                containers
+                    .iter()
+                    .map(|container| ContainerInfo {
+                        name: filter(&container.name),
+                        image: filter(&container.image),
+                        project: container.project.as_deref().map(filter),
+                    })
+                    .collect()
+            }),
+            commands: self
+                .commands
+                .iter()
+                .map(|command| CommandStatus {
+                    name: filter(&command.name),
+                    fields: filter_fields(&command.fields),
+                    error: command.error.as_deref().map(filter),
+                    last_run: command.last_run,
+                })
+                .collect(),
+            plugins: self
+                .plugins
+                .iter()
+                .map(|plugin| PluginStatus {
+                    name: filter(&plugin.name),
+                    fields: filter_fields(&plugin.fields),
+                    error: plugin.error.as_deref().map(filter),
+                    ..plugin.clone()
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlaybackStatus {
+    Playing,
+    Paused,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MediaInfo {
+    /// Display name of the player, e.g. "Spotify".
+    pub player: String,
+    pub title: String,
+    /// All artists, comma-separated.
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub position_seconds: Option<f64>,
+    pub length_seconds: Option<f64>,
+    pub status: PlaybackStatus,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GitInfo {
+    /// Directory name of the repository, or "a private project".
+    pub repo: String,
+    /// Branch name, or the short commit hash on a detached HEAD.
+    pub branch: Option<String>,
+    /// Whether tracked files have uncommitted changes.
+    pub dirty: bool,
+    /// Subject line of the last commit.
+    pub commit: Option<String>,
+    /// Set when the details were withheld by the per-repository privacy settings.
+    pub private: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EditorInfo {
+    /// E.g. "VS Code" or "Neovim".
+    pub editor: String,
+    /// File name without its directory.
+    pub file: String,
+    pub project: Option<String>,
+    pub language: Option<String>,
+    /// Image key for the language in the Discord application's assets.
+    pub language_asset: Option<String>,
+    /// Reported by an editor plugin rather than parsed from a window title.
+    pub from_plugin: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TerminalInfo {
+    /// Process name of the terminal emulator, e.g. "alacritty".
+    pub terminal: String,
+    /// Program name of the job, e.g. "cargo".
+    pub command: String,
+    /// The job's arguments after redaction, starting with the program name.
+    pub argv: Vec<String>,
+}
+
+impl TerminalInfo {
+    pub fn command_line(&self) -> String {
+        self.argv.join(" ")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatteryStatus {
+    Charging,
+    Discharging,
+    Full,
+    /// Plugged in but held below full, e.g. by a charge threshold.
+    NotCharging,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BatteryInfo {
+    pub percent: f32,
+    pub status: BatteryStatus,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Temperature {
+    /// Sensor label as reported by the system, e.g. "coretemp Package id 0".
+    pub label: String,
+    pub celsius: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LoadInfo {
+    /// Load average over 1, 5 and 15 minutes.
+    pub one: f64,
+    pub five: f64,
+    pub fifteen: f64,
+    pub swap_total: u64,
+    pub swap_used: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DiskUsage {
+    pub mount: String,
+    pub total: u64,
+    pub used: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NetworkThroughput {
+    pub interface: String,
+    /// Bytes per second since the previous sample.
+    pub received_per_second: u64,
+    pub sent_per_second: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ContainerInfo {
+    pub name: String,
+    /// Image reference as given when the container was created, e.g. "postgres:16".
+    pub image: String,
+    /// Docker Compose project, from the `com.docker.compose.project` label.
+    pub project: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CommandStatus {
+    pub name: String,
+    /// Fields from the last successful run, kept while later runs fail.
+    pub fields: BTreeMap<String, String>,
+    /// Why the last run failed, cleared by the next successful one.
+    pub error: Option<String>,
+    pub last_run: Option<DateTime<Local>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PluginStatus {
+    pub name: String,
+    /// Fields from the last successful poll, kept while later polls fail.
+    pub fields: BTreeMap<String, String>,
+    /// Why loading or the last poll failed, cleared by the next successful poll.
+    pub error: Option<String>,
+    pub last_run: Option<DateTime<Local>>,
+    /// What the plugin declared, once it loaded.
+    pub manifest: Option<PluginManifest>,
+}
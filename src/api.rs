@@ -0,0 +1,300 @@
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::io::{self, Read};
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tiny_http::{Header, Method, Request, Response, Server, StatusCode};
+use tungstenite::protocol::Role;
+use tungstenite::{Message, WebSocket};
+
+use crate::engine::{EngineEvent, PresenceEngine};
+
+/// Request bodies are small JSON objects; anything bigger is rejected.
+const MAX_BODY_BYTES: u64 = 64 * 1024;
+
+/// Idle WebSocket connections are pinged this often so dead clients get noticed.
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Deserialize)]
+struct MessageBody {
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct ProfileBody {
+    profile: Option<String>,
+}
+
+/// Localhost HTTP API with a WebSocket event stream at `/events` and Prometheus
+/// metrics at `/metrics`.
+/// The listening thread stops when the server is dropped.
+pub struct ApiServer {
+    server: Arc<Server>,
+    addr: SocketAddr,
+}
+
+impl ApiServer {
+    /// Binds to `127.0.0.1:port`; port 0 picks a free port. Fails if no token is configured.
+    pub fn start(port: u16, engine: Arc<Mutex<PresenceEngine>>) -> io::Result<ApiServer> {
+        let has_token = engine.lock().map(|engine| !engine.config.api.token.is_empty()).unwrap_or(false);
+        if !has_token {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the HTTP API needs api.token to be set",
+            ));
+        }
+        
+        let server = Server::http((Ipv4Addr::LOCALHOST, port))
+            .map_err(|e| io::Error::new(io::ErrorKind::AddrInUse, e.to_string()))?;
+        let addr = server
+            .server_addr()
+            .to_ip()
+            .ok_or_else(|| io::Error::other("HTTP API is not bound to an IP address"))?;
+        log::info!("HTTP API listening on http://{}", addr);
+        
+        let server = Arc::new(server);
+        let listener = Arc::clone(&server);
+        std::thread::spawn(move || {
+            for request in listener.incoming_requests() {
+                let engine = Arc::clone(&engine);
+                std::thread::spawn(move || handle_request(request, engine));
+            }
+        });
+        
+        Ok(ApiServer { server, addr })
+    }
+    
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+impl Drop for ApiServer {
+    fn drop(&mut self) {
+        self.server.unblock();
+    }
+}
+
+/// A random 48-character hex token for `api.token`.
+pub fn generate_token() -> Result<String, getrandom::Error> {
+    let mut bytes = [0u8; 24];
+    getrandom::getrandom(&mut bytes)?;
+    Ok(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+fn header(name: &str, value: &str) -> Header {
+    Header::from_bytes(name.as_bytes(), value.as_bytes()).expect("static header is valid")
+}
+
+fn json_response(status: u16, body: &Value) -> Response<io::Cursor<Vec<u8>>> {
+    Response::from_string(body.to_string())
+        .with_status_code(StatusCode(status))
+        .with_header(header("Content-Type", "application/json"))
+        .with_header(header("Access-Control-Allow-Origin", "*"))
+}
+
+fn error_response(status: u16, message: &str) -> Response<io::Cursor<Vec<u8>>> {
+    json_response(status, &json!({ "error": message }))
+}
+
+fn request_header<'a>(request: &'a Request, name: &'static str) -> Option<&'a str> {
+    request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv(name))
+        .map(|header| header.value.as_str())
+}
+
+/// Compares in time independent of where the first difference is.
+fn tokens_match(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Accepts `Authorization: Bearer <token>`, or a `token` query parameter for browser
+/// WebSocket clients, which cannot set headers.
+fn is_authorized(request: &Request, query: Option<&str>, expected: &str) -> bool {
+    if expected.is_empty() {
+        return false;
+    }
+    
+    let from_header = request_header(request, "Authorization").and_then(|value| value.strip_prefix("Bearer "));
+    let from_query = query.and_then(|query| {
+        query
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("token="))
+    });
+    
+    from_header
+        .or(from_query)
+        .map(|token| tokens_match(token.trim(), expected))
+        .unwrap_or(false)
+}
+
+fn read_json<T: for<'de> Deserialize<'de>>(request: &mut Request) -> Result<T, String> {
+    let mut body = String::new();
+    request
+        .as_reader()
+        .take(MAX_BODY_BYTES)
+        .read_to_string(&mut body)
+        .map_err(|e| format!("Could not read request body: {}", e))?;
+    serde_json::from_str(&body).map_err(|e| format!("Invalid request body: {}", e))
+}
+
+fn handle_request(mut request: Request, engine: Arc<Mutex<PresenceEngine>>) {
+    let url = request.url().to_string();
+    let (path, query) = match url.split_once('?') {
+        Some((path, query)) => (path, Some(query)),
+        None => (url.as_str(), None),
+    };
+    
+    // CORS preflight for browser dashboards that send the Authorization header
+    if *request.method() == Method::Options {
+        let response = Response::empty(204)
+            .with_header(header("Access-Control-Allow-Origin", "*"))
+            .with_header(header("Access-Control-Allow-Methods", "GET, POST, OPTIONS"))
+            .with_header(header("Access-Control-Allow-Headers", "Authorization, Content-Type"));
+        let _ = request.respond(response);
+        return;
+    }
+    
+    let token = match engine.lock() {
+        Ok(engine) => engine.config.api.token.clone(),
+        Err(_) => return,
+    };
+    if !is_authorized(&request, query, &token) {
+        let response = error_response(401, "Missing or invalid bearer token")
+            .with_header(header("WWW-Authenticate", "Bearer"));
+        let _ = request.respond(response);
+        return;
+    }
+    
+    let response = match (request.method(), path) {
+        (Method::Get, "/events") => return open_event_stream(request, engine),
+        (Method::Get, "/activity") => {
+            let engine = match engine.lock() {
+                Ok(engine) => engine,
+                Err(_) => return,
+            };
+            // Raw samples are withheld while hidden, like the presence itself
+            let hidden = engine.privacy.hidden || engine.hidden_reason.is_some();
+            let data = match engine.activity_data.lock() {
+                Ok(data) if !hidden => data.as_ref().map(|data| engine.filter_data(data)),
+                _ => None,
+            };
+            json_response(
+                200,
+                &json!({
+                    "activity": engine.composed_activity(),
+                    "data": data,
+                    "hidden": hidden,
+                    "hidden_reason": engine.hidden_reason,
+                    "profile": engine.settings.profile,
+                    "connected": engine.is_connected(),
+                }),
+            )
+        }
+        (Method::Post, "/message") => match read_json::<MessageBody>(&mut request) {
+            Ok(body) => match engine.lock() {
+                Ok(mut engine) => {
+                    engine.set_custom_message(&body.message);
+                    json_response(200, &engine.status())
+                }
+                Err(_) => return,
+            },
+            Err(e) => error_response(400, &e),
+        },
+        (Method::Post, "/profile") => match read_json::<ProfileBody>(&mut request) {
+            Ok(body) => match engine.lock() {
+                Ok(mut engine) => match engine.switch_profile(body.profile) {
+                    Ok(()) => json_response(200, &engine.status()),
+                    Err(e) => error_response(400, &e),
+                },
+                Err(_) => return,
+            },
+            Err(e) => error_response(400, &e),
+        },
+        (Method::Get, "/metrics") => match engine.lock() {
+            Ok(engine) => Response::from_string(engine.render_metrics())
+                .with_header(header("Content-Type", "text/plain; version=0.0.4")),
+            Err(_) => return,
+        },
+        (_, "/activity" | "/message" | "/profile" | "/events" | "/metrics") => error_response(405, "Method not allowed"),
+        _ => error_response(404, "Not found"),
+    };
+    
+    let _ = request.respond(response);
+}
+
+/// Upgrades to a WebSocket and pushes every presence and connection change as JSON.
+/// The stream is one-way; messages from the client are ignored.
+fn open_event_stream(request: Request, engine: Arc<Mutex<PresenceEngine>>) {
+    let accept_key = match request_header(&request, "Sec-WebSocket-Key") {
+        Some(key) if request_header(&request, "Upgrade").is_some_and(|value| value.eq_ignore_ascii_case("websocket")) => {
+            tungstenite::handshake::derive_accept_key(key.as_bytes())
+        }
+        _ => {
+            let _ = request.respond(error_response(400, "Expected a WebSocket upgrade"));
+            return;
+        }
+    };
+    
+    // Subscribe under the same lock as the snapshot so no change falls in between
+    let (events, snapshot) = match engine.lock() {
+        Ok(mut engine) => {
+            let events = engine.subscribe();
+            let snapshot = [
+                EngineEvent::Connection {
+                    connected: engine.is_connected(),
+                    status: engine.connection_status.clone(),
+                },
+                EngineEvent::Presence {
+                    activity: engine.composed_activity(),
+                    hidden_reason: engine.hidden_reason.clone(),
+                    profile: engine.settings.profile.clone(),
+                },
+            ];
+            (events, snapshot)
+        }
+        Err(_) => return,
+    };
+    
+    let response = Response::empty(101).with_header(header("Sec-WebSocket-Accept", &accept_key));
+    let stream = request.upgrade("websocket", response);
+    let mut socket = WebSocket::from_raw_socket(stream, Role::Server, None);
+    
+    // Returns false once the client is gone
+    let send = |socket: &mut WebSocket<_>, event: &EngineEvent| match serde_json::to_string(event) {
+        Ok(text) => socket.send(Message::Text(text)).is_ok(),
+        Err(e) => {
+            log::error!("Failed to serialize event: {}", e);
+            true
+        }
+    };
+    
+    for event in &snapshot {
+        if !send(&mut socket, event) {
+            return;
+        }
+    }
+    
+    loop {
+        let alive = match events.recv_timeout(PING_INTERVAL) {
+            Ok(event) => send(&mut socket, &event),
+            Err(RecvTimeoutError::Timeout) => socket.send(Message::Ping(Vec::new())).is_ok(),
+            Err(RecvTimeoutError::Disconnected) => false,
+        };
+        
+        if !alive {
+            break;
+        }
+    }
+    
+    let _ = socket.close(None);
+}
@@ -0,0 +1,365 @@
+use chrono::{Local, NaiveDate};
+use clap::{Parser, Subcommand, ValueEnum};
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use multi_rich_presence::config::{self, Config};
+use multi_rich_presence::history::History;
+use multi_rich_presence::privacy;
+use multi_rich_presence::report::Report;
+use multi_rich_presence::template;
+
+/// Command-line client for a running MultiRichPresence instance.
+#[derive(Parser)]
+#[command(name = "mrp", version, about)]
+struct Cli {
+    /// Print raw JSON instead of human-readable output
+    #[arg(long, global = true)]
+    json: bool,
+    
+    /// Control socket of the instance to talk to
+    #[arg(long, global = true, value_name = "PATH")]
+    socket: Option<PathBuf>,
+    
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Show the connection, profile and pause state
+    Status,
+    /// Set the details and/or state line
+    Set {
+        #[arg(long)]
+        details: Option<String>,
+        #[arg(long)]
+        state: Option<String>,
+    },
+    /// Clear the custom details and state lines
+    Clear,
+    /// List or switch profiles
+    Profile {
+        #[command(subcommand)]
+        command: ProfileCommand,
+    },
+    /// Hide presence, optionally for a duration such as 30m, 2h or 1h30m
+    Pause { duration: Option<String> },
+    /// Resume presence after a pause
+    Resume,
+    /// Show the presence that is currently being broadcast
+    Preview,
+    /// Make the running instance re-read its config file
+    Reload,
+    /// Report the file being edited, for editor plugins and shell hooks
+    Editor {
+        #[command(subcommand)]
+        command: EditorCommand,
+    },
+    /// Inspect the config file
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+    /// Summarize the recorded activity history; reads the database directly
+    Report {
+        /// First day, as YYYY-MM-DD; six days before --to by default
+        #[arg(long)]
+        from: Option<NaiveDate>,
+        /// Last day, as YYYY-MM-DD; today by default
+        #[arg(long)]
+        to: Option<NaiveDate>,
+        #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+        format: ReportFormat,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum ReportFormat {
+    Text,
+    Csv,
+    Json,
+}
+
+#[derive(Subcommand)]
+enum ProfileCommand {
+    /// List the configured profiles
+    List,
+    /// Switch to a profile
+    Use { name: String },
+    /// Switch back to the base profile
+    Reset,
+}
+
+#[derive(Subcommand)]
+enum EditorCommand {
+    /// Set the current file; only its name is shown
+    Set {
+        file: String,
+        /// Editor name, e.g. "Neovim"
+        #[arg(long)]
+        editor: Option<String>,
+        #[arg(long)]
+        project: Option<String>,
+        /// Language name; detected from the file extension when omitted
+        #[arg(long)]
+        language: Option<String>,
+    },
+    /// Go back to detecting the editor from window titles
+    Clear,
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Check a config file for syntax errors and broken references
+    Validate { path: Option<PathBuf> },
+    /// Print the config file location
+    Path,
+}
+
+fn text(value: &Value) -> Option<&str> {
+    value.as_str().filter(|text| !text.is_empty())
+}
+
+fn print_status(status: &Value) {
+    let connection = text(&status["connection"]).unwrap_or("Unknown");
+    println!("Discord:  {}", connection);
+    
+    let profile = text(&status["effective_profile"]).unwrap_or("Base");
+    match text(&status["source"]) {
+        Some(source) => println!("Profile:  {} ({})", profile, source),
+        None => println!("Profile:  {}", profile),
+    }
+    
+    if status["paused"].as_bool().unwrap_or(false) {
+        match text(&status["resume_at"]) {
+            Some(resume_at) => println!("Presence: paused until {}", format_time(resume_at)),
+            None => println!("Presence: paused until resumed"),
+        }
+    } else if let Some(reason) = text(&status["hidden_reason"]) {
+        println!("Presence: hidden ({})", reason);
+    } else {
+        println!("Presence: active");
+    }
+    
+    if let Some(message) = text(&status["message"]) {
+        println!("Details:  {}", message);
+    }
+    if let Some(state) = text(&status["state"]) {
+        println!("State:    {}", state);
+    }
+    
+    if let Some(errors) = status["config_errors"].as_array().filter(|errors| !errors.is_empty()) {
+        println!("Config:   config.json was not applied, the previous config stays in effect:");
+        for error in errors.iter().filter_map(text) {
+            println!("          {}", error);
+        }
+    }
+}
+
+fn format_time(timestamp: &str) -> String {
+    chrono::DateTime::parse_from_rfc3339(timestamp)
+        .map(|time| time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|_| timestamp.to_string())
+}
+
+fn print_preview(preview: &Value) {
+    let activity = &preview["activity"];
+    
+    if activity.is_null() {
+        match text(&preview["hidden_reason"]) {
+            Some(reason) => println!("Presence is hidden ({})", reason),
+            None if preview["hidden"].as_bool().unwrap_or(false) => println!("Presence is paused"),
+            None => println!("No activity data yet"),
+        }
+        return;
+    }
+    
+    println!("Details: {}", text(&activity["details"]).unwrap_or("-"));
+    println!("State:   {}", text(&activity["state"]).unwrap_or("-"));
+    println!(
+        "Image:   {} ({})",
+        text(&activity["large_image"]).unwrap_or("-"),
+        text(&activity["large_text"]).unwrap_or("-")
+    );
+}
+
+fn validate_config(path: Option<PathBuf>, as_json: bool) -> Result<bool, Box<dyn std::error::Error>> {
+    let path = match path {
+        Some(path) => path,
+        None => config::config_path()?,
+    };
+    
+    let problems = match std::fs::read_to_string(&path) {
+        Ok(text) => match Config::parse(&text) {
+            Ok(_) => Vec::new(),
+            Err(errors) => errors.iter().map(|e| e.to_string()).collect(),
+        },
+        Err(e) => vec![e.to_string()],
+    };
+    
+    if as_json {
+        println!("{}", json!({ "path": path, "valid": problems.is_empty(), "problems": problems }));
+    } else if problems.is_empty() {
+        println!("{}: OK", path.display());
+    } else {
+        for problem in &problems {
+            println!("{}: {}", path.display(), problem);
+        }
+    }
+    
+    Ok(problems.is_empty())
+}
+
+fn report(from: Option<NaiveDate>, to: Option<NaiveDate>, format: ReportFormat) -> Result<bool, Box<dyn std::error::Error>> {
+    let to = to.unwrap_or_else(|| Local::now().date_naive());
+    let from = from.unwrap_or(to - chrono::Duration::days(6));
+    if from > to {
+        return Err(format!("--from {} is after --to {}", from, to).into());
+    }
+    
+    let config = Config::load()?;
+    let path = config.history.database_path()?;
+    if !path.exists() {
+        return Err(format!("no activity history at {}; enable history in the settings first", path.display()).into());
+    }
+    let report = Report::from_history(&History::open(&config.history)?, from, to)?;
+    
+    match format {
+        ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+        ReportFormat::Csv => print!("{}", report.to_csv()),
+        ReportFormat::Text => print_report(&report),
+    }
+    Ok(true)
+}
+
+fn print_report(report: &Report) {
+    let duration = |seconds: i64| template::format_duration(seconds.max(0) as u64);
+    println!("{} to {}: {}", report.from, report.to, duration(report.total_seconds));
+    
+    let sections = [("Days", &report.days), ("Weeks", &report.weeks), ("Top apps", &report.apps), ("Projects", &report.projects)];
+    for (title, totals) in sections {
+        if totals.is_empty() {
+            continue;
+        }
+        println!();
+        println!("{}:", title);
+        for total in totals.iter().take(10) {
+            println!("  {:<24} {:>8}", total.name, duration(total.seconds));
+        }
+    }
+    
+    if !report.focus_streaks.is_empty() {
+        println!();
+        println!("Focus streaks:");
+        for streak in &report.focus_streaks {
+            let (start, end) = (streak.started_at.with_timezone(&Local), streak.ended_at.with_timezone(&Local));
+            println!(
+                "  {} {}-{}  {:<24} {:>8}",
+                start.format("%Y-%m-%d"),
+                start.format("%H:%M"),
+                end.format("%H:%M"),
+                streak.app,
+                duration(streak.seconds)
+            );
+        }
+    }
+}
+
+#[cfg(unix)]
+fn call(socket: Option<PathBuf>, method: &str, params: Value) -> Result<Value, Box<dyn std::error::Error>> {
+    use multi_rich_presence::control::{self, ControlClient};
+    
+    let path = socket.unwrap_or_else(control::socket_path);
+    let mut client = ControlClient::connect(&path).map_err(|e| {
+        format!(
+            "could not reach MultiRichPresence at {} ({}); is the app running?",
+            path.display(),
+            e
+        )
+    })?;
+    
+    client.call(method, params)
+}
+
+#[cfg(not(unix))]
+fn call(_socket: Option<PathBuf>, _method: &str, _params: Value) -> Result<Value, Box<dyn std::error::Error>> {
+    Err("the control socket is only available on Unix platforms".into())
+}
+
+fn run(cli: Cli) -> Result<bool, Box<dyn std::error::Error>> {
+    let (method, params) = match cli.command {
+        Command::Config { command } => {
+            return match command {
+                ConfigCommand::Validate { path } => validate_config(path, cli.json),
+                ConfigCommand::Path => {
+                    println!("{}", config::config_path()?.display());
+                    Ok(true)
+                }
+            };
+        }
+        Command::Report { from, to, format } => {
+            let format = if cli.json { ReportFormat::Json } else { format };
+            return report(from, to, format);
+        }
+        Command::Status => ("get_status", Value::Null),
+        Command::Set { details, state } => {
+            if details.is_none() && state.is_none() {
+                return Err("nothing to set; pass --details and/or --state".into());
+            }
+            ("set_activity", json!({ "details": details, "state": state }))
+        }
+        Command::Clear => ("clear_activity", Value::Null),
+        Command::Profile { command: ProfileCommand::List } => ("list_profiles", Value::Null),
+        Command::Profile { command: ProfileCommand::Use { name } } => ("use_profile", json!({ "profile": name })),
+        Command::Profile { command: ProfileCommand::Reset } => ("use_profile", json!({ "profile": null })),
+        Command::Pause { duration } => {
+            let duration_secs = duration.as_deref().map(privacy::parse_duration).transpose()?;
+            ("pause", json!({ "duration_secs": duration_secs }))
+        }
+        Command::Resume => ("resume", Value::Null),
+        Command::Preview => ("get_activity", Value::Null),
+        Command::Reload => ("reload_config", Value::Null),
+        Command::Editor {
+            command: EditorCommand::Set { file, editor, project, language },
+        } => (
+            "set_editor",
+            json!({ "file": file, "editor": editor, "project": project, "language": language }),
+        ),
+        Command::Editor { command: EditorCommand::Clear } => ("clear_editor", Value::Null),
+    };
+    
+    let result = call(cli.socket, method, params)?;
+    
+    if cli.json {
+        println!("{}", serde_json::to_string_pretty(&result)?);
+        return Ok(true);
+    }
+    
+    match method {
+        "get_activity" => print_preview(&result),
+        "list_profiles" => {
+            let active = text(&result["active"]);
+            let marker = |name: Option<&str>| if name == active { "*" } else { " " };
+            println!("{} Base", marker(None));
+            for name in result["profiles"].as_array().into_iter().flatten().filter_map(Value::as_str) {
+                println!("{} {}", marker(Some(name)), name);
+            }
+        }
+        _ => print_status(&result),
+    }
+    
+    Ok(true)
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("mrp: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
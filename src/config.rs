@@ -0,0 +1,652 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::history::HistoryConfig;
+use crate::hotkeys::HotkeysConfig;
+use crate::profile::Profile;
+use crate::provider::ProvidersConfig;
+use crate::rules::{Rule, RuleAction};
+use crate::schedule::{Schedule, ScheduleAction};
+use crate::script::ScriptingConfig;
+use crate::sink::{SinkConfig, SinkKind};
+use crate::template::PresenceTemplate;
+use crate::tray::TrayConfig;
+
+/// The live graphs keep at most a day of samples.
+pub const MAX_GRAPH_MINUTES: u32 = 24 * 60;
+
+pub const APP_ID: &str = "1419145226261495808"; // Replace with your Discord app ID
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Config {
+    pub blacklisted_words: Vec<String>,
+    pub show_system_stats: bool,
+    pub show_time: bool,
+    pub show_applications: bool,
+    pub custom_messages: Vec<String>,
+    pub update_interval_seconds: u64,
+    pub discord_app_id: String,
+    pub activity_filters: ActivityFilters,
+    #[serde(default)]
+    pub schedule: Schedule,
+    /// Template for the base profile; the built-in layout is used when unset.
+    #[serde(default)]
+    pub template: Option<PresenceTemplate>,
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+    #[serde(default)]
+    pub profiles: Vec<Profile>,
+    /// Manually selected profile, persisted across restarts. `None` is the base profile.
+    #[serde(default)]
+    pub active_profile: Option<String>,
+    #[serde(default)]
+    pub api: ApiConfig,
+    /// Outputs in addition to Discord, such as files and webhooks.
+    #[serde(default)]
+    pub sinks: Vec<SinkConfig>,
+    /// Optional data sources in addition to the system stats.
+    #[serde(default)]
+    pub providers: ProvidersConfig,
+    /// Rhai scripts consulted when no rule matches.
+    #[serde(default)]
+    pub scripting: ScriptingConfig,
+    /// Local activity history for reports; off by default.
+    #[serde(default)]
+    pub history: HistoryConfig,
+    /// How far back the live graphs in the GUI reach.
+    #[serde(default = "default_graph_minutes")]
+    pub graph_minutes: u32,
+    /// Tray icon and whether closing the window hides it there.
+    #[serde(default)]
+    pub tray: TrayConfig,
+    /// Global shortcuts for hiding presence and cycling profiles and messages; off by default.
+    #[serde(default)]
+    pub hotkeys: HotkeysConfig,
+}
+
+fn default_graph_minutes() -> u32 {
+    10
+}
+
+/// Opt-in HTTP API on localhost. The server follows changes to `enabled` and `port`
+/// while the app runs; the token is checked on every request.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiConfig {
+    pub enabled: bool,
+    pub port: u16,
+    /// Bearer token required on every request. The server refuses to start without one.
+    pub token: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActivityFilters {
+    /// Hides the processes in `SYSTEM_PROCESSES`.
+    pub hide_system_processes: bool,
+    pub hide_background_apps: bool,
+    pub minimum_cpu_usage: f32,
+    /// Process names that are never shown, compared case-insensitively.
+    pub blacklisted_processes: Vec<String>,
+    /// Names to show instead of process names, e.g. `"javaw.exe": "Minecraft"`. Rules and
+    /// the history see the display name.
+    #[serde(default)]
+    pub display_names: BTreeMap<String, String>,
+}
+
+/// Operating system processes hidden by `hide_system_processes`.
+pub const SYSTEM_PROCESSES: &[&str] = &["dwm.exe", "winlogon.exe", "csrss.exe"];
+
+impl ActivityFilters {
+    /// Whether the process called `name` is left out of the top processes.
+    pub fn hides(&self, name: &str) -> bool {
+        self.blacklisted_processes.iter().any(|listed| listed.eq_ignore_ascii_case(name))
+            || (self.hide_system_processes && SYSTEM_PROCESSES.iter().any(|listed| listed.eq_ignore_ascii_case(name)))
+    }
+    
+    /// The name to show for the process called `name`.
+    pub fn display_name<'a>(&'a self, name: &'a str) -> &'a str {
+        self.display_names
+            .iter()
+            .find(|(process, _)| process.eq_ignore_ascii_case(name))
+            .map(|(_, display)| display.as_str())
+            .unwrap_or(name)
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            blacklisted_words: vec![
+                "password".to_string(),
+                "secret".to_string(),
+                "private".to_string(),
+            ],
+            show_system_stats: true,
+            show_time: true,
+            show_applications: true,
+            custom_messages: vec!["Working on something cool".to_string()],
+            update_interval_seconds: 15,
+            discord_app_id: APP_ID.to_string(),
+            activity_filters: ActivityFilters::default(),
+            schedule: Schedule::default(),
+            template: None,
+            rules: Vec::new(),
+            profiles: Vec::new(),
+            active_profile: None,
+            api: ApiConfig::default(),
+            sinks: Vec::new(),
+            providers: ProvidersConfig::default(),
+            scripting: ScriptingConfig::default(),
+            history: HistoryConfig::default(),
+            graph_minutes: default_graph_minutes(),
+            tray: TrayConfig::default(),
+            hotkeys: HotkeysConfig::default(),
+        }
+    }
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 7316,
+            token: String::new(),
+        }
+    }
+}
+
+impl Default for ActivityFilters {
+    fn default() -> Self {
+        Self {
+            hide_system_processes: true,
+            hide_background_apps: true,
+            minimum_cpu_usage: 0.1,
+            blacklisted_processes: Vec::new(),
+            display_names: BTreeMap::new(),
+        }
+    }
+}
+
+fn project_dirs() -> Result<directories::ProjectDirs, Box<dyn std::error::Error>> {
+    Ok(directories::ProjectDirs::from("com", "multirichpresence", "MultiRichPresence")
+        .ok_or("Could not find project directory")?)
+}
+
+pub fn config_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    Ok(project_dirs()?.config_dir().join("config.json"))
+}
+
+/// Directory for runtime state that is not user configuration.
+pub fn data_dir() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    Ok(project_dirs()?.data_local_dir().to_path_buf())
+}
+
+/// A reason a config file was rejected, with the 1-based line and column of the
+/// offending value when it can be found.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigError {
+    pub position: Option<(usize, usize)>,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.position {
+            Some((line, column)) => write!(f, "line {}, column {}: {}", line, column, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// A problem `Config::validate` found, with the path of the setting it is about, like
+/// `profiles[2].inherits` or `providers.commands[0].timeout_seconds`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigProblem {
+    pub path: String,
+    pub message: String,
+}
+
+impl ConfigProblem {
+    pub fn at(path: impl Into<String>, message: impl Into<String>) -> Self {
+        ConfigProblem {
+            path: path.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ConfigProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Config {
+    pub fn load() -> Result<Config, Box<dyn std::error::Error>> {
+        let config_path = config_path()?;
+        
+        if config_path.exists() {
+            Config::load_from(&config_path)
+        } else {
+            Ok(Config::default())
+        }
+    }
+    
+    /// Reads a config file and checks it like `parse`; errors include the line and column.
+    pub fn load_from(path: &Path) -> Result<Config, Box<dyn std::error::Error>> {
+        let content = std::fs::read_to_string(path)?;
+        Config::parse(&content).map_err(|errors| errors.iter().map(|e| e.to_string()).collect::<Vec<_>>().join("; ").into())
+    }
+    
+    /// Parses and validates config text: the types must match the `Config` structure and
+    /// `validate` must find no problems. Syntax and type errors carry their position, as do
+    /// validation problems whose setting, such as `graph_minutes` or `profiles[1].inherits`,
+    /// is written in the file.
+    pub fn parse(text: &str) -> Result<Config, Vec<ConfigError>> {
+        let config: Config = match serde_json::from_str(text) {
+            Ok(config) => config,
+            Err(e) => {
+                // serde_json appends the position to the message
+                let message = e.to_string();
+                let message = match message.rfind(" at line ") {
+                    Some(end) => message[..end].to_string(),
+                    None => message,
+                };
+                return Err(vec![ConfigError {
+                    position: Some((e.line(), e.column())),
+                    message,
+                }]);
+            }
+        };
+        
+        let problems = config.validate();
+        if problems.is_empty() {
+            return Ok(config);
+        }
+        Err(problems
+            .into_iter()
+            .map(|problem| ConfigError {
+                position: setting_position(text, &problem.path),
+                message: problem.message,
+            })
+            .collect())
+    }
+    
+    /// Writes the config to a temporary file next to `config.json` and renames it into
+    /// place, so a crash or a concurrent reader never sees half a file. Returns the text
+    /// written.
+    pub fn save(&self) -> Result<String, Box<dyn std::error::Error>> {
+        let config_path = config_path()?;
+        
+        if let Some(parent) = config_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        
+        let content = serde_json::to_string_pretty(self)?;
+        let temporary = config_path.with_extension("json.saving");
+        std::fs::write(&temporary, &content)?;
+        std::fs::rename(&temporary, &config_path)?;
+        Ok(content)
+    }
+    
+    /// Checks references and patterns that deserialization alone cannot catch.
+    /// Returns one entry per problem; an empty list means the config is valid.
+    pub fn validate(&self) -> Vec<ConfigProblem> {
+        let mut problems = Vec::new();
+        let known = |name: &str| self.profiles.iter().any(|profile| profile.name == name);
+        
+        if self.update_interval_seconds == 0 {
+            problems.push(ConfigProblem::at("update_interval_seconds", "update_interval_seconds must be at least 1"));
+        }
+        
+        for (i, profile) in self.profiles.iter().enumerate() {
+            let path = format!("profiles[{}]", i);
+            if profile.name.trim().is_empty() {
+                problems.push(ConfigProblem::at(format!("{}.name", path), format!("{} has an empty name", path)));
+            }
+            if self.profiles[..i].iter().any(|other| other.name == profile.name) {
+                let message = format!("profile {:?} is defined more than once", profile.name);
+                problems.push(ConfigProblem::at(format!("{}.name", path), message));
+            }
+            if let Some(ref parent) = profile.inherits {
+                if !known(parent) {
+                    let message = format!("profile {:?} inherits from unknown profile {:?}", profile.name, parent);
+                    problems.push(ConfigProblem::at(format!("{}.inherits", path), message));
+                }
+            }
+            
+            // Walk the inheritance chain looking for a loop back to this profile
+            let mut next = profile.inherits.as_deref();
+            let mut steps = 0;
+            while let Some(current) = next {
+                if current == profile.name {
+                    let message = format!("profile {:?} inherits from itself", profile.name);
+                    problems.push(ConfigProblem::at(format!("{}.inherits", path), message));
+                    break;
+                }
+                steps += 1;
+                if steps > self.profiles.len() {
+                    break;
+                }
+                next = self.profile(current).and_then(|parent| parent.inherits.as_deref());
+            }
+        }
+        
+        if let Some(ref active) = self.active_profile {
+            if !known(active) {
+                problems.push(ConfigProblem::at("active_profile", format!("active_profile {:?} does not exist", active)));
+            }
+        }
+        
+        if self.api.enabled && self.api.token.is_empty() {
+            problems.push(ConfigProblem::at("api.token", "api.token must be set when the HTTP API is enabled"));
+        }
+        
+        if self.schedule.zone().is_err() {
+            let timezone = self.schedule.timezone.as_deref().unwrap_or_default();
+            let message = format!("schedule timezone {:?} is not a known IANA timezone", timezone);
+            problems.push(ConfigProblem::at("schedule.timezone", message));
+        }
+        
+        for (i, block) in self.schedule.blocks.iter().enumerate() {
+            // An empty profile leaves the active one in place, as the engine treats it
+            if let ScheduleAction::Profile(ref name) = block.action {
+                if !name.is_empty() && !known(name) {
+                    let message = format!("schedule block {:?} uses unknown profile {:?}", block.name, name);
+                    problems.push(ConfigProblem::at(format!("schedule.blocks[{}].action.profile", i), message));
+                }
+            }
+        }
+        
+        for (i, sink) in self.sinks.iter().enumerate() {
+            let path = format!("sinks[{}]", i);
+            if sink.name.trim().is_empty() {
+                problems.push(ConfigProblem::at(format!("{}.name", path), format!("{} has an empty name", path)));
+            }
+            if self.sinks[..i].iter().any(|other| other.name == sink.name) {
+                let message = format!("sink {:?} is defined more than once", sink.name);
+                problems.push(ConfigProblem::at(format!("{}.name", path), message));
+            }
+            for (j, profile) in sink.profiles.iter().enumerate() {
+                if !known(profile) {
+                    let message = format!("sink {:?} uses unknown profile {:?}", sink.name, profile);
+                    problems.push(ConfigProblem::at(format!("{}.profiles[{}]", path, j), message));
+                }
+            }
+            match sink.kind {
+                SinkKind::File { path: ref file, .. } if file.as_os_str().is_empty() => {
+                    let message = format!("sink {:?} has no file path", sink.name);
+                    problems.push(ConfigProblem::at(format!("{}.path", path), message));
+                }
+                SinkKind::Webhook { ref url, .. } if !(url.starts_with("http://") || url.starts_with("https://")) => {
+                    let message = format!("sink {:?} needs an http:// or https:// URL", sink.name);
+                    problems.push(ConfigProblem::at(format!("{}.url", path), message));
+                }
+                SinkKind::Mqtt(ref mqtt) if mqtt.host.trim().is_empty() => {
+                    let message = format!("sink {:?} has no MQTT broker host", sink.name);
+                    problems.push(ConfigProblem::at(format!("{}.host", path), message));
+                }
+                _ => {}
+            }
+        }
+        
+        for (i, source) in self.providers.commands.iter().enumerate() {
+            let path = format!("providers.commands[{}]", i);
+            let valid_name = !source.name.is_empty()
+                && source.name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+            if !valid_name {
+                let message = format!("{} needs a name made of letters, digits, _ and -", path);
+                problems.push(ConfigProblem::at(format!("{}.name", path), message));
+            }
+            if self.providers.commands[..i].iter().any(|other| other.name == source.name) {
+                let message = format!("command {:?} is defined more than once", source.name);
+                problems.push(ConfigProblem::at(format!("{}.name", path), message));
+            }
+            if source.command.trim().is_empty() {
+                let message = format!("command {:?} has nothing to run", source.name);
+                problems.push(ConfigProblem::at(format!("{}.command", path), message));
+            }
+            if source.interval_seconds == 0 || source.timeout_seconds == 0 {
+                let field = if source.interval_seconds == 0 { "interval_seconds" } else { "timeout_seconds" };
+                let message = format!("command {:?} needs an interval and timeout of at least 1 second", source.name);
+                problems.push(ConfigProblem::at(format!("{}.{}", path, field), message));
+            }
+        }
+        
+        if self.history.merge_gap_seconds == 0 {
+            problems.push(ConfigProblem::at("history.merge_gap_seconds", "history.merge_gap_seconds must be at least 1"));
+        }
+        
+        if !(1..=MAX_GRAPH_MINUTES).contains(&self.graph_minutes) {
+            let message = format!("graph_minutes must be between 1 and {}", MAX_GRAPH_MINUTES);
+            problems.push(ConfigProblem::at("graph_minutes", message));
+        }
+        
+        problems.extend(self.hotkeys.problems());
+        
+        for (i, plugin) in self.providers.plugins.iter().enumerate() {
+            let path = format!("providers.plugins[{}]", i);
+            let valid_name = !plugin.name.is_empty()
+                && plugin.name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+            if !valid_name {
+                let message = format!("{} needs a name made of letters, digits, _ and -", path);
+                problems.push(ConfigProblem::at(format!("{}.name", path), message));
+            }
+            if self.providers.plugins[..i].iter().any(|other| other.name == plugin.name) {
+                let message = format!("plugin {:?} is defined more than once", plugin.name);
+                problems.push(ConfigProblem::at(format!("{}.name", path), message));
+            }
+            if plugin.path.as_os_str().is_empty() {
+                let message = format!("plugin {:?} has no .wasm file", plugin.name);
+                problems.push(ConfigProblem::at(format!("{}.path", path), message));
+            }
+            if plugin.fuel == 0 || plugin.memory_mb == 0 {
+                let field = if plugin.fuel == 0 { "fuel" } else { "memory_mb" };
+                let message = format!("plugin {:?} needs fuel and memory_mb of at least 1", plugin.name);
+                problems.push(ConfigProblem::at(format!("{}.{}", path, field), message));
+            }
+            for (j, file) in plugin.grants.files.iter().enumerate().filter(|(_, file)| !file.is_absolute()) {
+                let message = format!("plugin {:?} is granted {}, which is not an absolute path", plugin.name, file.display());
+                problems.push(ConfigProblem::at(format!("{}.grants.files[{}]", path, j), message));
+            }
+        }
+        
+        for (i, script) in self.scripting.scripts.iter().enumerate() {
+            if script.as_os_str().is_empty() {
+                let path = format!("scripting.scripts[{}]", i);
+                problems.push(ConfigProblem::at(path.clone(), format!("{} has no file path", path)));
+            }
+        }
+        if self.scripting.max_operations == 0 || self.scripting.timeout_millis == 0 {
+            let field = if self.scripting.max_operations == 0 { "max_operations" } else { "timeout_millis" };
+            let message = "scripting needs max_operations and timeout_millis of at least 1";
+            problems.push(ConfigProblem::at(format!("scripting.{}", field), message));
+        }
+        
+        for (i, pattern) in self.providers.editor.patterns.iter().enumerate() {
+            let path = format!("providers.editor.patterns[{}].title", i);
+            match regex::Regex::new(&pattern.title) {
+                Ok(regex) if !regex.capture_names().any(|name| name == Some("file")) => {
+                    let message = format!("editor pattern for {:?} has no (?P<file>...) group", pattern.editor);
+                    problems.push(ConfigProblem::at(path, message));
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    let message = format!("editor pattern for {:?} is not a valid regex: {}", pattern.editor, e);
+                    problems.push(ConfigProblem::at(path, message));
+                }
+            }
+        }
+        
+        let rules = self
+            .rules
+            .iter()
+            .enumerate()
+            .map(|(i, rule)| ("base".to_string(), format!("rules[{}]", i), rule))
+            .chain(self.profiles.iter().enumerate().flat_map(|(j, profile)| {
+                profile.rules.iter().enumerate().map(move |(i, rule)| {
+                    (format!("profile {:?}", profile.name), format!("profiles[{}].rules[{}]", j, i), rule)
+                })
+            }));
+        
+        for (owner, path, rule) in rules {
+            if let Some(ref pattern) = rule.window {
+                if let Err(e) = regex::Regex::new(pattern) {
+                    let message = format!("{} rule {:?} has an invalid window pattern: {}", owner, rule.name, e);
+                    problems.push(ConfigProblem::at(format!("{}.window", path), message));
+                }
+            }
+            if let RuleAction::Profile(ref name) = rule.action {
+                if !known(name) {
+                    let message = format!("{} rule {:?} uses unknown profile {:?}", owner, rule.name, name);
+                    problems.push(ConfigProblem::at(format!("{}.action.profile", path), message));
+                }
+            }
+        }
+        
+        problems
+    }
+}
+
+enum PathSegment<'a> {
+    Key(&'a str),
+    Index(usize),
+}
+
+/// Where the setting at `setting`, like `hotkeys.next_profile` or `sinks[0].url`, is in
+/// `text`. None when the file leaves it out and the default is the problem.
+fn setting_position(text: &str, setting: &str) -> Option<(usize, usize)> {
+    let mut path = Vec::new();
+    for part in setting.split('.') {
+        let (key, index) = match part.split_once('[') {
+            Some((key, index)) => (key, Some(index.strip_suffix(']')?.parse().ok()?)),
+            None => (part, None),
+        };
+        if key.is_empty() || !key.chars().all(|c| c.is_ascii_lowercase() || c == '_') {
+            return None;
+        }
+        path.push(PathSegment::Key(key));
+        path.extend(index.map(PathSegment::Index));
+    }
+    
+    let offset = JsonScanner { bytes: text.as_bytes(), pos: 0 }.find(&path)?;
+    let before = &text[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    Some((before.matches('\n').count() + 1, before[line_start..].chars().count() + 1))
+}
+
+/// Walks JSON that is already known to be valid, to find where a value starts.
+struct JsonScanner<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> JsonScanner<'a> {
+    fn skip_whitespace(&mut self) {
+        while self.bytes.get(self.pos).is_some_and(|b| b.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+    
+    /// Skips a string starting at the opening quote and returns its raw contents.
+    fn string(&mut self) -> Option<&'a [u8]> {
+        let start = self.pos + 1;
+        self.pos = start;
+        loop {
+            match self.bytes.get(self.pos)? {
+                b'\\' => self.pos += 2,
+                b'"' => {
+                    self.pos += 1;
+                    return Some(&self.bytes[start..self.pos - 1]);
+                }
+                _ => self.pos += 1,
+            }
+        }
+    }
+    
+    fn skip_value(&mut self) -> Option<()> {
+        match *self.bytes.get(self.pos)? {
+            b'"' => {
+                self.string()?;
+            }
+            b'{' | b'[' => {
+                let mut depth = 0;
+                loop {
+                    match *self.bytes.get(self.pos)? {
+                        b'"' => {
+                            self.string()?;
+                            continue;
+                        }
+                        b'{' | b'[' => depth += 1,
+                        b'}' | b']' => {
+                            depth -= 1;
+                            if depth == 0 {
+                                self.pos += 1;
+                                return Some(());
+                            }
+                        }
+                        _ => {}
+                    }
+                    self.pos += 1;
+                }
+            }
+            // Numbers, booleans and null
+            _ => {
+                while self.bytes.get(self.pos).is_some_and(|b| !matches!(b, b',' | b'}' | b']') && !b.is_ascii_whitespace()) {
+                    self.pos += 1;
+                }
+            }
+        }
+        Some(())
+    }
+    
+    /// The offset of the value at `path`, starting from the value at the current position.
+    fn find(&mut self, path: &[PathSegment]) -> Option<usize> {
+        self.skip_whitespace();
+        let (segment, rest) = match path.split_first() {
+            Some(split) => split,
+            None => return Some(self.pos),
+        };
+        
+        let (open, close) = match segment {
+            PathSegment::Key(_) => (b'{', b'}'),
+            PathSegment::Index(_) => (b'[', b']'),
+        };
+        if *self.bytes.get(self.pos)? != open {
+            return None;
+        }
+        self.pos += 1;
+        
+        let mut index = 0;
+        loop {
+            self.skip_whitespace();
+            if *self.bytes.get(self.pos)? == close {
+                return None;
+            }
+            let found = match segment {
+                PathSegment::Key(key) => {
+                    let name = self.string()?;
+                    self.skip_whitespace();
+                    self.pos += 1; // ':'
+                    name == key.as_bytes()
+                }
+                PathSegment::Index(wanted) => index == *wanted,
+            };
+            if found {
+                return self.find(rest);
+            }
+            
+            self.skip_whitespace();
+            self.skip_value()?;
+            self.skip_whitespace();
+            if *self.bytes.get(self.pos)? != b',' {
+                return None;
+            }
+            self.pos += 1;
+            index += 1;
+        }
+    }
+}
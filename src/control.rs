@@ -0,0 +1,451 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fmt;
+use std::fs::Permissions;
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::engine::PresenceEngine;
+use crate::provider::EditorUpdate;
+
+// JSON-RPC 2.0 error codes
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+pub const APPLICATION_ERROR: i64 = -32000;
+
+/// One request per line on the control socket.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcRequest {
+    pub jsonrpc: String,
+    #[serde(default)]
+    pub id: Value,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcResponse {
+    pub jsonrpc: String,
+    #[serde(default)]
+    pub id: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for RpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (code {})", self.message, self.code)
+    }
+}
+
+impl std::error::Error for RpcError {}
+
+#[derive(Deserialize)]
+struct MessageParams {
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct ActivityParams {
+    #[serde(default)]
+    details: Option<String>,
+    #[serde(default)]
+    state: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ProfileParams {
+    profile: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+struct PauseParams {
+    #[serde(default)]
+    duration_secs: Option<u64>,
+}
+
+/// `$XDG_RUNTIME_DIR/multi-rich-presence/control.sock`, or a per-user directory
+/// under the temp dir when no runtime dir is set.
+pub fn socket_path() -> PathBuf {
+    let dir = match std::env::var_os("XDG_RUNTIME_DIR").filter(|dir| !dir.is_empty()) {
+        Some(runtime_dir) => PathBuf::from(runtime_dir).join("multi-rich-presence"),
+        None => std::env::temp_dir().join(format!("multi-rich-presence-{}", current_uid())),
+    };
+    
+    dir.join("control.sock")
+}
+
+fn current_uid() -> u32 {
+    // SAFETY: getuid has no preconditions and cannot fail
+    unsafe { libc::getuid() }
+}
+
+/// Makes sure the socket directory belongs to us and is not accessible to anyone else.
+fn prepare_socket_dir(dir: &Path) -> io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    
+    let metadata = std::fs::metadata(dir)?;
+    if metadata.uid() != current_uid() {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("{} is owned by another user", dir.display()),
+        ));
+    }
+    
+    if metadata.mode() & 0o077 != 0 {
+        std::fs::set_permissions(dir, Permissions::from_mode(0o700))?;
+    }
+    
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn peer_uid(stream: &UnixStream) -> io::Result<u32> {
+    use std::os::unix::io::AsRawFd;
+    
+    let mut credentials = libc::ucred { pid: 0, uid: 0, gid: 0 };
+    let mut length = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+    
+    // SAFETY: the buffer and length describe a valid, writable ucred
+    let result = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            &mut credentials as *mut libc::ucred as *mut libc::c_void,
+            &mut length,
+        )
+    };
+    
+    if result == 0 {
+        Ok(credentials.uid)
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn peer_uid(_stream: &UnixStream) -> io::Result<u32> {
+    // The 0600 socket file already restricts who can connect
+    Ok(current_uid())
+}
+
+/// Serves the JSON-RPC API on a Unix socket. The socket file is removed on drop.
+pub struct ControlServer {
+    path: PathBuf,
+}
+
+impl ControlServer {
+    pub fn start(path: &Path, engine: Arc<Mutex<PresenceEngine>>) -> io::Result<ControlServer> {
+        if let Some(dir) = path.parent() {
+            prepare_socket_dir(dir)?;
+        }
+        
+        if path.exists() {
+            if UnixStream::connect(path).is_ok() {
+                return Err(io::Error::new(
+                    io::ErrorKind::AddrInUse,
+                    format!("another instance is already listening on {}", path.display()),
+                ));
+            }
+            
+            // Left behind by an instance that did not shut down cleanly
+            std::fs::remove_file(path)?;
+        }
+        
+        let listener = UnixListener::bind(path)?;
+        std::fs::set_permissions(path, Permissions::from_mode(0o600))?;
+        log::info!("Control socket listening on {}", path.display());
+        
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => {
+                        let engine = Arc::clone(&engine);
+                        std::thread::spawn(move || handle_connection(stream, engine));
+                    }
+                    Err(e) => log::error!("Control socket accept failed: {}", e),
+                }
+            }
+        });
+        
+        Ok(ControlServer {
+            path: path.to_path_buf(),
+        })
+    }
+    
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for ControlServer {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+fn handle_connection(stream: UnixStream, engine: Arc<Mutex<PresenceEngine>>) {
+    match peer_uid(&stream) {
+        Ok(uid) if uid == current_uid() => {}
+        Ok(uid) => {
+            log::warn!("Rejected control connection from uid {}", uid);
+            return;
+        }
+        Err(e) => {
+            log::warn!("Could not check control connection credentials: {}", e);
+            return;
+        }
+    }
+    
+    let mut writer = match stream.try_clone() {
+        Ok(writer) => writer,
+        Err(e) => {
+            log::error!("Control connection failed: {}", e);
+            return;
+        }
+    };
+    
+    for line in BufReader::new(stream).lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        
+        if line.trim().is_empty() {
+            continue;
+        }
+        
+        let response = handle_line(&line, &engine);
+        let mut payload = serde_json::to_string(&response).unwrap_or_default();
+        payload.push('\n');
+        
+        if writer.write_all(payload.as_bytes()).is_err() {
+            break;
+        }
+    }
+}
+
+fn handle_line(line: &str, engine: &Arc<Mutex<PresenceEngine>>) -> RpcResponse {
+    let mut response = RpcResponse {
+        jsonrpc: "2.0".to_string(),
+        id: Value::Null,
+        result: None,
+        error: None,
+    };
+    
+    let request: RpcRequest = match serde_json::from_str(line) {
+        Ok(request) => request,
+        Err(e) => {
+            let code = if serde_json::from_str::<Value>(line).is_ok() {
+                INVALID_REQUEST
+            } else {
+                PARSE_ERROR
+            };
+            response.error = Some(RpcError::new(code, e.to_string()));
+            return response;
+        }
+    };
+    
+    response.id = request.id.clone();
+    
+    let result = if request.jsonrpc != "2.0" {
+        Err(RpcError::new(INVALID_REQUEST, "jsonrpc must be \"2.0\""))
+    } else {
+        check_params(&request.method, &request.params).and_then(|()| match engine.lock() {
+            Ok(mut engine) => dispatch(&mut engine, &request.method, request.params),
+            Err(_) => Err(RpcError::new(APPLICATION_ERROR, "engine state is poisoned")),
+        })
+    };
+    
+    match result {
+        Ok(result) => response.result = Some(result),
+        Err(error) => response.error = Some(error),
+    }
+    response
+}
+
+fn params<T: serde::de::DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    serde_json::from_value(params).map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))
+}
+
+/// The auto-resume delay of a `pause` request. Rejects delays that do not fit a
+/// `chrono::Duration` or would end past the last representable time.
+fn pause_duration(raw_params: &Value) -> Result<Option<chrono::Duration>, RpcError> {
+    let params: PauseParams = if raw_params.is_null() {
+        PauseParams::default()
+    } else {
+        params(raw_params.clone())?
+    };
+    
+    let secs = match params.duration_secs {
+        Some(secs) => secs,
+        None => return Ok(None),
+    };
+    let duration = i64::try_from(secs)
+        .ok()
+        .and_then(chrono::Duration::try_seconds)
+        .filter(|duration| chrono::Utc::now().checked_add_signed(*duration).is_some());
+    match duration {
+        Some(duration) => Ok(Some(duration)),
+        None => Err(RpcError::new(INVALID_PARAMS, format!("duration_secs {} is too large", secs))),
+    }
+}
+
+/// Checks the parameters that could not be acted on safely, before the engine is locked.
+fn check_params(method: &str, raw_params: &Value) -> Result<(), RpcError> {
+    match method {
+        "pause" => pause_duration(raw_params).map(|_| ()),
+        _ => Ok(()),
+    }
+}
+
+/// Runs one control method against the engine.
+pub fn dispatch(engine: &mut PresenceEngine, method: &str, raw_params: Value) -> Result<Value, RpcError> {
+    match method {
+        "set_message" => {
+            let params: MessageParams = params(raw_params)?;
+            engine.set_custom_message(&params.message);
+            Ok(engine.status())
+        }
+        "clear_message" => {
+            engine.set_custom_message("");
+            Ok(engine.status())
+        }
+        "set_activity" => {
+            let params: ActivityParams = params(raw_params)?;
+            // Lines that are not given keep their current value
+            let details = params.details.unwrap_or_else(|| engine.custom_message.clone());
+            let state = params.state.unwrap_or_else(|| engine.custom_state.clone());
+            engine.set_custom_activity(&details, &state);
+            Ok(engine.status())
+        }
+        "clear_activity" => {
+            engine.set_custom_activity("", "");
+            Ok(engine.status())
+        }
+        "use_profile" => {
+            let params: ProfileParams = params(raw_params)?;
+            engine
+                .switch_profile(params.profile)
+                .map_err(|e| RpcError::new(APPLICATION_ERROR, e))?;
+            Ok(engine.status())
+        }
+        "list_profiles" => {
+            let profiles: Vec<&str> = engine.config.profiles.iter().map(|p| p.name.as_str()).collect();
+            Ok(json!({
+                "active": engine.config.active_profile,
+                "profiles": profiles,
+            }))
+        }
+        "pause" => {
+            let duration = pause_duration(&raw_params)?;
+            engine.hide_presence(duration);
+            Ok(engine.status())
+        }
+        "resume" => {
+            engine.resume_presence();
+            Ok(engine.status())
+        }
+        "get_activity" => Ok(json!({
+            "activity": engine.composed_activity(),
+            "hidden": engine.privacy.hidden || engine.hidden_reason.is_some(),
+            "hidden_reason": engine.hidden_reason,
+            "profile": engine.settings.profile,
+        })),
+        "get_connection" => Ok(json!({
+            "connected": engine.is_connected(),
+            "status": engine.connection_status,
+        })),
+        "set_editor" => {
+            let update: EditorUpdate = params(raw_params)?;
+            engine
+                .set_editor(Some(update))
+                .map_err(|e| RpcError::new(APPLICATION_ERROR, e))?;
+            Ok(engine.status())
+        }
+        "clear_editor" => {
+            engine.set_editor(None).map_err(|e| RpcError::new(APPLICATION_ERROR, e))?;
+            Ok(engine.status())
+        }
+        "get_status" => Ok(engine.status()),
+        "reload_config" => {
+            engine
+                .reload_config()
+                .map_err(|e| RpcError::new(APPLICATION_ERROR, format!("Config reload failed: {}", e)))?;
+            Ok(engine.status())
+        }
+        _ => Err(RpcError::new(METHOD_NOT_FOUND, format!("Unknown method: {}", method))),
+    }
+}
+
+/// Blocking client for the control socket.
+pub struct ControlClient {
+    reader: BufReader<UnixStream>,
+    writer: UnixStream,
+    next_id: u64,
+}
+
+impl ControlClient {
+    pub fn connect(path: &Path) -> io::Result<ControlClient> {
+        let stream = UnixStream::connect(path)?;
+        stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+        
+        Ok(ControlClient {
+            writer: stream.try_clone()?,
+            reader: BufReader::new(stream),
+            next_id: 1,
+        })
+    }
+    
+    /// Sends a request and waits for its response. JSON-RPC errors come back as `RpcError`.
+    pub fn call(&mut self, method: &str, params: Value) -> Result<Value, Box<dyn std::error::Error>> {
+        let request = RpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: json!(self.next_id),
+            method: method.to_string(),
+            params,
+        };
+        self.next_id += 1;
+        
+        let mut payload = serde_json::to_string(&request)?;
+        payload.push('\n');
+        self.writer.write_all(payload.as_bytes())?;
+        
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err("control socket closed the connection".into());
+        }
+        
+        let response: RpcResponse = serde_json::from_str(&line)?;
+        match (response.result, response.error) {
+            (_, Some(error)) => Err(Box::new(error)),
+            (Some(result), None) => Ok(result),
+            (None, None) => Ok(Value::Null),
+        }
+    }
+}
@@ -0,0 +1,831 @@
+use chrono::Utc;
+use regex::Regex;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::activity::{ActivityData, ProcessEntry};
+use crate::config::{self, ActivityFilters, Config, ConfigError};
+use crate::history::{History, HistoryConfig, HistorySample};
+use crate::metrics::{self, Metrics};
+use crate::monitor::{self, MonitorOutputs};
+use crate::presence::{self, ComposedActivity};
+use crate::privacy::PrivacyState;
+use crate::profile::PresenceSettings;
+use crate::provider::{EditorUpdate, ProviderInputs, ProvidersConfig, PushedEditor, TitleParser};
+use crate::rules::{self, RuleAction, RulePatterns};
+use crate::samples::SampleHistory;
+use crate::schedule::ScheduleAction;
+use crate::script::{ScriptHost, ScriptOutcome};
+use crate::sink::{self, DiscordSink, PresenceSink, SinkConfig, SinkSlot};
+use crate::template::PresenceTemplate;
+
+/// Change notifications for push-based consumers such as the WebSocket stream.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EngineEvent {
+    /// The presence sent to Discord changed; `activity` is `None` while hidden.
+    Presence {
+        activity: Option<ComposedActivity>,
+        hidden_reason: Option<String>,
+        profile: Option<String>,
+    },
+    Connection {
+        connected: bool,
+        status: String,
+    },
+}
+
+/// Owns the Discord connection and everything that decides what gets broadcast.
+/// The GUI and the control socket share one engine behind an `Arc<Mutex<_>>`.
+pub struct PresenceEngine {
+    pub config: Config,
+    pub activity_data: Arc<Mutex<Option<ActivityData>>>,
+    pub metrics: Arc<Mutex<Metrics>>,
+    /// Recent stats for the live graphs.
+    pub samples: Arc<Mutex<SampleHistory>>,
+    /// Every running process, for the process browser.
+    pub processes: Arc<Mutex<Vec<ProcessEntry>>>,
+    monitor_filters: Arc<Mutex<ActivityFilters>>,
+    provider_config: Arc<Mutex<ProvidersConfig>>,
+    /// Seconds between monitor passes, read by the monitor before each one.
+    monitor_interval: Arc<Mutex<u64>>,
+    provider_inputs: ProviderInputs,
+    /// Editor title patterns for `set_editor`, compiled once per config.
+    editor_titles: TitleParser,
+    discord: DiscordSink,
+    sinks: Vec<SinkSlot>,
+    /// The sink configs `sinks` was built from, to notice edits.
+    sink_configs: Vec<SinkConfig>,
+    last_update: Instant,
+    word_filter: Regex,
+    subscribers: Vec<Sender<EngineEvent>>,
+    last_published: Option<Option<ComposedActivity>>,
+    applied_rule: Option<String>,
+    /// Compiled window patterns of every rule in the config.
+    pub rule_patterns: RulePatterns,
+    pub scripts: ScriptHost,
+    history: Option<History>,
+    /// The history config `history` was opened with, to notice edits.
+    history_config: HistoryConfig,
+    pub privacy: PrivacyState,
+    pub custom_message: String,
+    /// Replaces the state line while set, e.g. from `mrp set --state`.
+    pub custom_state: String,
+    pub connection_status: String,
+    
+    // Effective presence selection, refreshed on every tick
+    pub settings: PresenceSettings,
+    pub template_override: Option<PresenceTemplate>,
+    /// Blacklisted words replaced in a script's template, counted with every update.
+    template_override_hits: usize,
+    pub presence_source: Option<String>,
+    pub hidden_reason: Option<String>,
+    /// Why the config file was last rejected; the last good config stays in effect.
+    pub config_errors: Vec<ConfigError>,
+    /// `config.json` as the engine last read or wrote it. Finding the same text on reload
+    /// means the change was the engine's own save.
+    config_text: Option<String>,
+}
+
+impl PresenceEngine {
+    /// Starts from `config.json`, selecting and saving `profile` when given. A file that
+    /// cannot be read, parsed or validated is left alone: the engine runs with the defaults
+    /// and the reasons go to `config_errors`, which keeps the file from being saved over
+    /// until it is fixed.
+    pub fn load(profile: Option<String>) -> Self {
+        let read = config::config_path().and_then(|path| match std::fs::read_to_string(path) {
+            Ok(text) => Ok(Some(text)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        });
+        let (config, text, errors) = match read {
+            Ok(Some(text)) => match Config::parse(&text) {
+                Ok(config) => (config, Some(text), Vec::new()),
+                Err(errors) => (Config::default(), None, errors),
+            },
+            Ok(None) => (Config::default(), None, Vec::new()),
+            Err(e) => {
+                let error = ConfigError {
+                    position: None,
+                    message: format!("cannot read the config file: {}", e),
+                };
+                (Config::default(), None, vec![error])
+            }
+        };
+        if !errors.is_empty() {
+            let summary = errors.iter().map(|e| e.to_string()).collect::<Vec<_>>().join("; ");
+            log::error!("Rejected the config file, starting with the defaults: {}", summary);
+        }
+        
+        let mut engine = Self::new(config);
+        engine.config_errors = errors;
+        engine.config_text = text;
+        if profile.is_some() {
+            if let Err(e) = engine.switch_profile(profile) {
+                log::error!("{}", e);
+            }
+        }
+        engine
+    }
+    
+    pub fn new(config: Config) -> Self {
+        let settings = config.settings_for(config.active_profile.as_deref());
+        let word_filter = presence::create_word_filter(&settings.blacklisted_words);
+        
+        // Fail closed: an unreadable privacy state keeps presence hidden
+        let privacy = PrivacyState::load().unwrap_or_else(|e| {
+            log::error!("Failed to load privacy state, keeping presence hidden: {}", e);
+            PrivacyState {
+                hidden: true,
+                resume_at: None,
+            }
+        });
+        
+        let mut engine = Self {
+            activity_data: Arc::new(Mutex::new(None)),
+            metrics: Arc::new(Mutex::new(Metrics::default())),
+            samples: Arc::new(Mutex::new(SampleHistory::new(config.graph_minutes))),
+            processes: Arc::new(Mutex::new(Vec::new())),
+            monitor_filters: Arc::new(Mutex::new(settings.activity_filters.clone())),
+            provider_config: Arc::new(Mutex::new(config.providers.clone())),
+            monitor_interval: Arc::new(Mutex::new(config.update_interval_seconds)),
+            provider_inputs: ProviderInputs::default(),
+            editor_titles: TitleParser::new(&config.providers.editor),
+            discord: DiscordSink::default(),
+            sinks: sink::build_sinks(&config.sinks),
+            sink_configs: config.sinks.clone(),
+            last_update: Instant::now(),
+            word_filter,
+            subscribers: Vec::new(),
+            last_published: None,
+            applied_rule: None,
+            rule_patterns: RulePatterns::default(),
+            scripts: ScriptHost::new(&config.scripting, script_dir().as_deref()),
+            history: open_history(&config.history),
+            history_config: config.history.clone(),
+            privacy,
+            custom_message: String::new(),
+            custom_state: String::new(),
+            connection_status: "Disconnected".to_string(),
+            settings,
+            template_override: None,
+            template_override_hits: 0,
+            presence_source: None,
+            hidden_reason: None,
+            config_errors: Vec::new(),
+            config_text: None,
+            config,
+        };
+        
+        engine.connect_discord();
+        let outputs = MonitorOutputs {
+            activity_data: Arc::clone(&engine.activity_data),
+            samples: Arc::clone(&engine.samples),
+            processes: Arc::clone(&engine.processes),
+        };
+        monitor::start_system_monitoring(
+            outputs,
+            Arc::clone(&engine.monitor_filters),
+            Arc::clone(&engine.provider_config),
+            engine.provider_inputs.clone(),
+            Arc::clone(&engine.metrics),
+            Arc::clone(&engine.monitor_interval),
+        );
+        engine
+    }
+    
+    /// Spawns the thread that drives periodic updates, independent of the GUI.
+    pub fn spawn_ticker(engine: &Arc<Mutex<PresenceEngine>>) {
+        let engine = Arc::clone(engine);
+        
+        std::thread::spawn(move || loop {
+            std::thread::sleep(Duration::from_secs(1));
+            
+            match engine.lock() {
+                Ok(mut engine) => engine.tick(),
+                Err(_) => break,
+            }
+        });
+    }
+    
+    pub fn tick(&mut self) {
+        self.refresh_settings();
+        
+        if self.privacy.should_resume(Utc::now()) {
+            self.resume_presence();
+        }
+        
+        if self.last_update.elapsed() >= Duration::from_secs(self.config.update_interval_seconds) {
+            self.push_update();
+        }
+    }
+    
+    pub fn is_connected(&self) -> bool {
+        self.connection_status == "Connected"
+    }
+    
+    pub fn connect_discord(&mut self) {
+        self.record(|metrics| metrics.reconnect_attempts += 1);
+        
+        match self.discord.connect(&self.settings.discord_app_id) {
+            Ok(()) => {
+                self.set_connection_status("Connected".to_string());
+                log::info!("Connected to Discord RPC");
+            }
+            Err(e) => {
+                self.record(|metrics| metrics.connection_failures += 1);
+                self.set_connection_status(e.to_string());
+                log::error!("Failed to connect to Discord: {}", e);
+            }
+        }
+    }
+    
+    /// Rebuilds the extra sinks when their configuration changed.
+    fn refresh_sinks(&mut self) {
+        if self.config.sinks == self.sink_configs {
+            return;
+        }
+        
+        for slot in &mut self.sinks {
+            slot.close();
+        }
+        self.sinks = sink::build_sinks(&self.config.sinks);
+        self.sink_configs = self.config.sinks.clone();
+        log::info!("Rebuilt {} output sink(s)", self.sinks.len());
+    }
+    
+    /// Opens or closes the history database when its configuration changed.
+    fn refresh_history(&mut self) {
+        if self.config.history == self.history_config {
+            return;
+        }
+        
+        self.history = open_history(&self.config.history);
+        self.history_config = self.config.history.clone();
+    }
+    
+    /// Adds the presence being sent and the app in front to the history. Nothing is
+    /// recorded while presence is hidden.
+    fn record_history(&mut self) {
+        if self.history.is_none() {
+            return;
+        }
+        
+        let activity = self.composed_activity();
+        let data = self.activity_data.lock().ok().and_then(|data| data.clone());
+        let history = match self.history {
+            Some(ref mut history) => history,
+            None => return,
+        };
+        
+        let (activity, data) = match (activity, data) {
+            (Some(activity), Some(data)) => (activity, data),
+            _ => {
+                history.pause();
+                return;
+            }
+        };
+        
+        let sample = HistorySample {
+            presence: Some(&activity),
+            profile: self.settings.profile.as_deref(),
+            source: self.presence_source.as_deref(),
+            app: data.top_processes.first().map(|process| process.name.as_str()),
+            window: data.active_window.as_deref(),
+            project: data
+                .editor
+                .as_ref()
+                .and_then(|editor| editor.project.as_deref())
+                .or(data.git.as_ref().map(|git| git.repo.as_str())),
+        };
+        if let Err(e) = history.record(Utc::now(), &sample, &self.word_filter) {
+            log::error!("Failed to record history: {}", e);
+        }
+    }
+    
+    /// The open history database, if recording is enabled.
+    pub fn history(&self) -> Option<&History> {
+        self.history.as_ref()
+    }
+    
+    /// Deletes everything recorded so far.
+    pub fn clear_history(&mut self) -> Result<(), String> {
+        match self.history {
+            Some(ref mut history) => history.clear().map_err(|e| e.to_string()),
+            None => Err("History is disabled".to_string()),
+        }
+    }
+    
+    /// Hands the current presence to every extra sink; hidden presence clears them.
+    fn publish_sinks(&mut self) {
+        let activity = self.composed_activity();
+        let stats = self
+            .activity_data
+            .lock()
+            .ok()
+            .and_then(|data| data.as_ref().map(|data| data.system_stats.clone()));
+        
+        for slot in &mut self.sinks {
+            let profile = self.settings.profile.as_deref();
+            let mut result = slot.publish(activity.as_ref(), profile);
+            if let (Ok(()), Some(stats)) = (&result, &stats) {
+                result = slot.publish_stats(stats, profile);
+            }
+            
+            if let Err(e) = result {
+                log::error!("Sink {:?} failed: {}", slot.config.name, e);
+            }
+        }
+    }
+    
+    fn record(&self, update: impl FnOnce(&mut Metrics)) {
+        if let Ok(mut metrics) = self.metrics.lock() {
+            update(&mut metrics);
+        }
+    }
+    
+    /// Prometheus text exposition of the counters and current system stats.
+    pub fn render_metrics(&self) -> String {
+        let data = self.activity_data.lock().ok().and_then(|data| data.clone());
+        let metrics = match self.metrics.lock() {
+            Ok(metrics) => metrics.clone(),
+            Err(_) => Metrics::default(),
+        };
+        
+        metrics::render(
+            &metrics,
+            &metrics::Snapshot {
+                data: data.as_ref(),
+                connected: self.is_connected(),
+                hidden: self.privacy.hidden || self.hidden_reason.is_some(),
+            },
+        )
+    }
+    
+    /// Registers a listener for presence and connection changes. Listeners whose
+    /// receiver has been dropped are pruned on the next event.
+    pub fn subscribe(&mut self) -> Receiver<EngineEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers.push(sender);
+        receiver
+    }
+    
+    fn publish(&mut self, event: EngineEvent) {
+        self.subscribers.retain(|subscriber| subscriber.send(event.clone()).is_ok());
+    }
+    
+    fn set_connection_status(&mut self, status: String) {
+        if status == self.connection_status {
+            return;
+        }
+        
+        self.connection_status = status;
+        self.publish(EngineEvent::Connection {
+            connected: self.is_connected(),
+            status: self.connection_status.clone(),
+        });
+    }
+    
+    /// Publishes the presence that was just sent, if it differs from the last one.
+    fn publish_presence(&mut self) {
+        let activity = self.composed_activity();
+        if self.last_published.as_ref() == Some(&activity) {
+            return;
+        }
+        
+        self.last_published = Some(activity.clone());
+        self.publish(EngineEvent::Presence {
+            activity,
+            hidden_reason: self.hidden_reason.clone(),
+            profile: self.settings.profile.clone(),
+        });
+    }
+    
+    /// Connection, profile and pause state as reported by the control APIs.
+    pub fn status(&self) -> Value {
+        json!({
+            "connected": self.is_connected(),
+            "connection": self.connection_status,
+            "profile": self.config.active_profile,
+            "effective_profile": self.settings.profile,
+            "source": self.presence_source,
+            "paused": self.privacy.hidden,
+            "resume_at": self.privacy.resume_at,
+            "hidden_reason": self.hidden_reason,
+            "message": self.custom_message,
+            "state": self.custom_state,
+            "config_errors": self.config_errors.iter().map(|e| e.to_string()).collect::<Vec<_>>(),
+        })
+    }
+    
+    pub fn filter_text(&self, text: &str) -> String {
+        presence::filter_text(&self.word_filter, text)
+    }
+    
+    /// `data` as it may leave the app, e.g. through the HTTP API: text through the word
+    /// filter and hidden processes left out.
+    pub fn filter_data(&self, data: &ActivityData) -> ActivityData {
+        data.filtered(&self.word_filter, &self.settings.activity_filters)
+    }
+    
+    /// Works out which profile and template apply right now: the manually selected
+    /// profile, overridden by the active schedule block, overridden by the first
+    /// matching rule or, when no rule matches, by the first script with a decision.
+    pub fn refresh_settings(&mut self) {
+        let data = self.activity_data.lock().ok().and_then(|data| data.clone());
+        
+        let mut profile = self.config.active_profile.clone();
+        let mut source = None;
+        let mut hidden_reason = None;
+        
+        if let Some((_, block)) = self.config.schedule.active_block(Utc::now()) {
+            match block.action {
+                ScheduleAction::Off => hidden_reason = Some(format!("schedule: {}", block.name)),
+                ScheduleAction::Profile(ref name) if !name.is_empty() => {
+                    profile = Some(name.clone());
+                    source = Some(format!("schedule: {}", block.name));
+                }
+                ScheduleAction::Profile(_) => {}
+            }
+        }
+        
+        let mut settings = self.config.settings_for(profile.as_deref());
+        let mut template_override = None;
+        
+        let config = &self.config;
+        self.rule_patterns
+            .refresh(config.rules.iter().chain(config.profiles.iter().flat_map(|profile| &profile.rules)));
+        let matched = data
+            .as_ref()
+            .and_then(|data| rules::first_match(&settings.rules, data, &self.rule_patterns))
+            .map(|rule| (rule.name.clone(), format!("rule: {}", rule.name), rule.action.clone()));
+        
+        let mut applied_rule = None;
+        if let (Some((rule_name, rule_source, action)), None) = (matched, &hidden_reason) {
+            applied_rule = Some(rule_name);
+            match action {
+                RuleAction::Profile(name) => {
+                    settings = self.config.settings_for(Some(&name));
+                    source = Some(rule_source);
+                }
+                RuleAction::Template(template) => {
+                    template_override = Some(template);
+                    source = Some(rule_source);
+                }
+                RuleAction::Hide => hidden_reason = Some(rule_source),
+            }
+        }
+        
+        self.refresh_editor_titles();
+        if self.config.scripting != *self.scripts.config() {
+            self.scripts = ScriptHost::new(&self.config.scripting, script_dir().as_deref());
+        }
+        
+        let mut script_template = None;
+        if let (Some(data), None, None) = (&data, &applied_rule, &hidden_reason) {
+            let config = &self.config;
+            let decision = self.scripts.evaluate(data, settings.profile.as_deref(), |name| config.profile(name).is_some());
+            if let Some((script, outcome)) = decision {
+                match outcome {
+                    ScriptOutcome::Profile(name) => settings = self.config.settings_for(Some(&name)),
+                    ScriptOutcome::Template(template) => script_template = Some(template),
+                }
+                source = Some(format!("script: {}", script));
+            }
+        }
+        
+        if settings.blacklisted_words != self.settings.blacklisted_words {
+            self.word_filter = presence::create_word_filter(&settings.blacklisted_words);
+        }
+        
+        // A script's lines are built from activity data, so they are filtered like template fields
+        let mut template_override_hits = 0;
+        if let Some(template) = script_template {
+            let (filtered, hits) = presence::filter_template(&self.word_filter, &template);
+            template_override = Some(filtered);
+            template_override_hits = hits;
+        }
+        
+        if settings.activity_filters != self.settings.activity_filters {
+            if let Ok(mut filters) = self.monitor_filters.lock() {
+                *filters = settings.activity_filters.clone();
+            }
+        }
+        
+        if let Ok(mut providers) = self.provider_config.lock() {
+            if *providers != self.config.providers {
+                *providers = self.config.providers.clone();
+            }
+        }
+        
+        if let Ok(mut interval) = self.monitor_interval.lock() {
+            if *interval != self.config.update_interval_seconds {
+                *interval = self.config.update_interval_seconds;
+            }
+        }
+        
+        // Counted when a rule starts applying, not on every tick it keeps applying
+        if applied_rule != self.applied_rule {
+            if let Some(ref name) = applied_rule {
+                self.record(|metrics| metrics.record_rule_match(name));
+            }
+            self.applied_rule = applied_rule;
+        }
+        
+        self.refresh_sinks();
+        self.refresh_history();
+        
+        if let Ok(mut samples) = self.samples.lock() {
+            samples.set_minutes(self.config.graph_minutes);
+        }
+        
+        let reconnect = settings.discord_app_id != self.settings.discord_app_id;
+        
+        self.settings = settings;
+        self.template_override = template_override;
+        self.template_override_hits = template_override_hits;
+        self.presence_source = source;
+        self.hidden_reason = hidden_reason;
+        
+        if reconnect {
+            log::info!("Switching Discord application to {}", self.settings.discord_app_id);
+            self.connect_discord();
+        }
+    }
+    
+    /// Selects the manual profile (`None` for the base profile) and persists it.
+    pub fn switch_profile(&mut self, profile: Option<String>) -> Result<(), String> {
+        if let Some(ref name) = profile {
+            if self.config.profile(name).is_none() {
+                return Err(format!("Unknown profile: {}", name));
+            }
+        }
+        
+        log::info!("Switching to profile {:?}", profile.as_deref().unwrap_or("base"));
+        self.config.active_profile = profile;
+        
+        // Persist right away so the selection survives a crash
+        if let Err(e) = self.save_config() {
+            log::error!("Failed to save the profile selection: {}", e);
+        }
+        
+        self.refresh_settings();
+        self.push_update();
+        Ok(())
+    }
+    
+    pub fn set_custom_message(&mut self, message: &str) {
+        self.custom_message = message.to_string();
+        self.push_update();
+    }
+    
+    /// Sets the custom details and state lines together; empty strings clear them.
+    pub fn set_custom_activity(&mut self, details: &str, state: &str) {
+        self.custom_message = details.to_string();
+        self.custom_state = state.to_string();
+        self.push_update();
+    }
+    
+    fn refresh_editor_titles(&mut self) {
+        if self.config.providers.editor != *self.editor_titles.config() {
+            self.editor_titles = TitleParser::new(&self.config.providers.editor);
+        }
+    }
+    
+    /// Takes the current file from an editor plugin; `None` goes back to window titles.
+    pub fn set_editor(&mut self, update: Option<EditorUpdate>) -> Result<(), String> {
+        self.refresh_editor_titles();
+        let config = &self.config.providers.editor;
+        if !config.enabled {
+            return Err("The editor provider is disabled".to_string());
+        }
+        if update.as_ref().is_some_and(|update| update.file.trim().is_empty()) {
+            return Err("file must not be empty".to_string());
+        }
+        
+        let info = update.map(|update| update.resolve(config));
+        if let Ok(mut pushed) = self.provider_inputs.editor.lock() {
+            *pushed = info.clone().map(|info| PushedEditor {
+                info,
+                received: Instant::now(),
+            });
+        }
+        
+        // Show the change now rather than after the next monitoring sample
+        if let Ok(mut data) = self.activity_data.lock() {
+            if let Some(ref mut data) = *data {
+                data.editor = info.or_else(|| {
+                    data.active_window
+                        .as_deref()
+                        .and_then(|title| self.editor_titles.parse(title))
+                });
+            }
+        }
+        
+        self.push_update();
+        Ok(())
+    }
+    
+    /// Re-reads the config file and applies it when it parses and validates. Otherwise the
+    /// current config stays in effect and the reasons are kept in `config_errors`.
+    pub fn reload_config(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let path = config::config_path()?;
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => Some(text),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e.into()),
+        };
+        if text.is_some() && text == self.config_text {
+            self.config_errors.clear();
+            return Ok(());
+        }
+        
+        let config = match text {
+            Some(ref text) => Config::parse(text),
+            None => Ok(Config::default()),
+        };
+        match config {
+            Ok(config) => {
+                self.config_errors.clear();
+                self.config_text = text;
+                self.apply_config(config);
+                Ok(())
+            }
+            Err(errors) => {
+                let summary = errors.iter().map(|e| e.to_string()).collect::<Vec<_>>().join("; ");
+                log::error!("Rejected {}, keeping the current config: {}", path.display(), summary);
+                self.config_errors = errors;
+                Err(summary.into())
+            }
+        }
+    }
+    
+    /// Writes the config in effect to `config.json`, remembering the text so that the
+    /// reload the write triggers is recognized as the engine's own.
+    /// Refused while `config_errors` is set, since that would overwrite the rejected hand
+    /// edit before it is fixed, and when the config fails `Config::validate`, since the next
+    /// start would reject the file.
+    pub fn save_config(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.config_errors.is_empty() {
+            return Err("config.json has errors that were not applied; fix the file before saving from the app".into());
+        }
+        let problems = self.config.validate();
+        if !problems.is_empty() {
+            let problems: Vec<String> = problems.iter().map(ToString::to_string).collect();
+            return Err(format!("the config was not saved: {}", problems.join("; ")).into());
+        }
+        self.config_text = Some(self.config.save()?);
+        Ok(())
+    }
+    
+    /// Puts `config` in effect and brings every subsystem in line with it in one step, under
+    /// the engine lock: filters, providers, scripts, sinks, history and the Discord
+    /// application. Returns false when it equals the config already in effect, e.g. when only
+    /// the formatting of the file changed.
+    pub fn apply_config(&mut self, config: Config) -> bool {
+        if serde_json::to_value(&config).ok() == serde_json::to_value(&self.config).ok() {
+            return false;
+        }
+        
+        self.config = config;
+        log::info!("Reloaded config");
+        self.refresh_settings();
+        self.push_update();
+        true
+    }
+    
+    fn clear_discord_activity(&mut self) {
+        if !self.discord.is_connected() {
+            return;
+        }
+        
+        if let Err(e) = self.discord.update(None) {
+            log::error!("Failed to clear Discord activity: {}", e);
+        }
+    }
+    
+    /// Panic button: clears the activity right away and suspends all updates until
+    /// resumed, optionally with an auto-resume timer.
+    pub fn hide_presence(&mut self, duration: Option<chrono::Duration>) {
+        self.privacy.hide(duration);
+        if let Err(e) = self.privacy.save() {
+            log::error!("Failed to save privacy state: {}", e);
+        }
+        
+        self.clear_discord_activity();
+        self.publish_sinks();
+        self.publish_presence();
+        self.record_history();
+        log::info!("Presence hidden");
+    }
+    
+    pub fn resume_presence(&mut self) {
+        self.privacy.resume();
+        if let Err(e) = self.privacy.save() {
+            log::error!("Failed to save privacy state: {}", e);
+        }
+        
+        log::info!("Presence resumed");
+        self.push_update();
+    }
+    
+    /// The presence that would be broadcast right now, or `None` while it is hidden
+    /// or before the first monitoring sample arrived.
+    pub fn composed_activity(&self) -> Option<ComposedActivity> {
+        if self.privacy.hidden || self.hidden_reason.is_some() {
+            return None;
+        }
+        
+        let activity_data = self.activity_data.lock().ok()?;
+        let data = activity_data.as_ref()?;
+        
+        let mut composed = presence::compose(
+            data,
+            &self.settings,
+            self.template_override.as_ref(),
+            Some(&self.custom_message),
+            Some(&self.custom_state),
+            &self.word_filter,
+        );
+        composed.filter_hits += self.template_override_hits;
+        Some(composed)
+    }
+    
+    /// Sends an update now instead of waiting for the next interval.
+    pub fn push_update(&mut self) {
+        self.update_discord_activity();
+        self.publish_sinks();
+        self.publish_presence();
+        self.record_history();
+        self.last_update = Instant::now();
+    }
+    
+    fn update_discord_activity(&mut self) {
+        // The panic button, quiet hours and hide rules take precedence over everything else
+        if self.privacy.hidden || self.hidden_reason.is_some() {
+            self.record(|metrics| metrics.updates_suppressed += 1);
+            self.clear_discord_activity();
+            return;
+        }
+        
+        let composed = match self.composed_activity() {
+            Some(composed) => composed,
+            None => return,
+        };
+        
+        if !self.discord.is_connected() {
+            self.record(|metrics| metrics.updates_failed += 1);
+            return;
+        }
+        
+        match self.discord.update(Some(&composed)) {
+            Ok(()) => {
+                let filter_hits = composed.filter_hits as u64;
+                self.record(|metrics| {
+                    metrics.updates_sent += 1;
+                    metrics.filter_hits += filter_hits;
+                });
+            }
+            Err(e) => {
+                self.record(|metrics| metrics.updates_failed += 1);
+                log::error!("Failed to set Discord activity: {}", e);
+                self.set_connection_status(format!("Activity update failed: {}", e));
+            }
+        }
+    }
+    
+    /// Clears the activity everywhere and closes the connections. The config is not saved
+    /// here; settings are saved when they are changed.
+    pub fn shutdown(&mut self) {
+        let _ = self.discord.update(None);
+        self.discord.close();
+        
+        for slot in &mut self.sinks {
+            let _ = slot.publish(None, None);
+            slot.close();
+        }
+    }
+}
+
+fn open_history(config: &HistoryConfig) -> Option<History> {
+    if !config.enabled {
+        return None;
+    }
+    
+    match History::open(config) {
+        Ok(history) => Some(history),
+        Err(e) => {
+            log::error!("Failed to open the history database: {}", e);
+            None
+        }
+    }
+}
+
+/// Relative script paths are resolved against the directory of the config file.
+fn script_dir() -> Option<PathBuf> {
+    config::config_path().ok()?.parent().map(|dir| dir.to_path_buf())
+}
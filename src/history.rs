@@ -0,0 +1,324 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use regex::Regex;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::config;
+use crate::presence::{self, ComposedActivity};
+
+/// How often old sessions are pruned while the app keeps running.
+const PRUNE_INTERVAL: i64 = 3600;
+
+/// Local record of the presence that was shown and the app in front, kept in SQLite.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Sessions that ended longer ago than this are deleted; 0 keeps everything.
+    #[serde(default = "default_retention_days")]
+    pub retention_days: u32,
+    /// Matching samples further apart than this start a new session.
+    #[serde(default = "default_merge_gap_seconds")]
+    pub merge_gap_seconds: u64,
+    /// Whether window titles are stored next to the app name.
+    #[serde(default = "default_record_windows")]
+    pub record_windows: bool,
+    /// Database file; `history.sqlite3` in the data directory when unset.
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+}
+
+fn default_retention_days() -> u32 {
+    90
+}
+
+fn default_merge_gap_seconds() -> u64 {
+    120
+}
+
+fn default_record_windows() -> bool {
+    true
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            retention_days: default_retention_days(),
+            merge_gap_seconds: default_merge_gap_seconds(),
+            record_windows: default_record_windows(),
+            path: None,
+        }
+    }
+}
+
+impl HistoryConfig {
+    pub fn database_path(&self) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        match self.path {
+            Some(ref path) => Ok(path.clone()),
+            None => Ok(config::data_dir()?.join("history.sqlite3")),
+        }
+    }
+}
+
+/// A stretch of time during which the same presence was shown.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PresenceSession {
+    pub started_at: DateTime<Utc>,
+    pub ended_at: DateTime<Utc>,
+    pub details: Option<String>,
+    pub state: Option<String>,
+    pub profile: Option<String>,
+    /// What selected the presence, e.g. "rule: Gaming".
+    pub source: Option<String>,
+}
+
+/// A stretch of time during which the same app and window were in front.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AppSession {
+    pub started_at: DateTime<Utc>,
+    pub ended_at: DateTime<Utc>,
+    pub app: String,
+    pub window: Option<String>,
+    /// Editor project or repository being worked on, if any.
+    pub project: Option<String>,
+}
+
+impl PresenceSession {
+    pub fn duration(&self) -> Duration {
+        self.ended_at - self.started_at
+    }
+}
+
+impl AppSession {
+    pub fn duration(&self) -> Duration {
+        self.ended_at - self.started_at
+    }
+}
+
+/// One monitoring sample as handed to the history, before filtering.
+#[derive(Debug, Clone, Default)]
+pub struct HistorySample<'a> {
+    pub presence: Option<&'a ComposedActivity>,
+    pub profile: Option<&'a str>,
+    pub source: Option<&'a str>,
+    pub app: Option<&'a str>,
+    pub window: Option<&'a str>,
+    pub project: Option<&'a str>,
+}
+
+pub struct History {
+    connection: Connection,
+    config: HistoryConfig,
+    /// Set while recording was paused, so the next sample never extends an older session.
+    interrupted: bool,
+    last_pruned: Option<DateTime<Utc>>,
+}
+
+impl History {
+    /// Opens or creates the database at the configured path. Old sessions are pruned
+    /// with the first sample.
+    pub fn open(config: &HistoryConfig) -> Result<Self, Box<dyn std::error::Error>> {
+        let path = config.database_path()?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        
+        let connection = Connection::open(&path)?;
+        connection.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             CREATE TABLE IF NOT EXISTS presence_sessions (
+                 id INTEGER PRIMARY KEY,
+                 started_at INTEGER NOT NULL,
+                 ended_at INTEGER NOT NULL,
+                 details TEXT,
+                 state TEXT,
+                 profile TEXT,
+                 source TEXT
+             );
+             CREATE INDEX IF NOT EXISTS presence_sessions_ended_at ON presence_sessions (ended_at);
+             CREATE TABLE IF NOT EXISTS app_sessions (
+                 id INTEGER PRIMARY KEY,
+                 started_at INTEGER NOT NULL,
+                 ended_at INTEGER NOT NULL,
+                 app TEXT NOT NULL,
+                 window TEXT,
+                 project TEXT
+             );
+             CREATE INDEX IF NOT EXISTS app_sessions_ended_at ON app_sessions (ended_at);",
+        )?;
+        // Databases from before projects were recorded
+        if connection.prepare("SELECT project FROM app_sessions LIMIT 0").is_err() {
+            connection.execute_batch("ALTER TABLE app_sessions ADD COLUMN project TEXT;")?;
+        }
+        
+        Ok(Self {
+            connection,
+            config: config.clone(),
+            // A restart is a gap of unknown length
+            interrupted: true,
+            last_pruned: None,
+        })
+    }
+    
+    pub fn config(&self) -> &HistoryConfig {
+        &self.config
+    }
+    
+    /// Stores a sample taken at `now`. Every text goes through `word_filter` first, so
+    /// blacklisted words never reach the database. Consecutive matching samples extend
+    /// the current session instead of adding a row; a changed sample ends it at `now`.
+    pub fn record(&mut self, now: DateTime<Utc>, sample: &HistorySample, word_filter: &Regex) -> rusqlite::Result<()> {
+        let filter = |text: Option<&str>| text.map(|text| presence::filter_text(word_filter, text));
+        let merge_gap = (!std::mem::take(&mut self.interrupted)).then_some(self.config.merge_gap_seconds);
+        let transaction = self.connection.transaction()?;
+        
+        if let Some(activity) = sample.presence {
+            let values = [
+                filter(activity.details.as_deref()),
+                filter(activity.state.as_deref()),
+                filter(sample.profile),
+                filter(sample.source),
+            ];
+            let columns = ["details", "state", "profile", "source"];
+            extend_or_insert(&transaction, "presence_sessions", &columns, &values, now, merge_gap)?;
+        }
+        
+        if let Some(app) = sample.app {
+            let window = sample.window.filter(|_| self.config.record_windows);
+            let values = [filter(Some(app)), filter(window), filter(sample.project)];
+            let columns = ["app", "window", "project"];
+            extend_or_insert(&transaction, "app_sessions", &columns, &values, now, merge_gap)?;
+        }
+        
+        transaction.commit()?;
+        
+        if self.last_pruned.is_none_or(|last| (now - last).num_seconds() >= PRUNE_INTERVAL) {
+            self.prune(now)?;
+        }
+        Ok(())
+    }
+    
+    /// Ends the current sessions, e.g. while presence is hidden. Nothing is stored for
+    /// the paused time and the next sample starts new sessions.
+    pub fn pause(&mut self) {
+        self.interrupted = true;
+    }
+    
+    /// Deletes sessions that ended before the retention period.
+    pub fn prune(&mut self, now: DateTime<Utc>) -> rusqlite::Result<usize> {
+        self.last_pruned = Some(now);
+        if self.config.retention_days == 0 {
+            return Ok(0);
+        }
+        
+        let cutoff = (now - Duration::days(self.config.retention_days as i64)).timestamp();
+        let mut deleted = 0;
+        for table in ["presence_sessions", "app_sessions"] {
+            deleted += self.connection.execute(&format!("DELETE FROM {} WHERE ended_at < ?1", table), [cutoff])?;
+        }
+        if deleted > 0 {
+            log::info!("Pruned {} history session(s) older than {} days", deleted, self.config.retention_days);
+        }
+        Ok(deleted)
+    }
+    
+    /// Deletes all recorded sessions.
+    pub fn clear(&mut self) -> rusqlite::Result<()> {
+        self.interrupted = true;
+        self.connection.execute_batch("DELETE FROM presence_sessions; DELETE FROM app_sessions;")
+    }
+    
+    /// Presence sessions overlapping `from..to`, oldest first.
+    pub fn presence_sessions(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> rusqlite::Result<Vec<PresenceSession>> {
+        let mut statement = self.connection.prepare(
+            "SELECT started_at, ended_at, details, state, profile, source FROM presence_sessions
+             WHERE ended_at >= ?1 AND started_at < ?2 ORDER BY started_at, id",
+        )?;
+        let rows = statement.query_map([from.timestamp(), to.timestamp()], |row| {
+            Ok(PresenceSession {
+                started_at: timestamp(row.get(0)?),
+                ended_at: timestamp(row.get(1)?),
+                details: row.get(2)?,
+                state: row.get(3)?,
+                profile: row.get(4)?,
+                source: row.get(5)?,
+            })
+        })?;
+        rows.collect()
+    }
+    
+    /// App sessions overlapping `from..to`, oldest first.
+    pub fn app_sessions(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> rusqlite::Result<Vec<AppSession>> {
+        let mut statement = self.connection.prepare(
+            "SELECT started_at, ended_at, app, window, project FROM app_sessions
+             WHERE ended_at >= ?1 AND started_at < ?2 ORDER BY started_at, id",
+        )?;
+        let rows = statement.query_map([from.timestamp(), to.timestamp()], |row| {
+            Ok(AppSession {
+                started_at: timestamp(row.get(0)?),
+                ended_at: timestamp(row.get(1)?),
+                app: row.get(2)?,
+                window: row.get(3)?,
+                project: row.get(4)?,
+            })
+        })?;
+        rows.collect()
+    }
+}
+
+fn timestamp(seconds: i64) -> DateTime<Utc> {
+    Utc.timestamp_opt(seconds, 0).single().unwrap_or_default()
+}
+
+/// Moves the end of the newest row forward when it has the same `values` and ended at
+/// most `merge_gap` seconds ago; otherwise starts a new row at `now`. A recent row with
+/// other values still runs until `now`, so that consecutive sessions leave no holes.
+fn extend_or_insert(
+    connection: &Connection,
+    table: &str,
+    columns: &[&str],
+    values: &[Option<String>],
+    now: DateTime<Utc>,
+    merge_gap: Option<u64>,
+) -> rusqlite::Result<()> {
+    let now = now.timestamp();
+    
+    if let Some(gap) = merge_gap {
+        // `IS` rather than `=` so that NULL matches NULL
+        let conditions: Vec<String> = columns.iter().enumerate().map(|(i, column)| format!("{} IS ?{}", column, i + 2)).collect();
+        let query = format!(
+            "SELECT id FROM {table} WHERE id = (SELECT MAX(id) FROM {table}) AND ended_at >= ?1 AND {}",
+            conditions.join(" AND ")
+        );
+        let earliest = now - gap as i64;
+        let mut parameters: Vec<&dyn rusqlite::ToSql> = vec![&earliest];
+        parameters.extend(values.iter().map(|value| value as &dyn rusqlite::ToSql));
+        
+        let current: Option<i64> = connection.query_row(&query, parameters.as_slice(), |row| row.get(0)).optional()?;
+        if let Some(id) = current {
+            connection.execute(&format!("UPDATE {} SET ended_at = MAX(ended_at, ?1) WHERE id = ?2", table), params![now, id])?;
+            return Ok(());
+        }
+        
+        connection.execute(
+            &format!(
+                "UPDATE {table} SET ended_at = MAX(ended_at, ?1) WHERE id = (SELECT MAX(id) FROM {table}) AND ended_at >= ?2"
+            ),
+            params![now, earliest],
+        )?;
+    }
+    
+    let placeholders: Vec<String> = (0..columns.len()).map(|i| format!("?{}", i + 2)).collect();
+    let insert = format!(
+        "INSERT INTO {} (started_at, ended_at, {}) VALUES (?1, ?1, {})",
+        table,
+        columns.join(", "),
+        placeholders.join(", ")
+    );
+    let mut parameters: Vec<&dyn rusqlite::ToSql> = vec![&now];
+    parameters.extend(values.iter().map(|value| value as &dyn rusqlite::ToSql));
+    connection.execute(&insert, parameters.as_slice())?;
+    Ok(())
+}
@@ -0,0 +1,675 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use crate::config::{Config, ConfigProblem};
+use crate::engine::PresenceEngine;
+
+/// System-wide shortcuts, grabbed on X11 or requested from the desktop portal on Wayland.
+/// Bindings are written like `Ctrl+Alt+H`; an empty binding is unassigned.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HotkeysConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_toggle_privacy")]
+    pub toggle_privacy: String,
+    #[serde(default = "default_next_profile")]
+    pub next_profile: String,
+    #[serde(default = "default_next_message")]
+    pub next_message: String,
+}
+
+fn default_toggle_privacy() -> String {
+    "Ctrl+Alt+H".to_string()
+}
+
+fn default_next_profile() -> String {
+    "Ctrl+Alt+P".to_string()
+}
+
+fn default_next_message() -> String {
+    "Ctrl+Alt+M".to_string()
+}
+
+impl Default for HotkeysConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            toggle_privacy: default_toggle_privacy(),
+            next_profile: default_next_profile(),
+            next_message: default_next_message(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HotkeyAction {
+    TogglePrivacy,
+    NextProfile,
+    NextMessage,
+}
+
+impl HotkeyAction {
+    pub const ALL: [HotkeyAction; 3] = [HotkeyAction::TogglePrivacy, HotkeyAction::NextProfile, HotkeyAction::NextMessage];
+    
+    /// The config field, also used as the portal's shortcut id.
+    pub fn id(self) -> &'static str {
+        match self {
+            HotkeyAction::TogglePrivacy => "toggle_privacy",
+            HotkeyAction::NextProfile => "next_profile",
+            HotkeyAction::NextMessage => "next_message",
+        }
+    }
+    
+    pub fn from_id(id: &str) -> Option<HotkeyAction> {
+        HotkeyAction::ALL.into_iter().find(|action| action.id() == id)
+    }
+    
+    pub fn description(self) -> &'static str {
+        match self {
+            HotkeyAction::TogglePrivacy => "Hide or resume presence",
+            HotkeyAction::NextProfile => "Switch to the next profile",
+            HotkeyAction::NextMessage => "Show the next custom message",
+        }
+    }
+}
+
+impl HotkeysConfig {
+    pub fn binding(&self, action: HotkeyAction) -> &str {
+        match action {
+            HotkeyAction::TogglePrivacy => &self.toggle_privacy,
+            HotkeyAction::NextProfile => &self.next_profile,
+            HotkeyAction::NextMessage => &self.next_message,
+        }
+    }
+    
+    pub fn binding_mut(&mut self, action: HotkeyAction) -> &mut String {
+        match action {
+            HotkeyAction::TogglePrivacy => &mut self.toggle_privacy,
+            HotkeyAction::NextProfile => &mut self.next_profile,
+            HotkeyAction::NextMessage => &mut self.next_message,
+        }
+    }
+    
+    fn parsed(&self) -> Vec<(HotkeyAction, Hotkey)> {
+        HotkeyAction::ALL
+            .into_iter()
+            .filter_map(|action| self.binding(action).parse().ok().map(|hotkey| (action, hotkey)))
+            .collect()
+    }
+    
+    /// The bindings to register: those that parse, in action order, leaving out any that
+    /// repeat an earlier action's binding.
+    pub fn hotkeys(&self) -> Vec<(HotkeyAction, Hotkey)> {
+        let conflicts = self.conflicts();
+        self.parsed().into_iter().filter(|(action, _)| !conflicts.iter().any(|(_, later)| later == action)).collect()
+    }
+    
+    /// Pairs of actions bound to the same keys, the earlier action first.
+    pub fn conflicts(&self) -> Vec<(HotkeyAction, HotkeyAction)> {
+        let hotkeys = self.parsed();
+        let mut conflicts = Vec::new();
+        for (i, (action, hotkey)) in hotkeys.iter().enumerate() {
+            if let Some((first, _)) = hotkeys[..i].iter().find(|(_, other)| other == hotkey) {
+                conflicts.push((*first, *action));
+            }
+        }
+        conflicts
+    }
+    
+    /// Unparseable and duplicate bindings, for `Config::validate`.
+    pub fn problems(&self) -> Vec<ConfigProblem> {
+        let mut problems = Vec::new();
+        for action in HotkeyAction::ALL {
+            let binding = self.binding(action);
+            if binding.is_empty() {
+                continue;
+            }
+            if let Err(e) = binding.parse::<Hotkey>() {
+                let path = format!("hotkeys.{}", action.id());
+                problems.push(ConfigProblem::at(path.clone(), format!("{} {:?}: {}", path, binding, e)));
+            }
+        }
+        for (first, second) in self.conflicts() {
+            let message =
+                format!("hotkeys.{} and hotkeys.{} are both bound to {}", first.id(), second.id(), self.binding(second));
+            problems.push(ConfigProblem::at(format!("hotkeys.{}", second.id()), message));
+        }
+        problems
+    }
+}
+
+/// Keys that can be bound besides letters, digits and F1 to F24, with their X keysyms.
+/// Escape and Backspace are left out since the settings panel uses them.
+const NAMED_KEYS: &[(&str, u32)] = &[
+    ("Space", 0x0020),
+    ("Tab", 0xff09),
+    ("Home", 0xff50),
+    ("Left", 0xff51),
+    ("Up", 0xff52),
+    ("Right", 0xff53),
+    ("Down", 0xff54),
+    ("PageUp", 0xff55),
+    ("PageDown", 0xff56),
+    ("End", 0xff57),
+    ("Insert", 0xff63),
+    ("Delete", 0xffff),
+];
+
+const XK_F1: u32 = 0xffbe;
+
+/// A key with modifiers, such as `Ctrl+Alt+H`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Hotkey {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    pub super_key: bool,
+    /// Canonical name: `A`, `7`, `F5`, `PageUp`, ...
+    pub key: String,
+}
+
+impl Hotkey {
+    fn function_key(&self) -> Option<u32> {
+        self.key.strip_prefix('F').and_then(|number| number.parse().ok()).filter(|number| (1..=24).contains(number))
+    }
+    
+    /// The X keysym of the key, without modifiers.
+    pub fn keysym(&self) -> u32 {
+        if let Some(number) = self.function_key() {
+            return XK_F1 + number - 1;
+        }
+        match NAMED_KEYS.iter().find(|(name, _)| *name == self.key) {
+            Some((_, keysym)) => *keysym,
+            // Letters and digits are their lowercase ASCII code
+            None => self.key.to_ascii_lowercase().chars().next().map_or(0, |c| c as u32),
+        }
+    }
+    
+    /// The X modifier mask: Shift, Control, Mod1 for Alt and Mod4 for Super.
+    pub fn x11_modifiers(&self) -> u16 {
+        (self.shift as u16) | (self.ctrl as u16) << 2 | (self.alt as u16) << 3 | (self.super_key as u16) << 6
+    }
+    
+    /// The trigger in the XDG shortcuts format the portal expects, e.g. `CTRL+ALT+h`.
+    pub fn portal_trigger(&self) -> String {
+        let mut parts = Vec::new();
+        for (held, name) in [(self.ctrl, "CTRL"), (self.alt, "ALT"), (self.shift, "SHIFT"), (self.super_key, "LOGO")] {
+            if held {
+                parts.push(name.to_string());
+            }
+        }
+        let key = match self.key.as_str() {
+            "Space" => "space".to_string(),
+            "PageUp" => "Page_Up".to_string(),
+            "PageDown" => "Page_Down".to_string(),
+            key if key.len() == 1 => key.to_ascii_lowercase(),
+            key => key.to_string(),
+        };
+        parts.push(key);
+        parts.join("+")
+    }
+}
+
+impl FromStr for Hotkey {
+    type Err = String;
+    
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut hotkey = Hotkey {
+            ctrl: false,
+            alt: false,
+            shift: false,
+            super_key: false,
+            key: String::new(),
+        };
+        
+        let parts: Vec<&str> = text.split('+').map(str::trim).collect();
+        let (key, modifiers) = match parts.split_last() {
+            Some((key, modifiers)) if !key.is_empty() => (*key, modifiers),
+            _ => return Err("no key given".to_string()),
+        };
+        for modifier in modifiers {
+            let held = match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => &mut hotkey.ctrl,
+                "alt" => &mut hotkey.alt,
+                "shift" => &mut hotkey.shift,
+                "super" | "win" | "meta" | "logo" => &mut hotkey.super_key,
+                _ => return Err(format!("unknown modifier {:?}", modifier)),
+            };
+            *held = true;
+        }
+        
+        hotkey.key = if key.len() == 1 && key.chars().all(|c| c.is_ascii_alphanumeric()) {
+            key.to_ascii_uppercase()
+        } else if let Some((name, _)) = NAMED_KEYS.iter().find(|(name, _)| name.eq_ignore_ascii_case(key)) {
+            name.to_string()
+        } else {
+            key.to_ascii_uppercase()
+        };
+        if hotkey.key.len() > 1 && !NAMED_KEYS.iter().any(|(name, _)| *name == hotkey.key) && hotkey.function_key().is_none() {
+            return Err(format!("unknown key {:?}", key));
+        }
+        
+        // A bare letter would be taken from every application
+        if !(hotkey.ctrl || hotkey.alt || hotkey.super_key) && hotkey.function_key().is_none() {
+            return Err("needs Ctrl, Alt or Super unless the key is F1 to F24".to_string());
+        }
+        Ok(hotkey)
+    }
+}
+
+impl fmt::Display for Hotkey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (held, name) in [(self.ctrl, "Ctrl"), (self.alt, "Alt"), (self.shift, "Shift"), (self.super_key, "Super")] {
+            if held {
+                write!(f, "{}+", name)?;
+            }
+        }
+        write!(f, "{}", self.key)
+    }
+}
+
+/// Whether a binding is live, as reported by the grab or the portal.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BindingState {
+    /// Waiting for the desktop, which may ask the user first.
+    Pending,
+    /// Live, with the trigger as the desktop describes it.
+    Active(String),
+    /// Another application holds the keys, or the desktop did not assign them.
+    Conflict(String),
+}
+
+pub type BindingStates = Arc<Mutex<BTreeMap<HotkeyAction, BindingState>>>;
+
+/// The profile after the active one, wrapping from the last named profile back to the base profile.
+pub fn next_profile(config: &Config) -> Option<String> {
+    let position = match config.active_profile {
+        Some(ref active) => config.profiles.iter().position(|profile| profile.name == *active),
+        None => None,
+    };
+    let next = match position {
+        Some(i) => i + 1,
+        None if config.active_profile.is_some() => config.profiles.len(),
+        None => 0,
+    };
+    config.profiles.get(next).map(|profile| profile.name.clone())
+}
+
+/// The custom message after `current`, wrapping back to no message after the last one.
+pub fn next_message(messages: &[String], current: &str) -> String {
+    let messages: Vec<&String> = messages.iter().filter(|message| !message.is_empty()).collect();
+    let next = match messages.iter().position(|message| *message == current) {
+        Some(i) => i + 1,
+        None if current.is_empty() => 0,
+        None => messages.len(),
+    };
+    messages.get(next).map(|message| message.to_string()).unwrap_or_default()
+}
+
+/// Carries out the action bound to a hotkey.
+pub fn apply(action: HotkeyAction, engine: &Arc<Mutex<PresenceEngine>>) {
+    let mut engine = match engine.lock() {
+        Ok(engine) => engine,
+        Err(_) => return,
+    };
+    log::debug!("Hotkey: {}", action.description());
+    match action {
+        HotkeyAction::TogglePrivacy if engine.privacy.hidden => engine.resume_presence(),
+        HotkeyAction::TogglePrivacy => engine.hide_presence(None),
+        HotkeyAction::NextProfile => {
+            let next = next_profile(&engine.config);
+            if let Err(e) = engine.switch_profile(next) {
+                log::error!("Failed to switch profile from a hotkey: {}", e);
+            }
+        }
+        HotkeyAction::NextMessage => {
+            let next = next_message(&engine.config.custom_messages, &engine.custom_message);
+            engine.set_custom_message(&next);
+        }
+    }
+}
+
+fn pending(config: &HotkeysConfig) -> BindingStates {
+    let states = config.hotkeys().into_iter().map(|(action, _)| (action, BindingState::Pending)).collect();
+    Arc::new(Mutex::new(states))
+}
+
+fn set_state(states: &BindingStates, action: HotkeyAction, state: BindingState) {
+    if let Ok(mut states) = states.lock() {
+        states.insert(action, state);
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub use linux::Hotkeys;
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use x11rb::connection::Connection as _;
+    use x11rb::errors::ReplyError;
+    use x11rb::protocol::xproto::{ConnectionExt as _, GrabMode, ModMask};
+    use x11rb::protocol::{ErrorKind, Event};
+    use zbus::blocking::{Connection, MessageIterator, Proxy};
+    use zbus::message::Type;
+    use zbus::zvariant::{ObjectPath, OwnedValue, Value};
+    use zbus::MatchRule;
+    
+    use super::{apply, pending, set_state, BindingState, BindingStates, Hotkey, HotkeyAction, HotkeysConfig};
+    use crate::engine::PresenceEngine;
+    
+    const PORTAL: &str = "org.freedesktop.portal.Desktop";
+    const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
+    const SHORTCUTS_INTERFACE: &str = "org.freedesktop.portal.GlobalShortcuts";
+    
+    /// How often the X11 thread checks for key presses and whether to stop.
+    const POLL_INTERVAL: Duration = Duration::from_millis(50);
+    
+    /// Lock and NumLock, which must not change whether a hotkey matches.
+    const IGNORED_MODIFIERS: [u16; 4] = [0, 1 << 1, 1 << 4, 1 << 1 | 1 << 4];
+    const HOTKEY_MODIFIERS: u16 = 1 | 1 << 2 | 1 << 3 | 1 << 6;
+    
+    /// Live global hotkeys; dropping this releases them.
+    pub struct Hotkeys {
+        backend: &'static str,
+        states: BindingStates,
+        stop: Arc<AtomicBool>,
+        /// Closed on drop to end the portal thread, which waits for signals.
+        portal: Option<Connection>,
+    }
+    
+    impl Hotkeys {
+        /// Uses the GlobalShortcuts portal under Wayland, where X11 grabs only see X11 windows,
+        /// and key grabs on the X display otherwise.
+        pub fn start(config: &HotkeysConfig, engine: &Arc<Mutex<PresenceEngine>>) -> Result<Hotkeys, Box<dyn std::error::Error>> {
+            if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+                match Connection::session() {
+                    Ok(connection) => return Ok(Self::start_portal(connection, config, engine)),
+                    Err(e) => log::warn!("No session bus for the shortcuts portal: {}", e),
+                }
+            }
+            if std::env::var_os("DISPLAY").is_some() {
+                return Self::start_x11(None, config, engine);
+            }
+            Err("global hotkeys need an X11 display or the Wayland shortcuts portal".into())
+        }
+        
+        /// Binds through the portal on the bus at `address`, e.g. a private `dbus-daemon`.
+        pub fn start_portal_at(address: &str, config: &HotkeysConfig, engine: &Arc<Mutex<PresenceEngine>>) -> Result<Hotkeys, Box<dyn std::error::Error>> {
+            let connection = zbus::blocking::connection::Builder::address(address)?.build()?;
+            Ok(Self::start_portal(connection, config, engine))
+        }
+        
+        /// Grabs the keys on the X display `display`, or `$DISPLAY` when `None`.
+        pub fn start_x11(display: Option<&str>, config: &HotkeysConfig, engine: &Arc<Mutex<PresenceEngine>>) -> Result<Hotkeys, Box<dyn std::error::Error>> {
+            let (connection, screen) = x11rb::connect(display)?;
+            let root = connection.setup().roots[screen].root;
+            let hotkeys = Hotkeys {
+                backend: "X11",
+                states: pending(config),
+                stop: Arc::new(AtomicBool::new(false)),
+                portal: None,
+            };
+            
+            let grabbed = grab(&connection, root, config, &hotkeys.states)?;
+            let (stop, engine) = (Arc::clone(&hotkeys.stop), Arc::clone(engine));
+            std::thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    match connection.poll_for_event() {
+                        Ok(Some(Event::KeyPress(event))) => {
+                            let modifiers = u16::from(event.state) & HOTKEY_MODIFIERS;
+                            let bound = grabbed.iter().find(|(_, keycode, mask)| *keycode == event.detail && *mask == modifiers);
+                            if let Some((action, _, _)) = bound {
+                                apply(*action, &engine);
+                            }
+                        }
+                        Ok(Some(_)) => {}
+                        Ok(None) => std::thread::sleep(POLL_INTERVAL),
+                        Err(e) => {
+                            log::error!("Lost the X connection for hotkeys: {}", e);
+                            return;
+                        }
+                    }
+                }
+                for (_, keycode, mask) in grabbed {
+                    for ignored in IGNORED_MODIFIERS {
+                        let _ = connection.ungrab_key(keycode, root, ModMask::from(mask | ignored));
+                    }
+                }
+                let _ = connection.flush();
+            });
+            
+            Ok(hotkeys)
+        }
+        
+        fn start_portal(connection: Connection, config: &HotkeysConfig, engine: &Arc<Mutex<PresenceEngine>>) -> Hotkeys {
+            let hotkeys = Hotkeys {
+                backend: "portal",
+                states: pending(config),
+                stop: Arc::new(AtomicBool::new(false)),
+                portal: Some(connection.clone()),
+            };
+            
+            let (states, stop, engine, config) = (Arc::clone(&hotkeys.states), Arc::clone(&hotkeys.stop), Arc::clone(engine), config.clone());
+            std::thread::spawn(move || {
+                if let Err(e) = run_portal(&connection, &config, &states, &stop, &engine) {
+                    if !stop.load(Ordering::Relaxed) {
+                        log::error!("Global shortcuts portal failed: {}", e);
+                        if let Ok(mut states) = states.lock() {
+                            for state in states.values_mut() {
+                                *state = BindingState::Conflict(format!("portal error: {}", e));
+                            }
+                        }
+                    }
+                }
+            });
+            
+            hotkeys
+        }
+        
+        /// "X11" or "portal".
+        pub fn backend(&self) -> &'static str {
+            self.backend
+        }
+        
+        pub fn states(&self) -> std::collections::BTreeMap<HotkeyAction, BindingState> {
+            self.states.lock().map(|states| states.clone()).unwrap_or_default()
+        }
+    }
+    
+    impl Drop for Hotkeys {
+        fn drop(&mut self) {
+            self.stop.store(true, Ordering::Relaxed);
+            if let Some(connection) = self.portal.take() {
+                let _ = connection.close();
+            }
+        }
+    }
+    
+    /// A live X11 grab: the action, keycode and modifier mask.
+    type Grab = (HotkeyAction, u8, u16);
+    
+    /// Grabs every binding on `root`, under each combination of Lock and NumLock, and
+    /// returns the live ones.
+    fn grab(
+        connection: &impl x11rb::connection::Connection,
+        root: u32,
+        config: &HotkeysConfig,
+        states: &BindingStates,
+    ) -> Result<Vec<Grab>, Box<dyn std::error::Error>> {
+        let setup = connection.setup();
+        let (min, max) = (setup.min_keycode, setup.max_keycode);
+        let mapping = connection.get_keyboard_mapping(min, max - min + 1)?.reply()?;
+        let per_keycode = (mapping.keysyms_per_keycode as usize).max(1);
+        let keycode = |hotkey: &Hotkey| {
+            let keysym = hotkey.keysym();
+            mapping.keysyms.chunks(per_keycode).position(|keysyms| keysyms.contains(&keysym)).map(|i| min + i as u8)
+        };
+        
+        let mut grabbed = Vec::new();
+        for (action, hotkey) in config.hotkeys() {
+            let keycode = match keycode(&hotkey) {
+                Some(keycode) => keycode,
+                None => {
+                    set_state(states, action, BindingState::Conflict(format!("{} is not on this keyboard", hotkey.key)));
+                    continue;
+                }
+            };
+            
+            let mask = hotkey.x11_modifiers();
+            let mut result = Ok(());
+            for ignored in IGNORED_MODIFIERS {
+                result = connection
+                    .grab_key(true, root, ModMask::from(mask | ignored), keycode, GrabMode::ASYNC, GrabMode::ASYNC)?
+                    .check();
+                if result.is_err() {
+                    break;
+                }
+            }
+            match result {
+                Ok(()) => {
+                    set_state(states, action, BindingState::Active(hotkey.to_string()));
+                    grabbed.push((action, keycode, mask));
+                }
+                Err(ReplyError::X11Error(ref error)) if error.error_kind == ErrorKind::Access => {
+                    for ignored in IGNORED_MODIFIERS {
+                        let _ = connection.ungrab_key(keycode, root, ModMask::from(mask | ignored));
+                    }
+                    set_state(states, action, BindingState::Conflict(format!("{} is taken by another application", hotkey)));
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        connection.flush()?;
+        Ok(grabbed)
+    }
+    
+    /// Calls a portal method that answers through a Request object, and waits for its results.
+    fn request<B>(connection: &Connection, method: &str, body: &B, token: &str) -> Result<HashMap<String, OwnedValue>, Box<dyn std::error::Error>>
+    where
+        B: serde::Serialize + zbus::zvariant::DynamicType,
+    {
+        // The request path is predictable, so the response cannot arrive before we listen
+        let sender = connection.unique_name().ok_or("not connected")?.trim_start_matches(':').replace('.', "_");
+        let path = format!("{}/request/{}/{}", PORTAL_PATH, sender, token);
+        let rule = MatchRule::builder()
+            .msg_type(Type::Signal)
+            .interface("org.freedesktop.portal.Request")?
+            .member("Response")?
+            .path(path)?
+            .build();
+        let mut responses = MessageIterator::for_match_rule(rule, connection, Some(1))?;
+        
+        Proxy::new(connection, PORTAL, PORTAL_PATH, SHORTCUTS_INTERFACE)?.call_method(method, body)?;
+        let message = responses.next().ok_or("the portal went away")??;
+        let (response, results): (u32, HashMap<String, OwnedValue>) = message.body().deserialize()?;
+        match response {
+            0 => Ok(results),
+            1 => Err(format!("{} was cancelled", method).into()),
+            _ => Err(format!("{} failed", method).into()),
+        }
+    }
+    
+    fn run_portal(
+        connection: &Connection,
+        config: &HotkeysConfig,
+        states: &BindingStates,
+        stop: &AtomicBool,
+        engine: &Arc<Mutex<PresenceEngine>>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let token = format!("mrp_{}", std::process::id());
+        let options = HashMap::from([
+            ("handle_token", Value::from(format!("{}_session", token))),
+            ("session_handle_token", Value::from(token.clone())),
+        ]);
+        let results = request(connection, "CreateSession", &(options,), &format!("{}_session", token))?;
+        let session = match results.get("session_handle").map(|handle| &**handle) {
+            Some(Value::Str(handle)) => handle.to_string(),
+            Some(Value::ObjectPath(handle)) => handle.to_string(),
+            _ => return Err("the portal returned no session".into()),
+        };
+        let session = ObjectPath::try_from(session)?;
+        
+        let hotkeys = config.hotkeys();
+        let shortcuts: Vec<(&str, HashMap<&str, Value>)> = hotkeys
+            .iter()
+            .map(|(action, hotkey)| {
+                let properties = HashMap::from([
+                    ("description", Value::from(action.description())),
+                    ("preferred_trigger", Value::from(hotkey.portal_trigger())),
+                ]);
+                (action.id(), properties)
+            })
+            .collect();
+        let options = HashMap::from([("handle_token", Value::from(format!("{}_bind", token)))]);
+        let results = request(connection, "BindShortcuts", &(&session, shortcuts, "", options), &format!("{}_bind", token))?;
+        
+        // Shortcuts the desktop left out were not assigned, e.g. because the keys are taken
+        let bound: Vec<(String, HashMap<String, OwnedValue>)> = match results.get("shortcuts") {
+            Some(shortcuts) => shortcuts.try_clone()?.try_into()?,
+            None => Vec::new(),
+        };
+        for (action, hotkey) in &hotkeys {
+            let state = match bound.iter().find(|(id, _)| id == action.id()) {
+                Some((_, properties)) => {
+                    let trigger = properties
+                        .get("trigger_description")
+                        .and_then(|trigger| String::try_from(trigger.try_clone().ok()?).ok())
+                        .filter(|trigger| !trigger.is_empty())
+                        .unwrap_or_else(|| hotkey.to_string());
+                    BindingState::Active(trigger)
+                }
+                None => BindingState::Conflict(format!("the desktop did not assign {}", hotkey)),
+            };
+            set_state(states, *action, state);
+        }
+        
+        let rule = MatchRule::builder()
+            .msg_type(Type::Signal)
+            .interface(SHORTCUTS_INTERFACE)?
+            .member("Activated")?
+            .path(PORTAL_PATH)?
+            .build();
+        for message in MessageIterator::for_match_rule(rule, connection, None)? {
+            if stop.load(Ordering::Relaxed) {
+                break;
+            }
+            let message = message?;
+            let body = message.body();
+            let (activated_session, id, _, _): (ObjectPath, String, u64, HashMap<String, OwnedValue>) = body.deserialize()?;
+            if activated_session != session {
+                continue;
+            }
+            if let Some(action) = HotkeyAction::from_id(&id) {
+                apply(action, engine);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Stands in for global hotkeys where neither X11 nor the portal is available.
+#[cfg(not(target_os = "linux"))]
+pub struct Hotkeys;
+
+#[cfg(not(target_os = "linux"))]
+impl Hotkeys {
+    pub fn start(_config: &HotkeysConfig, _engine: &Arc<Mutex<PresenceEngine>>) -> Result<Hotkeys, Box<dyn std::error::Error>> {
+        Err("global hotkeys are only available on Linux".into())
+    }
+    
+    pub fn backend(&self) -> &'static str {
+        "none"
+    }
+    
+    pub fn states(&self) -> BTreeMap<HotkeyAction, BindingState> {
+        BTreeMap::new()
+    }
+}
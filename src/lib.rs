@@ -0,0 +1,23 @@
+pub mod activity;
+pub mod api;
+pub mod config;
+#[cfg(unix)]
+pub mod control;
+pub mod engine;
+pub mod history;
+pub mod hotkeys;
+pub mod metrics;
+pub mod monitor;
+pub mod presence;
+pub mod privacy;
+pub mod profile;
+pub mod provider;
+pub mod report;
+pub mod rules;
+pub mod samples;
+pub mod schedule;
+pub mod script;
+pub mod sink;
+pub mod template;
+pub mod tray;
+pub mod watch;
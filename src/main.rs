@@ -1,373 +1,494 @@
+mod widgets;
 
-use chrono::{DateTime, Local};
-use discord_rich_presence::{activity, DiscordIpc, DiscordIpcClient};
+use chrono::{Timelike, Utc};
+use clap::Parser;
 use eframe::egui;
-use regex::Regex;
-use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
-use sysinfo::System;
 
-const APP_ID: &str = "1419145226261495808"; // Replace with your Discord app ID
+use multi_rich_presence::api::{self, ApiServer};
+use multi_rich_presence::config::{ApiConfig, Config, MAX_GRAPH_MINUTES};
+#[cfg(unix)]
+use multi_rich_presence::control::{self, ControlServer};
+use multi_rich_presence::engine::PresenceEngine;
+use multi_rich_presence::hotkeys::{HotkeyAction, Hotkeys, HotkeysConfig};
+use multi_rich_presence::profile::{PresenceSettings, Profile};
+use multi_rich_presence::report::Report;
+use multi_rich_presence::schedule::{ScheduleAction, ScheduleBlock, WEEKDAYS};
+use multi_rich_presence::template;
+use multi_rich_presence::tray::{Tray, WindowAction};
+use multi_rich_presence::watch::ConfigWatcher;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Config {
-    pub blacklisted_words: Vec<String>,
-    pub show_system_stats: bool,
-    pub show_time: bool,
-    pub show_applications: bool,
-    pub custom_messages: Vec<String>,
-    pub update_interval_seconds: u64,
-    pub discord_app_id: String,
-    pub activity_filters: ActivityFilters,
-}
+/// How often an open report is rebuilt from the history.
+const REPORT_REFRESH: Duration = Duration::from_secs(60);
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ActivityFilters {
-    pub hide_system_processes: bool,
-    pub hide_background_apps: bool,
-    pub minimum_cpu_usage: f32,
-    pub blacklisted_processes: Vec<String>,
-}
+/// How often the daemon brings the HTTP API and hotkeys in line with the config.
+#[cfg(unix)]
+const DAEMON_SYNC_INTERVAL: Duration = Duration::from_secs(1);
 
-impl Default for Config {
-    fn default() -> Self {
-        Self {
-            blacklisted_words: vec![
-                "password".to_string(),
-                "secret".to_string(),
-                "private".to_string(),
-            ],
-            show_system_stats: true,
-            show_time: true,
-            show_applications: true,
-            custom_messages: vec!["Working on something cool".to_string()],
-            update_interval_seconds: 15,
-            discord_app_id: APP_ID.to_string(),
-            activity_filters: ActivityFilters::default(),
-        }
-    }
-}
+/// Global hotkeys and the config they were started from, or why they failed to start.
+type HotkeysSlot = Option<(HotkeysConfig, Result<Hotkeys, String>)>;
 
-impl Default for ActivityFilters {
-    fn default() -> Self {
-        Self {
-            hide_system_processes: true,
-            hide_background_apps: true,
-            minimum_cpu_usage: 0.1,
-            blacklisted_processes: vec![
-                "dwm.exe".to_string(),
-                "winlogon.exe".to_string(),
-                "csrss.exe".to_string(),
-            ],
-        }
-    }
-}
+/// The HTTP API with the port it was started on and whether a token was set, or why it
+/// failed to start.
+type ApiSlot = Option<((u16, bool), Result<ApiServer, String>)>;
 
-#[derive(Debug, Clone)]
-pub struct SystemStats {
-    pub cpu_usage: f32,
-    pub memory_usage: f64,
-    pub memory_total: u64,
-    pub memory_used: u64,
-    pub uptime: u64,
-    pub process_count: usize,
-}
-
-#[derive(Debug, Clone)]
-pub struct ProcessInfo {
-    pub name: String,
-    pub pid: u32,
-    pub cpu_usage: f32,
-    pub memory_usage: u64,
-    pub start_time: u64,
-}
-
-#[derive(Debug, Clone)]
-pub struct ActivityData {
-    pub system_stats: SystemStats,
-    pub current_time: DateTime<Local>,
-    pub top_processes: Vec<ProcessInfo>,
-    pub active_window: Option<String>,
+#[derive(Clone, Copy, PartialEq)]
+enum Tab {
+    Presence,
+    Processes,
+    Reports,
 }
 
 pub struct DiscordRpcApp {
-    config: Config,
-    activity_data: Arc<Mutex<Option<ActivityData>>>,
-    discord_client: Option<DiscordIpcClient>,
-    system: System,
-    last_update: Instant,
-    word_filter: Regex,
+    engine: Arc<Mutex<PresenceEngine>>,
+    #[cfg(unix)]
+    _control_server: Option<ControlServer>,
+    api_server: ApiSlot,
+    _config_watcher: Option<ConfigWatcher>,
+    tray: Option<Tray>,
+    ctx: egui::Context,
+    /// Set by Quit in the tray, so that the close is not turned into hiding the window.
+    quitting: Arc<AtomicBool>,
+    hotkeys: HotkeysSlot,
     
     // UI state
-    custom_message_input: String,
     show_config: bool,
     new_blacklisted_word: String,
-    new_custom_message: String,
-    connection_status: String,
-    
+    new_profile_name: String,
+    hide_duration_minutes: i64,
+    tab: Tab,
+    process_browser: widgets::ProcessBrowser,
+    recording_hotkey: Option<HotkeyAction>,
+    report_days: i64,
+    /// Built at the instant for `report_days`.
+    report: Option<(Instant, i64, Report)>,
+    /// Why the last save from the app was refused, until a save succeeds.
+    save_error: Option<String>,
 }
 
 impl DiscordRpcApp {
-    pub fn new(_cc: &eframe::CreationContext<'_>) -> Self {
-        let config = Self::load_config().unwrap_or_default();
-        let word_filter = Self::create_word_filter(&config.blacklisted_words);
+    pub fn new(cc: &eframe::CreationContext<'_>, profile: Option<String>) -> Self {
+        let engine = Arc::new(Mutex::new(PresenceEngine::load(profile)));
+        PresenceEngine::spawn_ticker(&engine);
         
-        let mut app = Self {
-            config,
-            activity_data: Arc::new(Mutex::new(None)),
-            discord_client: None,
-            system: System::new_all(),
-            last_update: Instant::now(),
-            word_filter,
-            custom_message_input: String::new(),
-            show_config: false,
-            new_blacklisted_word: String::new(),
-            new_custom_message: String::new(),
-            connection_status: "Disconnected".to_string(),
+        #[cfg(unix)]
+        let control_server = match ControlServer::start(&control::socket_path(), Arc::clone(&engine)) {
+            Ok(server) => Some(server),
+            Err(e) => {
+                log::error!("Failed to start control socket: {}", e);
+                None
+            }
         };
         
-        app.connect_discord();
-        app.start_system_monitoring();
-        app
-    }
-    
-    fn load_config() -> Result<Config, Box<dyn std::error::Error>> {
-        let dirs = directories::ProjectDirs::from("com", "multirichpresence", "MultiRichPresence")
-            .ok_or("Could not find project directory")?;
+        let config_watcher = start_config_watcher(&engine);
         
-        let config_path = dirs.config_dir().join("config.json");
-        
-        if config_path.exists() {
-            let content = std::fs::read_to_string(config_path)?;
-            Ok(serde_json::from_str(&content)?)
-        } else {
-            Ok(Config::default())
+        Self {
+            engine,
+            #[cfg(unix)]
+            _control_server: control_server,
+            api_server: None,
+            _config_watcher: config_watcher,
+            tray: None,
+            ctx: cc.egui_ctx.clone(),
+            quitting: Arc::new(AtomicBool::new(false)),
+            hotkeys: None,
+            recording_hotkey: None,
+            show_config: false,
+            new_blacklisted_word: String::new(),
+            new_profile_name: String::new(),
+            hide_duration_minutes: 0,
+            tab: Tab::Presence,
+            process_browser: widgets::ProcessBrowser::default(),
+            report_days: 7,
+            report: None,
+            save_error: None,
         }
     }
     
-    fn save_config(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let dirs = directories::ProjectDirs::from("com", "multirichpresence", "MultiRichPresence")
-            .ok_or("Could not find project directory")?;
-        
-        std::fs::create_dir_all(dirs.config_dir())?;
-        let config_path = dirs.config_dir().join("config.json");
-        
-        let content = serde_json::to_string_pretty(&self.config)?;
-        std::fs::write(config_path, content)?;
-        Ok(())
-    }
-    
-    fn create_word_filter(blacklisted_words: &[String]) -> Regex {
-        if blacklisted_words.is_empty() {
-            return Regex::new(r"(?i)^$").unwrap(); // Match nothing
+    /// Starts or stops the tray icon to match `enabled`. Called without the engine locked,
+    /// since starting the tray reads it.
+    fn sync_tray(&mut self, enabled: bool) {
+        if !enabled {
+            self.tray = None;
+            return;
+        }
+        if self.tray.is_some() {
+            return;
         }
         
-        let pattern = format!(
-            r"(?i)\b({})\b",
-            blacklisted_words.join("|")
-        );
-        
-        Regex::new(&pattern).unwrap_or_else(|_| Regex::new(r"(?i)^$").unwrap())
-    }
-    
-    fn connect_discord(&mut self) {
-        match DiscordIpcClient::new(&self.config.discord_app_id) {
-            Ok(mut client) => {
-                match client.connect() {
-                    Ok(_) => {
-                        self.discord_client = Some(client);
-                        self.connection_status = "Connected".to_string();
-                        log::info!("Connected to Discord RPC");
-                    }
-                    Err(e) => {
-                        self.connection_status = format!("Connection failed: {}", e);
-                        log::error!("Failed to connect to Discord: {}", e);
-                    }
+        let ctx = self.ctx.clone();
+        let quitting = Arc::clone(&self.quitting);
+        let on_window = Box::new(move |action: WindowAction| {
+            match action {
+                WindowAction::Open => {
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Visible(true));
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(false));
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+                }
+                WindowAction::Quit => {
+                    quitting.store(true, Ordering::Relaxed);
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Close);
                 }
             }
+            ctx.request_repaint();
+        });
+        match Tray::start(&self.engine, on_window) {
+            Ok(tray) => self.tray = Some(tray),
             Err(e) => {
-                self.connection_status = format!("Client creation failed: {}", e);
-                log::error!("Failed to create Discord client: {}", e);
+                log::error!("Failed to start the tray icon: {}", e);
+                // Not retried until the setting is toggled
+                if let Ok(mut engine) = self.engine.lock() {
+                    engine.config.tray.enabled = false;
+                }
             }
         }
     }
     
-    fn start_system_monitoring(&mut self) {
-        let activity_data = Arc::clone(&self.activity_data);
-        let config = self.config.clone();
+    /// Registers the hotkeys again whenever their config changes, including while one is
+    /// being rebound.
+    fn reports_ui(&mut self, ui: &mut egui::Ui, engine: &PresenceEngine) {
+        let history = match engine.history() {
+            Some(history) => history,
+            None => {
+                ui.label("Reports are built from the activity history. Enable it under Settings → History.");
+                return;
+            }
+        };
         
-        std::thread::spawn(move || {
-            let mut system = System::new_all();
-            
-            loop {
-                std::thread::sleep(Duration::from_secs(config.update_interval_seconds));
-                
-                system.refresh_all();
-                
-                let stats = SystemStats {
-                    cpu_usage: system.global_cpu_info().cpu_usage(),
-                    memory_usage: (system.used_memory() as f64 / system.total_memory() as f64) * 100.0,
-                    memory_total: system.total_memory(),
-                    memory_used: system.used_memory(),
-                    uptime: System::uptime(),
-                    process_count: system.processes().len(),
-                };
-                
-                let mut processes: Vec<ProcessInfo> = Vec::new();
-                
-                for (pid, process) in system.processes() {
-                    if config.activity_filters.hide_system_processes {
-                        if config.activity_filters.blacklisted_processes.contains(&process.name().to_string()) {
-                            continue;
-                        }
-                    }
-                    
-                    if process.cpu_usage() >= config.activity_filters.minimum_cpu_usage {
-                        processes.push(ProcessInfo {
-                            name: process.name().to_string(),
-                            pid: pid.as_u32(),
-                            cpu_usage: process.cpu_usage(),
-                            memory_usage: process.memory(),
-                            start_time: process.start_time(),
-                        });
+        let label = |days: i64| match days {
+            1 => "Today".to_string(),
+            days => format!("Last {} days", days),
+        };
+        let mut refresh = false;
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_source("report_days")
+                .selected_text(label(self.report_days))
+                .show_ui(ui, |ui| {
+                    for days in [1, 7, 30] {
+                        ui.selectable_value(&mut self.report_days, days, label(days));
                     }
-                }
-                
-                processes.sort_by(|a, b| b.cpu_usage.partial_cmp(&a.cpu_usage).unwrap_or(std::cmp::Ordering::Equal));
-                processes.truncate(5); // Keep top 5 processes
-                
-                let activity = ActivityData {
-                    system_stats: stats,
-                    current_time: Local::now(),
-                    top_processes: processes,
-                    active_window: Self::get_active_window_title(),
-                };
-                
-                if let Ok(mut data) = activity_data.lock() {
-                    *data = Some(activity);
+                });
+            refresh = ui.button("Refresh").clicked();
+            ui.small("Export with `mrp report --format csv` or `--format json`.");
+        });
+        
+        let stale = match self.report {
+            Some((built, days, _)) => days != self.report_days || built.elapsed() >= REPORT_REFRESH,
+            None => true,
+        };
+        if refresh || stale {
+            let to = chrono::Local::now().date_naive();
+            let from = to - chrono::Duration::days(self.report_days - 1);
+            match Report::from_history(history, from, to) {
+                Ok(report) => self.report = Some((Instant::now(), self.report_days, report)),
+                Err(e) => {
+                    log::error!("Failed to build report: {}", e);
+                    self.report = None;
                 }
             }
-        });
+        }
+        
+        ui.separator();
+        if let Some((_, _, ref report)) = self.report {
+            egui::ScrollArea::vertical().show(ui, |ui| widgets::report_ui(ui, report));
+        }
     }
     
-    #[cfg(windows)]
-    fn get_active_window_title() -> Option<String> {
-        use std::ffi::OsString;
-        use std::os::windows::ffi::OsStringExt;
-        use winapi::um::winuser::{GetForegroundWindow, GetWindowTextW};
+    fn api_ui(&self, ui: &mut egui::Ui, engine: &mut PresenceEngine) {
+        let api = &mut engine.config.api;
         
-        unsafe {
-            let hwnd = GetForegroundWindow();
-            if hwnd.is_null() {
-                return None;
+        ui.checkbox(&mut api.enabled, "Enable HTTP API on localhost");
+        ui.horizontal(|ui| {
+            ui.label("Port:");
+            ui.add(egui::DragValue::new(&mut api.port).clamp_range(1..=65535));
+        });
+        
+        ui.horizontal(|ui| {
+            ui.label("Bearer Token:");
+            ui.add(egui::TextEdit::singleline(&mut api.token).password(true));
+            if ui.button("Generate").clicked() {
+                match api::generate_token() {
+                    Ok(token) => api.token = token,
+                    Err(e) => log::error!("Failed to generate API token: {}", e),
+                }
             }
-            
-            let mut buffer: [u16; 512] = [0; 512];
-            let len = GetWindowTextW(hwnd, buffer.as_mut_ptr(), buffer.len() as i32);
-            
-            if len > 0 {
-                let os_string = OsString::from_wide(&buffer[..len as usize]);
-                os_string.into_string().ok()
-            } else {
-                None
+            if ui.button("Copy").clicked() {
+                ui.output_mut(|output| output.copied_text = api.token.clone());
             }
-        }
-    }
-    
-    #[cfg(not(windows))]
-    fn get_active_window_title() -> Option<String> {
-        None // Implement for other platforms as needed
-    }
-    
-    fn filter_text(&self, text: &str) -> String {
-        self.word_filter.replace_all(text, "[FILTERED]").to_string()
+        });
+        
+        match self.api_server {
+            Some((_, Ok(ref server))) => ui.label(format!("Listening on http://{}", server.addr())),
+            Some((_, Err(ref e))) => ui.colored_label(egui::Color32::RED, e),
+            None => ui.label("Not running"),
+        };
     }
     
-    fn update_discord_activity(&mut self) {
-    // Do all the filtering BEFORE borrowing discord_client mutably
-    let activity_data = self.activity_data.lock().unwrap();
-    
-    if let Some(ref data) = *activity_data {
-        let mut details = String::new();
-        let mut state = String::new();
+    fn schedule_ui(ui: &mut egui::Ui, engine: &mut PresenceEngine) {
+        let profile_names = profile_names(&engine.config);
+        let schedule = &mut engine.config.schedule;
         
-        // Pre-filter all text before mutable borrow
-        let filtered_custom_message = if !self.custom_message_input.is_empty() {
-            Some(self.filter_text(&self.custom_message_input))
-        } else {
-            None
-        };
+        ui.checkbox(&mut schedule.enabled, "Enable Schedule");
         
-        let filtered_process_name = if self.config.show_applications && !data.top_processes.is_empty() {
-            Some(self.filter_text(&data.top_processes[0].name))
-        } else {
-            None
+        ui.horizontal(|ui| {
+            ui.label("Timezone:");
+            let mut timezone = schedule.timezone.clone().unwrap_or_default();
+            if ui.add(egui::TextEdit::singleline(&mut timezone).hint_text("System local")).changed() {
+                schedule.timezone = if timezone.is_empty() { None } else { Some(timezone) };
+            }
+            if let Err(e) = schedule.zone() {
+                ui.colored_label(egui::Color32::RED, format!("{}, using the system timezone", e));
+            }
+        });
+        
+        match schedule.active_block(Utc::now()) {
+            Some((_, block)) => ui.label(format!("Active block: {}", block.describe())),
+            None => ui.label("Active block: none"),
         };
         
-        // Build details and state strings
-        if self.config.show_system_stats {
-            details = format!(
-                "CPU: {:.1}% | RAM: {:.1}%",
-                data.system_stats.cpu_usage,
-                data.system_stats.memory_usage
-            );
+        // Week grid: one cell per hour, colored by the block that covers it
+        let now = schedule.localize(Utc::now());
+        egui::Grid::new("schedule_week_grid").spacing([1.0, 1.0]).show(ui, |ui| {
+            ui.label("");
+            for hour in 0..24 {
+                ui.label(if hour % 6 == 0 { format!("{:02}", hour) } else { String::new() });
+            }
+            ui.end_row();
+            
+            for weekday in WEEKDAYS {
+                ui.label(weekday.to_string());
+                for hour in 0..24 {
+                    let (rect, response) = ui.allocate_exact_size(egui::vec2(14.0, 14.0), egui::Sense::hover());
+                    let block = schedule.block_at(weekday, hour);
+                    let color = match block {
+                        Some((index, block)) => widgets::schedule_block_color(index, block),
+                        None => ui.visuals().faint_bg_color,
+                    };
+                    ui.painter().rect_filled(rect, 2.0, color);
+                    
+                    if weekday == now.weekday && hour == now.time.hour() {
+                        ui.painter().rect_stroke(rect, 2.0, egui::Stroke::new(2.0, egui::Color32::WHITE));
+                    }
+                    
+                    if let Some((_, block)) = block {
+                        response.on_hover_text(block.describe());
+                    }
+                }
+                ui.end_row();
+            }
+        });
+        
+        ui.separator();
+        
+        let mut to_remove = None;
+        for (i, block) in schedule.blocks.iter_mut().enumerate() {
+            ui.push_id(i, |ui| {
+                ui.horizontal(|ui| {
+                    let (rect, _) = ui.allocate_exact_size(egui::vec2(10.0, 10.0), egui::Sense::hover());
+                    ui.painter().rect_filled(rect, 2.0, widgets::schedule_block_color(i, block));
+                    ui.add(egui::TextEdit::singleline(&mut block.name).desired_width(140.0));
+                    if ui.button("Remove").clicked() {
+                        to_remove = Some(i);
+                    }
+                });
+                
+                ui.horizontal(|ui| {
+                    for weekday in WEEKDAYS {
+                        let selected = block.days.contains(&weekday);
+                        if ui.selectable_label(selected, weekday.to_string()).clicked() {
+                            if selected {
+                                block.days.retain(|day| *day != weekday);
+                            } else {
+                                block.days.push(weekday);
+                            }
+                        }
+                    }
+                });
+                
+                ui.horizontal(|ui| {
+                    ui.label("From");
+                    widgets::time_edit(ui, &mut block.start);
+                    ui.label("to");
+                    widgets::time_edit(ui, &mut block.end);
+                    
+                    let is_off = block.action == ScheduleAction::Off;
+                    egui::ComboBox::from_id_source("schedule_action")
+                        .selected_text(if is_off { "Presence off" } else { "Profile" })
+                        .show_ui(ui, |ui| {
+                            if ui.selectable_label(is_off, "Presence off").clicked() {
+                                block.action = ScheduleAction::Off;
+                            }
+                            if ui.selectable_label(!is_off, "Profile").clicked() && is_off {
+                                block.action = ScheduleAction::Profile(String::new());
+                            }
+                        });
+                    
+                    if let ScheduleAction::Profile(ref mut profile) = block.action {
+                        egui::ComboBox::from_id_source("schedule_profile")
+                            .selected_text(profile.as_str())
+                            .show_ui(ui, |ui| {
+                                for name in &profile_names {
+                                    ui.selectable_value(profile, name.clone(), name);
+                                }
+                            });
+                    }
+                });
+                
+                ui.separator();
+            });
         }
         
-        if self.config.show_time {
-            if !state.is_empty() {
-                state.push_str(" | ");
-            }
-            state.push_str(&format!("Time: {}", data.current_time.format("%H:%M:%S")));
+        if let Some(index) = to_remove {
+            schedule.blocks.remove(index);
         }
         
-        if let Some(ref process_name) = filtered_process_name {
-            if !state.is_empty() {
-                state.push_str(" | ");
-            }
-            state.push_str(&format!("Running: {}", process_name));
+        if ui.button("Add Block").clicked() {
+            schedule.blocks.push(ScheduleBlock::default());
         }
+    }
+    
+    fn profiles_ui(&mut self, ui: &mut egui::Ui, engine: &mut PresenceEngine) {
+        let profile_names = profile_names(&engine.config);
         
-        // NOW do the mutable borrow
-        if let Some(ref mut client) = self.discord_client {
-            let mut activity_builder = activity::Activity::new();
-            
-            if let Some(ref message) = filtered_custom_message {
-                activity_builder = activity_builder.details(message);
-            } else if !details.is_empty() {
-                activity_builder = activity_builder.details(&details);
-            }
-            
-            if !state.is_empty() {
-                activity_builder = activity_builder.state(&state);
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut self.new_profile_name);
+            let name = self.new_profile_name.trim().to_string();
+            if ui.button("Add Profile").clicked() && !name.is_empty() && !profile_names.contains(&name) {
+                engine.config.profiles.push(Profile::new(&name));
+                self.new_profile_name.clear();
             }
+        });
+        
+        // Values shown for fields a profile does not override
+        let inherited: Vec<PresenceSettings> = engine
+            .config
+            .profiles
+            .iter()
+            .map(|profile| engine.config.settings_for(profile.inherits.as_deref()))
+            .collect();
+        
+        let mut to_remove = None;
+        for (i, profile) in engine.config.profiles.iter_mut().enumerate() {
+            let inherited = &inherited[i];
             
-            activity_builder = activity_builder.timestamps(
-                activity::Timestamps::new().start(data.current_time.timestamp())
-            );
-            
-            activity_builder = activity_builder.assets(
-                activity::Assets::new()
-                    .large_image("default")
-                    .large_text("MultiRichPresence")
-            );
-            
-            if let Err(e) = client.set_activity(activity_builder) {
-                log::error!("Failed to set Discord activity: {}", e);
-                self.connection_status = format!("Activity update failed: {}", e);
-                }
+            ui.push_id(i, |ui| {
+                egui::CollapsingHeader::new(&profile.name).show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Name:");
+                        ui.text_edit_singleline(&mut profile.name);
+                        if ui.button("Remove Profile").clicked() {
+                            to_remove = Some(i);
+                        }
+                    });
+                    
+                    ui.horizontal(|ui| {
+                        ui.label("Inherits from:");
+                        egui::ComboBox::from_id_source("profile_inherits")
+                            .selected_text(profile.inherits.as_deref().unwrap_or("Base"))
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut profile.inherits, None, "Base");
+                                for name in profile_names.iter().filter(|name| **name != profile.name) {
+                                    ui.selectable_value(&mut profile.inherits, Some(name.clone()), name);
+                                }
+                            });
+                    });
+                    
+                    ui.horizontal(|ui| {
+                        ui.label("Discord App ID:");
+                        widgets::optional_text_edit(ui, &mut profile.discord_app_id, "inherited");
+                    });
+                    
+                    widgets::override_checkbox(ui, &mut profile.show_system_stats, inherited.show_system_stats, "Show System Stats");
+                    widgets::override_checkbox(ui, &mut profile.show_time, inherited.show_time, "Show Time");
+                    widgets::override_checkbox(ui, &mut profile.show_applications, inherited.show_applications, "Show Applications");
+                    widgets::override_checkbox(ui, &mut profile.listening, inherited.listening, "Show \"Listening to\"");
+                    
+                    let mut override_words = profile.blacklisted_words.is_some();
+                    if ui.checkbox(&mut override_words, "Override Blacklisted Words").changed() {
+                        profile.blacklisted_words = if override_words {
+                            Some(inherited.blacklisted_words.clone())
+                        } else {
+                            None
+                        };
+                    }
+                    if let Some(ref mut words) = profile.blacklisted_words {
+                        let mut text = words.join(", ");
+                        if ui.add(egui::TextEdit::singleline(&mut text).hint_text("comma-separated")).changed() {
+                            *words = text
+                                .split(',')
+                                .map(|word| word.trim().to_string())
+                                .filter(|word| !word.is_empty())
+                                .collect();
+                        }
+                    }
+                    
+                    let mut override_filters = profile.activity_filters.is_some();
+                    if ui.checkbox(&mut override_filters, "Override Activity Filters").changed() {
+                        profile.activity_filters = if override_filters {
+                            Some(inherited.activity_filters.clone())
+                        } else {
+                            None
+                        };
+                    }
+                    if let Some(ref mut filters) = profile.activity_filters {
+                        ui.checkbox(&mut filters.hide_system_processes, "Hide System Processes");
+                        ui.add(egui::Slider::new(&mut filters.minimum_cpu_usage, 0.0..=10.0).text("Minimum CPU").suffix("%"));
+                    }
+                    
+                    widgets::optional_template_ui(ui, &mut profile.template, "Custom Template");
+                    
+                    ui.label("Rules:");
+                    widgets::rules_ui(ui, &mut profile.rules, &profile_names, &engine.rule_patterns);
+                });
+            });
+        }
+        
+        if let Some(index) = to_remove {
+            let removed = engine.config.profiles.remove(index);
+            if engine.config.active_profile.as_deref() == Some(removed.name.as_str()) {
+                let _ = engine.switch_profile(None);
             }
         }
     }
 }
 
+fn profile_names(config: &Config) -> Vec<String> {
+    config.profiles.iter().map(|profile| profile.name.clone()).collect()
+}
+
 impl eframe::App for DiscordRpcApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        // Update Discord activity periodically
-        if self.last_update.elapsed() >= Duration::from_secs(self.config.update_interval_seconds) {
-            self.update_discord_activity();
-            self.last_update = Instant::now();
+        let (tray_config, hotkeys_config, api_config) = self
+            .engine
+            .lock()
+            .map(|engine| (engine.config.tray.clone(), engine.config.hotkeys.clone(), engine.config.api.clone()))
+            .unwrap_or_default();
+        self.sync_tray(tray_config.enabled);
+        // Not while rebinding, so the old keys stay usable until the new ones are in
+        if self.recording_hotkey.is_none() {
+            sync_hotkeys(&mut self.hotkeys, &hotkeys_config, &self.engine);
         }
+        sync_api_server(&mut self.api_server, &api_config, &self.engine);
+        
+        let tray_visible = self.tray.as_ref().is_some_and(Tray::is_visible);
+        if ctx.input(|input| input.viewport().close_requested())
+            && tray_config.close_to_tray
+            && tray_visible
+            && !self.quitting.load(Ordering::Relaxed)
+        {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            ctx.send_viewport_cmd(egui::ViewportCommand::Visible(false));
+        }
+        
+        // Periodic updates run on the engine's own thread; the GUI only edits and displays
+        let shared = Arc::clone(&self.engine);
+        let mut engine = match shared.lock() {
+            Ok(engine) => engine,
+            Err(_) => return,
+        };
+        engine.refresh_settings();
         
         // Request repaint for real-time updates
         ctx.request_repaint_after(Duration::from_secs(1));
@@ -375,19 +496,65 @@ impl eframe::App for DiscordRpcApp {
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.heading("MultiRichPresence");
             
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut self.tab, Tab::Presence, "Presence");
+                ui.selectable_value(&mut self.tab, Tab::Processes, "Processes");
+                ui.selectable_value(&mut self.tab, Tab::Reports, "Reports");
+            });
+            ui.separator();
+            
+            if let Some(ref error) = self.save_error {
+                ui.colored_label(egui::Color32::RED, error);
+            }
+            
+            match self.tab {
+                Tab::Presence => {}
+                Tab::Processes => {
+                    let processes = engine.processes.lock().map(|processes| processes.clone()).unwrap_or_default();
+                    let profile = engine.settings.profile.clone();
+                    // New rules go where the active profile reads them, like hiding and renaming
+                    let rules_location = match profile.as_deref().filter(|name| engine.config.profile(name).is_some()) {
+                        Some(name) => format!("Settings → Profiles → {}", name),
+                        None => "Settings → Rules".to_string(),
+                    };
+                    let filters = engine.config.activity_filters_mut(profile.as_deref());
+                    let unchanged = filters.clone();
+                    let rule = widgets::process_browser_ui(ui, &mut self.process_browser, &processes, filters, &rules_location);
+                    let changed = rule.is_some() || *filters != unchanged;
+                    if let Some(rule) = rule {
+                        engine.config.rules_mut(profile.as_deref()).push(rule);
+                    }
+                    if changed {
+                        save_config(&mut engine, &mut self.save_error);
+                    }
+                    return;
+                }
+                Tab::Reports => {
+                    self.reports_ui(ui, &engine);
+                    return;
+                }
+            }
+            
+            if !engine.config_errors.is_empty() {
+                ui.colored_label(egui::Color32::RED, "config.json was not applied, the previous config stays in effect:");
+                for error in &engine.config_errors {
+                    ui.colored_label(egui::Color32::RED, format!("  {}", error));
+                }
+            }
+            
             ui.horizontal(|ui| {
                 ui.label("Status:");
                 ui.colored_label(
-                    if self.connection_status == "Connected" {
+                    if engine.connection_status == "Connected" {
                         egui::Color32::GREEN
                     } else {
                         egui::Color32::RED
                     },
-                    &self.connection_status,
+                    &engine.connection_status,
                 );
                 
                 if ui.button("Reconnect").clicked() {
-                    self.connect_discord();
+                    engine.connect_discord();
                 }
                 
                 ui.separator();
@@ -395,6 +562,72 @@ impl eframe::App for DiscordRpcApp {
                 if ui.button("Settings").clicked() {
                     self.show_config = !self.show_config;
                 }
+                
+                ui.separator();
+                
+                ui.label("Profile:");
+                let current = engine.config.active_profile.clone();
+                let mut selected = current.clone();
+                egui::ComboBox::from_id_source("active_profile")
+                    .selected_text(current.as_deref().unwrap_or("Base"))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut selected, None, "Base");
+                        for profile in &engine.config.profiles {
+                            ui.selectable_value(&mut selected, Some(profile.name.clone()), &profile.name);
+                        }
+                    });
+                if selected != current {
+                    if let Err(e) = engine.switch_profile(selected) {
+                        log::error!("Failed to switch profile: {}", e);
+                    }
+                }
+                
+                if let Some(ref reason) = engine.hidden_reason {
+                    ui.label(format!("Hidden ({})", reason));
+                } else if let Some(ref source) = engine.presence_source {
+                    ui.label(format!(
+                        "Using {} ({})",
+                        engine.settings.profile.as_deref().unwrap_or("Base"),
+                        source
+                    ));
+                }
+            });
+            
+            ui.horizontal(|ui| {
+                if engine.privacy.hidden {
+                    ui.colored_label(egui::Color32::YELLOW, "Presence hidden");
+                    if let Some(remaining) = engine.privacy.remaining(Utc::now()) {
+                        ui.label(format!(
+                            "(resumes in {}m {:02}s)",
+                            remaining.num_minutes(),
+                            remaining.num_seconds() % 60
+                        ));
+                    }
+                    if ui.button("Resume").clicked() {
+                        engine.resume_presence();
+                    }
+                } else {
+                    let hide = egui::Button::new("Hide Presence").fill(egui::Color32::DARK_RED);
+                    if ui.add(hide).clicked() {
+                        let duration = Some(self.hide_duration_minutes)
+                            .filter(|minutes| *minutes > 0)
+                            .map(chrono::Duration::minutes);
+                        engine.hide_presence(duration);
+                    }
+                    
+                    ui.label("for");
+                    let label = |minutes: i64| match minutes {
+                        0 => "Until resumed".to_string(),
+                        minutes => format!("{} minutes", minutes),
+                    };
+                    egui::ComboBox::from_id_source("hide_duration")
+                        .selected_text(label(self.hide_duration_minutes))
+                        .show_ui(ui, |ui| {
+                            for minutes in [0, 15, 30, 60, 120] {
+                                ui.selectable_value(&mut self.hide_duration_minutes, minutes, label(minutes));
+                            }
+                        });
+                }
             });
             
             ui.separator();
@@ -402,9 +635,9 @@ impl eframe::App for DiscordRpcApp {
             // Custom message input
             ui.horizontal(|ui| {
                 ui.label("Custom Message:");
-                ui.text_edit_singleline(&mut self.custom_message_input);
+                ui.text_edit_singleline(&mut engine.custom_message);
                 if ui.button("Clear").clicked() {
-                    self.custom_message_input.clear();
+                    engine.custom_message.clear();
                 }
             });
             
@@ -412,7 +645,7 @@ impl eframe::App for DiscordRpcApp {
             
             // Activity preview
             ui.collapsing("Activity Preview", |ui| {
-                if let Ok(activity_data) = self.activity_data.lock() {
+                if let Ok(activity_data) = engine.activity_data.lock() {
                     if let Some(ref data) = *activity_data {
                         ui.label(format!("CPU Usage: {:.1}%", data.system_stats.cpu_usage));
                         ui.label(format!("Memory Usage: {:.1}% ({} MB / {} MB)", 
@@ -421,20 +654,90 @@ impl eframe::App for DiscordRpcApp {
                             data.system_stats.memory_total / 1024 / 1024
                         ));
                         ui.label(format!("Process Count: {}", data.system_stats.process_count));
+                        ui.label(format!("Uptime: {}", template::format_duration(data.system_stats.uptime)));
                         ui.label(format!("Current Time: {}", data.current_time.format("%Y-%m-%d %H:%M:%S")));
                         
                         if !data.top_processes.is_empty() {
                             ui.label("Top Processes:");
+                            let samples = engine.samples.lock().ok();
                             for process in &data.top_processes {
-                                ui.label(format!("  {} - {:.1}% CPU", 
-                                    self.filter_text(&process.name), 
-                                    process.cpu_usage
-                                ));
+                                ui.horizontal(|ui| {
+                                    ui.label(format!("  {} - {:.1}% CPU", 
+                                        engine.filter_text(&process.name), 
+                                        process.cpu_usage
+                                    ));
+                                    if let Some(series) = samples.as_ref().and_then(|samples| samples.process(process.pid)) {
+                                        widgets::sparkline(ui, series, engine.config.graph_minutes);
+                                    }
+                                });
                             }
                         }
                         
                         if let Some(ref window) = data.active_window {
-                            ui.label(format!("Active Window: {}", self.filter_text(window)));
+                            ui.label(format!("Active Window: {}", engine.filter_text(window)));
+                        }
+                        
+                        if let Some(ref editor) = data.editor {
+                            let language = editor.language.as_deref().map(|language| format!(" ({})", language)).unwrap_or_default();
+                            ui.label(format!("Editing: {}{} in {}", engine.filter_text(&editor.file), language, editor.editor));
+                        }
+                        
+                        if let Some(ref battery) = data.battery {
+                            ui.label(format!("Battery: {:.0}% ({:?})", battery.percent, battery.status));
+                        }
+                        
+                        for temperature in &data.temperatures {
+                            ui.label(format!("Temperature: {} {}", temperature.label, template::format_celsius(temperature.celsius)));
+                        }
+                        
+                        if let Some(ref load) = data.load {
+                            ui.label(format!(
+                                "Load: {:.2} {:.2} {:.2} | Swap: {} / {}",
+                                load.one,
+                                load.five,
+                                load.fifteen,
+                                template::format_bytes(load.swap_used),
+                                template::format_bytes(load.swap_total)
+                            ));
+                        }
+                        
+                        for disk in &data.disks {
+                            ui.label(format!("Disk {}: {} / {}", disk.mount, template::format_bytes(disk.used), template::format_bytes(disk.total)));
+                        }
+                        
+                        for network in &data.networks {
+                            ui.label(format!(
+                                "Network {}: ↓ {} ↑ {}",
+                                network.interface,
+                                template::format_rate(network.received_per_second),
+                                template::format_rate(network.sent_per_second)
+                            ));
+                        }
+                        
+                        if let Some(ref containers) = data.containers {
+                            let names: Vec<&str> = containers.iter().map(|container| container.name.as_str()).collect();
+                            ui.label(format!("Containers: {} ({} running)", engine.filter_text(&names.join(", ")), containers.len()));
+                        }
+                        
+                        if let Some(ref terminal) = data.terminal {
+                            ui.label(format!("Terminal: {} in {}", engine.filter_text(&terminal.command_line()), terminal.terminal));
+                        }
+                        
+                        if let Some(ref git) = data.git {
+                            let branch = git.branch.as_deref().map(|branch| format!(" on {}", branch)).unwrap_or_default();
+                            let dirty = if git.dirty { " (uncommitted changes)" } else { "" };
+                            ui.label(format!("Repository: {}{}{}", engine.filter_text(&git.repo), engine.filter_text(&branch), dirty));
+                        }
+                        
+                        if let Some(ref media) = data.media {
+                            let artist = media.artist.as_deref().map(|artist| format!(" by {}", artist)).unwrap_or_default();
+                            ui.label(format!(
+                                "Media: {}{} ({}, {:?})",
+                                engine.filter_text(&media.title),
+                                engine.filter_text(&artist),
+                                media.player,
+                                media.status
+                            ));
                         }
                     } else {
                         ui.label("No activity data available");
@@ -442,32 +745,41 @@ impl eframe::App for DiscordRpcApp {
                 }
             });
             
+            ui.collapsing("Live Graphs", |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Show the last");
+                    ui.add(egui::DragValue::new(&mut engine.config.graph_minutes).clamp_range(1..=MAX_GRAPH_MINUTES).suffix(" min"));
+                });
+                if let Ok(samples) = engine.samples.lock() {
+                    widgets::stats_graphs_ui(ui, &samples);
+                }
+            });
+            
             // Configuration panel
             if self.show_config {
                 ui.separator();
                 ui.heading("Configuration");
                 
-                ui.checkbox(&mut self.config.show_system_stats, "Show System Stats");
-                ui.checkbox(&mut self.config.show_time, "Show Time");
-                ui.checkbox(&mut self.config.show_applications, "Show Applications");
+                ui.checkbox(&mut engine.config.show_system_stats, "Show System Stats");
+                ui.checkbox(&mut engine.config.show_time, "Show Time");
+                ui.checkbox(&mut engine.config.show_applications, "Show Applications");
                 
                 ui.horizontal(|ui| {
                     ui.label("Update Interval (seconds):");
-                    ui.add(egui::Slider::new(&mut self.config.update_interval_seconds, 5..=300));
+                    ui.add(egui::Slider::new(&mut engine.config.update_interval_seconds, 5..=300));
                 });
                 
                 ui.collapsing("Word Filter", |ui| {
                     ui.horizontal(|ui| {
                         ui.text_edit_singleline(&mut self.new_blacklisted_word);
                         if ui.button("Add Blacklisted Word").clicked() && !self.new_blacklisted_word.is_empty() {
-                            self.config.blacklisted_words.push(self.new_blacklisted_word.clone());
+                            engine.config.blacklisted_words.push(self.new_blacklisted_word.clone());
                             self.new_blacklisted_word.clear();
-                            self.word_filter = Self::create_word_filter(&self.config.blacklisted_words);
                         }
                     });
                     
                     let mut to_remove = None;
-                    for (i, word) in self.config.blacklisted_words.iter().enumerate() {
+                    for (i, word) in engine.config.blacklisted_words.iter().enumerate() {
                         ui.horizontal(|ui| {
                             ui.label(word);
                             if ui.button("Remove").clicked() {
@@ -477,31 +789,136 @@ impl eframe::App for DiscordRpcApp {
                     }
                     
                     if let Some(index) = to_remove {
-                        self.config.blacklisted_words.remove(index);
-                        self.word_filter = Self::create_word_filter(&self.config.blacklisted_words);
+                        engine.config.blacklisted_words.remove(index);
                     }
                 });
                 
                 ui.collapsing("Activity Filters", |ui| {
-                    ui.checkbox(&mut self.config.activity_filters.hide_system_processes, "Hide System Processes");
-                    ui.checkbox(&mut self.config.activity_filters.hide_background_apps, "Hide Background Apps");
+                    ui.checkbox(&mut engine.config.activity_filters.hide_system_processes, "Hide System Processes");
+                    ui.checkbox(&mut engine.config.activity_filters.hide_background_apps, "Hide Background Apps");
                     
                     ui.horizontal(|ui| {
                         ui.label("Minimum CPU Usage:");
-                        ui.add(egui::Slider::new(&mut self.config.activity_filters.minimum_cpu_usage, 0.0..=10.0).suffix("%"));
+                        ui.add(egui::Slider::new(&mut engine.config.activity_filters.minimum_cpu_usage, 0.0..=10.0).suffix("%"));
                     });
+                    
+                    ui.horizontal(|ui| {
+                        ui.label("Blacklisted Processes:");
+                        widgets::comma_list_edit(ui, &mut engine.config.activity_filters.blacklisted_processes, "e.g. steam.exe, discord");
+                    });
+                    ui.label("Display Names:");
+                    widgets::map_edit(ui, &mut engine.config.activity_filters.display_names, "process", "shown as");
+                    ui.small("The Processes tab can hide, rename and create rules from running processes.");
                 });
                 
-                ui.horizontal(|ui| {
-                    if ui.button("Save Config").clicked() {
-                        if let Err(e) = self.save_config() {
-                            log::error!("Failed to save config: {}", e);
+                ui.collapsing("Template", |ui| {
+                    widgets::optional_template_ui(ui, &mut engine.config.template, "Custom Template");
+                });
+                
+                ui.collapsing("Rules", |ui| {
+                    let profile_names = profile_names(&engine.config);
+                    let engine = &mut *engine;
+                    widgets::rules_ui(ui, &mut engine.config.rules, &profile_names, &engine.rule_patterns);
+                });
+                
+                ui.collapsing("Profiles", |ui| {
+                    self.profiles_ui(ui, &mut engine);
+                });
+                
+                ui.collapsing("Schedule", |ui| {
+                    Self::schedule_ui(ui, &mut engine);
+                });
+                
+                ui.collapsing("Media Players", |ui| {
+                    widgets::media_ui(ui, &mut engine.config.providers.media);
+                });
+                
+                ui.collapsing("Editors", |ui| {
+                    widgets::editor_ui(ui, &mut engine.config.providers.editor);
+                });
+                
+                ui.collapsing("System Sensors", |ui| {
+                    widgets::sensors_ui(ui, &mut engine.config.providers);
+                });
+                
+                ui.collapsing("External Commands", |ui| {
+                    let statuses = engine
+                        .activity_data
+                        .lock()
+                        .ok()
+                        .and_then(|data| data.as_ref().map(|data| data.commands.clone()))
+                        .unwrap_or_default();
+                    widgets::commands_ui(ui, &mut engine.config.providers.commands, &statuses);
+                });
+                
+                ui.collapsing("Plugins", |ui| {
+                    let statuses = engine
+                        .activity_data
+                        .lock()
+                        .ok()
+                        .and_then(|data| data.as_ref().map(|data| data.plugins.clone()))
+                        .unwrap_or_default();
+                    widgets::plugins_ui(ui, &mut engine.config.providers.plugins, &statuses);
+                });
+                
+                ui.collapsing("History", |ui| {
+                    widgets::history_ui(ui, &mut engine.config.history);
+                    if engine.config.history.enabled && ui.button("Clear History").clicked() {
+                        if let Err(e) = engine.clear_history() {
+                            log::error!("Failed to clear history: {}", e);
                         }
                     }
+                });
+                
+                ui.collapsing("Scripts", |ui| {
+                    let scripts = engine.scripts.scripts().to_vec();
+                    widgets::scripts_ui(ui, &mut engine.config.scripting, &scripts);
+                });
+                
+                ui.collapsing("Containers", |ui| {
+                    widgets::containers_ui(ui, &mut engine.config.providers.containers);
+                });
+                
+                ui.collapsing("Terminals", |ui| {
+                    widgets::terminal_ui(ui, &mut engine.config.providers.terminal);
+                });
+                
+                ui.collapsing("Git Repositories", |ui| {
+                    widgets::git_ui(ui, &mut engine.config.providers.git);
+                });
+                
+                ui.collapsing("Output Sinks", |ui| {
+                    widgets::sinks_ui(ui, &mut engine.config.sinks);
+                });
+                
+                ui.collapsing("Global Hotkeys", |ui| {
+                    let (states, note) = match self.hotkeys {
+                        Some((_, Ok(ref hotkeys))) => (hotkeys.states(), format!("Registered through {}", hotkeys.backend())),
+                        Some((_, Err(ref e))) => (Default::default(), e.clone()),
+                        None => (Default::default(), String::new()),
+                    };
+                    let note = Some(note.as_str()).filter(|note| !note.is_empty());
+                    widgets::hotkeys_ui(ui, &mut engine.config.hotkeys, &mut self.recording_hotkey, &states, note);
+                });
+                
+                ui.collapsing("System Tray", |ui| {
+                    widgets::tray_ui(ui, &mut engine.config.tray, tray_visible);
+                });
+                
+                ui.collapsing("HTTP API", |ui| {
+                    self.api_ui(ui, &mut engine);
+                });
+                
+                ui.horizontal(|ui| {
+                    let save = ui
+                        .add_enabled(engine.config_errors.is_empty(), egui::Button::new("Save Config"))
+                        .on_disabled_hover_text("config.json has errors; fix the file first so saving does not overwrite it");
+                    if save.clicked() {
+                        save_config(&mut engine, &mut self.save_error);
+                    }
                     
                     if ui.button("Reset to Default").clicked() {
-                        self.config = Config::default();
-                        self.word_filter = Self::create_word_filter(&self.config.blacklisted_words);
+                        engine.config = Config::default();
                     }
                 });
             }
@@ -509,18 +926,128 @@ impl eframe::App for DiscordRpcApp {
     }
     
     fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
-        if let Some(ref mut client) = self.discord_client {
-            let _ = client.clear_activity();
-            let _ = client.close();
+        if let Ok(mut engine) = self.engine.lock() {
+            engine.shutdown();
         }
-        
-        let _ = self.save_config();
     }
 }
 
+/// Desktop Discord Rich Presence with profiles, rules and schedules.
+#[derive(Parser)]
+#[command(name = "multi-rich-presence", version, about)]
+struct Args {
+    /// Select (and persist) the active profile on startup
+    #[arg(long, value_name = "NAME")]
+    profile: Option<String>,
+    
+    /// Run without a window; control the instance with `mrp`
+    #[arg(long)]
+    daemon: bool,
+}
+
+/// Watches config.json so hand edits apply without a restart.
+fn start_config_watcher(engine: &Arc<Mutex<PresenceEngine>>) -> Option<ConfigWatcher> {
+    match ConfigWatcher::start(engine) {
+        Ok(watcher) => Some(watcher),
+        Err(e) => {
+            log::warn!("Not watching the config file: {}", e);
+            None
+        }
+    }
+}
+
+/// Saves the config from the GUI, keeping the reason in `save_error` when it is refused.
+fn save_config(engine: &mut PresenceEngine, save_error: &mut Option<String>) {
+    *save_error = match engine.save_config() {
+        Ok(()) => None,
+        Err(e) => {
+            log::error!("Failed to save config: {}", e);
+            Some(e.to_string())
+        }
+    };
+}
+
+/// Starts, restarts or stops the global hotkeys to match `config`.
+fn sync_hotkeys(hotkeys: &mut HotkeysSlot, config: &HotkeysConfig, engine: &Arc<Mutex<PresenceEngine>>) {
+    if !config.enabled {
+        *hotkeys = None;
+        return;
+    }
+    if hotkeys.as_ref().is_some_and(|(started, _)| started == config) {
+        return;
+    }
+    
+    // Release the old bindings before taking the new ones
+    *hotkeys = None;
+    let started = Hotkeys::start(config, engine).map_err(|e| e.to_string());
+    if let Err(ref e) = started {
+        log::error!("Failed to start global hotkeys: {}", e);
+    }
+    *hotkeys = Some((config.clone(), started));
+}
+
+/// Starts, restarts or stops the HTTP API to match `config`. The token is checked on every
+/// request, so only a change of port, or a token being set at all, needs a new server.
+fn sync_api_server(server: &mut ApiSlot, config: &ApiConfig, engine: &Arc<Mutex<PresenceEngine>>) {
+    if !config.enabled {
+        *server = None;
+        return;
+    }
+    let wanted = (config.port, !config.token.is_empty());
+    if server.as_ref().is_some_and(|(started, _)| *started == wanted) {
+        return;
+    }
+    
+    // Free the old port before binding the new one
+    *server = None;
+    let started = ApiServer::start(config.port, Arc::clone(engine)).map_err(|e| e.to_string());
+    if let Err(ref e) = started {
+        log::error!("Failed to start HTTP API: {}", e);
+    }
+    *server = Some((wanted, started));
+}
+
+/// Headless mode: the engine and control socket without the GUI.
+#[cfg(unix)]
+fn run_daemon(profile: Option<String>) -> Result<(), Box<dyn std::error::Error>> {
+    let engine = Arc::new(Mutex::new(PresenceEngine::load(profile)));
+    PresenceEngine::spawn_ticker(&engine);
+    let _config_watcher = start_config_watcher(&engine);
+    
+    // Without a window the socket is the only way in, so failing to bind is fatal
+    let server = ControlServer::start(&control::socket_path(), Arc::clone(&engine))?;
+    log::info!("Running headless, control socket at {}", server.path().display());
+    
+    // Follow config reloads the way the window does
+    let (mut api_server, mut hotkeys) = (None, None);
+    loop {
+        let (api_config, hotkeys_config) = engine
+            .lock()
+            .map(|engine| (engine.config.api.clone(), engine.config.hotkeys.clone()))
+            .unwrap_or_default();
+        sync_api_server(&mut api_server, &api_config, &engine);
+        sync_hotkeys(&mut hotkeys, &hotkeys_config, &engine);
+        std::thread::sleep(DAEMON_SYNC_INTERVAL);
+    }
+}
+
+#[cfg(not(unix))]
+fn run_daemon(_profile: Option<String>) -> Result<(), Box<dyn std::error::Error>> {
+    Err("daemon mode needs the control socket, which is only available on Unix platforms".into())
+}
+
 fn main() -> eframe::Result<()> {
     env_logger::init();
     
+    let args = Args::parse();
+    if args.daemon {
+        if let Err(e) = run_daemon(args.profile) {
+            eprintln!("multi-rich-presence: {}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([800.0, 600.0])
@@ -532,6 +1059,6 @@ fn main() -> eframe::Result<()> {
     eframe::run_native(
         "MultiRichPresence",
         options,
-        Box::new(|cc| Box::new(DiscordRpcApp::new(cc))),
+        Box::new(|cc| Box::new(DiscordRpcApp::new(cc, args.profile))),
     )
 }
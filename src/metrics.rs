@@ -0,0 +1,149 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::time::Duration;
+
+use crate::activity::ActivityData;
+
+/// Counters the engine and data providers record into; rendered for `/metrics`.
+#[derive(Debug, Clone, Default)]
+pub struct Metrics {
+    pub updates_sent: u64,
+    /// Updates not sent because presence was hidden by the panic button, schedule or a rule.
+    pub updates_suppressed: u64,
+    /// Updates that could not be delivered, including while disconnected.
+    pub updates_failed: u64,
+    pub reconnect_attempts: u64,
+    pub connection_failures: u64,
+    /// Blacklisted words replaced in sent updates.
+    pub filter_hits: u64,
+    /// How often each rule started applying, keyed by rule name.
+    pub rule_matches: BTreeMap<String, u64>,
+    /// Time each data provider took per sample, keyed by provider name.
+    pub provider_latency: BTreeMap<String, ProviderLatency>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ProviderLatency {
+    pub last_seconds: f64,
+    pub total_seconds: f64,
+    pub samples: u64,
+}
+
+/// Point-in-time values rendered as gauges next to the counters.
+pub struct Snapshot<'a> {
+    pub data: Option<&'a ActivityData>,
+    pub connected: bool,
+    pub hidden: bool,
+}
+
+impl Metrics {
+    pub fn record_provider_latency(&mut self, provider: &str, elapsed: Duration) {
+        let latency = self.provider_latency.entry(provider.to_string()).or_default();
+        latency.last_seconds = elapsed.as_secs_f64();
+        latency.total_seconds += elapsed.as_secs_f64();
+        latency.samples += 1;
+    }
+    
+    pub fn record_rule_match(&mut self, rule: &str) {
+        *self.rule_matches.entry(rule.to_string()).or_default() += 1;
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+fn single(out: &mut String, name: &str, kind: &str, help: &str, value: impl std::fmt::Display) {
+    header(out, name, kind, help);
+    let _ = writeln!(out, "{} {}", name, value);
+}
+
+/// Renders everything in the Prometheus text exposition format.
+pub fn render(metrics: &Metrics, snapshot: &Snapshot) -> String {
+    let mut out = String::new();
+    
+    if let Some(data) = snapshot.data {
+        let stats = &data.system_stats;
+        single(&mut out, "mrp_cpu_usage_percent", "gauge", "Global CPU usage.", stats.cpu_usage);
+        single(&mut out, "mrp_memory_usage_percent", "gauge", "Memory in use, as a percentage of total.", stats.memory_usage);
+        single(&mut out, "mrp_memory_used_bytes", "gauge", "Memory in use, in bytes.", stats.memory_used);
+        single(&mut out, "mrp_memory_total_bytes", "gauge", "Total memory, in bytes.", stats.memory_total);
+        single(&mut out, "mrp_process_count", "gauge", "Running processes.", stats.process_count);
+        single(&mut out, "mrp_system_uptime_seconds", "gauge", "System uptime.", stats.uptime);
+    }
+    
+    single(
+        &mut out,
+        "mrp_discord_connected",
+        "gauge",
+        "Whether the Discord connection is up.",
+        u8::from(snapshot.connected),
+    );
+    single(
+        &mut out,
+        "mrp_presence_hidden",
+        "gauge",
+        "Whether presence is currently hidden.",
+        u8::from(snapshot.hidden),
+    );
+    
+    header(&mut out, "mrp_presence_updates_total", "counter", "Presence updates by result.");
+    for (result, count) in [
+        ("sent", metrics.updates_sent),
+        ("suppressed", metrics.updates_suppressed),
+        ("failed", metrics.updates_failed),
+    ] {
+        let _ = writeln!(out, "mrp_presence_updates_total{{result=\"{}\"}} {}", result, count);
+    }
+    
+    single(
+        &mut out,
+        "mrp_reconnect_attempts_total",
+        "counter",
+        "Attempts to connect to Discord.",
+        metrics.reconnect_attempts,
+    );
+    single(
+        &mut out,
+        "mrp_connection_failures_total",
+        "counter",
+        "Failed attempts to connect to Discord.",
+        metrics.connection_failures,
+    );
+    single(
+        &mut out,
+        "mrp_filter_hits_total",
+        "counter",
+        "Blacklisted words replaced in sent updates.",
+        metrics.filter_hits,
+    );
+    
+    header(&mut out, "mrp_rule_matches_total", "counter", "Times each rule started applying.");
+    for (rule, count) in &metrics.rule_matches {
+        let _ = writeln!(out, "mrp_rule_matches_total{{rule=\"{}\"}} {}", escape_label(rule), count);
+    }
+    
+    header(&mut out, "mrp_provider_latency_seconds", "summary", "Time data providers take per sample.");
+    for (provider, latency) in &metrics.provider_latency {
+        let provider = escape_label(provider);
+        let _ = writeln!(out, "mrp_provider_latency_seconds_sum{{provider=\"{}\"}} {}", provider, latency.total_seconds);
+        let _ = writeln!(out, "mrp_provider_latency_seconds_count{{provider=\"{}\"}} {}", provider, latency.samples);
+    }
+    
+    header(&mut out, "mrp_provider_last_latency_seconds", "gauge", "Latency of each provider's latest sample.");
+    for (provider, latency) in &metrics.provider_latency {
+        let _ = writeln!(
+            out,
+            "mrp_provider_last_latency_seconds{{provider=\"{}\"}} {}",
+            escape_label(provider),
+            latency.last_seconds
+        );
+    }
+    
+    out
+}
@@ -0,0 +1,331 @@
+use chrono::Local;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use sysinfo::{System, Users};
+
+use crate::activity::{ActivityData, ProcessEntry, ProcessInfo, SystemStats};
+use crate::config::ActivityFilters;
+use crate::metrics::Metrics;
+use crate::provider::{self, Provider, ProviderInputs, ProvidersConfig};
+use crate::samples::SampleHistory;
+
+/// Shared state the monitoring thread writes after every pass.
+#[derive(Clone)]
+pub struct MonitorOutputs {
+    pub activity_data: Arc<Mutex<Option<ActivityData>>>,
+    /// Recent stats for the live graphs.
+    pub samples: Arc<Mutex<SampleHistory>>,
+    /// Every running process, unfiltered, for the process browser.
+    pub processes: Arc<Mutex<Vec<ProcessEntry>>>,
+}
+
+/// Spawns the background thread that samples system stats and the top processes
+/// every `update_interval` seconds, applying the current activity filters, and then
+/// runs the enabled providers. Providers are rebuilt when their config changes, and the
+/// interval is read again before every pass.
+pub fn start_system_monitoring(
+    outputs: MonitorOutputs,
+    monitor_filters: Arc<Mutex<ActivityFilters>>,
+    provider_config: Arc<Mutex<ProvidersConfig>>,
+    provider_inputs: ProviderInputs,
+    metrics: Arc<Mutex<Metrics>>,
+    update_interval: Arc<Mutex<u64>>,
+) {
+    std::thread::spawn(move || {
+        let mut system = System::new_all();
+        let mut users = Users::new_with_refreshed_list();
+        let mut providers: Vec<Box<dyn Provider>> = Vec::new();
+        let mut providers_built_from = None;
+        let mut active_window = ActiveWindow::default();
+        
+        loop {
+            let interval = update_interval.lock().map(|interval| *interval).unwrap_or(1);
+            std::thread::sleep(Duration::from_secs(interval));
+            
+            let started = Instant::now();
+            system.refresh_all();
+            
+            let filters = match monitor_filters.lock() {
+                Ok(filters) => filters.clone(),
+                Err(_) => ActivityFilters::default(),
+            };
+            
+            let stats = SystemStats {
+                cpu_usage: system.global_cpu_info().cpu_usage(),
+                memory_usage: (system.used_memory() as f64 / system.total_memory() as f64) * 100.0,
+                memory_total: system.total_memory(),
+                memory_used: system.used_memory(),
+                uptime: System::uptime(),
+                process_count: system.processes().len(),
+            };
+            
+            let mut processes: Vec<ProcessInfo> = Vec::new();
+            let mut entries: Vec<ProcessEntry> = Vec::new();
+            
+            // Accounts created since the last pass
+            if system.processes().values().any(|process| process.user_id().is_some_and(|uid| users.get_user_by_id(uid).is_none())) {
+                users.refresh_list();
+            }
+            
+            for (pid, process) in system.processes() {
+                entries.push(ProcessEntry {
+                    name: process.name().to_string(),
+                    pid: pid.as_u32(),
+                    exe: process.exe().map(|exe| exe.display().to_string()),
+                    cpu_usage: process.cpu_usage(),
+                    memory_usage: process.memory(),
+                    start_time: process.start_time(),
+                    user: process.user_id().and_then(|uid| users.get_user_by_id(uid)).map(|user| user.name().to_string()),
+                });
+                
+                if filters.hides(process.name()) {
+                    continue;
+                }
+                
+                if process.cpu_usage() >= filters.minimum_cpu_usage {
+                    processes.push(ProcessInfo {
+                        name: filters.display_name(process.name()).to_string(),
+                        pid: pid.as_u32(),
+                        cpu_usage: process.cpu_usage(),
+                        memory_usage: process.memory(),
+                        start_time: process.start_time(),
+                    });
+                }
+            }
+            
+            processes.sort_by(|a, b| b.cpu_usage.partial_cmp(&a.cpu_usage).unwrap_or(std::cmp::Ordering::Equal));
+            processes.truncate(5); // Keep top 5 processes
+            
+            let focused = active_window.focused();
+            let mut activity = ActivityData {
+                system_stats: stats,
+                current_time: Local::now(),
+                top_processes: processes,
+                active_window: focused.title,
+                active_window_pid: focused.pid,
+                media: None,
+                git: None,
+                editor: None,
+                terminal: None,
+                battery: None,
+                temperatures: Vec::new(),
+                load: None,
+                disks: Vec::new(),
+                networks: Vec::new(),
+                containers: None,
+                commands: Vec::new(),
+                plugins: Vec::new(),
+            };
+            
+            if let Ok(mut metrics) = metrics.lock() {
+                metrics.record_provider_latency("system", started.elapsed());
+            }
+            
+            let config = provider_config.lock().map(|config| config.clone()).unwrap_or_default();
+            if providers_built_from.as_ref() != Some(&config) {
+                providers = provider::build_providers(&config, &provider_inputs);
+                providers_built_from = Some(config);
+            }
+            
+            for provider in &mut providers {
+                let started = Instant::now();
+                if let Err(e) = provider.sample(&mut activity) {
+                    log::warn!("Provider {} failed: {}", provider.name(), e);
+                }
+                
+                if let Ok(mut metrics) = metrics.lock() {
+                    metrics.record_provider_latency(provider.name(), started.elapsed());
+                }
+            }
+            
+            if let Ok(mut samples) = outputs.samples.lock() {
+                samples.record(&activity);
+            }
+            
+            if let Ok(mut processes) = outputs.processes.lock() {
+                *processes = entries;
+            }
+            
+            if let Ok(mut data) = outputs.activity_data.lock() {
+                *data = Some(activity);
+            }
+        }
+    });
+}
+
+#[cfg(windows)]
+pub fn get_active_window_title() -> Option<String> {
+    use std::ffi::OsString;
+    use std::os::windows::ffi::OsStringExt;
+    use winapi::um::winuser::{GetForegroundWindow, GetWindowTextW};
+    
+    unsafe {
+        let hwnd = GetForegroundWindow();
+        if hwnd.is_null() {
+            return None;
+        }
+        
+        let mut buffer: [u16; 512] = [0; 512];
+        let len = GetWindowTextW(hwnd, buffer.as_mut_ptr(), buffer.len() as i32);
+        
+        if len > 0 {
+            let os_string = OsString::from_wide(&buffer[..len as usize]);
+            os_string.into_string().ok()
+        } else {
+            None
+        }
+    }
+}
+
+/// Title and owning process of the focused window.
+#[derive(Debug, Default)]
+pub struct FocusedWindow {
+    pub title: Option<String>,
+    /// Only known on Linux, from `_NET_WM_PID`.
+    pub pid: Option<u32>,
+}
+
+/// Reads the focused window. On Linux this goes through X11, keeping one connection
+/// open between samples and reconnecting after it fails.
+#[derive(Default)]
+pub struct ActiveWindow {
+    #[cfg(target_os = "linux")]
+    x11: Option<x11::X11Window>,
+}
+
+impl ActiveWindow {
+    #[cfg(windows)]
+    pub fn focused(&mut self) -> FocusedWindow {
+        FocusedWindow {
+            title: get_active_window_title(),
+            pid: None,
+        }
+    }
+    
+    #[cfg(target_os = "linux")]
+    pub fn focused(&mut self) -> FocusedWindow {
+        if self.x11.is_none() {
+            self.x11 = match x11::X11Window::connect() {
+                Ok(x11) => Some(x11),
+                Err(e) => {
+                    log::debug!("Cannot read the active window from X11: {}", e);
+                    return FocusedWindow::default();
+                }
+            };
+        }
+        
+        let x11 = match self.x11.as_ref() {
+            Some(x11) => x11,
+            None => return FocusedWindow::default(),
+        };
+        match x11.focused() {
+            Ok(focused) => focused,
+            Err(e) => {
+                log::warn!("Lost the X11 connection for the active window: {}", e);
+                self.x11 = None;
+                FocusedWindow::default()
+            }
+        }
+    }
+    
+    #[cfg(not(any(windows, target_os = "linux")))]
+    pub fn focused(&mut self) -> FocusedWindow {
+        FocusedWindow::default() // Implement for other platforms as needed
+    }
+}
+
+/// The EWMH `_NET_ACTIVE_WINDOW` with its `_NET_WM_NAME` and `_NET_WM_PID`, as set by the
+/// window manager and the client.
+/// Under Wayland only XWayland windows are visible this way.
+#[cfg(target_os = "linux")]
+mod x11 {
+    use x11rb::connection::Connection as _;
+    use x11rb::errors::ReplyError;
+    use x11rb::protocol::xproto::{Atom, AtomEnum, ConnectionExt as _, Window};
+    use x11rb::rust_connection::RustConnection;
+    
+    use super::FocusedWindow;
+    
+    /// Longest title read, in 32-bit units.
+    const MAX_TITLE_LENGTH: u32 = 1024;
+    
+    pub struct X11Window {
+        connection: RustConnection,
+        root: Window,
+        net_active_window: Atom,
+        net_wm_name: Atom,
+        net_wm_pid: Atom,
+        utf8_string: Atom,
+    }
+    
+    impl X11Window {
+        pub fn connect() -> Result<X11Window, Box<dyn std::error::Error>> {
+            let (connection, screen) = x11rb::connect(None)?;
+            let root = connection.setup().roots[screen].root;
+            let atom = |name: &str| -> Result<Atom, Box<dyn std::error::Error>> {
+                Ok(connection.intern_atom(false, name.as_bytes())?.reply()?.atom)
+            };
+            let (net_active_window, net_wm_name, net_wm_pid, utf8_string) =
+                (atom("_NET_ACTIVE_WINDOW")?, atom("_NET_WM_NAME")?, atom("_NET_WM_PID")?, atom("UTF8_STRING")?);
+            
+            Ok(X11Window {
+                connection,
+                root,
+                net_active_window,
+                net_wm_name,
+                net_wm_pid,
+                utf8_string,
+            })
+        }
+        
+        /// Empty when no window has focus. Errors mean the connection is gone.
+        pub fn focused(&self) -> Result<FocusedWindow, Box<dyn std::error::Error>> {
+            let window = match self.active_window()? {
+                Some(window) => window,
+                None => return Ok(FocusedWindow::default()),
+            };
+            
+            Ok(FocusedWindow {
+                title: self.title(window)?,
+                pid: self.pid(window)?,
+            })
+        }
+        
+        fn title(&self, window: Window) -> Result<Option<String>, Box<dyn std::error::Error>> {
+            if let Some(name) = self.text_property(window, self.net_wm_name, self.utf8_string)? {
+                return Ok(Some(String::from_utf8_lossy(&name).into_owned()));
+            }
+            
+            // Older clients only set the Latin-1 WM_NAME
+            let name = self.text_property(window, AtomEnum::WM_NAME.into(), AtomEnum::STRING.into())?;
+            Ok(name.map(|name| name.iter().map(|&byte| byte as char).collect()))
+        }
+        
+        /// The bytes of a text property, or `None` when it is unset or empty or the
+        /// window is already gone.
+        fn text_property(&self, window: Window, property: Atom, kind: Atom) -> Result<Option<Vec<u8>>, Box<dyn std::error::Error>> {
+            match self.connection.get_property(false, window, property, kind, 0, MAX_TITLE_LENGTH)?.reply() {
+                Ok(reply) => Ok(Some(reply.value).filter(|value| !value.is_empty())),
+                Err(ReplyError::X11Error(_)) => Ok(None),
+                Err(e) => Err(e.into()),
+            }
+        }
+        
+        /// Set by the client itself, so only as trustworthy as the process drawing the window.
+        fn pid(&self, window: Window) -> Result<Option<u32>, Box<dyn std::error::Error>> {
+            match self.connection.get_property(false, window, self.net_wm_pid, AtomEnum::CARDINAL, 0, 1)?.reply() {
+                Ok(reply) => Ok(reply.value32().and_then(|mut values| values.next()).filter(|pid| *pid != 0)),
+                Err(ReplyError::X11Error(_)) => Ok(None),
+                Err(e) => Err(e.into()),
+            }
+        }
+        
+        fn active_window(&self) -> Result<Option<Window>, Box<dyn std::error::Error>> {
+            let reply = self
+                .connection
+                .get_property(false, self.root, self.net_active_window, AtomEnum::WINDOW, 0, 1)?
+                .reply()?;
+            Ok(reply.value32().and_then(|mut values| values.next()).filter(|window| *window != 0))
+        }
+    }
+}
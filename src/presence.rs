@@ -0,0 +1,295 @@
+use discord_rich_presence::activity;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::collections::BTreeMap;
+
+use crate::activity::{ActivityData, MediaInfo, PlaybackStatus};
+use crate::profile::PresenceSettings;
+use crate::template::{self, PresenceTemplate};
+
+/// The presence exactly as it will be sent, after templates and filters ran.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ComposedActivity {
+    pub details: Option<String>,
+    pub state: Option<String>,
+    pub large_image: String,
+    pub large_text: String,
+    pub start_timestamp: Option<i64>,
+    /// Shown by Discord as the remaining time, e.g. for a song.
+    #[serde(default)]
+    pub end_timestamp: Option<i64>,
+    #[serde(default)]
+    pub kind: ActivityKind,
+    /// Blacklisted words the word filter replaced in the text above.
+    #[serde(skip)]
+    pub filter_hits: usize,
+}
+
+/// The verb Discord shows in front of the application name.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityKind {
+    #[default]
+    Playing,
+    Listening,
+}
+
+/// Replaces each blacklisted word in filtered text.
+pub const FILTERED_MARKER: &str = "[FILTERED]";
+
+pub fn create_word_filter(blacklisted_words: &[String]) -> Regex {
+    if blacklisted_words.is_empty() {
+        return Regex::new(r"(?i)^$").unwrap(); // Match nothing
+    }
+    
+    let pattern = format!(
+        r"(?i)\b({})\b",
+        blacklisted_words.join("|")
+    );
+    
+    Regex::new(&pattern).unwrap_or_else(|_| Regex::new(r"(?i)^$").unwrap())
+}
+
+pub fn filter_text(word_filter: &Regex, text: &str) -> String {
+    word_filter.replace_all(text, FILTERED_MARKER).to_string()
+}
+
+/// Like `filter_text`, also returning how many words were replaced.
+fn filter_counted(word_filter: &Regex, text: &str) -> (String, usize) {
+    let hits = word_filter.find_iter(text).count();
+    (filter_text(word_filter, text), hits)
+}
+
+/// Filters the text of a template that was built from activity data, such as one a
+/// script returned, rather than written by the user. Returns the hits in all its lines.
+pub fn filter_template(word_filter: &Regex, template: &PresenceTemplate) -> (PresenceTemplate, usize) {
+    let mut hits = 0;
+    let mut filter = |text: &str| {
+        let (filtered, count) = filter_counted(word_filter, text);
+        hits += count;
+        filtered
+    };
+    
+    let filtered = PresenceTemplate {
+        details: filter(&template.details),
+        state: filter(&template.state),
+        large_image: template.large_image.as_deref().map(&mut filter),
+        large_text: template.large_text.as_deref().map(&mut filter),
+    };
+    (filtered, hits)
+}
+
+/// The playing track when the presence should switch to "Listening to" it.
+fn listening_to<'a>(
+    data: &'a ActivityData,
+    settings: &PresenceSettings,
+    template: Option<&PresenceTemplate>,
+    custom_message: Option<&str>,
+) -> Option<&'a MediaInfo> {
+    // A rule template or custom message says what the user is doing instead
+    if !settings.listening || template.is_some() || custom_message.is_some_and(|message| !message.is_empty()) {
+        return None;
+    }
+    
+    data.media.as_ref().filter(|media| media.status == PlaybackStatus::Playing)
+}
+
+/// Builds the presence for one update. A custom message always replaces the details
+/// line and a custom state the state line; `template` overrides the settings' own
+/// template (e.g. from a rule). While music plays and listening is enabled, the
+/// track replaces the settings' template and built-in layout.
+pub fn compose(
+    data: &ActivityData,
+    settings: &PresenceSettings,
+    template: Option<&PresenceTemplate>,
+    custom_message: Option<&str>,
+    custom_state: Option<&str>,
+    word_filter: &Regex,
+) -> ComposedActivity {
+    // Hits are counted as text is filtered, and only for text that ends up visible
+    let hits = Cell::new(0);
+    let filter = |text: &str| {
+        let (filtered, count) = filter_counted(word_filter, text);
+        hits.set(hits.get() + count);
+        filtered
+    };
+    
+    let filtered_custom_message = custom_message
+        .filter(|message| !message.is_empty())
+        .map(|message| filter_counted(word_filter, message));
+    let filtered_custom_state = custom_state
+        .filter(|state| !state.is_empty())
+        .map(|state| filter_counted(word_filter, state));
+    
+    let mut composed = ComposedActivity {
+        large_image: "default".to_string(),
+        large_text: "MultiRichPresence".to_string(),
+        start_timestamp: Some(data.current_time.timestamp()),
+        ..Default::default()
+    };
+    
+    match template.or(settings.template.as_ref()) {
+        Some(template) => {
+            let mut fields = template::activity_fields(data);
+            let mut field_hits = BTreeMap::new();
+            for (name, value) in fields.iter_mut() {
+                let (filtered, count) = filter_counted(word_filter, value);
+                *value = filtered;
+                field_hits.insert(name.clone(), count);
+            }
+            if let Some((ref message, _)) = filtered_custom_message {
+                fields.insert("message".to_string(), message.clone());
+            }
+            if let Some(ref profile) = settings.profile {
+                fields.insert("profile".to_string(), profile.clone());
+            }
+            
+            composed.details = Some(template::render(&template.details, &fields));
+            composed.state = Some(template::render(&template.state, &fields));
+            if let Some(ref image) = template.large_image {
+                composed.large_image = template::render(image, &fields);
+            }
+            if let Some(ref text) = template.large_text {
+                composed.large_text = template::render(text, &fields);
+            }
+            
+            // A custom message replaces the details line, so its fields are not shown
+            let mut shown = vec![&template.state];
+            if filtered_custom_message.is_none() {
+                shown.push(&template.details);
+            }
+            shown.extend(template.large_text.as_ref());
+            let used: usize = shown
+                .into_iter()
+                .flat_map(|text| template::placeholders(text))
+                .filter_map(|name| field_hits.get(&name))
+                .sum();
+            hits.set(hits.get() + used);
+        }
+        None => {
+            let mut details = String::new();
+            let mut state = String::new();
+            
+            if settings.show_system_stats {
+                details = format!(
+                    "CPU: {:.1}% | RAM: {:.1}%",
+                    data.system_stats.cpu_usage,
+                    data.system_stats.memory_usage
+                );
+            }
+            
+            if settings.show_time {
+                state.push_str(&format!("Time: {}", data.current_time.format("%H:%M:%S")));
+            }
+            
+            if settings.show_applications {
+                // The file, command or project says more about what is being done than the busiest process
+                let running = if let Some(ref editor) = data.editor {
+                    Some(format!("Editing {}", editor.file))
+                } else if let Some(ref terminal) = data.terminal {
+                    Some(format!("Running: {}", terminal.command_line()))
+                } else if let Some(ref git) = data.git {
+                    match git.branch {
+                        Some(ref branch) => Some(format!("Working on {} ({})", git.repo, branch)),
+                        None => Some(format!("Working on {}", git.repo)),
+                    }
+                } else {
+                    data.top_processes.first().map(|process| format!("Running: {}", process.name))
+                };
+                if let Some(running) = running {
+                    if !state.is_empty() {
+                        state.push_str(" | ");
+                    }
+                    state.push_str(&filter(&running));
+                }
+            }
+            
+            composed.details = Some(details);
+            composed.state = Some(state);
+            
+            if let Some(editor) = data.editor.as_ref().filter(|_| settings.show_applications) {
+                if let Some(ref language) = editor.language {
+                    composed.large_text = filter(&format!("Language: {}", language));
+                }
+                if let Some(ref asset) = editor.language_asset {
+                    composed.large_image = asset.clone();
+                }
+            }
+        }
+    }
+    
+    if let Some(media) = listening_to(data, settings, template, custom_message) {
+        let now = data.current_time.timestamp();
+        let position = media.position_seconds.unwrap_or(0.0) as i64;
+        
+        // The track replaces everything counted so far
+        hits.set(0);
+        composed.kind = ActivityKind::Listening;
+        composed.details = Some(filter(&media.title));
+        composed.state = media.artist.as_deref().map(|artist| format!("by {}", filter(artist)));
+        composed.large_text = filter(media.album.as_deref().unwrap_or(&media.player));
+        composed.start_timestamp = Some(now - position);
+        composed.end_timestamp = media.length_seconds.map(|length| now - position + length as i64);
+    }
+    
+    if let Some((message, count)) = filtered_custom_message {
+        composed.details = Some(message);
+        hits.set(hits.get() + count);
+    }
+    
+    if let Some((state, count)) = filtered_custom_state {
+        composed.state = Some(state);
+        hits.set(hits.get() + count);
+    }
+    
+    composed.details = composed.details.filter(|details| !details.is_empty());
+    composed.state = composed.state.filter(|state| !state.is_empty());
+    composed.filter_hits = hits.get();
+    composed
+}
+
+impl ComposedActivity {
+    /// A copy with `word_filter` applied to the visible text, for sinks with their own blacklist.
+    pub fn filtered(&self, word_filter: &Regex) -> ComposedActivity {
+        ComposedActivity {
+            details: self.details.as_deref().map(|details| filter_text(word_filter, details)),
+            state: self.state.as_deref().map(|state| filter_text(word_filter, state)),
+            large_text: filter_text(word_filter, &self.large_text),
+            ..self.clone()
+        }
+    }
+    
+    pub fn to_discord(&self) -> activity::Activity<'_> {
+        let mut activity_builder = activity::Activity::new();
+        
+        if let Some(ref details) = self.details {
+            activity_builder = activity_builder.details(details);
+        }
+        
+        if let Some(ref state) = self.state {
+            activity_builder = activity_builder.state(state);
+        }
+        
+        if self.start_timestamp.is_some() || self.end_timestamp.is_some() {
+            let mut timestamps = activity::Timestamps::new();
+            if let Some(start) = self.start_timestamp {
+                timestamps = timestamps.start(start);
+            }
+            if let Some(end) = self.end_timestamp {
+                timestamps = timestamps.end(end);
+            }
+            activity_builder = activity_builder.timestamps(timestamps);
+        }
+        
+        if self.kind == ActivityKind::Listening {
+            activity_builder = activity_builder.activity_type(activity::ActivityType::Listening);
+        }
+        
+        activity_builder.assets(
+            activity::Assets::new()
+                .large_image(&self.large_image)
+                .large_text(&self.large_text)
+        )
+    }
+}
@@ -0,0 +1,113 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::config;
+
+/// The longest pause `parse_duration` accepts.
+pub const MAX_PAUSE_SECS: u64 = 365 * 24 * 60 * 60;
+
+/// Panic-button state. While hidden, no presence is broadcast at all. It is written
+/// to disk before the activity is cleared so a crash or restart never re-broadcasts.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PrivacyState {
+    pub hidden: bool,
+    /// When set, presence resumes automatically at this time.
+    pub resume_at: Option<DateTime<Utc>>,
+}
+
+fn state_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    Ok(config::data_dir()?.join("privacy.json"))
+}
+
+impl PrivacyState {
+    pub fn load() -> Result<PrivacyState, Box<dyn std::error::Error>> {
+        let path = state_path()?;
+        
+        if path.exists() {
+            let content = std::fs::read_to_string(path)?;
+            Ok(serde_json::from_str(&content)?)
+        } else {
+            Ok(PrivacyState::default())
+        }
+    }
+    
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let path = state_path()?;
+        
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        
+        // Write then rename so a crash never leaves a truncated state file behind
+        let temp_path = path.with_extension("json.tmp");
+        std::fs::write(&temp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(temp_path, path)?;
+        Ok(())
+    }
+    
+    pub fn hide(&mut self, duration: Option<Duration>) {
+        self.hidden = true;
+        // A delay past the last representable time never resumes on its own
+        self.resume_at = duration.and_then(|duration| Utc::now().checked_add_signed(duration));
+    }
+    
+    pub fn resume(&mut self) {
+        self.hidden = false;
+        self.resume_at = None;
+    }
+    
+    /// True once the auto-resume timer has run out.
+    pub fn should_resume(&self, now: DateTime<Utc>) -> bool {
+        self.hidden && self.resume_at.is_some_and(|resume_at| now >= resume_at)
+    }
+    
+    pub fn remaining(&self, now: DateTime<Utc>) -> Option<Duration> {
+        self.resume_at
+            .filter(|_| self.hidden)
+            .map(|resume_at| (resume_at - now).max(Duration::zero()))
+    }
+}
+
+/// Parses pause durations like `90s`, `30m`, `2h`, `1d` or combinations such as `1h30m`,
+/// up to `MAX_PAUSE_SECS`.
+pub fn parse_duration(text: &str) -> Result<u64, String> {
+    let mut total: u64 = 0;
+    let mut number = String::new();
+    
+    for c in text.trim().chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 60 * 60,
+            'd' => 24 * 60 * 60,
+            _ => return Err(format!("invalid duration {:?}: unknown unit {:?}", text, c)),
+        };
+        if number.is_empty() {
+            return Err(format!("invalid duration {:?}: expected a number before {:?}", text, c));
+        }
+        // Digits that do not fit a u64 are far too long as well
+        total = number
+            .parse::<u64>()
+            .ok()
+            .and_then(|value| value.checked_mul(unit))
+            .and_then(|secs| total.checked_add(secs))
+            .filter(|total| *total <= MAX_PAUSE_SECS)
+            .ok_or_else(|| format!("invalid duration {:?}: longer than 365 days", text))?;
+        number.clear();
+    }
+    
+    if !number.is_empty() {
+        return Err(format!("invalid duration {:?}: missing unit (s, m, h or d)", text));
+    }
+    if total == 0 {
+        return Err(format!("invalid duration {:?}", text));
+    }
+    
+    Ok(total)
+}
@@ -0,0 +1,179 @@
+use serde::{Deserialize, Serialize};
+
+use crate::config::{ActivityFilters, Config};
+use crate::rules::Rule;
+use crate::template::PresenceTemplate;
+
+/// A named set of overrides on top of the base configuration or another profile.
+/// Unset fields are inherited; rules are prepended to the inherited ones.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Profile {
+    pub name: String,
+    /// Profile to inherit from. The base configuration is used when unset.
+    #[serde(default)]
+    pub inherits: Option<String>,
+    #[serde(default)]
+    pub discord_app_id: Option<String>,
+    #[serde(default)]
+    pub template: Option<PresenceTemplate>,
+    #[serde(default)]
+    pub blacklisted_words: Option<Vec<String>>,
+    #[serde(default)]
+    pub show_system_stats: Option<bool>,
+    #[serde(default)]
+    pub show_time: Option<bool>,
+    #[serde(default)]
+    pub show_applications: Option<bool>,
+    #[serde(default)]
+    pub activity_filters: Option<ActivityFilters>,
+    /// Overrides `providers.media.listening`.
+    #[serde(default)]
+    pub listening: Option<bool>,
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+}
+
+/// The effective presence settings after resolving a profile's inheritance chain.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PresenceSettings {
+    pub profile: Option<String>,
+    pub discord_app_id: String,
+    pub blacklisted_words: Vec<String>,
+    pub show_system_stats: bool,
+    pub show_time: bool,
+    pub show_applications: bool,
+    pub activity_filters: ActivityFilters,
+    /// Switch to a "Listening to" presence while a media player is playing.
+    pub listening: bool,
+    pub template: Option<PresenceTemplate>,
+    pub rules: Vec<Rule>,
+}
+
+impl Profile {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+    
+    fn apply(&self, settings: &mut PresenceSettings) {
+        settings.profile = Some(self.name.clone());
+        
+        if let Some(ref app_id) = self.discord_app_id {
+            settings.discord_app_id = app_id.clone();
+        }
+        if let Some(ref template) = self.template {
+            settings.template = Some(template.clone());
+        }
+        if let Some(ref words) = self.blacklisted_words {
+            settings.blacklisted_words = words.clone();
+        }
+        if let Some(show) = self.show_system_stats {
+            settings.show_system_stats = show;
+        }
+        if let Some(show) = self.show_time {
+            settings.show_time = show;
+        }
+        if let Some(show) = self.show_applications {
+            settings.show_applications = show;
+        }
+        if let Some(ref filters) = self.activity_filters {
+            settings.activity_filters = filters.clone();
+        }
+        if let Some(listening) = self.listening {
+            settings.listening = listening;
+        }
+        
+        let mut rules = self.rules.clone();
+        rules.append(&mut settings.rules);
+        settings.rules = rules;
+    }
+}
+
+impl Config {
+    pub fn profile(&self, name: &str) -> Option<&Profile> {
+        self.profiles.iter().find(|profile| profile.name == name)
+    }
+    
+    pub fn base_settings(&self) -> PresenceSettings {
+        PresenceSettings {
+            profile: None,
+            discord_app_id: self.discord_app_id.clone(),
+            blacklisted_words: self.blacklisted_words.clone(),
+            show_system_stats: self.show_system_stats,
+            show_time: self.show_time,
+            show_applications: self.show_applications,
+            activity_filters: self.activity_filters.clone(),
+            listening: self.providers.media.listening,
+            template: self.template.clone(),
+            rules: self.rules.clone(),
+        }
+    }
+    
+    /// Resolves `name` against its inheritance chain. Unknown profiles fall back to
+    /// the base settings and inheritance cycles are cut at the first repeat.
+    pub fn settings_for(&self, name: Option<&str>) -> PresenceSettings {
+        let mut chain: Vec<&Profile> = Vec::new();
+        let mut next = name;
+        
+        while let Some(current) = next {
+            if chain.iter().any(|profile| profile.name == current) {
+                log::warn!("Profile inheritance cycle at {:?}", current);
+                break;
+            }
+            
+            match self.profile(current) {
+                Some(profile) => {
+                    chain.push(profile);
+                    next = profile.inherits.as_deref();
+                }
+                None => {
+                    log::warn!("Unknown profile {:?}", current);
+                    break;
+                }
+            }
+        }
+        
+        let mut settings = self.base_settings();
+        for profile in chain.iter().rev() {
+            profile.apply(&mut settings);
+        }
+        settings
+    }
+    
+    /// Where new rules for `name` go: the profile's own rules, which take precedence over
+    /// the inherited ones, or the base rules when no known profile is active.
+    pub fn rules_mut(&mut self, name: Option<&str>) -> &mut Vec<Rule> {
+        match name.and_then(|name| self.profiles.iter().position(|profile| profile.name == name)) {
+            Some(index) => &mut self.profiles[index].rules,
+            None => &mut self.rules,
+        }
+    }
+    
+    /// The activity filters in effect for `name`, for editing: those of the nearest
+    /// profile in its chain that overrides them, or the base filters.
+    pub fn activity_filters_mut(&mut self, name: Option<&str>) -> &mut ActivityFilters {
+        let mut visited: Vec<&str> = Vec::new();
+        let mut next = name;
+        let mut owner = None;
+        
+        while let Some(current) = next.filter(|current| !visited.contains(current)) {
+            visited.push(current);
+            let index = match self.profiles.iter().position(|profile| profile.name == current) {
+                Some(index) => index,
+                None => break,
+            };
+            if self.profiles[index].activity_filters.is_some() {
+                owner = Some(index);
+                break;
+            }
+            next = self.profiles[index].inherits.as_deref();
+        }
+        
+        match owner {
+            Some(index) => self.profiles[index].activity_filters.get_or_insert_with(ActivityFilters::default),
+            None => &mut self.activity_filters,
+        }
+    }
+}
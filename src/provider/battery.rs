@@ -0,0 +1,132 @@
+use serde::{Deserialize, Serialize};
+
+/// Battery charge and charging state from `/sys/class/power_supply`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BatteryConfig {
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+#[cfg(target_os = "linux")]
+pub use sysfs::BatteryProvider;
+
+#[cfg(target_os = "linux")]
+mod sysfs {
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    
+    use crate::activity::{ActivityData, BatteryInfo, BatteryStatus};
+    use crate::provider::Provider;
+    
+    /// One battery as read from sysfs.
+    struct Battery {
+        /// Energy (µWh) or charge (µAh) now and when full, when the battery reports them.
+        now_and_full: Option<(u64, u64)>,
+        capacity: f32,
+        status: String,
+    }
+    
+    /// Combines all system batteries, weighted by their size when it is known.
+    pub struct BatteryProvider {
+        sysfs_root: PathBuf,
+    }
+    
+    impl Default for BatteryProvider {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+    
+    impl BatteryProvider {
+        pub fn new() -> Self {
+            Self::with_sysfs_root("/sys")
+        }
+        
+        /// Reads batteries from a different `/sys`, e.g. a fake tree in tests.
+        pub fn with_sysfs_root(sysfs_root: impl Into<PathBuf>) -> Self {
+            Self {
+                sysfs_root: sysfs_root.into(),
+            }
+        }
+        
+        fn batteries(&self) -> Vec<Battery> {
+            let entries = match fs::read_dir(self.sysfs_root.join("class/power_supply")) {
+                Ok(entries) => entries,
+                Err(_) => return Vec::new(),
+            };
+            
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                // Wireless mice and headsets report a battery with the "Device" scope
+                .filter(|dir| read(dir, "type").as_deref() == Some("Battery") && read(dir, "scope").as_deref() != Some("Device"))
+                .filter_map(|dir| {
+                    let now_and_full = number(&dir, "energy_now")
+                        .zip(number(&dir, "energy_full"))
+                        .or_else(|| number(&dir, "charge_now").zip(number(&dir, "charge_full")))
+                        .filter(|(_, full)| *full > 0);
+                    let capacity = match now_and_full {
+                        Some((now, full)) => now as f32 / full as f32 * 100.0,
+                        None => number(&dir, "capacity")? as f32,
+                    };
+                    Some(Battery {
+                        now_and_full,
+                        capacity,
+                        status: read(&dir, "status").unwrap_or_default(),
+                    })
+                })
+                .collect()
+        }
+        
+        pub fn current(&self) -> Option<BatteryInfo> {
+            let batteries = self.batteries();
+            if batteries.is_empty() {
+                return None;
+            }
+            
+            let sizes: Option<Vec<(u64, u64)>> = batteries.iter().map(|battery| battery.now_and_full).collect();
+            let percent = match sizes {
+                Some(sizes) => {
+                    let (now, full) = sizes.iter().fold((0, 0), |(now, full), size| (now + size.0, full + size.1));
+                    now as f32 / full as f32 * 100.0
+                }
+                None => batteries.iter().map(|battery| battery.capacity).sum::<f32>() / batteries.len() as f32,
+            };
+            
+            let any = |status: &str| batteries.iter().any(|battery| battery.status == status);
+            let status = if any("Charging") {
+                BatteryStatus::Charging
+            } else if any("Discharging") {
+                BatteryStatus::Discharging
+            } else if batteries.iter().all(|battery| battery.status == "Full") {
+                BatteryStatus::Full
+            } else {
+                BatteryStatus::NotCharging
+            };
+            
+            Some(BatteryInfo {
+                percent: percent.clamp(0.0, 100.0),
+                status,
+            })
+        }
+    }
+    
+    impl Provider for BatteryProvider {
+        fn name(&self) -> &str {
+            "battery"
+        }
+        
+        fn sample(&mut self, data: &mut ActivityData) -> Result<(), Box<dyn std::error::Error>> {
+            data.battery = self.current();
+            Ok(())
+        }
+    }
+    
+    fn read(dir: &Path, name: &str) -> Option<String> {
+        fs::read_to_string(dir.join(name)).ok().map(|text| text.trim().to_string())
+    }
+    
+    fn number(dir: &Path, name: &str) -> Option<u64> {
+        read(dir, name)?.parse().ok()
+    }
+}
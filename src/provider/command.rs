@@ -0,0 +1,267 @@
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::activity::{ActivityData, CommandStatus};
+use crate::provider::Provider;
+
+/// Output beyond this many bytes is ignored.
+const MAX_OUTPUT: u64 = 64 * 1024;
+
+/// How long output is still read after the command exited.
+const PIPE_GRACE: Duration = Duration::from_secs(1);
+
+/// A user-configured command whose output becomes template fields.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CommandSource {
+    /// Namespace of the fields: `{name}` for text, `{name.key}` for JSON.
+    pub name: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Program to run, or the command line when `shell` is set.
+    pub command: String,
+    /// Arguments, passed as they are. With `shell` they are `$1`, `$2`, ...
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Run `command` through `sh -c` (`cmd /C` on Windows), which allows pipes and
+    /// variables but also makes quoting mistakes dangerous.
+    #[serde(default)]
+    pub shell: bool,
+    #[serde(default = "default_interval_seconds")]
+    pub interval_seconds: u64,
+    #[serde(default = "default_timeout_seconds")]
+    pub timeout_seconds: u64,
+    #[serde(default)]
+    pub output: CommandOutput,
+}
+
+/// How stdout is turned into fields.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommandOutput {
+    /// The first non-empty line.
+    #[default]
+    Text,
+    /// An object whose values, nested ones joined with dots, become fields.
+    Json,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_interval_seconds() -> u64 {
+    60
+}
+
+fn default_timeout_seconds() -> u64 {
+    5
+}
+
+impl CommandSource {
+    pub fn new(name: &str, command: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            enabled: default_enabled(),
+            command: command.to_string(),
+            args: Vec::new(),
+            shell: false,
+            interval_seconds: default_interval_seconds(),
+            timeout_seconds: default_timeout_seconds(),
+            output: CommandOutput::default(),
+        }
+    }
+    
+    fn process(&self) -> Command {
+        if !self.shell {
+            let mut command = Command::new(&self.command);
+            command.args(&self.args);
+            return command;
+        }
+        
+        if cfg!(windows) {
+            let mut command = Command::new("cmd");
+            command.arg("/C").arg(&self.command).args(&self.args);
+            command
+        } else {
+            // The word after the script is $0, so the arguments start at $1
+            let mut command = Command::new("sh");
+            command.arg("-c").arg(&self.command).arg(&self.name).args(&self.args);
+            command
+        }
+    }
+    
+    /// Runs the command once and parses its output into fields.
+    pub fn run(&self) -> Result<BTreeMap<String, String>, String> {
+        let mut child = self
+            .process()
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("cannot start {:?}: {}", self.command, e))?;
+        
+        // Read on separate threads so a chatty command cannot block on a full pipe
+        let stdout = read_in_background(child.stdout.take());
+        let stderr = read_in_background(child.stderr.take());
+        
+        let deadline = Instant::now() + Duration::from_secs(self.timeout_seconds);
+        let status = loop {
+            match child.try_wait().map_err(|e| e.to_string())? {
+                Some(status) => break status,
+                None if Instant::now() >= deadline => {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return Err(format!("timed out after {} s", self.timeout_seconds));
+                }
+                None => std::thread::sleep(Duration::from_millis(20)),
+            }
+        };
+        
+        // Background processes started by the command may keep the pipes open
+        let stdout = stdout.recv_timeout(PIPE_GRACE).unwrap_or_default();
+        if !status.success() {
+            let stderr = stderr.recv_timeout(PIPE_GRACE).unwrap_or_default();
+            let reason = stderr.lines().map(str::trim).find(|line| !line.is_empty()).unwrap_or_default().to_string();
+            return Err(match status.code() {
+                Some(code) if reason.is_empty() => format!("exited with status {}", code),
+                Some(code) => format!("exited with status {}: {}", code, reason),
+                None => "killed by a signal".to_string(),
+            });
+        }
+        
+        parse_output(&self.name, self.output, &stdout)
+    }
+}
+
+fn read_in_background(pipe: Option<impl Read + Send + 'static>) -> mpsc::Receiver<String> {
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut bytes = Vec::new();
+        if let Some(pipe) = pipe {
+            let _ = pipe.take(MAX_OUTPUT).read_to_end(&mut bytes);
+        }
+        let _ = sender.send(String::from_utf8_lossy(&bytes).to_string());
+    });
+    receiver
+}
+
+/// Turns command output into fields under the `name` namespace.
+pub fn parse_output(name: &str, output: CommandOutput, stdout: &str) -> Result<BTreeMap<String, String>, String> {
+    let mut fields = BTreeMap::new();
+    match output {
+        CommandOutput::Text => {
+            let line = stdout.lines().map(str::trim).find(|line| !line.is_empty()).unwrap_or_default();
+            fields.insert(name.to_string(), line.to_string());
+        }
+        CommandOutput::Json => {
+            let value: Value = serde_json::from_str(stdout).map_err(|e| format!("invalid JSON output: {}", e))?;
+            flatten(name, &value, &mut fields);
+        }
+    }
+    Ok(fields)
+}
+
+/// `{"ticket": {"key": "WEB-42"}}` becomes `name.ticket.key = WEB-42`; array items are
+/// numbered from 0 and scalars at the top level become `name` itself.
+fn flatten(prefix: &str, value: &Value, fields: &mut BTreeMap<String, String>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                flatten(&format!("{}.{}", prefix, key), value, fields);
+            }
+        }
+        Value::Array(items) => {
+            for (i, value) in items.iter().enumerate() {
+                flatten(&format!("{}.{}", prefix, i), value, fields);
+            }
+        }
+        Value::String(text) => {
+            fields.insert(prefix.to_string(), text.clone());
+        }
+        Value::Null => {
+            fields.insert(prefix.to_string(), String::new());
+        }
+        other => {
+            fields.insert(prefix.to_string(), other.to_string());
+        }
+    }
+}
+
+/// Runs each enabled command on its own thread and interval, keeping the last good
+/// fields and the last error. Sampling only copies that state, so a slow command never
+/// holds up the monitoring thread.
+pub struct CommandProvider {
+    statuses: Vec<Arc<Mutex<CommandStatus>>>,
+    // Dropping the senders wakes the command threads and ends them
+    _stop: Vec<mpsc::Sender<()>>,
+}
+
+impl CommandProvider {
+    pub fn new(sources: &[CommandSource]) -> Self {
+        let mut statuses = Vec::new();
+        let mut stop = Vec::new();
+        
+        for source in sources.iter().filter(|source| source.enabled) {
+            let status = Arc::new(Mutex::new(CommandStatus {
+                name: source.name.clone(),
+                fields: BTreeMap::new(),
+                error: None,
+                last_run: None,
+            }));
+            let (sender, receiver) = mpsc::channel::<()>();
+            
+            let source = source.clone();
+            let shared = Arc::clone(&status);
+            std::thread::spawn(move || loop {
+                let result = source.run();
+                if let Ok(mut status) = shared.lock() {
+                    status.last_run = Some(Local::now());
+                    match result {
+                        Ok(fields) => {
+                            status.fields = fields;
+                            status.error = None;
+                        }
+                        Err(e) => {
+                            log::warn!("Command {} failed: {}", source.name, e);
+                            status.error = Some(e);
+                        }
+                    }
+                }
+                
+                match receiver.recv_timeout(Duration::from_secs(source.interval_seconds.max(1))) {
+                    Err(RecvTimeoutError::Timeout) => continue,
+                    _ => break,
+                }
+            });
+            
+            statuses.push(status);
+            stop.push(sender);
+        }
+        
+        Self { statuses, _stop: stop }
+    }
+    
+    /// The current state of every command, in config order.
+    pub fn statuses(&self) -> Vec<CommandStatus> {
+        self.statuses.iter().filter_map(|status| status.lock().ok().map(|status| status.clone())).collect()
+    }
+}
+
+impl Provider for CommandProvider {
+    fn name(&self) -> &str {
+        "command"
+    }
+    
+    fn sample(&mut self, data: &mut ActivityData) -> Result<(), Box<dyn std::error::Error>> {
+        data.commands = self.statuses();
+        Ok(())
+    }
+}
+
@@ -0,0 +1,214 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Running Docker or Podman containers, listed through the Docker-compatible API.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ContainersConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// API socket; found automatically when unset.
+    #[serde(default)]
+    pub socket: Option<PathBuf>,
+    /// Containers whose name, image or compose project matches one of these are left out.
+    /// `*` matches any text, e.g. `buildx_*`.
+    #[serde(default)]
+    pub hidden: Vec<String>,
+}
+
+/// Matches a `hidden` entry, where `*` stands for any text.
+fn glob(pattern: &str) -> Regex {
+    let pattern = pattern.split('*').map(regex::escape).collect::<Vec<_>>().join(".*");
+    Regex::new(&format!("^{}$", pattern)).unwrap()
+}
+
+/// The `hidden` entries of a config, compiled once.
+pub struct HiddenContainers {
+    patterns: Vec<Regex>,
+}
+
+impl HiddenContainers {
+    pub fn new(hidden: &[String]) -> Self {
+        Self {
+            patterns: hidden.iter().map(|pattern| glob(pattern)).collect(),
+        }
+    }
+    
+    /// Whether any of `values` (name, image or project) matches a `hidden` entry. Images
+    /// also match without their tag, so `postgres` hides `postgres:16`.
+    pub fn matches(&self, values: &[&str]) -> bool {
+        values.iter().any(|value| {
+            let untagged = match value.rsplit_once(':') {
+                Some((image, tag)) if !tag.contains('/') => image,
+                _ => value,
+            };
+            self.patterns.iter().any(|pattern| pattern.is_match(value) || pattern.is_match(untagged))
+        })
+    }
+}
+
+impl ContainersConfig {
+    /// The configured socket, or the first Docker or Podman socket that exists.
+    pub fn socket_path(&self) -> Option<PathBuf> {
+        if let Some(ref socket) = self.socket {
+            return Some(socket.clone());
+        }
+        
+        let mut candidates = Vec::new();
+        for variable in ["DOCKER_HOST", "CONTAINER_HOST"] {
+            if let Some(path) = std::env::var(variable).ok().and_then(|host| host.strip_prefix("unix://").map(PathBuf::from)) {
+                candidates.push(path);
+            }
+        }
+        candidates.push(PathBuf::from("/var/run/docker.sock"));
+        if let Some(runtime_dir) = std::env::var_os("XDG_RUNTIME_DIR") {
+            candidates.push(PathBuf::from(runtime_dir).join("podman/podman.sock"));
+        }
+        candidates.push(PathBuf::from("/run/podman/podman.sock"));
+        if let Some(home) = std::env::var_os("HOME") {
+            candidates.push(PathBuf::from(home).join(".docker/run/docker.sock"));
+        }
+        
+        candidates.into_iter().find(|path| path.exists())
+    }
+}
+
+#[cfg(unix)]
+pub use api::ContainersProvider;
+
+#[cfg(unix)]
+mod api {
+    use serde::Deserialize;
+    use std::collections::BTreeMap;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::os::unix::net::UnixStream;
+    use std::path::Path;
+    use std::time::Duration;
+    
+    use super::{ContainersConfig, HiddenContainers};
+    use crate::activity::{ActivityData, ContainerInfo};
+    use crate::provider::Provider;
+    
+    const TIMEOUT: Duration = Duration::from_secs(2);
+    const COMPOSE_PROJECT_LABEL: &str = "com.docker.compose.project";
+    
+    /// One entry of `GET /containers/json`.
+    #[derive(Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct Container {
+        #[serde(default)]
+        names: Vec<String>,
+        #[serde(default)]
+        image: String,
+        #[serde(default)]
+        labels: Option<BTreeMap<String, String>>,
+    }
+    
+    pub struct ContainersProvider {
+        config: ContainersConfig,
+        hidden: HiddenContainers,
+    }
+    
+    impl ContainersProvider {
+        pub fn new(config: ContainersConfig) -> Self {
+            Self {
+                hidden: HiddenContainers::new(&config.hidden),
+                config,
+            }
+        }
+        
+        /// Running containers, newest first, without the hidden ones.
+        pub fn current(&self) -> Result<Vec<ContainerInfo>, Box<dyn std::error::Error>> {
+            let socket = self.config.socket_path().ok_or("no Docker or Podman socket found")?;
+            // Without `all=true` only running containers are listed
+            let body = get(&socket, "/containers/json")?;
+            let containers: Vec<Container> = serde_json::from_slice(&body)?;
+            
+            Ok(containers
+                .into_iter()
+                .filter_map(|container| {
+                    let name = container.names.first()?.trim_start_matches('/').to_string();
+                    let project = container.labels.and_then(|mut labels| labels.remove(COMPOSE_PROJECT_LABEL));
+                    let values = [name.as_str(), container.image.as_str(), project.as_deref().unwrap_or_default()];
+                    if self.hidden.matches(&values) {
+                        return None;
+                    }
+                    Some(ContainerInfo {
+                        name,
+                        image: container.image,
+                        project,
+                    })
+                })
+                .collect())
+        }
+    }
+    
+    impl Provider for ContainersProvider {
+        fn name(&self) -> &str {
+            "containers"
+        }
+        
+        fn sample(&mut self, data: &mut ActivityData) -> Result<(), Box<dyn std::error::Error>> {
+            data.containers = Some(self.current()?);
+            Ok(())
+        }
+    }
+    
+    /// A minimal HTTP/1.1 GET over a Unix socket, returning the body of a 200 response.
+    fn get(socket: &Path, path: &str) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let mut stream = UnixStream::connect(socket).map_err(|e| format!("cannot connect to {}: {}", socket.display(), e))?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        stream.set_write_timeout(Some(TIMEOUT))?;
+        write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n", path)?;
+        
+        let mut reader = BufReader::new(stream);
+        let mut status_line = String::new();
+        reader.read_line(&mut status_line)?;
+        let status = status_line.split_whitespace().nth(1).ok_or("malformed HTTP response")?;
+        if status != "200" {
+            return Err(format!("container API answered {}", status_line.trim()).into());
+        }
+        
+        let mut content_length = None;
+        let mut chunked = false;
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                let value = value.trim();
+                match name.trim().to_ascii_lowercase().as_str() {
+                    "content-length" => content_length = Some(value.parse::<usize>()?),
+                    "transfer-encoding" => chunked = value.eq_ignore_ascii_case("chunked"),
+                    _ => {}
+                }
+            }
+        }
+        
+        let mut body = Vec::new();
+        if chunked {
+            loop {
+                let mut size_line = String::new();
+                reader.read_line(&mut size_line)?;
+                let size_text = size_line.trim().split(';').next().unwrap_or_default();
+                let size = usize::from_str_radix(size_text, 16)?;
+                if size == 0 {
+                    break;
+                }
+                let start = body.len();
+                body.resize(start + size, 0);
+                reader.read_exact(&mut body[start..])?;
+                // Each chunk ends with CRLF
+                reader.read_line(&mut String::new())?;
+            }
+        } else if let Some(length) = content_length {
+            body.resize(length, 0);
+            reader.read_exact(&mut body)?;
+        } else {
+            reader.read_to_end(&mut body)?;
+        }
+        
+        Ok(body)
+    }
+}
@@ -0,0 +1,71 @@
+use serde::{Deserialize, Serialize};
+use sysinfo::Disks;
+
+use crate::activity::{ActivityData, DiskUsage};
+use crate::provider::Provider;
+
+/// Used and total space of selected mount points, read through sysinfo.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiskConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Mount points to report, in this order; the first one backs the `{disk_*}` fields.
+    #[serde(default = "default_mounts")]
+    pub mounts: Vec<String>,
+}
+
+fn default_mounts() -> Vec<String> {
+    if cfg!(windows) {
+        vec!["C:\\".to_string()]
+    } else {
+        vec!["/".to_string()]
+    }
+}
+
+impl Default for DiskConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            mounts: default_mounts(),
+        }
+    }
+}
+
+pub struct DiskProvider {
+    config: DiskConfig,
+    disks: Disks,
+}
+
+impl DiskProvider {
+    pub fn new(config: DiskConfig) -> Self {
+        Self {
+            config,
+            disks: Disks::new_with_refreshed_list(),
+        }
+    }
+}
+
+impl Provider for DiskProvider {
+    fn name(&self) -> &str {
+        "disk"
+    }
+    
+    fn sample(&mut self, data: &mut ActivityData) -> Result<(), Box<dyn std::error::Error>> {
+        // Removable drives come and go, so the list is refreshed along with the usage
+        self.disks.refresh_list();
+        data.disks = self
+            .config
+            .mounts
+            .iter()
+            .filter_map(|mount| {
+                let disk = self.disks.list().iter().find(|disk| disk.mount_point() == std::path::Path::new(mount))?;
+                Some(DiskUsage {
+                    mount: mount.clone(),
+                    total: disk.total_space(),
+                    used: disk.total_space().saturating_sub(disk.available_space()),
+                })
+            })
+            .collect();
+        Ok(())
+    }
+}
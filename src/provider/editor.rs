@@ -0,0 +1,273 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::Provider;
+use crate::activity::{ActivityData, EditorInfo};
+
+/// Editors and IDEs, recognized from the active window title or told by editor plugins
+/// over the control socket.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EditorConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Tried in order against the active window title.
+    #[serde(default = "default_patterns")]
+    pub patterns: Vec<EditorPattern>,
+    /// Extra or overriding language mappings, keyed by file extension or file name.
+    #[serde(default)]
+    pub languages: BTreeMap<String, Language>,
+    /// How long an update pushed by a plugin wins over window titles.
+    #[serde(default = "default_plugin_timeout")]
+    pub plugin_timeout_seconds: u64,
+}
+
+/// A window title pattern. The regex needs a `file` group and may have a `project` group.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EditorPattern {
+    pub editor: String,
+    pub title: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Language {
+    pub name: String,
+    /// Image key in the Discord application's assets.
+    pub asset: String,
+}
+
+fn default_patterns() -> Vec<EditorPattern> {
+    let pattern = |editor: &str, title: &str| EditorPattern {
+        editor: editor.to_string(),
+        title: title.to_string(),
+    };
+    
+    vec![
+        // "● main.rs - multi-rich-presence - Visual Studio Code"
+        pattern(
+            "VS Code",
+            r"^(?:● )?(?P<file>.+?)(?: - (?P<project>.+?))? - (?:Visual Studio Code|VSCodium)(?: - Insiders)?$",
+        ),
+        // "multi-rich-presence – main.rs" or "project [~/src/project] – .../src/main.rs – RustRover"
+        pattern(
+            "JetBrains",
+            r"^(?P<project>.+?)(?: \[.*\])? – (?:.*/)?(?P<file>[^/\s]+\.\w+)(?: – (?:IntelliJ IDEA|PyCharm|CLion|GoLand|RustRover|WebStorm|PhpStorm|Rider|RubyMine|DataGrip|Android Studio).*)?$",
+        ),
+        // "main.rs + (~/src/multi-rich-presence) - NVIM"
+        pattern("Neovim", r"^(?P<file>.+?) (?:[-+=]+ )?\((?P<project>.+?)\) - N?VIM$"),
+        // "main.rs - GNU Emacs at laptop"
+        pattern("Emacs", r"^(?P<file>.+?) - GNU Emacs(?: at .*)?$"),
+    ]
+}
+
+fn default_plugin_timeout() -> u64 {
+    120
+}
+
+impl Default for EditorConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            patterns: default_patterns(),
+            languages: BTreeMap::new(),
+            plugin_timeout_seconds: default_plugin_timeout(),
+        }
+    }
+}
+
+/// Built-in languages as (extension or file name, name, asset key).
+const LANGUAGES: &[(&str, &str, &str)] = &[
+    ("rs", "Rust", "rust"),
+    ("py", "Python", "python"),
+    ("js", "JavaScript", "javascript"),
+    ("mjs", "JavaScript", "javascript"),
+    ("ts", "TypeScript", "typescript"),
+    ("jsx", "React", "react"),
+    ("tsx", "React", "react"),
+    ("go", "Go", "go"),
+    ("c", "C", "c"),
+    ("h", "C", "c"),
+    ("cpp", "C++", "cpp"),
+    ("cc", "C++", "cpp"),
+    ("hpp", "C++", "cpp"),
+    ("cs", "C#", "csharp"),
+    ("java", "Java", "java"),
+    ("kt", "Kotlin", "kotlin"),
+    ("swift", "Swift", "swift"),
+    ("rb", "Ruby", "ruby"),
+    ("php", "PHP", "php"),
+    ("lua", "Lua", "lua"),
+    ("zig", "Zig", "zig"),
+    ("hs", "Haskell", "haskell"),
+    ("ex", "Elixir", "elixir"),
+    ("exs", "Elixir", "elixir"),
+    ("dart", "Dart", "dart"),
+    ("nix", "Nix", "nix"),
+    ("el", "Emacs Lisp", "emacs-lisp"),
+    ("vim", "Vim Script", "vim"),
+    ("sh", "Shell", "shell"),
+    ("bash", "Shell", "shell"),
+    ("zsh", "Shell", "shell"),
+    ("sql", "SQL", "sql"),
+    ("html", "HTML", "html"),
+    ("css", "CSS", "css"),
+    ("scss", "Sass", "sass"),
+    ("md", "Markdown", "markdown"),
+    ("json", "JSON", "json"),
+    ("toml", "TOML", "toml"),
+    ("yaml", "YAML", "yaml"),
+    ("yml", "YAML", "yaml"),
+    ("dockerfile", "Docker", "docker"),
+    ("makefile", "Makefile", "makefile"),
+];
+
+/// The language of `file`, looked up by file name first and then by extension.
+/// `overrides` wins over the built-in table.
+pub fn language_for(file: &str, overrides: &BTreeMap<String, Language>) -> Option<Language> {
+    let name = file.to_lowercase();
+    let extension = Path::new(&name).extension().map(|extension| extension.to_string_lossy().to_string());
+    
+    [Some(name), extension].into_iter().flatten().find_map(|key| {
+        overrides.get(&key).cloned().or_else(|| {
+            LANGUAGES
+                .iter()
+                .find(|(known, _, _)| *known == key)
+                .map(|(_, name, asset)| Language {
+                    name: name.to_string(),
+                    asset: asset.to_string(),
+                })
+        })
+    })
+}
+
+/// What an editor plugin reports; unset fields are filled in like for window titles.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EditorUpdate {
+    #[serde(default)]
+    pub editor: Option<String>,
+    pub file: String,
+    #[serde(default)]
+    pub project: Option<String>,
+    #[serde(default)]
+    pub language: Option<String>,
+}
+
+impl EditorUpdate {
+    /// Only the file name is kept so full paths never end up in the presence.
+    pub fn resolve(&self, config: &EditorConfig) -> EditorInfo {
+        let file = Path::new(&self.file)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| self.file.clone());
+        let detected = language_for(&file, &config.languages);
+        
+        EditorInfo {
+            editor: self.editor.clone().unwrap_or_else(|| "Editor".to_string()),
+            project: self.project.clone(),
+            language: self.language.clone().or_else(|| detected.as_ref().map(|language| language.name.clone())),
+            language_asset: detected.map(|language| language.asset),
+            file,
+            from_plugin: true,
+        }
+    }
+}
+
+/// The latest plugin update, shared between the engine and the editor provider.
+#[derive(Debug, Clone)]
+pub struct PushedEditor {
+    pub info: EditorInfo,
+    pub received: Instant,
+}
+
+/// The editor title patterns, compiled once for the config they came from.
+#[derive(Debug)]
+pub struct TitleParser {
+    config: EditorConfig,
+    patterns: Vec<(String, Regex)>,
+}
+
+impl TitleParser {
+    /// Invalid patterns are logged and left out; `Config::validate` rejects them.
+    pub fn new(config: &EditorConfig) -> Self {
+        let patterns = config
+            .patterns
+            .iter()
+            .filter_map(|pattern| match Regex::new(&pattern.title) {
+                Ok(regex) => Some((pattern.editor.clone(), regex)),
+                Err(e) => {
+                    log::warn!("Invalid title pattern for {}: {}", pattern.editor, e);
+                    None
+                }
+            })
+            .collect();
+        
+        Self {
+            config: config.clone(),
+            patterns,
+        }
+    }
+    
+    pub fn config(&self) -> &EditorConfig {
+        &self.config
+    }
+    
+    /// Matches a window title against the editor patterns.
+    pub fn parse(&self, title: &str) -> Option<EditorInfo> {
+        self.patterns.iter().find_map(|(editor, regex)| {
+            let captures = regex.captures(title)?;
+            let file = captures.name("file")?.as_str().trim().to_string();
+            let language = language_for(&file, &self.config.languages);
+            
+            Some(EditorInfo {
+                editor: editor.clone(),
+                project: captures.name("project").map(|project| project.as_str().trim().to_string()),
+                language: language.as_ref().map(|language| language.name.clone()),
+                language_asset: language.map(|language| language.asset),
+                file,
+                from_plugin: false,
+            })
+        })
+    }
+}
+
+pub struct EditorProvider {
+    titles: TitleParser,
+    pushed: Arc<Mutex<Option<PushedEditor>>>,
+}
+
+impl EditorProvider {
+    pub fn new(config: EditorConfig, pushed: Arc<Mutex<Option<PushedEditor>>>) -> Self {
+        Self {
+            titles: TitleParser::new(&config),
+            pushed,
+        }
+    }
+}
+
+impl Provider for EditorProvider {
+    fn name(&self) -> &str {
+        "editor"
+    }
+    
+    fn sample(&mut self, data: &mut ActivityData) -> Result<(), Box<dyn std::error::Error>> {
+        let timeout = Duration::from_secs(self.titles.config().plugin_timeout_seconds);
+        let pushed = self
+            .pushed
+            .lock()
+            .ok()
+            .and_then(|pushed| pushed.clone())
+            .filter(|pushed| pushed.received.elapsed() < timeout);
+        
+        data.editor = match pushed {
+            Some(pushed) => Some(pushed.info),
+            None => data
+                .active_window
+                .as_deref()
+                .and_then(|title| self.titles.parse(title)),
+        };
+        Ok(())
+    }
+}
@@ -0,0 +1,222 @@
+use serde::{Deserialize, Serialize};
+
+/// Shown instead of the name of a repository that is private.
+pub const PRIVATE_PROJECT: &str = "a private project";
+
+/// The git repository of the focused window's process, found through `/proc/<pid>/cwd`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GitConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Process names below the focused window whose working directory is checked, most
+    /// recently started first, before the focused process itself.
+    #[serde(default = "default_processes")]
+    pub processes: Vec<String>,
+    /// Repositories without an entry in `repos` are shown as "a private project".
+    #[serde(default = "default_private_by_default")]
+    pub private_by_default: bool,
+    #[serde(default)]
+    pub repos: Vec<RepoPrivacy>,
+}
+
+/// Privacy for one repository, matched by directory name or by absolute path.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RepoPrivacy {
+    pub repo: String,
+    pub private: bool,
+}
+
+fn default_processes() -> Vec<String> {
+    [
+        "code", "codium", "nvim", "vim", "emacs", "hx", "zed", "subl", "idea", "rustrover", "pycharm", "clion",
+        "goland", "bash", "zsh", "fish",
+    ]
+    .iter()
+    .map(|name| name.to_string())
+    .collect()
+}
+
+fn default_private_by_default() -> bool {
+    true
+}
+
+impl Default for GitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            processes: default_processes(),
+            private_by_default: default_private_by_default(),
+            repos: Vec::new(),
+        }
+    }
+}
+
+impl GitConfig {
+    /// Whether the repository at `path` (named `name`) must be hidden.
+    pub fn is_private(&self, name: &str, path: &std::path::Path) -> bool {
+        self.repos
+            .iter()
+            .find(|entry| entry.repo == name || std::path::Path::new(&entry.repo) == path)
+            .map(|entry| entry.private)
+            .unwrap_or(self.private_by_default)
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub use proc::GitProvider;
+
+#[cfg(target_os = "linux")]
+mod proc {
+    use std::collections::HashMap;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::process::Command;
+    use std::time::{Duration, Instant, SystemTime};
+    
+    use super::{GitConfig, PRIVATE_PROJECT};
+    use crate::activity::{ActivityData, GitInfo};
+    use crate::provider::{process, Provider};
+    
+    /// How long a repository's branch, commit and dirty flag are reused when nothing in its
+    /// `.git` directory changed. Edits to tracked files only show as dirty after this.
+    const REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+    
+    /// The git output for one repository and the state of `.git` it was read from.
+    struct Cached {
+        info: GitInfo,
+        stamp: Vec<Option<SystemTime>>,
+        read_at: Instant,
+    }
+    
+    /// Finds the repository the user is working in: the working directory of an editor or
+    /// shell below the focused window, or of the focused process itself.
+    pub struct GitProvider {
+        config: GitConfig,
+        proc_root: PathBuf,
+        cache: HashMap<PathBuf, Cached>,
+    }
+    
+    impl GitProvider {
+        pub fn new(config: GitConfig) -> Self {
+            Self::with_proc_root(config, "/proc")
+        }
+        
+        /// Reads processes from a different `/proc`, e.g. a fake tree in tests.
+        pub fn with_proc_root(config: GitConfig, proc_root: impl Into<PathBuf>) -> Self {
+            Self {
+                config,
+                proc_root: proc_root.into(),
+                cache: HashMap::new(),
+            }
+        }
+        
+        /// Working directories of the configured processes below `focused_pid`, newest process
+        /// first, followed by the focused process's own.
+        fn candidate_dirs(&self, focused_pid: u32) -> Vec<PathBuf> {
+            let processes = process::processes(&self.proc_root);
+            
+            // Walk down from the focused process; parents always come before their children
+            let mut below = vec![focused_pid];
+            let mut index = 0;
+            while index < below.len() {
+                let parent = below[index];
+                below.extend(processes.iter().filter(|process| process.ppid == parent).map(|process| process.pid));
+                index += 1;
+            }
+            
+            let mut candidates: Vec<(u64, u32)> = processes
+                .iter()
+                .filter(|process| {
+                    process.pid != focused_pid
+                        && below.contains(&process.pid)
+                        && self.config.processes.contains(&process.name)
+                })
+                .map(|process| (process.start_time, process.pid))
+                .collect();
+            candidates.sort_by_key(|(started, _)| std::cmp::Reverse(*started));
+            
+            candidates
+                .into_iter()
+                .map(|(_, pid)| pid)
+                .chain(std::iter::once(focused_pid))
+                // Processes of other users fail here, which is fine
+                .filter_map(|pid| fs::read_link(self.proc_root.join(pid.to_string()).join("cwd")).ok())
+                .collect()
+        }
+        
+        /// `None` without a focused window or when its processes are not in a repository.
+        pub fn current(&mut self, focused_pid: Option<u32>) -> Option<GitInfo> {
+            let root = self.candidate_dirs(focused_pid?).iter().find_map(|dir| repository_root(dir))?;
+            let name = root.file_name()?.to_string_lossy().to_string();
+            
+            if self.config.is_private(&name, &root) {
+                return Some(GitInfo {
+                    repo: PRIVATE_PROJECT.to_string(),
+                    branch: None,
+                    dirty: false,
+                    commit: None,
+                    private: true,
+                });
+            }
+            
+            self.cache.retain(|_, cached| cached.read_at.elapsed() < REFRESH_INTERVAL);
+            let stamp = git_stamp(&root);
+            if let Some(cached) = self.cache.get(&root).filter(|cached| cached.stamp == stamp) {
+                return Some(cached.info.clone());
+            }
+            
+            let info = GitInfo {
+                branch: git(&root, &["symbolic-ref", "--short", "-q", "HEAD"])
+                    .or_else(|| git(&root, &["rev-parse", "--short", "HEAD"])),
+                dirty: git(&root, &["status", "--porcelain", "--untracked-files=no"]).is_some(),
+                commit: git(&root, &["log", "-1", "--format=%s"]),
+                repo: name,
+                private: false,
+            };
+            self.cache.insert(
+                root,
+                Cached {
+                    info: info.clone(),
+                    stamp,
+                    read_at: Instant::now(),
+                },
+            );
+            Some(info)
+        }
+    }
+    
+    impl Provider for GitProvider {
+        fn name(&self) -> &str {
+            "git"
+        }
+        
+        fn sample(&mut self, data: &mut ActivityData) -> Result<(), Box<dyn std::error::Error>> {
+            data.git = self.current(data.active_window_pid);
+            Ok(())
+        }
+    }
+    
+    /// The closest directory at or above `dir` that contains `.git`.
+    fn repository_root(dir: &Path) -> Option<PathBuf> {
+        dir.ancestors().find(|dir| dir.join(".git").exists()).map(Path::to_path_buf)
+    }
+    
+    /// Modification times of the files git rewrites on checkout, commit, reset and staging.
+    fn git_stamp(repo: &Path) -> Vec<Option<SystemTime>> {
+        ["HEAD", "index", "logs/HEAD"]
+            .iter()
+            .map(|file| fs::metadata(repo.join(".git").join(file)).and_then(|metadata| metadata.modified()).ok())
+            .collect()
+    }
+    
+    /// Trimmed output of a git command, `None` when it fails or prints nothing.
+    fn git(repo: &Path, args: &[&str]) -> Option<String> {
+        let output = Command::new("git").arg("-C").arg(repo).args(args).output().ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        
+        let text = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        Some(text).filter(|text| !text.is_empty())
+    }
+}
@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize};
+
+/// Load average and swap usage from `/proc/loadavg` and `/proc/meminfo`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LoadConfig {
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+#[cfg(target_os = "linux")]
+pub use procfs::LoadProvider;
+
+#[cfg(target_os = "linux")]
+mod procfs {
+    use std::fs;
+    use std::path::PathBuf;
+    
+    use crate::activity::{ActivityData, LoadInfo};
+    use crate::provider::Provider;
+    
+    pub struct LoadProvider {
+        proc_root: PathBuf,
+    }
+    
+    impl Default for LoadProvider {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+    
+    impl LoadProvider {
+        pub fn new() -> Self {
+            Self::with_proc_root("/proc")
+        }
+        
+        /// Reads from a different `/proc`, e.g. a fake tree in tests.
+        pub fn with_proc_root(proc_root: impl Into<PathBuf>) -> Self {
+            Self {
+                proc_root: proc_root.into(),
+            }
+        }
+        
+        pub fn current(&self) -> Result<LoadInfo, Box<dyn std::error::Error>> {
+            let loadavg = fs::read_to_string(self.proc_root.join("loadavg"))?;
+            let mut averages = loadavg.split_whitespace().map(str::parse::<f64>);
+            let mut next = || -> Result<f64, Box<dyn std::error::Error>> {
+                Ok(averages.next().ok_or("/proc/loadavg is truncated")??)
+            };
+            let (one, five, fifteen) = (next()?, next()?, next()?);
+            
+            let meminfo = fs::read_to_string(self.proc_root.join("meminfo"))?;
+            // Values are in kB, e.g. "SwapTotal:       8388604 kB"
+            let kilobytes = |key: &str| -> u64 {
+                meminfo
+                    .lines()
+                    .find_map(|line| line.strip_prefix(key)?.strip_prefix(':'))
+                    .and_then(|value| value.split_whitespace().next()?.parse().ok())
+                    .unwrap_or(0)
+            };
+            let swap_total = kilobytes("SwapTotal") * 1024;
+            let swap_free = kilobytes("SwapFree") * 1024;
+            
+            Ok(LoadInfo {
+                one,
+                five,
+                fifteen,
+                swap_total,
+                swap_used: swap_total.saturating_sub(swap_free),
+            })
+        }
+    }
+    
+    impl Provider for LoadProvider {
+        fn name(&self) -> &str {
+            "load"
+        }
+        
+        fn sample(&mut self, data: &mut ActivityData) -> Result<(), Box<dyn std::error::Error>> {
+            data.load = Some(self.current()?);
+            Ok(())
+        }
+    }
+}
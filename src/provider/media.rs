@@ -0,0 +1,235 @@
+use serde::{Deserialize, Serialize};
+
+/// MPRIS media players on the D-Bus session bus.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MediaConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Show a "Listening to" presence while music plays. Profiles can override this.
+    #[serde(default = "default_listening")]
+    pub listening: bool,
+    /// Players to ignore, matched case-insensitively against the MPRIS bus name
+    /// (e.g. "firefox" for `org.mpris.MediaPlayer2.firefox.instance1234`).
+    #[serde(default)]
+    pub ignored_players: Vec<String>,
+}
+
+fn default_listening() -> bool {
+    true
+}
+
+impl Default for MediaConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            listening: default_listening(),
+            ignored_players: Vec::new(),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub use mpris::MediaProvider;
+
+#[cfg(target_os = "linux")]
+mod mpris {
+    use zbus::blocking::fdo::{DBusProxy, PropertiesProxy};
+    use zbus::blocking::Connection;
+    use zbus::names::InterfaceName;
+    use zbus::zvariant::Value;
+    
+    use super::MediaConfig;
+    use crate::activity::{ActivityData, MediaInfo, PlaybackStatus};
+    use crate::provider::Provider;
+    
+    const BUS_NAME_PREFIX: &str = "org.mpris.MediaPlayer2.";
+    const OBJECT_PATH: &str = "/org/mpris/MediaPlayer2";
+    const ROOT_INTERFACE: &str = "org.mpris.MediaPlayer2";
+    const PLAYER_INTERFACE: &str = "org.mpris.MediaPlayer2.Player";
+    
+    /// Polls MPRIS players and reports the one that is playing, or else the first
+    /// paused one. Stopped players are ignored.
+    pub struct MediaProvider {
+        config: MediaConfig,
+        /// Bus to connect to instead of the session bus.
+        address: Option<String>,
+        connection: Option<Connection>,
+    }
+    
+    impl MediaProvider {
+        pub fn new(config: MediaConfig) -> Self {
+            Self {
+                config,
+                address: None,
+                connection: None,
+            }
+        }
+        
+        /// Watches the bus at `address`, e.g. a private `dbus-daemon`.
+        pub fn with_address(config: MediaConfig, address: &str) -> Self {
+            Self {
+                address: Some(address.to_string()),
+                ..Self::new(config)
+            }
+        }
+        
+        fn connection(&mut self) -> zbus::Result<&Connection> {
+            if self.connection.is_none() {
+                let connection = match self.address {
+                    Some(ref address) => zbus::blocking::connection::Builder::address(address.as_str())?.build()?,
+                    None => Connection::session()?,
+                };
+                self.connection = Some(connection);
+            }
+            
+            Ok(self.connection.as_ref().expect("connection was just set"))
+        }
+        
+        fn is_ignored(&self, player_id: &str) -> bool {
+            self.config
+                .ignored_players
+                .iter()
+                .any(|ignored| player_id.to_lowercase().starts_with(&ignored.to_lowercase()))
+        }
+        
+        /// The player to report, if any.
+        pub fn current(&mut self) -> Result<Option<MediaInfo>, Box<dyn std::error::Error>> {
+            let connection = self.connection()?.clone();
+            let mut names: Vec<String> = DBusProxy::new(&connection)?
+                .list_names()?
+                .into_iter()
+                .map(|name| name.to_string())
+                .filter(|name| name.starts_with(BUS_NAME_PREFIX))
+                .collect();
+            names.sort();
+            
+            let mut paused = None;
+            for name in names {
+                if self.is_ignored(&name[BUS_NAME_PREFIX.len()..]) {
+                    continue;
+                }
+                
+                match read_player(&connection, &name) {
+                    Ok(Some(media)) if media.status == PlaybackStatus::Playing => return Ok(Some(media)),
+                    Ok(Some(media)) => {
+                        paused.get_or_insert(media);
+                    }
+                    Ok(None) => {}
+                    // Players come and go; one misbehaving player should not hide the rest
+                    Err(e) => log::debug!("Could not read MPRIS player {}: {}", name, e),
+                }
+            }
+            
+            Ok(paused)
+        }
+    }
+    
+    impl Provider for MediaProvider {
+        fn name(&self) -> &str {
+            "media"
+        }
+        
+        fn sample(&mut self, data: &mut ActivityData) -> Result<(), Box<dyn std::error::Error>> {
+            match self.current() {
+                Ok(media) => {
+                    data.media = media;
+                    Ok(())
+                }
+                Err(e) => {
+                    // Reconnect on the next sample, e.g. after the session bus restarted
+                    self.connection = None;
+                    Err(e)
+                }
+            }
+        }
+    }
+    
+    fn read_player(connection: &Connection, name: &str) -> Result<Option<MediaInfo>, Box<dyn std::error::Error>> {
+        let properties = PropertiesProxy::builder(connection)
+            .destination(name)?
+            .path(OBJECT_PATH)?
+            .build()?;
+        let player = properties.get_all(Some(InterfaceName::from_static_str_unchecked(PLAYER_INTERFACE)).into())?;
+        
+        let status = match player.get("PlaybackStatus").and_then(|value| string(value)).as_deref() {
+            Some("Playing") => PlaybackStatus::Playing,
+            Some("Paused") => PlaybackStatus::Paused,
+            _ => return Ok(None),
+        };
+        
+        let metadata = player.get("Metadata");
+        let entry = |key: &str| metadata.and_then(|metadata| dict_entry(metadata, key));
+        
+        let title = match entry("xesam:title").and_then(string) {
+            Some(title) if !title.is_empty() => title,
+            _ => return Ok(None),
+        };
+        let artists = entry("xesam:artist").map(strings).unwrap_or_default();
+        
+        let identity = properties
+            .get(InterfaceName::from_static_str_unchecked(ROOT_INTERFACE), "Identity")
+            .ok()
+            .and_then(|value| string(&value));
+        
+        Ok(Some(MediaInfo {
+            player: identity.unwrap_or_else(|| player_id(name)),
+            title,
+            artist: Some(artists.join(", ")).filter(|artist| !artist.is_empty()),
+            album: entry("xesam:album").and_then(string).filter(|album| !album.is_empty()),
+            position_seconds: player.get("Position").and_then(|value| micros(value)).map(seconds),
+            length_seconds: entry("mpris:length").and_then(micros).filter(|length| *length > 0).map(seconds),
+            status,
+        }))
+    }
+    
+    /// `org.mpris.MediaPlayer2.vlc.instance42` becomes `vlc`.
+    fn player_id(name: &str) -> String {
+        let id = &name[BUS_NAME_PREFIX.len()..];
+        id.split('.').next().unwrap_or(id).to_string()
+    }
+    
+    fn seconds(micros: i64) -> f64 {
+        micros as f64 / 1_000_000.0
+    }
+    
+    fn string(value: &Value) -> Option<String> {
+        match value {
+            Value::Str(text) => Some(text.to_string()),
+            Value::Value(inner) => string(inner),
+            _ => None,
+        }
+    }
+    
+    /// A string list; players also send a single string where the spec wants a list.
+    fn strings(value: &Value) -> Vec<String> {
+        match value {
+            Value::Array(array) => array.inner().iter().filter_map(string).collect(),
+            Value::Value(inner) => strings(inner),
+            other => string(other).into_iter().collect(),
+        }
+    }
+    
+    /// Microseconds; the spec says `x` but some players send other integer types.
+    fn micros(value: &Value) -> Option<i64> {
+        match *value {
+            Value::I64(micros) => Some(micros),
+            Value::U64(micros) => i64::try_from(micros).ok(),
+            Value::I32(micros) => Some(micros.into()),
+            Value::U32(micros) => Some(micros.into()),
+            Value::F64(micros) => Some(micros as i64),
+            Value::Value(ref inner) => micros(inner),
+            _ => None,
+        }
+    }
+    
+    fn dict_entry<'a>(value: &'a Value<'a>, key: &str) -> Option<&'a Value<'a>> {
+        match value {
+            Value::Dict(dict) => dict
+                .iter()
+                .find(|(entry_key, _)| matches!(entry_key, Value::Str(text) if text.as_str() == key))
+                .map(|(_, value)| value),
+            Value::Value(inner) => dict_entry(inner, key),
+            _ => None,
+        }
+    }
+}
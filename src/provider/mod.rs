@@ -0,0 +1,173 @@
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+
+use crate::activity::ActivityData;
+
+mod battery;
+mod command;
+mod containers;
+mod disk;
+mod editor;
+mod git;
+mod load;
+mod media;
+mod network;
+mod plugin;
+#[cfg(target_os = "linux")]
+mod process;
+mod terminal;
+mod thermal;
+
+pub use battery::BatteryConfig;
+#[cfg(target_os = "linux")]
+pub use battery::BatteryProvider;
+pub use command::{parse_output, CommandOutput, CommandProvider, CommandSource};
+pub use containers::{ContainersConfig, HiddenContainers};
+#[cfg(unix)]
+pub use containers::ContainersProvider;
+pub use disk::{DiskConfig, DiskProvider};
+pub use editor::{
+    language_for, EditorConfig, EditorPattern, EditorProvider, EditorUpdate, Language, PushedEditor, TitleParser,
+};
+
+pub use git::{GitConfig, RepoPrivacy, PRIVATE_PROJECT};
+#[cfg(target_os = "linux")]
+pub use git::GitProvider;
+pub use load::LoadConfig;
+#[cfg(target_os = "linux")]
+pub use load::LoadProvider;
+pub use media::MediaConfig;
+#[cfg(target_os = "linux")]
+pub use media::MediaProvider;
+pub use network::NetworkConfig;
+#[cfg(target_os = "linux")]
+pub use network::NetworkProvider;
+pub use plugin::{Capability, Plugin, PluginConfig, PluginGrants, PluginManifest, PluginProvider, DENIED, FAILED};
+pub use terminal::{sanitize_argv, TerminalConfig, REDACTED};
+#[cfg(target_os = "linux")]
+pub use terminal::TerminalProvider;
+pub use thermal::{ThermalConfig, ThermalProvider};
+
+/// A data source sampled on the monitoring thread, after the system stats, to add
+/// its part to the activity data.
+pub trait Provider: Send {
+    /// Used for logging and as the `provider` label in metrics.
+    fn name(&self) -> &str;
+    
+    fn sample(&mut self, data: &mut ActivityData) -> Result<(), Box<dyn std::error::Error>>;
+}
+
+/// Settings for the optional providers. The system provider is always on.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProvidersConfig {
+    #[serde(default)]
+    pub media: MediaConfig,
+    #[serde(default)]
+    pub git: GitConfig,
+    #[serde(default)]
+    pub editor: EditorConfig,
+    #[serde(default)]
+    pub terminal: TerminalConfig,
+    #[serde(default)]
+    pub battery: BatteryConfig,
+    #[serde(default)]
+    pub thermal: ThermalConfig,
+    #[serde(default)]
+    pub load: LoadConfig,
+    #[serde(default)]
+    pub disk: DiskConfig,
+    #[serde(default)]
+    pub network: NetworkConfig,
+    #[serde(default)]
+    pub containers: ContainersConfig,
+    /// External commands whose output becomes template fields.
+    #[serde(default)]
+    pub commands: Vec<CommandSource>,
+    /// WebAssembly plugins whose snapshots become template fields.
+    #[serde(default)]
+    pub plugins: Vec<PluginConfig>,
+}
+
+/// State handed to providers from outside the monitoring thread, such as updates
+/// from editor plugins arriving over the control socket.
+#[derive(Debug, Clone, Default)]
+pub struct ProviderInputs {
+    pub editor: Arc<Mutex<Option<PushedEditor>>>,
+}
+
+/// Builds the enabled providers. Providers that are unavailable on this platform are
+/// skipped with a warning.
+pub fn build_providers(config: &ProvidersConfig, inputs: &ProviderInputs) -> Vec<Box<dyn Provider>> {
+    let mut providers: Vec<Box<dyn Provider>> = Vec::new();
+    
+    if config.media.enabled {
+        #[cfg(target_os = "linux")]
+        providers.push(Box::new(MediaProvider::new(config.media.clone())));
+        #[cfg(not(target_os = "linux"))]
+        log::warn!("The media provider needs MPRIS, which is only available on Linux");
+    }
+    
+    if config.git.enabled {
+        #[cfg(target_os = "linux")]
+        providers.push(Box::new(GitProvider::new(config.git.clone())));
+        #[cfg(not(target_os = "linux"))]
+        log::warn!("The git provider needs /proc, which is only available on Linux");
+    }
+    
+    if config.editor.enabled {
+        providers.push(Box::new(EditorProvider::new(config.editor.clone(), Arc::clone(&inputs.editor))));
+    }
+    
+    if config.terminal.enabled {
+        #[cfg(target_os = "linux")]
+        providers.push(Box::new(TerminalProvider::new(config.terminal.clone())));
+        #[cfg(not(target_os = "linux"))]
+        log::warn!("The terminal provider needs /proc, which is only available on Linux");
+    }
+    
+    if config.battery.enabled {
+        #[cfg(target_os = "linux")]
+        providers.push(Box::new(BatteryProvider::new()));
+        #[cfg(not(target_os = "linux"))]
+        log::warn!("The battery provider needs /sys, which is only available on Linux");
+    }
+    
+    if config.thermal.enabled {
+        providers.push(Box::new(ThermalProvider::new(config.thermal.clone())));
+    }
+    
+    if config.load.enabled {
+        #[cfg(target_os = "linux")]
+        providers.push(Box::new(LoadProvider::new()));
+        #[cfg(not(target_os = "linux"))]
+        log::warn!("The load provider needs /proc, which is only available on Linux");
+    }
+    
+    if config.disk.enabled {
+        providers.push(Box::new(DiskProvider::new(config.disk.clone())));
+    }
+    
+    if config.network.enabled {
+        #[cfg(target_os = "linux")]
+        providers.push(Box::new(NetworkProvider::new(config.network.clone())));
+        #[cfg(not(target_os = "linux"))]
+        log::warn!("The network provider needs /sys, which is only available on Linux");
+    }
+    
+    if config.containers.enabled {
+        #[cfg(unix)]
+        providers.push(Box::new(ContainersProvider::new(config.containers.clone())));
+        #[cfg(not(unix))]
+        log::warn!("The containers provider needs a Unix socket, which is not supported on this platform");
+    }
+    
+    if config.commands.iter().any(|source| source.enabled) {
+        providers.push(Box::new(CommandProvider::new(&config.commands)));
+    }
+    
+    if config.plugins.iter().any(|plugin| plugin.enabled) {
+        providers.push(Box::new(PluginProvider::new(&config.plugins)));
+    }
+    
+    providers
+}
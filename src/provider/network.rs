@@ -0,0 +1,130 @@
+use serde::{Deserialize, Serialize};
+
+/// Throughput per network interface from the counters in `/sys/class/net`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NetworkConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Interfaces to report, in this order. Empty reports every physical interface,
+    /// which leaves out loopback, bridges and container interfaces.
+    #[serde(default)]
+    pub interfaces: Vec<String>,
+}
+
+#[cfg(target_os = "linux")]
+pub use sysfs::NetworkProvider;
+
+#[cfg(target_os = "linux")]
+mod sysfs {
+    use std::collections::BTreeMap;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::time::Instant;
+    
+    use super::NetworkConfig;
+    use crate::activity::{ActivityData, NetworkThroughput};
+    use crate::provider::Provider;
+    
+    /// Received and sent byte counters of one interface.
+    type Counters = (u64, u64);
+    
+    pub struct NetworkProvider {
+        config: NetworkConfig,
+        sysfs_root: PathBuf,
+        previous: Option<(Instant, BTreeMap<String, Counters>)>,
+    }
+    
+    impl NetworkProvider {
+        pub fn new(config: NetworkConfig) -> Self {
+            Self::with_sysfs_root(config, "/sys")
+        }
+        
+        /// Reads interfaces from a different `/sys`, e.g. a fake tree in tests.
+        pub fn with_sysfs_root(config: NetworkConfig, sysfs_root: impl Into<PathBuf>) -> Self {
+            Self {
+                config,
+                sysfs_root: sysfs_root.into(),
+                previous: None,
+            }
+        }
+        
+        fn interfaces(&self) -> Vec<String> {
+            if !self.config.interfaces.is_empty() {
+                return self.config.interfaces.clone();
+            }
+            
+            let mut interfaces: Vec<String> = fs::read_dir(self.sysfs_root.join("class/net"))
+                .map(|entries| {
+                    entries
+                        .flatten()
+                        // Only hardware interfaces have a device link
+                        .filter(|entry| entry.path().join("device").exists())
+                        .map(|entry| entry.file_name().to_string_lossy().to_string())
+                        .collect()
+                })
+                .unwrap_or_default();
+            interfaces.sort();
+            interfaces
+        }
+        
+        /// Throughput since the previous call, which is taken at `now`. The first call
+        /// only records the counters and reports nothing.
+        pub fn measure(&mut self, now: Instant) -> Vec<NetworkThroughput> {
+            let counters: Vec<(String, Counters)> = self
+                .interfaces()
+                .into_iter()
+                .filter_map(|interface| {
+                    let counters = read_counters(&self.sysfs_root.join("class/net").join(&interface))?;
+                    Some((interface, counters))
+                })
+                .collect();
+            
+            let throughput = match self.previous {
+                Some((then, ref previous)) => {
+                    let seconds = now.duration_since(then).as_secs_f64();
+                    // Counters reset when an interface goes down and up again
+                    let rate = |now: u64, before: u64| {
+                        if seconds > 0.0 {
+                            (now.saturating_sub(before) as f64 / seconds) as u64
+                        } else {
+                            0
+                        }
+                    };
+                    counters
+                        .iter()
+                        .filter_map(|(interface, (received, sent))| {
+                            let (received_before, sent_before) = previous.get(interface)?;
+                            Some(NetworkThroughput {
+                                interface: interface.clone(),
+                                received_per_second: rate(*received, *received_before),
+                                sent_per_second: rate(*sent, *sent_before),
+                            })
+                        })
+                        .collect()
+                }
+                None => Vec::new(),
+            };
+            
+            self.previous = Some((now, counters.into_iter().collect()));
+            throughput
+        }
+    }
+    
+    impl Provider for NetworkProvider {
+        fn name(&self) -> &str {
+            "network"
+        }
+        
+        fn sample(&mut self, data: &mut ActivityData) -> Result<(), Box<dyn std::error::Error>> {
+            data.networks = self.measure(Instant::now());
+            Ok(())
+        }
+    }
+    
+    fn read_counters(interface_dir: &Path) -> Option<Counters> {
+        let read = |name: &str| -> Option<u64> {
+            fs::read_to_string(interface_dir.join("statistics").join(name)).ok()?.trim().parse().ok()
+        };
+        Some((read("rx_bytes")?, read("tx_bytes")?))
+    }
+}
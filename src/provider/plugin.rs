@@ -0,0 +1,409 @@
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use wasmi::core::TrapCode;
+use wasmi::{Caller, Engine, Extern, Instance, Linker, Memory, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+use crate::activity::{ActivityData, PluginStatus, ProcessInfo};
+use crate::provider::{parse_output, CommandOutput, Provider};
+
+/// Module name of the functions the host offers to plugins.
+const HOST_MODULE: &str = "mrp";
+
+/// Snapshots and manifests beyond this many bytes are rejected.
+const MAX_OUTPUT: usize = 64 * 1024;
+
+/// Files beyond this many bytes are cut off when a plugin reads them.
+const MAX_FILE_SIZE: u64 = 1024 * 1024;
+
+/// Returned by host functions when the capability or file is not granted.
+pub const DENIED: i32 = -1;
+/// Returned by host functions when the granted operation failed, e.g. a missing file.
+pub const FAILED: i32 = -2;
+
+/// A WebAssembly module that provides template fields under its `name` namespace.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PluginConfig {
+    /// Namespace of the fields: `{name.field}`.
+    pub name: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// The `.wasm` file; relative paths are resolved against the config directory.
+    pub path: PathBuf,
+    /// What the plugin may access beyond its own memory. Nothing by default.
+    #[serde(default)]
+    pub grants: PluginGrants,
+    /// Instructions one call into the plugin may execute before it is stopped.
+    #[serde(default = "default_fuel")]
+    pub fuel: u64,
+    /// Linear memory the plugin may grow to.
+    #[serde(default = "default_memory_mb")]
+    pub memory_mb: u32,
+}
+
+/// Capabilities the user granted to one plugin.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PluginGrants {
+    /// Files the plugin may read; a directory grants every file below it.
+    #[serde(default)]
+    pub files: Vec<PathBuf>,
+    /// Whether the plugin may read the process list, after the activity filters.
+    #[serde(default)]
+    pub process_list: bool,
+}
+
+/// A capability a plugin asks for in its manifest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Capability {
+    ReadFile,
+    ProcessList,
+}
+
+/// What a plugin declares about itself through `mrp_manifest`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PluginManifest {
+    /// Fields the plugin provides; `ticket` also covers nested ones such as `ticket.key`.
+    pub fields: Vec<String>,
+    #[serde(default = "default_interval_seconds")]
+    pub interval_seconds: u64,
+    #[serde(default)]
+    pub capabilities: Vec<Capability>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_fuel() -> u64 {
+    10_000_000
+}
+
+fn default_memory_mb() -> u32 {
+    16
+}
+
+fn default_interval_seconds() -> u64 {
+    60
+}
+
+impl PluginConfig {
+    pub fn new(name: &str, path: impl Into<PathBuf>) -> Self {
+        Self {
+            name: name.to_string(),
+            enabled: default_enabled(),
+            path: path.into(),
+            grants: PluginGrants::default(),
+            fuel: default_fuel(),
+            memory_mb: default_memory_mb(),
+        }
+    }
+    
+    fn resolved_path(&self) -> PathBuf {
+        if self.path.is_relative() {
+            if let Some(dir) = crate::config::config_path().ok().as_deref().and_then(Path::parent) {
+                return dir.join(&self.path);
+            }
+        }
+        self.path.clone()
+    }
+}
+
+impl PluginGrants {
+    pub fn allows(&self, capability: Capability) -> bool {
+        match capability {
+            Capability::ReadFile => !self.files.is_empty(),
+            Capability::ProcessList => self.process_list,
+        }
+    }
+    
+    /// Whether `path` is a granted file or below a granted directory. Both sides are
+    /// resolved first, so `..` and symlinks cannot step outside a grant.
+    pub fn allows_file(&self, path: &Path) -> bool {
+        if !path.is_absolute() {
+            return false;
+        }
+        let path = match path.canonicalize() {
+            Ok(path) => path,
+            Err(_) => return false,
+        };
+        self.files
+            .iter()
+            .filter_map(|granted| granted.canonicalize().ok())
+            .any(|granted| path.starts_with(granted))
+    }
+}
+
+/// Per-instance state the host functions work with.
+struct HostState {
+    limits: StoreLimits,
+    grants: PluginGrants,
+    /// The process list as JSON, prepared before each poll when granted.
+    processes: String,
+}
+
+/// A loaded plugin. Every call runs with a fresh fuel budget, so a plugin that loops
+/// forever is stopped instead of stalling the monitoring thread.
+pub struct Plugin {
+    config: PluginConfig,
+    store: Store<HostState>,
+    instance: Instance,
+    manifest: PluginManifest,
+}
+
+impl Plugin {
+    /// Compiles and instantiates the plugin and reads its manifest.
+    pub fn load(config: &PluginConfig) -> Result<Self, String> {
+        let path = config.resolved_path();
+        let wasm = std::fs::read(&path).map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+        
+        let mut engine_config = wasmi::Config::default();
+        engine_config.consume_fuel(true);
+        let engine = Engine::new(&engine_config);
+        let module = Module::new(&engine, &wasm[..]).map_err(|e| format!("invalid module: {}", e))?;
+        
+        let state = HostState {
+            limits: StoreLimitsBuilder::new()
+                .memory_size(config.memory_mb as usize * 1024 * 1024)
+                .instances(1)
+                .memories(1)
+                .tables(1)
+                .build(),
+            grants: config.grants.clone(),
+            processes: String::new(),
+        };
+        let mut store = Store::new(&engine, state);
+        store.limiter(|state| &mut state.limits);
+        store.set_fuel(config.fuel).map_err(|e| e.to_string())?;
+        
+        // Only these imports exist: no WASI, so no clock, network or file system
+        let mut linker = Linker::<HostState>::new(&engine);
+        linker
+            .func_wrap(HOST_MODULE, "log", host_log)
+            .and_then(|linker| linker.func_wrap(HOST_MODULE, "read_file", host_read_file))
+            .and_then(|linker| linker.func_wrap(HOST_MODULE, "process_list", host_process_list))
+            .map_err(|e| e.to_string())?;
+        
+        let instance = linker
+            .instantiate(&mut store, &module)
+            .and_then(|instance| instance.start(&mut store))
+            .map_err(|e| match module.imports().find(|import| import.module() != HOST_MODULE) {
+                Some(import) => format!("imports {}::{}, which plugins cannot use", import.module(), import.name()),
+                None => describe(&e, config.fuel),
+            })?;
+        
+        let mut plugin = Self {
+            config: config.clone(),
+            store,
+            instance,
+            manifest: PluginManifest {
+                fields: Vec::new(),
+                interval_seconds: default_interval_seconds(),
+                capabilities: Vec::new(),
+            },
+        };
+        let manifest = plugin.call("mrp_manifest")?;
+        plugin.manifest = serde_json::from_slice(&manifest).map_err(|e| format!("invalid manifest: {}", e))?;
+        Ok(plugin)
+    }
+    
+    pub fn manifest(&self) -> &PluginManifest {
+        &self.manifest
+    }
+    
+    /// Asks the plugin for a snapshot and turns it into `{name.field}` fields. Fields the
+    /// manifest does not declare are dropped.
+    pub fn poll(&mut self, processes: &[ProcessInfo]) -> Result<BTreeMap<String, String>, String> {
+        self.store.data_mut().processes = if self.config.grants.process_list {
+            let processes: Vec<_> = processes
+                .iter()
+                .map(|process| serde_json::json!({ "name": process.name, "pid": process.pid, "cpu_usage": process.cpu_usage }))
+                .collect();
+            serde_json::Value::Array(processes).to_string()
+        } else {
+            String::new()
+        };
+        
+        let snapshot = self.call("mrp_poll")?;
+        let snapshot = String::from_utf8(snapshot).map_err(|_| "the snapshot is not UTF-8".to_string())?;
+        let mut fields = parse_output(&self.config.name, CommandOutput::Json, &snapshot)?;
+        
+        let prefix = format!("{}.", self.config.name);
+        fields.retain(|name, _| {
+            let field = name.strip_prefix(&prefix).unwrap_or(name);
+            self.manifest
+                .fields
+                .iter()
+                .any(|declared| field == declared || field.starts_with(&format!("{}.", declared)))
+        });
+        Ok(fields)
+    }
+    
+    /// Calls an exported `() -> i64` function that returns a pointer and length packed
+    /// as `ptr << 32 | len`, and copies those bytes out of the plugin's memory.
+    fn call(&mut self, export: &str) -> Result<Vec<u8>, String> {
+        let function = self
+            .instance
+            .get_typed_func::<(), i64>(&self.store, export)
+            .map_err(|_| format!("does not export {}() -> i64", export))?;
+        
+        self.store.set_fuel(self.config.fuel).map_err(|e| e.to_string())?;
+        let packed = function.call(&mut self.store, ()).map_err(|e| describe(&e, self.config.fuel))?;
+        
+        let (pointer, length) = ((packed as u64 >> 32) as usize, (packed as u64 & 0xffff_ffff) as usize);
+        if length > MAX_OUTPUT {
+            return Err(format!("{} returned {} bytes, more than the {} allowed", export, length, MAX_OUTPUT));
+        }
+        let memory = self.instance.get_memory(&self.store, "memory").ok_or("does not export its memory")?;
+        let mut bytes = vec![0; length];
+        memory
+            .read(&self.store, pointer, &mut bytes)
+            .map_err(|_| format!("{} returned a range outside its memory", export))?;
+        Ok(bytes)
+    }
+}
+
+/// Turns a trap into a message, naming the fuel limit when that was the cause.
+fn describe(error: &wasmi::Error, fuel: u64) -> String {
+    match error.as_trap_code() {
+        Some(TrapCode::OutOfFuel) => format!("ran out of fuel after {} instructions", fuel),
+        _ => error.to_string(),
+    }
+}
+
+fn memory(caller: &Caller<'_, HostState>) -> Option<Memory> {
+    caller.get_export("memory").and_then(Extern::into_memory)
+}
+
+fn read_guest(caller: &Caller<'_, HostState>, pointer: i32, length: i32) -> Option<Vec<u8>> {
+    let length = usize::try_from(length).ok().filter(|length| *length <= MAX_OUTPUT)?;
+    let mut bytes = vec![0; length];
+    memory(caller)?.read(caller, pointer as u32 as usize, &mut bytes).ok()?;
+    Some(bytes)
+}
+
+/// Copies `bytes` into the plugin's buffer when they fit and returns their length, so a
+/// plugin whose buffer was too small can retry with a bigger one.
+fn write_guest(caller: &mut Caller<'_, HostState>, bytes: &[u8], pointer: i32, capacity: i32) -> i32 {
+    let length = match i32::try_from(bytes.len()) {
+        Ok(length) => length,
+        Err(_) => return FAILED,
+    };
+    if length > capacity {
+        return length;
+    }
+    match memory(caller) {
+        Some(memory) if memory.write(&mut *caller, pointer as u32 as usize, bytes).is_ok() => length,
+        _ => FAILED,
+    }
+}
+
+/// `log(ptr, len)`: writes a line to the application log.
+fn host_log(caller: Caller<'_, HostState>, pointer: i32, length: i32) {
+    if let Some(bytes) = read_guest(&caller, pointer, length) {
+        log::info!("Plugin: {}", String::from_utf8_lossy(&bytes));
+    }
+}
+
+/// `read_file(path_ptr, path_len, buf_ptr, buf_len) -> len`: reads a granted file.
+fn host_read_file(mut caller: Caller<'_, HostState>, path_pointer: i32, path_length: i32, pointer: i32, capacity: i32) -> i32 {
+    let path = match read_guest(&caller, path_pointer, path_length).and_then(|bytes| String::from_utf8(bytes).ok()) {
+        Some(path) => PathBuf::from(path),
+        None => return FAILED,
+    };
+    if !caller.data().grants.allows_file(&path) {
+        log::warn!("Plugin was denied reading {}", path.display());
+        return DENIED;
+    }
+    
+    let mut contents = Vec::new();
+    match std::fs::File::open(&path).and_then(|file| file.take(MAX_FILE_SIZE).read_to_end(&mut contents)) {
+        Ok(_) => write_guest(&mut caller, &contents, pointer, capacity),
+        Err(_) => FAILED,
+    }
+}
+
+/// `process_list(buf_ptr, buf_len) -> len`: the filtered processes as a JSON array.
+fn host_process_list(mut caller: Caller<'_, HostState>, pointer: i32, capacity: i32) -> i32 {
+    if !caller.data().grants.process_list {
+        return DENIED;
+    }
+    let processes = caller.data().processes.clone();
+    write_guest(&mut caller, processes.as_bytes(), pointer, capacity)
+}
+
+/// Polls each enabled plugin on its declared interval on the monitoring thread,
+/// keeping the last good fields and the last error like the command provider.
+pub struct PluginProvider {
+    plugins: Vec<(Option<Plugin>, PluginStatus, Option<Instant>)>,
+}
+
+impl PluginProvider {
+    pub fn new(configs: &[PluginConfig]) -> Self {
+        let plugins = configs
+            .iter()
+            .filter(|config| config.enabled)
+            .map(|config| {
+                let mut status = PluginStatus {
+                    name: config.name.clone(),
+                    fields: BTreeMap::new(),
+                    error: None,
+                    last_run: None,
+                    manifest: None,
+                };
+                let plugin = match Plugin::load(config) {
+                    Ok(plugin) => {
+                        status.manifest = Some(plugin.manifest().clone());
+                        Some(plugin)
+                    }
+                    Err(e) => {
+                        log::warn!("Plugin {} could not be loaded: {}", config.name, e);
+                        status.error = Some(e);
+                        None
+                    }
+                };
+                (plugin, status, None)
+            })
+            .collect();
+        
+        Self { plugins }
+    }
+}
+
+impl Provider for PluginProvider {
+    fn name(&self) -> &str {
+        "plugin"
+    }
+    
+    fn sample(&mut self, data: &mut ActivityData) -> Result<(), Box<dyn std::error::Error>> {
+        let now = Instant::now();
+        for (plugin, status, next_poll) in &mut self.plugins {
+            let plugin = match plugin {
+                Some(plugin) => plugin,
+                None => continue,
+            };
+            if next_poll.is_some_and(|next_poll| now < next_poll) {
+                continue;
+            }
+            
+            *next_poll = Some(now + Duration::from_secs(plugin.manifest().interval_seconds.max(1)));
+            status.last_run = Some(Local::now());
+            match plugin.poll(&data.top_processes) {
+                Ok(fields) => {
+                    status.fields = fields;
+                    status.error = None;
+                }
+                Err(e) => {
+                    log::warn!("Plugin {} failed: {}", status.name, e);
+                    status.error = Some(e);
+                }
+            }
+        }
+        
+        data.plugins = self.plugins.iter().map(|(_, status, _)| status.clone()).collect();
+        Ok(())
+    }
+}
@@ -0,0 +1,49 @@
+//! Processes as listed in `/proc`, for the providers that follow the focused window.
+
+use std::fs;
+use std::path::Path;
+
+/// The fields of `/proc/<pid>/stat` the providers need.
+pub struct Stat {
+    pub pid: u32,
+    pub name: String,
+    pub ppid: u32,
+    pub pgrp: u32,
+    pub tty: u32,
+    /// Process group in the foreground of the controlling terminal.
+    pub tpgid: i64,
+    /// In clock ticks since boot.
+    pub start_time: u64,
+}
+
+/// Every process under `proc_root` whose `stat` could be read.
+pub fn processes(proc_root: &Path) -> Vec<Stat> {
+    let entries = match fs::read_dir(proc_root) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+    
+    entries
+        .flatten()
+        .filter_map(|entry| entry.file_name().to_str()?.parse::<u32>().ok())
+        .filter_map(|pid| read_stat(&proc_root.join(pid.to_string()), pid))
+        .collect()
+}
+
+fn read_stat(process_dir: &Path, pid: u32) -> Option<Stat> {
+    let stat = fs::read_to_string(process_dir.join("stat")).ok()?;
+    // The command name in parentheses may contain spaces, so split at the outer ones
+    let (head, rest) = stat.rsplit_once(')')?;
+    let (_, name) = head.split_once('(')?;
+    let fields: Vec<&str> = rest.split_whitespace().collect();
+    
+    Some(Stat {
+        pid,
+        name: name.to_string(),
+        ppid: fields.get(1)?.parse().ok()?,
+        pgrp: fields.get(2)?.parse().ok()?,
+        tty: fields.get(4)?.parse().ok()?,
+        tpgid: fields.get(5)?.parse().ok()?,
+        start_time: fields.get(19)?.parse().ok()?,
+    })
+}
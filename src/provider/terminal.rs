@@ -0,0 +1,206 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Replaces the arguments that redaction removed.
+pub const REDACTED: &str = "…";
+
+/// Leading plain words such as `build` in `cargo build` are kept by redaction, up to this many.
+const MAX_SUBCOMMANDS: usize = 2;
+
+/// The foreground job of the focused terminal emulator, found through the TTYs of its shells.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TerminalConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Process names of terminal emulators whose shells are checked.
+    #[serde(default = "default_emulators")]
+    pub emulators: Vec<String>,
+    /// Show only the program name and its subcommands instead of the full argument list.
+    #[serde(default = "default_redact_arguments")]
+    pub redact_arguments: bool,
+    /// Commands that are never shown. A job is hidden when its program or any of its
+    /// arguments is one of these, which also covers `sudo pass ...`.
+    #[serde(default = "default_hidden_commands")]
+    pub hidden_commands: Vec<String>,
+}
+
+fn default_emulators() -> Vec<String> {
+    [
+        "alacritty", "kitty", "foot", "wezterm-gui", "ghostty", "gnome-terminal-server", "ptyxis", "konsole",
+        "xfce4-terminal", "tilix", "terminator", "xterm", "urxvt", "st",
+    ]
+    .iter()
+    .map(|name| name.to_string())
+    .collect()
+}
+
+fn default_redact_arguments() -> bool {
+    true
+}
+
+fn default_hidden_commands() -> Vec<String> {
+    [
+        "pass", "gopass", "gpg", "gpg2", "age", "ssh-add", "ssh-keygen", "passwd", "vault", "op", "bw",
+        "keepassxc-cli", "secret-tool",
+    ]
+    .iter()
+    .map(|name| name.to_string())
+    .collect()
+}
+
+impl Default for TerminalConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            emulators: default_emulators(),
+            redact_arguments: default_redact_arguments(),
+            hidden_commands: default_hidden_commands(),
+        }
+    }
+}
+
+impl TerminalConfig {
+    /// Whether `name` is one of the hidden commands.
+    pub fn is_hidden(&self, name: &str) -> bool {
+        self.hidden_commands.iter().any(|hidden| hidden == name)
+    }
+}
+
+/// The program name in `arg`, without its directory or a login shell's leading `-`.
+fn program_name(arg: &str) -> String {
+    let name = Path::new(arg)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| arg.to_string());
+    name.trim_start_matches('-').to_string()
+}
+
+/// A subcommand-like argument: short, lowercase and without paths, hosts or values.
+fn is_plain_word(arg: &str) -> bool {
+    arg.len() <= 24
+        && arg.starts_with(|c: char| c.is_ascii_lowercase())
+        && arg.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+}
+
+/// The argument list as it may be shown, starting with the program name. `None` when the
+/// command is hidden.
+///
+/// With redaction, everything after the leading subcommands is replaced by a single `…`,
+/// so `cargo build --release` becomes `cargo build …` and `curl -H "Authorization: ..."`
+/// becomes `curl …`.
+pub fn sanitize_argv(argv: &[String], config: &TerminalConfig) -> Option<Vec<String>> {
+    let program = program_name(argv.first()?);
+    // Wrappers such as sudo or env put the real command in an argument
+    if argv.iter().any(|arg| config.is_hidden(&program_name(arg))) {
+        return None;
+    }
+    
+    let mut sanitized = vec![program];
+    if !config.redact_arguments {
+        sanitized.extend(argv[1..].iter().cloned());
+        return Some(sanitized);
+    }
+    
+    sanitized.extend(argv[1..].iter().take_while(|arg| is_plain_word(arg)).take(MAX_SUBCOMMANDS).cloned());
+    if argv.len() > sanitized.len() {
+        sanitized.push(REDACTED.to_string());
+    }
+    Some(sanitized)
+}
+
+#[cfg(target_os = "linux")]
+pub use proc::TerminalProvider;
+
+#[cfg(target_os = "linux")]
+mod proc {
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    
+    use super::{program_name, sanitize_argv, TerminalConfig};
+    use crate::activity::{ActivityData, TerminalInfo};
+    use crate::provider::{process, Provider};
+    
+    /// Reports the most recently started foreground job in the terminal emulator that owns
+    /// the focused window.
+    pub struct TerminalProvider {
+        config: TerminalConfig,
+        proc_root: PathBuf,
+    }
+    
+    impl TerminalProvider {
+        pub fn new(config: TerminalConfig) -> Self {
+            Self::with_proc_root(config, "/proc")
+        }
+        
+        /// Reads processes from a different `/proc`, e.g. a fake tree in tests.
+        pub fn with_proc_root(config: TerminalConfig, proc_root: impl Into<PathBuf>) -> Self {
+            Self {
+                config,
+                proc_root: proc_root.into(),
+            }
+        }
+        
+        /// `comm` is cut to 15 bytes, so longer names such as gnome-terminal-server match by prefix.
+        fn emulator_named(&self, name: &str) -> Option<String> {
+            self.config
+                .emulators
+                .iter()
+                .find(|emulator| *emulator == name || (name.len() == 15 && emulator.starts_with(name)))
+                .cloned()
+        }
+        
+        /// `None` unless `focused_pid` is a terminal emulator, so a job is never reported
+        /// while another application has focus. An emulator with several windows or tabs in
+        /// one process reports the newest job among them.
+        pub fn current(&self, focused_pid: Option<u32>) -> Option<TerminalInfo> {
+            let processes = process::processes(&self.proc_root);
+            let by_pid = |pid: u32| processes.iter().find(|process| process.pid == pid);
+            let focused = by_pid(focused_pid?)?;
+            let terminal = self.emulator_named(&focused.name)?;
+            
+            // Shells of that emulator whose TTY has another process group in the foreground;
+            // a shell waiting at its prompt is in the foreground itself
+            let job = processes
+                .iter()
+                .filter(|shell| shell.ppid == focused.pid)
+                .filter(|shell| shell.tty != 0 && shell.tpgid > 0 && shell.tpgid != i64::from(shell.pgrp))
+                .filter_map(|shell| by_pid(shell.tpgid as u32))
+                .max_by_key(|job| job.start_time)?;
+            
+            let mut argv = read_cmdline(&self.proc_root.join(job.pid.to_string()));
+            if argv.is_empty() {
+                argv.push(job.name.clone());
+            }
+            
+            let argv = sanitize_argv(&argv, &self.config)?;
+            Some(TerminalInfo {
+                terminal,
+                command: argv[0].clone(),
+                argv,
+            })
+        }
+    }
+    
+    impl Provider for TerminalProvider {
+        fn name(&self) -> &str {
+            "terminal"
+        }
+        
+        fn sample(&mut self, data: &mut ActivityData) -> Result<(), Box<dyn std::error::Error>> {
+            // Hidden commands must not reappear as the busiest process either
+            data.top_processes.retain(|process| !self.config.is_hidden(&program_name(&process.name)));
+            data.terminal = self.current(data.active_window_pid);
+            Ok(())
+        }
+    }
+    
+    /// The NUL-separated argument list; empty for kernel threads and unreadable processes.
+    fn read_cmdline(process_dir: &Path) -> Vec<String> {
+        let cmdline = fs::read(process_dir.join("cmdline")).unwrap_or_default();
+        cmdline
+            .split(|byte| *byte == 0)
+            .filter(|arg| !arg.is_empty())
+            .map(|arg| String::from_utf8_lossy(arg).to_string())
+            .collect()
+    }
+}
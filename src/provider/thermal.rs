@@ -0,0 +1,59 @@
+use serde::{Deserialize, Serialize};
+use sysinfo::Components;
+
+use crate::activity::{ActivityData, Temperature};
+use crate::provider::Provider;
+
+/// Temperature sensors, read through sysinfo.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ThermalConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Only sensors whose label contains one of these (ignoring case) are reported;
+    /// empty reports all of them.
+    #[serde(default)]
+    pub sensors: Vec<String>,
+}
+
+impl ThermalConfig {
+    pub fn includes(&self, label: &str) -> bool {
+        let label = label.to_lowercase();
+        self.sensors.is_empty() || self.sensors.iter().any(|sensor| label.contains(&sensor.to_lowercase()))
+    }
+}
+
+pub struct ThermalProvider {
+    config: ThermalConfig,
+    components: Components,
+}
+
+impl ThermalProvider {
+    pub fn new(config: ThermalConfig) -> Self {
+        Self {
+            config,
+            components: Components::new_with_refreshed_list(),
+        }
+    }
+}
+
+impl Provider for ThermalProvider {
+    fn name(&self) -> &str {
+        "thermal"
+    }
+    
+    fn sample(&mut self, data: &mut ActivityData) -> Result<(), Box<dyn std::error::Error>> {
+        self.components.refresh();
+        data.temperatures = self
+            .components
+            .iter()
+            .filter(|component| self.config.includes(component.label()))
+            // Sensors that could not be read report NaN
+            .filter(|component| component.temperature().is_finite())
+            .map(|component| Temperature {
+                label: component.label().to_string(),
+                celsius: component.temperature(),
+            })
+            .collect();
+        Ok(())
+    }
+}
@@ -0,0 +1,215 @@
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, TimeZone, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::history::{AppSession, History};
+
+/// Time in one app, with only short interruptions, that counts as a focus streak.
+pub const FOCUS_MINIMUM_SECONDS: i64 = 25 * 60;
+/// Switching away for less than this does not end a focus streak.
+pub const FOCUS_INTERRUPTION_SECONDS: i64 = 2 * 60;
+
+/// Time spent per day, week, app and project between two dates, both inclusive.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Report {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub total_seconds: i64,
+    /// Every day of the range, oldest first, named `YYYY-MM-DD`.
+    pub days: Vec<Total>,
+    /// ISO weeks touched by the range, oldest first, named e.g. `2024-W10`.
+    pub weeks: Vec<Total>,
+    /// Most used first.
+    pub apps: Vec<Total>,
+    /// Most used first; time without a known project is left out.
+    pub projects: Vec<Total>,
+    /// Oldest first.
+    pub focus_streaks: Vec<FocusStreak>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Total {
+    pub name: String,
+    pub seconds: i64,
+}
+
+/// A stretch of at least `FOCUS_MINIMUM_SECONDS` in one app.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FocusStreak {
+    pub app: String,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: DateTime<Utc>,
+    /// Time actually spent in the app, without the interruptions.
+    pub seconds: i64,
+}
+
+/// Start of `date` in `tz`, as UTC.
+fn day_start<Tz: TimeZone>(date: NaiveDate, tz: &Tz) -> DateTime<Utc> {
+    let midnight = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+    // Midnight can be skipped by a DST change; the day then starts an hour later
+    tz.from_local_datetime(&midnight)
+        .earliest()
+        .or_else(|| tz.from_local_datetime(&(midnight + Duration::hours(1))).earliest())
+        .map(|start| start.with_timezone(&Utc))
+        .unwrap_or_else(|| Utc.from_utc_datetime(&midnight))
+}
+
+/// The UTC instants covering the days `from` to `to` (inclusive) in `tz`.
+pub fn range<Tz: TimeZone>(from: NaiveDate, to: NaiveDate, tz: &Tz) -> (DateTime<Utc>, DateTime<Utc>) {
+    (day_start(from, tz), day_start(to.succ_opt().unwrap_or(to), tz))
+}
+
+/// Sorts totals by time spent, longest first, and by name on ties.
+fn ranked(totals: BTreeMap<String, i64>) -> Vec<Total> {
+    let mut totals: Vec<Total> = totals.into_iter().map(|(name, seconds)| Total { name, seconds }).collect();
+    totals.sort_by(|a, b| b.seconds.cmp(&a.seconds).then_with(|| a.name.cmp(&b.name)));
+    totals
+}
+
+fn week_name(date: NaiveDate) -> String {
+    let week = date.iso_week();
+    format!("{}-W{:02}", week.year(), week.week())
+}
+
+impl Report {
+    /// Builds a report from app sessions; parts of sessions outside the range are ignored
+    /// and days are split at midnight in `tz`.
+    pub fn build<Tz: TimeZone>(sessions: &[AppSession], from: NaiveDate, to: NaiveDate, tz: &Tz) -> Report {
+        let (start, end) = range(from, to, tz);
+        
+        let mut days: BTreeMap<NaiveDate, i64> = BTreeMap::new();
+        let mut date = from;
+        while date <= to {
+            days.insert(date, 0);
+            date = match date.succ_opt() {
+                Some(next) => next,
+                None => break,
+            };
+        }
+        
+        let mut apps = BTreeMap::new();
+        let mut projects = BTreeMap::new();
+        let mut clipped = Vec::new();
+        
+        for session in sessions {
+            let (started_at, ended_at) = (session.started_at.max(start), session.ended_at.min(end));
+            if ended_at <= started_at {
+                continue;
+            }
+            
+            let seconds = (ended_at - started_at).num_seconds();
+            *apps.entry(session.app.clone()).or_insert(0) += seconds;
+            if let Some(ref project) = session.project {
+                *projects.entry(project.clone()).or_insert(0) += seconds;
+            }
+            
+            let mut piece = started_at;
+            while piece < ended_at {
+                let date = piece.with_timezone(tz).date_naive();
+                let next = date.succ_opt().map(|next| day_start(next, tz)).filter(|next| *next > piece).unwrap_or(ended_at);
+                let piece_end = ended_at.min(next);
+                *days.entry(date).or_insert(0) += (piece_end - piece).num_seconds();
+                piece = piece_end;
+            }
+            
+            clipped.push((session.app.as_str(), started_at, ended_at));
+        }
+        
+        let mut weeks = BTreeMap::new();
+        for (date, seconds) in &days {
+            *weeks.entry(week_name(*date)).or_insert(0) += seconds;
+        }
+        
+        Report {
+            from,
+            to,
+            total_seconds: days.values().sum(),
+            days: days
+                .into_iter()
+                .map(|(date, seconds)| Total { name: date.format("%Y-%m-%d").to_string(), seconds })
+                .collect(),
+            weeks: weeks.into_iter().map(|(name, seconds)| Total { name, seconds }).collect(),
+            apps: ranked(apps),
+            projects: ranked(projects),
+            focus_streaks: focus_streaks(&clipped),
+        }
+    }
+    
+    /// Reads the sessions of the days `from` to `to` in the local time zone.
+    pub fn from_history(history: &History, from: NaiveDate, to: NaiveDate) -> rusqlite::Result<Report> {
+        let (start, end) = range(from, to, &Local);
+        let sessions = history.app_sessions(start, end)?;
+        Ok(Report::build(&sessions, from, to, &Local))
+    }
+    
+    /// The report as CSV with the columns `kind,name,seconds,start,end`, where kind is
+    /// day, week, app, project or streak. Only streaks have a start and end.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("kind,name,seconds,start,end\n");
+        let sections = [("day", &self.days), ("week", &self.weeks), ("app", &self.apps), ("project", &self.projects)];
+        for (kind, totals) in sections {
+            for total in totals {
+                csv.push_str(&format!("{},{},{},,\n", kind, csv_field(&total.name), total.seconds));
+            }
+        }
+        for streak in &self.focus_streaks {
+            csv.push_str(&format!(
+                "streak,{},{},{},{}\n",
+                csv_field(&streak.app),
+                streak.seconds,
+                streak.started_at.to_rfc3339(),
+                streak.ended_at.to_rfc3339()
+            ));
+        }
+        csv
+    }
+}
+
+/// Quotes a CSV field when it contains a separator, quote or line break.
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// Finds streaks in `(app, start, end)` sessions sorted by start. Another app in front for
+/// less than `FOCUS_INTERRUPTION_SECONDS` is tolerated but does not count.
+fn focus_streaks(sessions: &[(&str, DateTime<Utc>, DateTime<Utc>)]) -> Vec<FocusStreak> {
+    let mut streaks = Vec::new();
+    let mut current: Option<FocusStreak> = None;
+    
+    let mut finish = |streak: Option<FocusStreak>| {
+        if let Some(streak) = streak.filter(|streak| streak.seconds >= FOCUS_MINIMUM_SECONDS) {
+            streaks.push(streak);
+        }
+    };
+    
+    for &(app, started_at, ended_at) in sessions {
+        let seconds = (ended_at - started_at).num_seconds();
+        match current {
+            Some(ref mut streak)
+                if streak.app == app && (started_at - streak.ended_at).num_seconds() <= FOCUS_INTERRUPTION_SECONDS =>
+            {
+                streak.ended_at = streak.ended_at.max(ended_at);
+                streak.seconds += seconds;
+                continue;
+            }
+            // A short switch to another app
+            Some(ref streak) if (ended_at - streak.ended_at).num_seconds() <= FOCUS_INTERRUPTION_SECONDS => continue,
+            _ => {}
+        }
+        
+        finish(current.take());
+        current = Some(FocusStreak {
+            app: app.to_string(),
+            started_at,
+            ended_at,
+            seconds,
+        });
+    }
+    
+    finish(current);
+    streaks
+}
@@ -0,0 +1,117 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use crate::activity::ActivityData;
+use crate::template::PresenceTemplate;
+
+/// A rule fires when every condition it sets matches the current activity.
+/// Rules without any condition never fire.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Rule {
+    pub name: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Process name (case-insensitive) that must be among the top processes.
+    #[serde(default)]
+    pub process: Option<String>,
+    /// Regular expression matched against the active window title.
+    #[serde(default)]
+    pub window: Option<String>,
+    pub action: RuleAction,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleAction {
+    Profile(String),
+    Template(PresenceTemplate),
+    Hide,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Self {
+            name: "New rule".to_string(),
+            enabled: true,
+            process: None,
+            window: None,
+            action: RuleAction::Hide,
+        }
+    }
+}
+
+impl Rule {
+    /// Whether the rule fires for `data`. Window patterns come precompiled from `patterns`;
+    /// a pattern that is missing there or failed to compile never matches.
+    pub fn matches(&self, data: &ActivityData, patterns: &RulePatterns) -> bool {
+        if !self.enabled || (self.process.is_none() && self.window.is_none()) {
+            return false;
+        }
+        
+        if let Some(ref process) = self.process {
+            if !data.top_processes.iter().any(|p| p.name.eq_ignore_ascii_case(process)) {
+                return false;
+            }
+        }
+        
+        if let Some(ref pattern) = self.window {
+            let regex = match patterns.get(pattern) {
+                Some(regex) => regex,
+                None => return false,
+            };
+            
+            match data.active_window {
+                Some(ref title) if regex.is_match(title) => {}
+                _ => return false,
+            }
+        }
+        
+        true
+    }
+}
+
+/// The window patterns of a set of rules, compiled once rather than on every tick.
+#[derive(Debug, Default)]
+pub struct RulePatterns {
+    compiled: HashMap<String, Result<Regex, String>>,
+}
+
+impl RulePatterns {
+    /// Compiles the patterns of `rules` that are new since the last call and forgets the
+    /// ones no rule uses any more. Invalid patterns are logged once, when first seen.
+    pub fn refresh<'a>(&mut self, rules: impl IntoIterator<Item = &'a Rule>) {
+        let patterns: HashSet<&str> = rules.into_iter().filter_map(|rule| rule.window.as_deref()).collect();
+        self.compiled.retain(|pattern, _| patterns.contains(pattern.as_str()));
+        
+        for pattern in patterns {
+            if self.compiled.contains_key(pattern) {
+                continue;
+            }
+            
+            let compiled = Regex::new(pattern).map_err(|e| e.to_string());
+            if let Err(ref e) = compiled {
+                log::warn!("Invalid window pattern {:?}, rules using it never match: {}", pattern, e);
+            }
+            self.compiled.insert(pattern.to_string(), compiled);
+        }
+    }
+    
+    pub fn get(&self, pattern: &str) -> Option<&Regex> {
+        self.compiled.get(pattern)?.as_ref().ok()
+    }
+    
+    /// Why `pattern` did not compile, if it did not.
+    pub fn error(&self, pattern: &str) -> Option<&str> {
+        self.compiled.get(pattern)?.as_ref().err().map(|e| e.as_str())
+    }
+}
+
+/// Returns the first rule in `rules` that matches `data`.
+pub fn first_match<'a>(rules: &'a [Rule], data: &ActivityData, patterns: &RulePatterns) -> Option<&'a Rule> {
+    rules.iter().find(|rule| rule.matches(data, patterns))
+}
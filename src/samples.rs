@@ -0,0 +1,148 @@
+use chrono::{DateTime, Duration, Local};
+use std::collections::{BTreeMap, VecDeque};
+
+use crate::activity::ActivityData;
+
+/// Upper bound on the samples kept per series, whatever the window and interval.
+pub const MAX_SAMPLES: usize = 4096;
+
+/// System-wide values from one monitoring pass.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StatsSample {
+    pub at: DateTime<Local>,
+    pub cpu_usage: f32,
+    pub memory_usage: f64,
+    pub memory_used: u64,
+    pub process_count: usize,
+}
+
+/// One process's values from a monitoring pass in which it was a top process.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProcessSample {
+    pub at: DateTime<Local>,
+    pub cpu_usage: f32,
+    pub memory_usage: u64,
+}
+
+/// Keeps the last samples of a series, dropping the oldest once it is full.
+#[derive(Debug, Clone)]
+pub struct RingBuffer<T> {
+    items: VecDeque<T>,
+    capacity: usize,
+}
+
+impl<T> RingBuffer<T> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            items: VecDeque::new(),
+            capacity: capacity.max(1),
+        }
+    }
+    
+    pub fn push(&mut self, item: T) {
+        if self.items.len() == self.capacity {
+            self.items.pop_front();
+        }
+        self.items.push_back(item);
+    }
+    
+    /// Drops items from the front while `stale` holds.
+    fn trim_front(&mut self, stale: impl Fn(&T) -> bool) {
+        while self.items.front().is_some_and(&stale) {
+            self.items.pop_front();
+        }
+    }
+    
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+    
+    pub fn last(&self) -> Option<&T> {
+        self.items.back()
+    }
+    
+    /// Oldest first.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &T> + ExactSizeIterator {
+        self.items.iter()
+    }
+}
+
+/// Recent system and per-process samples for the live graphs, covering the last
+/// `minutes` minutes. Filled by the monitoring thread.
+#[derive(Debug, Clone)]
+pub struct SampleHistory {
+    minutes: u32,
+    system: RingBuffer<StatsSample>,
+    /// Keyed by PID; a process leaves once it has not been a top process for the whole window.
+    processes: BTreeMap<u32, RingBuffer<ProcessSample>>,
+}
+
+impl SampleHistory {
+    pub fn new(minutes: u32) -> Self {
+        Self {
+            minutes,
+            system: RingBuffer::new(MAX_SAMPLES),
+            processes: BTreeMap::new(),
+        }
+    }
+    
+    pub fn minutes(&self) -> u32 {
+        self.minutes
+    }
+    
+    /// Changes the window; samples older than a shorter window are dropped right away.
+    pub fn set_minutes(&mut self, minutes: u32) {
+        if minutes == self.minutes {
+            return;
+        }
+        self.minutes = minutes;
+        if let Some(now) = self.system.last().map(|sample| sample.at) {
+            self.trim(now);
+        }
+    }
+    
+    /// Adds the stats and top processes of a monitoring pass.
+    pub fn record(&mut self, data: &ActivityData) {
+        let now = data.current_time;
+        let stats = &data.system_stats;
+        self.system.push(StatsSample {
+            at: now,
+            cpu_usage: stats.cpu_usage,
+            memory_usage: stats.memory_usage,
+            memory_used: stats.memory_used,
+            process_count: stats.process_count,
+        });
+        
+        for process in &data.top_processes {
+            self.processes.entry(process.pid).or_insert_with(|| RingBuffer::new(MAX_SAMPLES)).push(ProcessSample {
+                at: now,
+                cpu_usage: process.cpu_usage,
+                memory_usage: process.memory_usage,
+            });
+        }
+        
+        self.trim(now);
+    }
+    
+    fn trim(&mut self, now: DateTime<Local>) {
+        let oldest = now - Duration::minutes(self.minutes as i64);
+        self.system.trim_front(|sample| sample.at < oldest);
+        for samples in self.processes.values_mut() {
+            samples.trim_front(|sample| sample.at < oldest);
+        }
+        self.processes.retain(|_, samples| !samples.is_empty());
+    }
+    
+    pub fn system(&self) -> &RingBuffer<StatsSample> {
+        &self.system
+    }
+    
+    /// Samples of the process with `pid`, from the passes in which it was a top process.
+    pub fn process(&self, pid: u32) -> Option<&RingBuffer<ProcessSample>> {
+        self.processes.get(&pid)
+    }
+}
@@ -0,0 +1,136 @@
+use chrono::{DateTime, Datelike, Local, NaiveTime, Timelike, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+
+pub const WEEKDAYS: [Weekday; 7] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+    Weekday::Sat,
+    Weekday::Sun,
+];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Schedule {
+    pub enabled: bool,
+    /// IANA timezone name such as "Europe/Berlin". The system timezone is used when unset.
+    pub timezone: Option<String>,
+    pub blocks: Vec<ScheduleBlock>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleBlock {
+    pub name: String,
+    pub days: Vec<Weekday>,
+    pub start: NaiveTime,
+    /// A block whose end is before its start runs past midnight into the next day.
+    pub end: NaiveTime,
+    pub action: ScheduleAction,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduleAction {
+    Profile(String),
+    Off,
+}
+
+/// The weekday and time of day a schedule is evaluated against.
+#[derive(Debug, Clone, Copy)]
+pub struct ScheduleTime {
+    pub weekday: Weekday,
+    pub time: NaiveTime,
+}
+
+impl Default for ScheduleBlock {
+    fn default() -> Self {
+        Self {
+            name: "New block".to_string(),
+            days: WEEKDAYS[..5].to_vec(),
+            start: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+            end: NaiveTime::from_hms_opt(17, 0, 0).unwrap(),
+            action: ScheduleAction::Off,
+        }
+    }
+}
+
+impl Schedule {
+    /// The configured timezone, or `None` for system local time. An unknown name is an
+    /// error; `Config::validate` rejects it when the config is loaded.
+    pub fn zone(&self) -> Result<Option<chrono_tz::Tz>, String> {
+        match self.timezone.as_deref().filter(|tz| !tz.is_empty()) {
+            Some(tz) => tz.parse::<chrono_tz::Tz>().map(Some).map_err(|e| e.to_string()),
+            None => Ok(None),
+        }
+    }
+    
+    /// Converts `now` into the schedule's timezone, falling back to local time
+    /// when the configured name is not a known IANA zone.
+    pub fn localize(&self, now: DateTime<Utc>) -> ScheduleTime {
+        let zoned = match self.zone() {
+            Ok(Some(tz)) => now.with_timezone(&tz).naive_local(),
+            _ => now.with_timezone(&Local).naive_local(),
+        };
+        
+        ScheduleTime {
+            weekday: zoned.weekday(),
+            time: zoned.time(),
+        }
+    }
+    
+    /// Returns the first block covering `now`, if the schedule is enabled.
+    pub fn active_block(&self, now: DateTime<Utc>) -> Option<(usize, &ScheduleBlock)> {
+        if !self.enabled {
+            return None;
+        }
+        
+        let at = self.localize(now);
+        self.blocks
+            .iter()
+            .enumerate()
+            .find(|(_, block)| block.covers(at.weekday, at.time))
+    }
+    
+    /// Returns the first block covering the given hour slot, used by the week grid.
+    pub fn block_at(&self, weekday: Weekday, hour: u32) -> Option<(usize, &ScheduleBlock)> {
+        let time = NaiveTime::from_hms_opt(hour, 30, 0)?;
+        self.blocks
+            .iter()
+            .enumerate()
+            .find(|(_, block)| block.covers(weekday, time))
+    }
+}
+
+impl ScheduleBlock {
+    pub fn covers(&self, weekday: Weekday, time: NaiveTime) -> bool {
+        if self.start == self.end {
+            // Same start and end means the whole day
+            return self.days.contains(&weekday);
+        }
+        
+        if self.start < self.end {
+            self.days.contains(&weekday) && time >= self.start && time < self.end
+        } else {
+            (self.days.contains(&weekday) && time >= self.start)
+                || (self.days.contains(&weekday.pred()) && time < self.end)
+        }
+    }
+    
+    pub fn describe(&self) -> String {
+        let action = match self.action {
+            ScheduleAction::Profile(ref profile) => format!("profile \"{}\"", profile),
+            ScheduleAction::Off => "presence off".to_string(),
+        };
+        
+        format!(
+            "{} ({:02}:{:02}-{:02}:{:02}, {})",
+            self.name,
+            self.start.hour(),
+            self.start.minute(),
+            self.end.hour(),
+            self.end.minute(),
+            action
+        )
+    }
+}
@@ -0,0 +1,283 @@
+use rhai::module_resolvers::DummyModuleResolver;
+use rhai::{Dynamic, Engine, EvalAltResult, Map, Scope, AST};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::activity::ActivityData;
+use crate::template::{self, PresenceTemplate};
+
+/// Rhai scripts that pick a profile or the activity text when the declarative rules
+/// cannot express a condition.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScriptingConfig {
+    /// Script files, tried in order; relative paths are resolved against the config directory.
+    #[serde(default)]
+    pub scripts: Vec<PathBuf>,
+    /// Operations one script run may take before it is stopped.
+    #[serde(default = "default_max_operations")]
+    pub max_operations: u64,
+    /// Wall-clock time one script run may take before it is stopped.
+    #[serde(default = "default_timeout_millis")]
+    pub timeout_millis: u64,
+}
+
+fn default_max_operations() -> u64 {
+    100_000
+}
+
+fn default_timeout_millis() -> u64 {
+    50
+}
+
+impl Default for ScriptingConfig {
+    fn default() -> Self {
+        Self {
+            scripts: Vec::new(),
+            max_operations: default_max_operations(),
+            timeout_millis: default_timeout_millis(),
+        }
+    }
+}
+
+/// What a script decided.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScriptOutcome {
+    Profile(String),
+    Template(PresenceTemplate),
+}
+
+/// One script file and the result of loading and last running it.
+#[derive(Debug, Clone)]
+pub struct Script {
+    pub path: PathBuf,
+    modified: Option<SystemTime>,
+    ast: Option<AST>,
+    /// Why the current file could not be loaded; cleared once it compiles again.
+    load_error: Option<String>,
+    /// Why the last run failed.
+    run_error: Option<String>,
+}
+
+impl Script {
+    /// The file name, used to name the script in presence sources and the GUI.
+    pub fn name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| self.path.display().to_string())
+    }
+    
+    /// The problem to show for this script, with loading problems first since an
+    /// older version may still be running cleanly.
+    pub fn error(&self) -> Option<&str> {
+        self.load_error.as_deref().or(self.run_error.as_deref())
+    }
+}
+
+/// Loads, reloads and runs the configured scripts in a sandboxed engine.
+pub struct ScriptHost {
+    engine: Engine,
+    config: ScriptingConfig,
+    scripts: Vec<Script>,
+    /// When the running script must stop, checked by the engine's progress callback.
+    deadline: Arc<Mutex<Option<Instant>>>,
+}
+
+impl ScriptHost {
+    pub fn new(config: &ScriptingConfig, base_dir: Option<&Path>) -> Self {
+        let deadline = Arc::new(Mutex::new(None::<Instant>));
+        let mut engine = Engine::new();
+        
+        // No file or module access, and bounded memory and recursion
+        engine.set_module_resolver(DummyModuleResolver::new());
+        engine.set_max_operations(config.max_operations);
+        engine.set_max_call_levels(32);
+        engine.set_max_expr_depths(64, 32);
+        engine.set_max_string_size(4096);
+        engine.set_max_array_size(1024);
+        engine.set_max_map_size(1024);
+        engine.on_print(|text| log::info!("Script: {}", text));
+        engine.on_debug(|text, _, position| log::debug!("Script at {}: {}", position, text));
+        
+        let progress_deadline = Arc::clone(&deadline);
+        engine.on_progress(move |_| {
+            let deadline = progress_deadline.lock().ok().and_then(|deadline| *deadline);
+            match deadline {
+                Some(deadline) if Instant::now() > deadline => Some(Dynamic::from("time limit exceeded")),
+                _ => None,
+            }
+        });
+        
+        let scripts = config
+            .scripts
+            .iter()
+            .map(|path| Script {
+                path: match base_dir {
+                    Some(dir) if path.is_relative() => dir.join(path),
+                    _ => path.clone(),
+                },
+                modified: None,
+                ast: None,
+                load_error: None,
+                run_error: None,
+            })
+            .collect();
+        
+        let mut host = Self {
+            engine,
+            config: config.clone(),
+            scripts,
+            deadline,
+        };
+        host.reload();
+        host
+    }
+    
+    pub fn config(&self) -> &ScriptingConfig {
+        &self.config
+    }
+    
+    pub fn scripts(&self) -> &[Script] {
+        &self.scripts
+    }
+    
+    /// Recompiles every script whose file changed since it was last loaded. A script
+    /// that fails to compile keeps running its previous version, while a file that
+    /// cannot be read stops it.
+    pub fn reload(&mut self) {
+        for script in &mut self.scripts {
+            let modified = std::fs::metadata(&script.path).and_then(|metadata| metadata.modified()).ok();
+            if modified.is_some() && modified == script.modified {
+                continue;
+            }
+            script.modified = modified;
+            
+            let source = match std::fs::read_to_string(&script.path) {
+                Ok(source) => source,
+                Err(e) => {
+                    script.ast = None;
+                    script.load_error = Some(format!("cannot read {}: {}", script.path.display(), e));
+                    continue;
+                }
+            };
+            
+            match self.engine.compile(&source) {
+                Ok(ast) => {
+                    if script.ast.is_some() {
+                        log::info!("Reloaded script {}", script.path.display());
+                    }
+                    script.ast = Some(ast);
+                    script.load_error = None;
+                }
+                Err(e) => {
+                    log::warn!("Script {} has an error: {}", script.path.display(), e);
+                    script.load_error = Some(e.to_string());
+                }
+            }
+        }
+    }
+    
+    /// Runs the scripts in order with `data`, the template fields and the current
+    /// profile in scope, returning the first decision. Failing scripts are skipped and
+    /// keep their error until they run cleanly again.
+    pub fn evaluate(
+        &mut self,
+        data: &ActivityData,
+        profile: Option<&str>,
+        known_profile: impl Fn(&str) -> bool,
+    ) -> Option<(String, ScriptOutcome)> {
+        if self.scripts.is_empty() {
+            return None;
+        }
+        self.reload();
+        
+        let snapshot = match rhai::serde::to_dynamic(data) {
+            Ok(snapshot) => snapshot,
+            Err(e) => {
+                log::error!("Cannot pass activity data to scripts: {}", e);
+                return None;
+            }
+        };
+        let fields: Map = template::activity_fields(data)
+            .into_iter()
+            .map(|(name, value)| (name.into(), Dynamic::from(value)))
+            .collect();
+        
+        for index in 0..self.scripts.len() {
+            let ast = match self.scripts[index].ast {
+                Some(ref ast) => ast.clone(),
+                None => continue,
+            };
+            
+            let mut scope = Scope::new();
+            scope.push_constant("data", snapshot.clone());
+            scope.push_constant("fields", fields.clone());
+            scope.push_constant("profile", profile.map(|name| Dynamic::from(name.to_string())).unwrap_or(Dynamic::UNIT));
+            
+            if let Ok(mut deadline) = self.deadline.lock() {
+                *deadline = Some(Instant::now() + Duration::from_millis(self.config.timeout_millis));
+            }
+            let result = self.engine.eval_ast_with_scope::<Dynamic>(&mut scope, &ast);
+            if let Ok(mut deadline) = self.deadline.lock() {
+                *deadline = None;
+            }
+            
+            let script = &mut self.scripts[index];
+            let outcome = result
+                .map_err(|e| match *e {
+                    // The reason given by the progress callback, rather than just "terminated"
+                    EvalAltResult::ErrorTerminated(ref reason, position) => format!("{} ({})", reason, position),
+                    _ => e.to_string(),
+                })
+                .and_then(|value| outcome_from(value, &known_profile));
+            match outcome {
+                Ok(outcome) => {
+                    script.run_error = None;
+                    if let Some(outcome) = outcome {
+                        return Some((script.name(), outcome));
+                    }
+                }
+                Err(e) => {
+                    log::warn!("Script {} failed: {}", script.path.display(), e);
+                    script.run_error = Some(e);
+                }
+            }
+        }
+        
+        None
+    }
+}
+
+/// Reads a script's return value: nothing, or a map with either `profile` or any of
+/// `details`, `state`, `large_image` and `large_text`.
+fn outcome_from(value: Dynamic, known_profile: impl Fn(&str) -> bool) -> Result<Option<ScriptOutcome>, String> {
+    if value.is_unit() {
+        return Ok(None);
+    }
+    
+    let map = value
+        .try_cast::<Map>()
+        .ok_or("scripts must return nothing or a map such as #{ details: \"...\" }")?;
+    let mut template = PresenceTemplate::default();
+    let mut has_text = false;
+    
+    for (key, value) in map {
+        let text = value
+            .into_string()
+            .map_err(|kind| format!("{} must be a string, not {}", key, kind))?;
+        match key.as_str() {
+            "profile" if !known_profile(&text) => return Err(format!("profile {:?} does not exist", text)),
+            "profile" => return Ok(Some(ScriptOutcome::Profile(text))),
+            "details" => template.details = text,
+            "state" => template.state = text,
+            "large_image" => template.large_image = Some(text),
+            "large_text" => template.large_text = Some(text),
+            other => return Err(format!("unknown key {:?} in the returned map", other)),
+        }
+        has_text = true;
+    }
+    
+    Ok(has_text.then_some(ScriptOutcome::Template(template)))
+}
@@ -0,0 +1,56 @@
+use discord_rich_presence::{DiscordIpc, DiscordIpcClient};
+
+use super::PresenceSink;
+use crate::presence::ComposedActivity;
+
+/// The Discord IPC connection. Unlike the other sinks it is always present and its
+/// connection state is reported in the UI.
+#[derive(Default)]
+pub struct DiscordSink {
+    client: Option<DiscordIpcClient>,
+    cleared: bool,
+}
+
+impl DiscordSink {
+    /// Closes any existing connection and connects with `app_id`.
+    pub fn connect(&mut self, app_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        self.close();
+        
+        let mut client = DiscordIpcClient::new(app_id)
+            .map_err(|e| format!("Client creation failed: {}", e))?;
+        client.connect().map_err(|e| format!("Connection failed: {}", e))?;
+        
+        self.client = Some(client);
+        self.cleared = false;
+        Ok(())
+    }
+    
+    pub fn is_connected(&self) -> bool {
+        self.client.is_some()
+    }
+}
+
+impl PresenceSink for DiscordSink {
+    fn update(&mut self, activity: Option<&ComposedActivity>) -> Result<(), Box<dyn std::error::Error>> {
+        let client = self.client.as_mut().ok_or("not connected to Discord")?;
+        
+        match activity {
+            Some(activity) => {
+                client.set_activity(activity.to_discord())?;
+                self.cleared = false;
+            }
+            None if !self.cleared => {
+                client.clear_activity()?;
+                self.cleared = true;
+            }
+            None => {}
+        }
+        Ok(())
+    }
+    
+    fn close(&mut self) {
+        if let Some(ref mut client) = self.client.take() {
+            let _ = client.close();
+        }
+    }
+}
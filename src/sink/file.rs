@@ -0,0 +1,37 @@
+use std::path::PathBuf;
+
+use super::{PresenceSink, SinkFormat, SinkOutput};
+use crate::presence::ComposedActivity;
+
+/// Writes the presence to a file, replacing it atomically so readers such as OBS
+/// never see a half-written file.
+pub struct FileSink {
+    path: PathBuf,
+    format: SinkFormat,
+    output: SinkOutput,
+}
+
+impl FileSink {
+    pub fn new(path: PathBuf, format: SinkFormat, output: SinkOutput) -> Self {
+        Self { path, format, output }
+    }
+}
+
+impl PresenceSink for FileSink {
+    fn update(&mut self, activity: Option<&ComposedActivity>) -> Result<(), Box<dyn std::error::Error>> {
+        let content = match self.format {
+            SinkFormat::Text => self.output.text(activity),
+            SinkFormat::Json => serde_json::to_string_pretty(&self.output.json(activity))?,
+        };
+        
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        
+        let mut temp_path = self.path.clone().into_os_string();
+        temp_path.push(".tmp");
+        std::fs::write(&temp_path, content)?;
+        std::fs::rename(temp_path, &self.path)?;
+        Ok(())
+    }
+}
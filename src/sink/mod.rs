@@ -0,0 +1,237 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crate::activity::SystemStats;
+use crate::presence::{self, ComposedActivity};
+use crate::template::{self, TemplateFields};
+
+mod discord;
+mod file;
+mod mqtt;
+mod stdout;
+mod webhook;
+
+pub use discord::DiscordSink;
+pub use file::FileSink;
+pub use mqtt::{MqttConfig, MqttSink};
+pub use stdout::StdoutSink;
+pub use webhook::WebhookSink;
+
+/// Somewhere a composed presence is published to.
+pub trait PresenceSink: Send {
+    /// Publishes a presence; `None` means presence is hidden and should be cleared.
+    fn update(&mut self, activity: Option<&ComposedActivity>) -> Result<(), Box<dyn std::error::Error>>;
+    
+    /// Receives each system stats sample; most sinks only care about the presence.
+    fn update_stats(&mut self, _stats: &SystemStats) -> Result<(), Box<dyn std::error::Error>> {
+        Ok(())
+    }
+    
+    /// Called once before the sink is dropped.
+    fn close(&mut self) {}
+}
+
+/// One configured output in addition to Discord.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SinkConfig {
+    pub name: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(flatten)]
+    pub kind: SinkKind,
+    /// Profiles the sink publishes in; empty means every profile.
+    #[serde(default)]
+    pub profiles: Vec<String>,
+    /// Words filtered for this sink only, on top of the profile's blacklist.
+    #[serde(default)]
+    pub blacklisted_words: Vec<String>,
+    #[serde(default)]
+    pub output: SinkOutput,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SinkKind {
+    /// Rewrites a file on every change, e.g. for an OBS text source.
+    File { path: PathBuf, format: SinkFormat },
+    Stdout { format: SinkFormat },
+    /// POSTs the JSON output to a URL.
+    Webhook {
+        url: String,
+        #[serde(default)]
+        headers: BTreeMap<String, String>,
+    },
+    /// Publishes to an MQTT broker, e.g. for Home Assistant.
+    Mqtt(MqttConfig),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SinkFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+/// Field mapping from the composed activity to what a sink writes. Templates use the
+/// placeholders `details`, `state`, `large_image`, `large_text`, `start_timestamp` and
+/// `end_timestamp`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SinkOutput {
+    /// Text output; `"{details}\n{state}"` when unset.
+    #[serde(default)]
+    pub text: Option<String>,
+    /// JSON output keys and their templates; the whole composed activity when empty.
+    #[serde(default)]
+    pub fields: BTreeMap<String, String>,
+}
+
+const DEFAULT_TEXT: &str = "{details}\n{state}";
+
+impl SinkConfig {
+    pub fn new(name: &str, kind: SinkKind) -> Self {
+        Self {
+            name: name.to_string(),
+            enabled: true,
+            kind,
+            profiles: Vec::new(),
+            blacklisted_words: Vec::new(),
+            output: SinkOutput::default(),
+        }
+    }
+    
+    /// Whether the sink publishes while `profile` is in effect.
+    pub fn applies_to(&self, profile: Option<&str>) -> bool {
+        self.profiles.is_empty() || profile.is_some_and(|profile| self.profiles.iter().any(|name| name == profile))
+    }
+    
+    pub fn build(&self) -> Result<Box<dyn PresenceSink>, Box<dyn std::error::Error>> {
+        Ok(match self.kind {
+            SinkKind::File { ref path, format } => Box::new(FileSink::new(path.clone(), format, self.output.clone())),
+            SinkKind::Stdout { format } => Box::new(StdoutSink::new(format, self.output.clone())),
+            SinkKind::Webhook { ref url, ref headers } => {
+                Box::new(WebhookSink::new(url, headers.clone(), self.output.clone())?)
+            }
+            SinkKind::Mqtt(ref mqtt) => Box::new(MqttSink::new(mqtt, self.output.clone())?),
+        })
+    }
+}
+
+fn activity_fields(activity: &ComposedActivity) -> TemplateFields {
+    let mut fields = TemplateFields::new();
+    fields.insert("details".to_string(), activity.details.clone().unwrap_or_default());
+    fields.insert("state".to_string(), activity.state.clone().unwrap_or_default());
+    fields.insert("large_image".to_string(), activity.large_image.clone());
+    fields.insert("large_text".to_string(), activity.large_text.clone());
+    fields.insert(
+        "start_timestamp".to_string(),
+        activity.start_timestamp.map(|start| start.to_string()).unwrap_or_default(),
+    );
+    fields.insert(
+        "end_timestamp".to_string(),
+        activity.end_timestamp.map(|end| end.to_string()).unwrap_or_default(),
+    );
+    fields
+}
+
+impl SinkOutput {
+    /// The text form; empty while presence is hidden.
+    pub fn text(&self, activity: Option<&ComposedActivity>) -> String {
+        match activity {
+            Some(activity) => template::render(self.text.as_deref().unwrap_or(DEFAULT_TEXT), &activity_fields(activity)),
+            None => String::new(),
+        }
+    }
+    
+    /// The JSON form; `null` while presence is hidden.
+    pub fn json(&self, activity: Option<&ComposedActivity>) -> Value {
+        let activity = match activity {
+            Some(activity) => activity,
+            None => return Value::Null,
+        };
+        
+        if self.fields.is_empty() {
+            return serde_json::to_value(activity).unwrap_or(Value::Null);
+        }
+        
+        let fields = activity_fields(activity);
+        self.fields
+            .iter()
+            .map(|(key, template)| (key.clone(), Value::String(template::render(template, &fields))))
+            .collect::<serde_json::Map<_, _>>()
+            .into()
+    }
+}
+
+/// A built sink with its filter and the last presence it was given.
+pub struct SinkSlot {
+    pub config: SinkConfig,
+    sink: Box<dyn PresenceSink>,
+    word_filter: Regex,
+    last: Option<Option<ComposedActivity>>,
+}
+
+impl SinkSlot {
+    pub fn new(config: SinkConfig) -> Result<SinkSlot, Box<dyn std::error::Error>> {
+        Ok(SinkSlot {
+            sink: config.build()?,
+            word_filter: presence::create_word_filter(&config.blacklisted_words),
+            config,
+            last: None,
+        })
+    }
+    
+    /// Hands the presence to the sink if it changed since the last call. Sinks that
+    /// do not apply to `profile` are cleared instead.
+    pub fn publish(
+        &mut self,
+        activity: Option<&ComposedActivity>,
+        profile: Option<&str>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let activity = activity
+            .filter(|_| self.config.applies_to(profile))
+            .map(|activity| activity.filtered(&self.word_filter));
+        
+        if self.last.as_ref() == Some(&activity) {
+            return Ok(());
+        }
+        
+        self.sink.update(activity.as_ref())?;
+        self.last = Some(activity);
+        Ok(())
+    }
+    
+    /// Hands a stats sample to the sink if it applies to `profile`.
+    pub fn publish_stats(&mut self, stats: &SystemStats, profile: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.config.applies_to(profile) {
+            return Ok(());
+        }
+        self.sink.update_stats(stats)
+    }
+    
+    pub fn close(&mut self) {
+        self.sink.close();
+    }
+}
+
+/// Builds the enabled sinks, skipping (and logging) any that fail to start.
+pub fn build_sinks(configs: &[SinkConfig]) -> Vec<SinkSlot> {
+    configs
+        .iter()
+        .filter(|config| config.enabled)
+        .filter_map(|config| match SinkSlot::new(config.clone()) {
+            Ok(slot) => Some(slot),
+            Err(e) => {
+                log::error!("Failed to start sink {:?}: {}", config.name, e);
+                None
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,264 @@
+use rumqttc::{Client, Event, LastWill, MqttOptions, Outgoing, Packet, QoS};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use super::{PresenceSink, SinkOutput};
+use crate::activity::SystemStats;
+use crate::presence::ComposedActivity;
+
+const ONLINE: &str = "online";
+const OFFLINE: &str = "offline";
+
+/// How long to wait before reconnecting after the broker went away.
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MqttConfig {
+    pub host: String,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    /// Also used to build Home Assistant unique IDs, so keep it stable per machine.
+    #[serde(default = "default_client_id")]
+    pub client_id: String,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    #[serde(default = "default_presence_topic")]
+    pub presence_topic: String,
+    #[serde(default = "default_stats_topic")]
+    pub stats_topic: String,
+    /// Receives `online` on connect and `offline` as the Last Will.
+    #[serde(default = "default_availability_topic")]
+    pub availability_topic: String,
+    #[serde(default = "default_retain")]
+    pub retain: bool,
+    /// Publishes Home Assistant MQTT discovery configs for the presence and stats sensors.
+    #[serde(default)]
+    pub discovery: bool,
+    #[serde(default = "default_discovery_prefix")]
+    pub discovery_prefix: String,
+}
+
+fn default_port() -> u16 {
+    1883
+}
+
+fn default_client_id() -> String {
+    "multi-rich-presence".to_string()
+}
+
+fn default_presence_topic() -> String {
+    "multi-rich-presence/presence".to_string()
+}
+
+fn default_stats_topic() -> String {
+    "multi-rich-presence/stats".to_string()
+}
+
+fn default_availability_topic() -> String {
+    "multi-rich-presence/availability".to_string()
+}
+
+fn default_retain() -> bool {
+    true
+}
+
+fn default_discovery_prefix() -> String {
+    "homeassistant".to_string()
+}
+
+impl Default for MqttConfig {
+    fn default() -> Self {
+        Self {
+            host: "localhost".to_string(),
+            port: default_port(),
+            client_id: default_client_id(),
+            username: None,
+            password: None,
+            presence_topic: default_presence_topic(),
+            stats_topic: default_stats_topic(),
+            availability_topic: default_availability_topic(),
+            retain: default_retain(),
+            discovery: false,
+            discovery_prefix: default_discovery_prefix(),
+        }
+    }
+}
+
+impl MqttConfig {
+    /// The client ID reduced to characters Home Assistant accepts in IDs and topics.
+    fn node_id(&self) -> String {
+        self.client_id
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '_' || c == '-' { c } else { '_' })
+            .collect()
+    }
+    
+    /// Retained Home Assistant discovery messages, one per sensor.
+    pub fn discovery_messages(&self) -> Vec<(String, Value)> {
+        let node_id = self.node_id();
+        let device = json!({
+            "identifiers": [node_id],
+            "name": format!("MultiRichPresence ({})", self.client_id),
+            "manufacturer": "MultiRichPresence",
+        });
+        
+        let sensors = [
+            ("details", "Presence details", &self.presence_topic, "{{ (value_json or {}).get('details') or '' }}", None),
+            ("state", "Presence state", &self.presence_topic, "{{ (value_json or {}).get('state') or '' }}", None),
+            ("cpu", "CPU usage", &self.stats_topic, "{{ value_json.cpu_usage | round(1) }}", Some("%")),
+            ("memory", "Memory usage", &self.stats_topic, "{{ value_json.memory_usage | round(1) }}", Some("%")),
+            ("process_count", "Processes", &self.stats_topic, "{{ value_json.process_count }}", None),
+        ];
+        
+        sensors
+            .into_iter()
+            .map(|(object_id, name, state_topic, value_template, unit)| {
+                let mut config = json!({
+                    "name": name,
+                    "unique_id": format!("{}_{}", node_id, object_id),
+                    "object_id": format!("{}_{}", node_id, object_id),
+                    "state_topic": state_topic,
+                    "value_template": value_template,
+                    "availability_topic": self.availability_topic,
+                    "payload_available": ONLINE,
+                    "payload_not_available": OFFLINE,
+                    "device": device,
+                });
+                if let Some(unit) = unit {
+                    config["unit_of_measurement"] = json!(unit);
+                    config["state_class"] = json!("measurement");
+                }
+                
+                let topic = format!("{}/sensor/{}/{}/config", self.discovery_prefix, node_id, object_id);
+                (topic, config)
+            })
+            .collect()
+    }
+}
+
+/// Publishes presence and system stats to an MQTT broker. The connection is driven
+/// on a worker thread that reconnects on its own; publishes never block the engine.
+pub struct MqttSink {
+    client: Client,
+    config: MqttConfig,
+    output: SinkOutput,
+    /// Last presence payload, republished after a reconnect.
+    last_presence: Arc<Mutex<Option<String>>>,
+    closed: Arc<AtomicBool>,
+}
+
+impl MqttSink {
+    pub fn new(config: &MqttConfig, output: SinkOutput) -> Result<Self, Box<dyn std::error::Error>> {
+        if config.host.trim().is_empty() {
+            return Err("MQTT sink needs a broker host".into());
+        }
+        
+        let mut options = MqttOptions::new(config.client_id.clone(), config.host.clone(), config.port);
+        options.set_keep_alive(Duration::from_secs(30));
+        options.set_last_will(LastWill::new(&config.availability_topic, OFFLINE, QoS::AtLeastOnce, true));
+        if let Some(ref username) = config.username {
+            options.set_credentials(username.clone(), config.password.clone().unwrap_or_default());
+        }
+        
+        let (client, mut connection) = Client::new(options, 64);
+        let last_presence = Arc::new(Mutex::new(None::<String>));
+        let closed = Arc::new(AtomicBool::new(false));
+        
+        let worker_client = client.clone();
+        let worker_config = config.clone();
+        let worker_presence = Arc::clone(&last_presence);
+        let worker_closed = Arc::clone(&closed);
+        
+        std::thread::spawn(move || {
+            for event in connection.iter() {
+                match event {
+                    Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                        log::info!("Connected to MQTT broker {}:{}", worker_config.host, worker_config.port);
+                        announce(&worker_client, &worker_config, &worker_presence);
+                    }
+                    Ok(Event::Outgoing(Outgoing::Disconnect)) => break,
+                    Ok(_) => {}
+                    Err(e) => {
+                        if worker_closed.load(Ordering::SeqCst) {
+                            break;
+                        }
+                        log::warn!("MQTT connection to {} failed: {}", worker_config.host, e);
+                        std::thread::sleep(RECONNECT_DELAY);
+                    }
+                }
+            }
+        });
+        
+        Ok(Self {
+            client,
+            config: config.clone(),
+            output,
+            last_presence,
+            closed,
+        })
+    }
+    
+    fn publish(&self, topic: &str, payload: String) -> Result<(), Box<dyn std::error::Error>> {
+        self.client
+            .try_publish(topic, QoS::AtLeastOnce, self.config.retain, payload)
+            .map_err(|e| format!("MQTT publish to {} failed: {}", topic, e).into())
+    }
+}
+
+/// Sent after every (re)connect: availability, discovery configs and the latest presence.
+fn announce(client: &Client, config: &MqttConfig, last_presence: &Mutex<Option<String>>) {
+    let mut messages = vec![(config.availability_topic.clone(), ONLINE.to_string())];
+    
+    if config.discovery {
+        for (topic, payload) in config.discovery_messages() {
+            messages.push((topic, payload.to_string()));
+        }
+    }
+    
+    if let Some(presence) = last_presence.lock().ok().and_then(|presence| presence.clone()) {
+        messages.push((config.presence_topic.clone(), presence));
+    }
+    
+    for (topic, payload) in messages {
+        if let Err(e) = client.try_publish(topic, QoS::AtLeastOnce, true, payload) {
+            log::error!("MQTT announce failed: {}", e);
+        }
+    }
+}
+
+impl PresenceSink for MqttSink {
+    fn update(&mut self, activity: Option<&ComposedActivity>) -> Result<(), Box<dyn std::error::Error>> {
+        let payload = self.output.json(activity).to_string();
+        if let Ok(mut last) = self.last_presence.lock() {
+            *last = Some(payload.clone());
+        }
+        self.publish(&self.config.presence_topic, payload)
+    }
+    
+    fn update_stats(&mut self, stats: &SystemStats) -> Result<(), Box<dyn std::error::Error>> {
+        self.publish(&self.config.stats_topic, serde_json::to_string(stats)?)
+    }
+    
+    fn close(&mut self) {
+        if self.closed.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        
+        // The Last Will only covers unclean disconnects
+        let _ = self
+            .client
+            .try_publish(&self.config.availability_topic, QoS::AtLeastOnce, true, OFFLINE);
+        let _ = self.client.try_disconnect();
+    }
+}
+
+impl Drop for MqttSink {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
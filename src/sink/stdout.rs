@@ -0,0 +1,31 @@
+use std::io::Write;
+
+use super::{PresenceSink, SinkFormat, SinkOutput};
+use crate::presence::ComposedActivity;
+
+/// Prints each change on its own line; handy with `--daemon` and shell pipelines.
+pub struct StdoutSink {
+    format: SinkFormat,
+    output: SinkOutput,
+}
+
+impl StdoutSink {
+    pub fn new(format: SinkFormat, output: SinkOutput) -> Self {
+        Self { format, output }
+    }
+}
+
+impl PresenceSink for StdoutSink {
+    fn update(&mut self, activity: Option<&ComposedActivity>) -> Result<(), Box<dyn std::error::Error>> {
+        let line = match self.format {
+            // Multi-line text is joined so one change stays one line
+            SinkFormat::Text => self.output.text(activity).replace('\n', " | "),
+            SinkFormat::Json => self.output.json(activity).to_string(),
+        };
+        
+        let mut stdout = std::io::stdout().lock();
+        writeln!(stdout, "{}", line)?;
+        stdout.flush()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,60 @@
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::mpsc::{self, Sender};
+use std::time::Duration;
+
+use super::{PresenceSink, SinkOutput};
+use crate::presence::ComposedActivity;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// POSTs the JSON output to a URL. Requests are made on a worker thread so a slow
+/// endpoint never holds up the engine; failures are logged there.
+pub struct WebhookSink {
+    sender: Option<Sender<Value>>,
+    output: SinkOutput,
+}
+
+impl WebhookSink {
+    pub fn new(url: &str, headers: BTreeMap<String, String>, output: SinkOutput) -> Result<Self, Box<dyn std::error::Error>> {
+        if !(url.starts_with("http://") || url.starts_with("https://")) {
+            return Err(format!("webhook URL must start with http:// or https://, got {:?}", url).into());
+        }
+        
+        let (sender, receiver) = mpsc::channel::<Value>();
+        let url = url.to_string();
+        let agent = ureq::AgentBuilder::new().timeout(TIMEOUT).build();
+        
+        std::thread::spawn(move || {
+            for payload in receiver {
+                let mut request = agent.post(&url);
+                for (name, value) in &headers {
+                    request = request.set(name, value);
+                }
+                
+                if let Err(e) = request.send_json(payload) {
+                    log::error!("Webhook POST to {} failed: {}", url, e);
+                }
+            }
+        });
+        
+        Ok(Self {
+            sender: Some(sender),
+            output,
+        })
+    }
+}
+
+impl PresenceSink for WebhookSink {
+    fn update(&mut self, activity: Option<&ComposedActivity>) -> Result<(), Box<dyn std::error::Error>> {
+        let sender = self.sender.as_ref().ok_or("webhook sink is closed")?;
+        sender
+            .send(self.output.json(activity))
+            .map_err(|_| "webhook worker stopped".into())
+    }
+    
+    fn close(&mut self) {
+        // Dropping the sender lets the worker finish queued requests and exit
+        self.sender = None;
+    }
+}
@@ -0,0 +1,303 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::activity::{ActivityData, BatteryStatus, PlaybackStatus};
+
+/// Field values available to templates, keyed by placeholder name.
+pub type TemplateFields = BTreeMap<String, String>;
+
+/// Details/state lines with `{field}` placeholders, e.g. `"CPU {cpu}% | {app}"`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PresenceTemplate {
+    #[serde(default)]
+    pub details: String,
+    #[serde(default)]
+    pub state: String,
+    #[serde(default)]
+    pub large_image: Option<String>,
+    #[serde(default)]
+    pub large_text: Option<String>,
+}
+
+/// Replaces `{name}` placeholders with values from `fields`. Unknown fields render
+/// as empty text and `{{`/`}}` produce literal braces.
+pub fn render(template: &str, fields: &TemplateFields) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut chars = template.chars().peekable();
+    
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                output.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                output.push('}');
+            }
+            '{' => {
+                let mut name = String::new();
+                let mut closed = false;
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        closed = true;
+                        break;
+                    }
+                    name.push(c);
+                }
+                
+                if closed {
+                    if let Some(value) = fields.get(name.trim()) {
+                        output.push_str(value);
+                    }
+                } else {
+                    output.push('{');
+                    output.push_str(&name);
+                }
+            }
+            c => output.push(c),
+        }
+    }
+    
+    output.trim().to_string()
+}
+
+/// Names of the fields `template` uses, read the way `render` reads them.
+pub fn placeholders(template: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut chars = template.chars().peekable();
+    
+    while let Some(c) = chars.next() {
+        match c {
+            '{' | '}' if chars.peek() == Some(&c) => {
+                chars.next();
+            }
+            '{' => {
+                let mut name = String::new();
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        names.push(name.trim().to_string());
+                        break;
+                    }
+                    name.push(c);
+                }
+            }
+            _ => {}
+        }
+    }
+    
+    names
+}
+
+/// `m:ss`, as media players show durations.
+fn minutes_seconds(seconds: f64) -> String {
+    let seconds = seconds.max(0.0) as u64;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+/// Bytes in the largest unit that keeps the value at or above 1, e.g. `1.5 GB`.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+/// E.g. `340.0 KB/s`.
+pub fn format_rate(bytes_per_second: u64) -> String {
+    format!("{}/s", format_bytes(bytes_per_second))
+}
+
+/// The two largest units of a duration, e.g. `3d 4h`, `2h 15m` or `12m`.
+pub fn format_duration(seconds: u64) -> String {
+    let (days, hours, minutes) = (seconds / 86_400, seconds / 3_600 % 24, seconds / 60 % 60);
+    if days > 0 {
+        format!("{}d {}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+/// E.g. `62°C`.
+pub fn format_celsius(celsius: f32) -> String {
+    format!("{:.0}°C", celsius)
+}
+
+/// How many names `{container_names}` and `{container_images}` list before ending with `…`.
+const SHOWN_CONTAINERS: usize = 3;
+
+/// The first few distinct values, comma-separated, with `…` when some were left out.
+fn short_list<'a>(values: impl Iterator<Item = &'a str>) -> String {
+    let mut distinct: Vec<&str> = Vec::new();
+    for value in values {
+        if !distinct.contains(&value) {
+            distinct.push(value);
+        }
+    }
+    
+    let mut list = distinct.iter().take(SHOWN_CONTAINERS).copied().collect::<Vec<_>>().join(", ");
+    if distinct.len() > SHOWN_CONTAINERS {
+        list.push('…');
+    }
+    list
+}
+
+/// Builds the template fields derived from a monitoring sample.
+pub fn activity_fields(data: &ActivityData) -> TemplateFields {
+    let mut fields = TemplateFields::new();
+    let stats = &data.system_stats;
+    
+    fields.insert("cpu".to_string(), format!("{:.1}", stats.cpu_usage));
+    fields.insert("memory".to_string(), format!("{:.1}", stats.memory_usage));
+    fields.insert("memory_used_mb".to_string(), (stats.memory_used / 1024 / 1024).to_string());
+    fields.insert("memory_total_mb".to_string(), (stats.memory_total / 1024 / 1024).to_string());
+    fields.insert("process_count".to_string(), stats.process_count.to_string());
+    fields.insert("uptime".to_string(), format_duration(stats.uptime));
+    fields.insert("time".to_string(), data.current_time.format("%H:%M:%S").to_string());
+    fields.insert("date".to_string(), data.current_time.format("%Y-%m-%d").to_string());
+    
+    if let Some(process) = data.top_processes.first() {
+        fields.insert("app".to_string(), process.name.clone());
+        fields.insert("app_cpu".to_string(), format!("{:.1}", process.cpu_usage));
+    }
+    
+    if let Some(ref window) = data.active_window {
+        fields.insert("window".to_string(), window.clone());
+    }
+    
+    if let Some(ref media) = data.media {
+        fields.insert("media_title".to_string(), media.title.clone());
+        fields.insert("media_artist".to_string(), media.artist.clone().unwrap_or_default());
+        fields.insert("media_album".to_string(), media.album.clone().unwrap_or_default());
+        fields.insert("media_player".to_string(), media.player.clone());
+        let status = match media.status {
+            PlaybackStatus::Playing => "Playing",
+            PlaybackStatus::Paused => "Paused",
+        };
+        fields.insert("media_status".to_string(), status.to_string());
+        if let Some(position) = media.position_seconds {
+            fields.insert("media_position".to_string(), minutes_seconds(position));
+        }
+        if let Some(length) = media.length_seconds {
+            fields.insert("media_length".to_string(), minutes_seconds(length));
+        }
+    }
+    
+    if let Some(ref editor) = data.editor {
+        fields.insert("editor".to_string(), editor.editor.clone());
+        fields.insert("editor_file".to_string(), editor.file.clone());
+        fields.insert("editor_project".to_string(), editor.project.clone().unwrap_or_default());
+        fields.insert("language".to_string(), editor.language.clone().unwrap_or_default());
+        fields.insert("language_asset".to_string(), editor.language_asset.clone().unwrap_or_default());
+    }
+    
+    if let Some(ref terminal) = data.terminal {
+        fields.insert("terminal".to_string(), terminal.terminal.clone());
+        fields.insert("command".to_string(), terminal.command.clone());
+        fields.insert("command_line".to_string(), terminal.command_line());
+    }
+    
+    if let Some(ref git) = data.git {
+        fields.insert("git_repo".to_string(), git.repo.clone());
+        fields.insert("git_branch".to_string(), git.branch.clone().unwrap_or_default());
+        fields.insert("git_dirty".to_string(), if git.dirty { "*" } else { "" }.to_string());
+        fields.insert("git_commit".to_string(), git.commit.clone().unwrap_or_default());
+    }
+    
+    if let Some(ref battery) = data.battery {
+        fields.insert("battery".to_string(), format!("{:.0}", battery.percent));
+        let status = match battery.status {
+            BatteryStatus::Charging => "Charging",
+            BatteryStatus::Discharging => "Discharging",
+            BatteryStatus::Full => "Full",
+            BatteryStatus::NotCharging => "Not charging",
+        };
+        fields.insert("battery_status".to_string(), status.to_string());
+    }
+    
+    // The hottest sensor, plus one field per sensor label
+    let hottest = data.temperatures.iter().max_by(|a, b| a.celsius.total_cmp(&b.celsius));
+    if let Some(temperature) = hottest {
+        fields.insert("temperature".to_string(), format_celsius(temperature.celsius));
+    }
+    for temperature in &data.temperatures {
+        fields.insert(format!("temperature:{}", temperature.label), format_celsius(temperature.celsius));
+    }
+    
+    if let Some(ref load) = data.load {
+        fields.insert("load".to_string(), format!("{:.2}", load.one));
+        fields.insert("load_5".to_string(), format!("{:.2}", load.five));
+        fields.insert("load_15".to_string(), format!("{:.2}", load.fifteen));
+        let swap = if load.swap_total > 0 {
+            load.swap_used as f64 / load.swap_total as f64 * 100.0
+        } else {
+            0.0
+        };
+        fields.insert("swap".to_string(), format!("{:.1}", swap));
+        fields.insert("swap_used".to_string(), format_bytes(load.swap_used));
+        fields.insert("swap_total".to_string(), format_bytes(load.swap_total));
+    }
+    
+    // The first configured mount without a suffix, then every mount by path
+    for (i, disk) in data.disks.iter().enumerate() {
+        let percent = if disk.total > 0 {
+            disk.used as f64 / disk.total as f64 * 100.0
+        } else {
+            0.0
+        };
+        let values = [
+            ("disk", format!("{:.1}", percent)),
+            ("disk_used", format_bytes(disk.used)),
+            ("disk_total", format_bytes(disk.total)),
+        ];
+        for (name, value) in values {
+            if i == 0 {
+                fields.insert(name.to_string(), value.clone());
+            }
+            fields.insert(format!("{}:{}", name, disk.mount), value);
+        }
+    }
+    
+    // Totals over all reported interfaces, then every interface by name
+    if !data.networks.is_empty() {
+        let received = data.networks.iter().map(|network| network.received_per_second).sum();
+        let sent = data.networks.iter().map(|network| network.sent_per_second).sum();
+        fields.insert("net_down".to_string(), format_rate(received));
+        fields.insert("net_up".to_string(), format_rate(sent));
+    }
+    for network in &data.networks {
+        fields.insert(format!("net_down:{}", network.interface), format_rate(network.received_per_second));
+        fields.insert(format!("net_up:{}", network.interface), format_rate(network.sent_per_second));
+    }
+    
+    if let Some(ref containers) = data.containers {
+        fields.insert("containers".to_string(), containers.len().to_string());
+        fields.insert("container_names".to_string(), short_list(containers.iter().map(|container| container.name.as_str())));
+        fields.insert("container_images".to_string(), short_list(containers.iter().map(|container| container.image.as_str())));
+        let mut projects: Vec<&str> = containers.iter().filter_map(|container| container.project.as_deref()).collect();
+        projects.sort_unstable();
+        projects.dedup();
+        fields.insert("compose_projects".to_string(), projects.join(", "));
+    }
+    
+    // Command and plugin fields come last, so they can deliberately replace a built-in field
+    for command in &data.commands {
+        fields.extend(command.fields.iter().map(|(name, value)| (name.clone(), value.clone())));
+    }
+    for plugin in &data.plugins {
+        fields.extend(plugin.fields.iter().map(|(name, value)| (name.clone(), value.clone())));
+    }
+    
+    fields
+}
@@ -0,0 +1,643 @@
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+
+use crate::engine::PresenceEngine;
+
+/// Tray icon with a quick-action menu, as a StatusNotifierItem on Linux.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrayConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Closing the window hides it to the tray instead of quitting, while the icon is shown.
+    #[serde(default = "default_true")]
+    pub close_to_tray: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for TrayConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            close_to_tray: true,
+        }
+    }
+}
+
+/// What the tray asks of the window, which it does not own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowAction {
+    Open,
+    Quit,
+}
+
+pub type WindowCallback = Box<dyn Fn(WindowAction) + Send + Sync>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrayStatus {
+    Connected,
+    Disconnected,
+    Paused,
+}
+
+/// What the tray shows, taken from the engine.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrayState {
+    pub status: TrayStatus,
+    pub profiles: Vec<String>,
+    /// The manually selected profile; `None` is the base profile.
+    pub active_profile: Option<String>,
+    pub messages: Vec<String>,
+    pub message: String,
+    pub tooltip: String,
+}
+
+impl TrayState {
+    pub fn from_engine(engine: &PresenceEngine) -> Self {
+        let status = if engine.privacy.hidden {
+            TrayStatus::Paused
+        } else if engine.connection_status == "Connected" {
+            TrayStatus::Connected
+        } else {
+            TrayStatus::Disconnected
+        };
+        
+        let mut tooltip = match status {
+            TrayStatus::Paused => "Presence paused".to_string(),
+            _ => format!(
+                "{}: {}",
+                engine.connection_status,
+                engine.settings.profile.as_deref().unwrap_or("Base")
+            ),
+        };
+        if let Some(ref reason) = engine.hidden_reason {
+            tooltip.push_str(&format!(" (hidden by {})", reason));
+        }
+        
+        Self {
+            status,
+            profiles: engine.config.profiles.iter().map(|profile| profile.name.clone()).collect(),
+            active_profile: engine.config.active_profile.clone(),
+            messages: engine.config.custom_messages.clone(),
+            message: engine.custom_message.clone(),
+            tooltip,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MenuAction {
+    TogglePause,
+    Profile(Option<String>),
+    /// Sets the custom message; an empty one clears it.
+    Message(String),
+    Window(WindowAction),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuToggle {
+    Checkmark(bool),
+    Radio(bool),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MenuItem {
+    /// Unique within one menu; 0 is the root.
+    pub id: i32,
+    /// Empty for separators.
+    pub label: String,
+    pub action: Option<MenuAction>,
+    pub toggle: Option<MenuToggle>,
+    pub enabled: bool,
+    pub children: Vec<MenuItem>,
+}
+
+impl MenuItem {
+    pub fn is_separator(&self) -> bool {
+        self.label.is_empty()
+    }
+    
+    /// This item or a descendant with `id`.
+    pub fn find(&self, id: i32) -> Option<&MenuItem> {
+        if self.id == id {
+            return Some(self);
+        }
+        self.children.iter().find_map(|child| child.find(id))
+    }
+}
+
+/// Builds the tray menu for `state`: pause, profile and message submenus, and the window.
+pub fn menu(state: &TrayState) -> MenuItem {
+    let mut next_id = 0;
+    let mut item = |label: &str, action: Option<MenuAction>, toggle: Option<MenuToggle>, children: Vec<MenuItem>| {
+        next_id += 1;
+        MenuItem {
+            id: next_id,
+            label: label.to_string(),
+            enabled: action.is_some() || !children.is_empty(),
+            action,
+            toggle,
+            children,
+        }
+    };
+    
+    let paused = state.status == TrayStatus::Paused;
+    let pause = item("Pause Presence", Some(MenuAction::TogglePause), Some(MenuToggle::Checkmark(paused)), Vec::new());
+    
+    let mut profiles = vec![item(
+        "Base",
+        Some(MenuAction::Profile(None)),
+        Some(MenuToggle::Radio(state.active_profile.is_none())),
+        Vec::new(),
+    )];
+    for name in &state.profiles {
+        let active = state.active_profile.as_ref() == Some(name);
+        profiles.push(item(name, Some(MenuAction::Profile(Some(name.clone()))), Some(MenuToggle::Radio(active)), Vec::new()));
+    }
+    let profiles = item("Profile", None, None, profiles);
+    
+    let mut messages = vec![item(
+        "None",
+        Some(MenuAction::Message(String::new())),
+        Some(MenuToggle::Radio(state.message.is_empty())),
+        Vec::new(),
+    )];
+    for message in state.messages.iter().filter(|message| !message.is_empty()) {
+        let active = state.message == *message;
+        messages.push(item(message, Some(MenuAction::Message(message.clone())), Some(MenuToggle::Radio(active)), Vec::new()));
+    }
+    let messages = item("Custom Message", None, None, messages);
+    
+    let children = vec![
+        pause,
+        item("", None, None, Vec::new()),
+        profiles,
+        messages,
+        item("", None, None, Vec::new()),
+        item("Open Window", Some(MenuAction::Window(WindowAction::Open)), None, Vec::new()),
+        item("Quit", Some(MenuAction::Window(WindowAction::Quit)), None, Vec::new()),
+    ];
+    MenuItem {
+        id: 0,
+        label: "MultiRichPresence".to_string(),
+        action: None,
+        toggle: None,
+        enabled: true,
+        children,
+    }
+}
+
+/// Carries out a menu action. Window actions go to `on_window` after the engine is unlocked.
+pub fn apply(action: &MenuAction, engine: &Arc<Mutex<PresenceEngine>>, on_window: &WindowCallback) {
+    if let MenuAction::Window(action) = action {
+        on_window(*action);
+        return;
+    }
+    
+    let mut engine = match engine.lock() {
+        Ok(engine) => engine,
+        Err(_) => return,
+    };
+    match action {
+        MenuAction::TogglePause if engine.privacy.hidden => engine.resume_presence(),
+        MenuAction::TogglePause => engine.hide_presence(None),
+        MenuAction::Profile(profile) => {
+            if let Err(e) = engine.switch_profile(profile.clone()) {
+                log::error!("Failed to switch profile from the tray: {}", e);
+            }
+        }
+        MenuAction::Message(message) => engine.set_custom_message(message),
+        MenuAction::Window(_) => {}
+    }
+}
+
+/// A round icon in the status color, as ARGB32 in network byte order.
+pub fn icon(status: TrayStatus, size: i32) -> Vec<u8> {
+    let (red, green, blue) = match status {
+        TrayStatus::Connected => (0x23, 0xa5, 0x5a),
+        TrayStatus::Disconnected => (0xf2, 0x3f, 0x43),
+        TrayStatus::Paused => (0x80, 0x84, 0x8e),
+    };
+    let center = size as f32 / 2.0;
+    let radius = center - 1.0;
+    
+    let mut pixels = Vec::with_capacity((size * size * 4) as usize);
+    for y in 0..size {
+        for x in 0..size {
+            let distance = ((x as f32 + 0.5 - center).powi(2) + (y as f32 + 0.5 - center).powi(2)).sqrt();
+            // One pixel of anti-aliasing at the edge
+            let alpha = (radius - distance + 0.5).clamp(0.0, 1.0);
+            pixels.extend_from_slice(&[(alpha * 255.0).round() as u8, red, green, blue]);
+        }
+    }
+    pixels
+}
+
+#[cfg(target_os = "linux")]
+pub use sni::Tray;
+
+#[cfg(target_os = "linux")]
+mod sni {
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use zbus::blocking::fdo::DBusProxy;
+    use zbus::blocking::{Connection, Proxy};
+    use zbus::zvariant::{OwnedObjectPath, OwnedValue, StructureBuilder, Type, Value};
+    
+    use super::{apply, icon, menu, MenuItem, MenuToggle, TrayState, WindowAction, WindowCallback};
+    use crate::engine::PresenceEngine;
+    
+    const ITEM_PATH: &str = "/StatusNotifierItem";
+    const ITEM_INTERFACE: &str = "org.kde.StatusNotifierItem";
+    const MENU_PATH: &str = "/MenuBar";
+    const MENU_INTERFACE: &str = "com.canonical.dbusmenu";
+    const WATCHER: &str = "org.kde.StatusNotifierWatcher";
+    
+    /// How often the icon and menu are compared against the engine.
+    const REFRESH_INTERVAL: Duration = Duration::from_millis(500);
+    
+    type Pixmap = (i32, i32, Vec<u8>);
+    
+    struct Shared {
+        engine: Arc<Mutex<PresenceEngine>>,
+        on_window: WindowCallback,
+        menu: Mutex<MenuState>,
+    }
+    
+    struct MenuState {
+        revision: u32,
+        state: TrayState,
+        menu: MenuItem,
+    }
+    
+    impl Shared {
+        fn state(&self) -> TrayState {
+            self.menu.lock().map(|menu| menu.state.clone()).unwrap_or_else(|e| e.into_inner().state.clone())
+        }
+    }
+    
+    /// The tray icon, registered with the desktop's StatusNotifierWatcher. The menu follows
+    /// the engine, and the item is registered again whenever a tray host (re)appears.
+    pub struct Tray {
+        visible: Arc<AtomicBool>,
+        stop: Arc<AtomicBool>,
+        _connection: Connection,
+    }
+    
+    impl Tray {
+        /// Shows the icon on the session bus.
+        pub fn start(engine: &Arc<Mutex<PresenceEngine>>, on_window: WindowCallback) -> Result<Tray, Box<dyn std::error::Error>> {
+            Self::start_with(Connection::session()?, engine, on_window)
+        }
+        
+        /// Shows the icon on the bus at `address`, e.g. a private `dbus-daemon`.
+        pub fn start_at(address: &str, engine: &Arc<Mutex<PresenceEngine>>, on_window: WindowCallback) -> Result<Tray, Box<dyn std::error::Error>> {
+            Self::start_with(zbus::blocking::connection::Builder::address(address)?.build()?, engine, on_window)
+        }
+        
+        fn start_with(connection: Connection, engine: &Arc<Mutex<PresenceEngine>>, on_window: WindowCallback) -> Result<Tray, Box<dyn std::error::Error>> {
+            let state = match engine.lock() {
+                Ok(engine) => TrayState::from_engine(&engine),
+                Err(_) => return Err("the engine is poisoned".into()),
+            };
+            let shared = Arc::new(Shared {
+                engine: Arc::clone(engine),
+                on_window,
+                menu: Mutex::new(MenuState {
+                    revision: 1,
+                    menu: menu(&state),
+                    state,
+                }),
+            });
+            
+            let name = format!("org.kde.StatusNotifierItem-{}-1", std::process::id());
+            connection.object_server().at(ITEM_PATH, Item { shared: Arc::clone(&shared) })?;
+            connection.object_server().at(MENU_PATH, Menu { shared: Arc::clone(&shared) })?;
+            connection.request_name(name.as_str())?;
+            
+            let tray = Tray {
+                visible: Arc::new(AtomicBool::new(false)),
+                stop: Arc::new(AtomicBool::new(false)),
+                _connection: connection.clone(),
+            };
+            let (visible, stop) = (Arc::clone(&tray.visible), Arc::clone(&tray.stop));
+            std::thread::spawn(move || {
+                let mut registered_with = None;
+                while !stop.load(Ordering::Relaxed) {
+                    registered_with = register(&connection, &name, registered_with);
+                    visible.store(registered_with.is_some(), Ordering::Relaxed);
+                    refresh(&connection, &shared);
+                    std::thread::sleep(REFRESH_INTERVAL);
+                }
+            });
+            
+            Ok(tray)
+        }
+        
+        /// Whether a tray host is showing the icon; without one, closing the window should quit.
+        pub fn is_visible(&self) -> bool {
+            self.visible.load(Ordering::Relaxed)
+        }
+    }
+    
+    impl Drop for Tray {
+        fn drop(&mut self) {
+            self.stop.store(true, Ordering::Relaxed);
+        }
+    }
+    
+    /// Registers the item with the current watcher unless it already is; returns the
+    /// watcher's unique name, or `None` while no tray host is running.
+    fn register(connection: &Connection, name: &str, registered_with: Option<String>) -> Option<String> {
+        let owner = DBusProxy::new(connection).ok()?.get_name_owner(WATCHER.try_into().ok()?).ok()?.to_string();
+        if registered_with.as_deref() == Some(owner.as_str()) {
+            return registered_with;
+        }
+        
+        let watcher = Proxy::new(connection, WATCHER, "/StatusNotifierWatcher", WATCHER).ok()?;
+        match watcher.call_method("RegisterStatusNotifierItem", &(name,)) {
+            Ok(_) => {
+                log::info!("Tray icon registered with {}", owner);
+                Some(owner)
+            }
+            Err(e) => {
+                log::warn!("Failed to register the tray icon: {}", e);
+                None
+            }
+        }
+    }
+    
+    /// Rebuilds the menu when the engine changed and tells the host what to reload.
+    fn refresh(connection: &Connection, shared: &Shared) {
+        let state = match shared.engine.lock() {
+            Ok(engine) => TrayState::from_engine(&engine),
+            Err(_) => return,
+        };
+        
+        let (revision, previous) = {
+            let mut current = match shared.menu.lock() {
+                Ok(current) => current,
+                Err(_) => return,
+            };
+            if current.state == state {
+                return;
+            }
+            current.revision += 1;
+            current.menu = menu(&state);
+            (current.revision, std::mem::replace(&mut current.state, state.clone()))
+        };
+        
+        let mut signals: Vec<(&str, &str, &str)> = vec![(MENU_PATH, MENU_INTERFACE, "LayoutUpdated")];
+        if previous.status != state.status {
+            signals.push((ITEM_PATH, ITEM_INTERFACE, "NewIcon"));
+        }
+        if previous.tooltip != state.tooltip {
+            signals.push((ITEM_PATH, ITEM_INTERFACE, "NewToolTip"));
+        }
+        for (path, interface, signal) in signals {
+            let result = if signal == "LayoutUpdated" {
+                connection.emit_signal(None::<&str>, path, interface, signal, &(revision, 0i32))
+            } else {
+                connection.emit_signal(None::<&str>, path, interface, signal, &())
+            };
+            if let Err(e) = result {
+                log::debug!("Failed to emit {}: {}", signal, e);
+            }
+        }
+    }
+    
+    fn pixmaps(state: &TrayState) -> Vec<Pixmap> {
+        [22, 32, 48].into_iter().map(|size| (size, size, icon(state.status, size))).collect()
+    }
+    
+    struct Item {
+        shared: Arc<Shared>,
+    }
+    
+    #[zbus::interface(name = "org.kde.StatusNotifierItem")]
+    impl Item {
+        #[zbus(property)]
+        fn category(&self) -> String {
+            "ApplicationStatus".to_string()
+        }
+        
+        #[zbus(property)]
+        fn id(&self) -> String {
+            "multi-rich-presence".to_string()
+        }
+        
+        #[zbus(property)]
+        fn title(&self) -> String {
+            "MultiRichPresence".to_string()
+        }
+        
+        #[zbus(property)]
+        fn status(&self) -> String {
+            "Active".to_string()
+        }
+        
+        #[zbus(property)]
+        fn icon_name(&self) -> String {
+            String::new()
+        }
+        
+        #[zbus(property)]
+        fn icon_pixmap(&self) -> Vec<Pixmap> {
+            pixmaps(&self.shared.state())
+        }
+        
+        #[zbus(property)]
+        fn tool_tip(&self) -> (String, Vec<Pixmap>, String, String) {
+            let state = self.shared.state();
+            (String::new(), Vec::new(), "MultiRichPresence".to_string(), state.tooltip)
+        }
+        
+        #[zbus(property)]
+        fn item_is_menu(&self) -> bool {
+            false
+        }
+        
+        #[zbus(property)]
+        fn menu(&self) -> OwnedObjectPath {
+            OwnedObjectPath::try_from(MENU_PATH).expect("valid object path")
+        }
+        
+        fn activate(&self, _x: i32, _y: i32) {
+            (self.shared.on_window)(WindowAction::Open);
+        }
+        
+        fn secondary_activate(&self, _x: i32, _y: i32) {}
+        
+        fn context_menu(&self, _x: i32, _y: i32) {}
+        
+        fn scroll(&self, _delta: i32, _orientation: String) {}
+    }
+    
+    /// A dbusmenu layout node: `(ia{sv}av)`.
+    #[derive(Debug, serde::Serialize, Type)]
+    struct Layout {
+        id: i32,
+        properties: HashMap<String, OwnedValue>,
+        children: Vec<OwnedValue>,
+    }
+    
+    fn owned(value: Value) -> OwnedValue {
+        OwnedValue::try_from(value).expect("menu properties hold no file descriptors")
+    }
+    
+    fn properties(item: &MenuItem) -> HashMap<String, OwnedValue> {
+        let mut properties = HashMap::new();
+        if item.is_separator() {
+            properties.insert("type".to_string(), owned(Value::from("separator")));
+            return properties;
+        }
+        
+        properties.insert("label".to_string(), owned(Value::from(item.label.replace('_', "__"))));
+        if !item.enabled {
+            properties.insert("enabled".to_string(), owned(Value::from(false)));
+        }
+        if !item.children.is_empty() {
+            properties.insert("children-display".to_string(), owned(Value::from("submenu")));
+        }
+        match item.toggle {
+            Some(MenuToggle::Checkmark(on)) | Some(MenuToggle::Radio(on)) => {
+                let kind = if matches!(item.toggle, Some(MenuToggle::Radio(_))) { "radio" } else { "checkmark" };
+                properties.insert("toggle-type".to_string(), owned(Value::from(kind)));
+                properties.insert("toggle-state".to_string(), owned(Value::from(on as i32)));
+            }
+            None => {}
+        }
+        properties
+    }
+    
+    /// `item` down to `depth` levels of children; -1 is unlimited.
+    fn layout(item: &MenuItem, depth: i32) -> Layout {
+        let children = match depth {
+            0 => Vec::new(),
+            depth => item
+                .children
+                .iter()
+                .map(|child| {
+                    let child = layout(child, depth - 1);
+                    let structure = StructureBuilder::new()
+                        .add_field(child.id)
+                        .add_field(child.properties)
+                        .add_field(child.children)
+                        .build();
+                    owned(Value::from(structure))
+                })
+                .collect(),
+        };
+        Layout {
+            id: item.id,
+            properties: properties(item),
+            children,
+        }
+    }
+    
+    struct Menu {
+        shared: Arc<Shared>,
+    }
+    
+    impl Menu {
+        fn with_menu<T>(&self, read: impl FnOnce(u32, &MenuItem) -> T) -> T {
+            let current = self.shared.menu.lock().unwrap_or_else(|e| e.into_inner());
+            read(current.revision, &current.menu)
+        }
+    }
+    
+    #[zbus::interface(name = "com.canonical.dbusmenu")]
+    impl Menu {
+        fn get_layout(&self, parent_id: i32, recursion_depth: i32, _property_names: Vec<String>) -> zbus::fdo::Result<(u32, Layout)> {
+            self.with_menu(|revision, menu| match menu.find(parent_id) {
+                Some(parent) => Ok((revision, layout(parent, recursion_depth))),
+                None => Err(zbus::fdo::Error::InvalidArgs(format!("no menu item {}", parent_id))),
+            })
+        }
+        
+        fn get_group_properties(&self, ids: Vec<i32>, _property_names: Vec<String>) -> Vec<(i32, HashMap<String, OwnedValue>)> {
+            self.with_menu(|_, menu| {
+                ids.iter().filter_map(|id| menu.find(*id)).map(|item| (item.id, properties(item))).collect()
+            })
+        }
+        
+        fn get_property(&self, id: i32, name: String) -> zbus::fdo::Result<OwnedValue> {
+            self.with_menu(|_, menu| {
+                menu.find(id)
+                    .and_then(|item| properties(item).remove(&name))
+                    .ok_or_else(|| zbus::fdo::Error::InvalidArgs(format!("menu item {} has no property {}", id, name)))
+            })
+        }
+        
+        fn event(&self, id: i32, event_id: String, _data: OwnedValue, _timestamp: u32) {
+            if event_id != "clicked" {
+                return;
+            }
+            
+            // Look the action up first: applying it locks the engine
+            let action = self.with_menu(|_, menu| menu.find(id).and_then(|item| item.action.clone()));
+            if let Some(action) = action {
+                apply(&action, &self.shared.engine, &self.shared.on_window);
+            }
+        }
+        
+        fn event_group(&self, events: Vec<(i32, String, OwnedValue, u32)>) -> Vec<i32> {
+            let mut missing = Vec::new();
+            for (id, event_id, data, timestamp) in events {
+                if self.with_menu(|_, menu| menu.find(id).is_none()) {
+                    missing.push(id);
+                } else {
+                    self.event(id, event_id, data, timestamp);
+                }
+            }
+            missing
+        }
+        
+        fn about_to_show(&self, _id: i32) -> bool {
+            false
+        }
+        
+        fn about_to_show_group(&self, _ids: Vec<i32>) -> (Vec<i32>, Vec<i32>) {
+            (Vec::new(), Vec::new())
+        }
+        
+        #[zbus(property)]
+        fn version(&self) -> u32 {
+            3
+        }
+        
+        #[zbus(property)]
+        fn text_direction(&self) -> String {
+            "ltr".to_string()
+        }
+        
+        #[zbus(property)]
+        fn status(&self) -> String {
+            "normal".to_string()
+        }
+        
+        #[zbus(property)]
+        fn icon_theme_path(&self) -> Vec<String> {
+            Vec::new()
+        }
+    }
+}
+
+/// Stands in for the tray where no tray protocol is implemented.
+#[cfg(not(target_os = "linux"))]
+pub struct Tray;
+
+#[cfg(not(target_os = "linux"))]
+impl Tray {
+    pub fn start(_engine: &Arc<Mutex<PresenceEngine>>, _on_window: WindowCallback) -> Result<Tray, Box<dyn std::error::Error>> {
+        Err("the tray icon is only available on Linux".into())
+    }
+    
+    pub fn is_visible(&self) -> bool {
+        false
+    }
+}
@@ -0,0 +1,101 @@
+#[cfg(not(target_os = "linux"))]
+use std::sync::{Arc, Mutex};
+
+#[cfg(not(target_os = "linux"))]
+use crate::engine::PresenceEngine;
+
+#[cfg(target_os = "linux")]
+pub use linux::ConfigWatcher;
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use inotify::{Inotify, WatchMask};
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    
+    use crate::config;
+    use crate::engine::PresenceEngine;
+    
+    /// How often the watcher checks for changes and whether to stop.
+    const POLL_INTERVAL: Duration = Duration::from_millis(100);
+    
+    /// Reloads the config whenever `config.json` is written. Watches the directory rather
+    /// than the file, since editors often save by renaming a new file over the old one.
+    /// Dropping this stops watching.
+    pub struct ConfigWatcher {
+        path: PathBuf,
+        stop: Arc<AtomicBool>,
+    }
+    
+    impl ConfigWatcher {
+        pub fn start(engine: &Arc<Mutex<PresenceEngine>>) -> Result<ConfigWatcher, Box<dyn std::error::Error>> {
+            let path = config::config_path()?;
+            let (dir, name) = match (path.parent(), path.file_name()) {
+                (Some(dir), Some(name)) => (dir.to_path_buf(), name.to_os_string()),
+                _ => return Err(format!("{} has no parent directory", path.display()).into()),
+            };
+            std::fs::create_dir_all(&dir)?;
+            
+            let mut inotify = Inotify::init()?;
+            inotify.watches().add(&dir, WatchMask::CLOSE_WRITE | WatchMask::MOVED_TO)?;
+            
+            let watcher = ConfigWatcher {
+                path,
+                stop: Arc::new(AtomicBool::new(false)),
+            };
+            let (stop, engine) = (Arc::clone(&watcher.stop), Arc::clone(engine));
+            std::thread::spawn(move || {
+                let mut buffer = [0; 4096];
+                loop {
+                    std::thread::sleep(POLL_INTERVAL);
+                    if stop.load(Ordering::Relaxed) {
+                        return;
+                    }
+                    
+                    // Several events from one save arrive together, so one reload covers them
+                    let changed = match inotify.read_events(&mut buffer) {
+                        Ok(events) => events.into_iter().any(|event| event.name == Some(name.as_os_str())),
+                        Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => false,
+                        Err(e) => {
+                            log::error!("Stopped watching the config file: {}", e);
+                            return;
+                        }
+                    };
+                    if changed {
+                        if let Ok(mut engine) = engine.lock() {
+                            // A rejected file is logged and kept in `config_errors` by the engine,
+                            // and the engine's own saves are recognized and skipped there
+                            let _ = engine.reload_config();
+                        }
+                    }
+                }
+            });
+            
+            Ok(watcher)
+        }
+        
+        pub fn path(&self) -> &std::path::Path {
+            &self.path
+        }
+    }
+    
+    impl Drop for ConfigWatcher {
+        fn drop(&mut self) {
+            self.stop.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Stands in for the watcher where inotify is not available; the config is still
+/// reloaded with `mrp reload`.
+#[cfg(not(target_os = "linux"))]
+pub struct ConfigWatcher;
+
+#[cfg(not(target_os = "linux"))]
+impl ConfigWatcher {
+    pub fn start(_engine: &Arc<Mutex<PresenceEngine>>) -> Result<ConfigWatcher, Box<dyn std::error::Error>> {
+        Err("watching the config file is only available on Linux".into())
+    }
+}
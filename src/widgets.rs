@@ -0,0 +1,1356 @@
+use chrono::{NaiveTime, Timelike};
+use eframe::egui;
+use egui_plot::{Bar, BarChart, Line, Plot, PlotPoints};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use multi_rich_presence::activity::{CommandStatus, PluginStatus, ProcessEntry};
+use multi_rich_presence::config::ActivityFilters;
+use multi_rich_presence::history::HistoryConfig;
+use multi_rich_presence::hotkeys::{BindingState, Hotkey, HotkeyAction, HotkeysConfig};
+use multi_rich_presence::report::{self, Report};
+use multi_rich_presence::provider::{Capability, CommandOutput, CommandSource, EditorConfig, EditorPattern, GitConfig, ContainersConfig, Language, MediaConfig, PluginConfig, ProvidersConfig, RepoPrivacy, TerminalConfig};
+use multi_rich_presence::rules::{Rule, RuleAction, RulePatterns};
+use multi_rich_presence::samples::{ProcessSample, RingBuffer, SampleHistory, StatsSample};
+use multi_rich_presence::schedule::{ScheduleAction, ScheduleBlock};
+use multi_rich_presence::script::{Script, ScriptingConfig};
+use multi_rich_presence::sink::{MqttConfig, SinkConfig, SinkFormat, SinkKind};
+use multi_rich_presence::template::{self, PresenceTemplate};
+use multi_rich_presence::tray::TrayConfig;
+
+pub const TEMPLATE_FIELDS_HELP: &str =
+    "Fields: {cpu} {memory} {memory_used_mb} {memory_total_mb} {process_count} {time} {date} {app} {app_cpu} {window} {message} {profile} {media_title} {media_artist} {media_album} {media_player} {media_status} {media_position} {media_length} {git_repo} {git_branch} {git_dirty} {git_commit} {editor} {editor_file} {editor_project} {language} {language_asset} {terminal} {command} {command_line} {uptime} {battery} {battery_status} {temperature} {load} {load_5} {load_15} {swap} {swap_used} {swap_total} {disk} {disk_used} {disk_total} {net_down} {net_up} {containers} {container_names} {container_images} {compose_projects}; per sensor, mount or interface: {temperature:LABEL} {disk:/home} {net_down:wlan0}";
+
+pub const SINK_FIELDS_HELP: &str = "Fields: {details} {state} {large_image} {large_text} {start_timestamp} {end_timestamp}";
+
+pub fn time_edit(ui: &mut egui::Ui, time: &mut NaiveTime) {
+    let mut hour = time.hour();
+    let mut minute = time.minute();
+    
+    let hour_changed = ui.add(egui::DragValue::new(&mut hour).clamp_range(0..=23)).changed();
+    ui.label(":");
+    let minute_changed = ui.add(egui::DragValue::new(&mut minute).clamp_range(0..=59)).changed();
+    
+    if hour_changed || minute_changed {
+        if let Some(updated) = NaiveTime::from_hms_opt(hour, minute, 0) {
+            *time = updated;
+        }
+    }
+}
+
+pub fn schedule_block_color(index: usize, block: &ScheduleBlock) -> egui::Color32 {
+    const PALETTE: [egui::Color32; 6] = [
+        egui::Color32::from_rgb(70, 130, 180),
+        egui::Color32::from_rgb(60, 160, 90),
+        egui::Color32::from_rgb(200, 140, 50),
+        egui::Color32::from_rgb(150, 90, 180),
+        egui::Color32::from_rgb(40, 160, 160),
+        egui::Color32::from_rgb(190, 80, 110),
+    ];
+    
+    match block.action {
+        ScheduleAction::Off => egui::Color32::from_rgb(90, 90, 90),
+        ScheduleAction::Profile(_) => PALETTE[index % PALETTE.len()],
+    }
+}
+
+/// Single-line editor for an optional string; clearing the text unsets it.
+pub fn optional_text_edit(ui: &mut egui::Ui, value: &mut Option<String>, hint: &str) {
+    let mut text = value.clone().unwrap_or_default();
+    if ui.add(egui::TextEdit::singleline(&mut text).hint_text(hint).desired_width(140.0)).changed() {
+        *value = if text.is_empty() { None } else { Some(text) };
+    }
+}
+
+/// Checkbox pair for a profile override: the first toggles the override, the second
+/// edits its value. `inherited` is shown while no override is set.
+pub fn override_checkbox(ui: &mut egui::Ui, value: &mut Option<bool>, inherited: bool, label: &str) {
+    ui.horizontal(|ui| {
+        let mut overridden = value.is_some();
+        if ui.checkbox(&mut overridden, "Override").changed() {
+            *value = if overridden { Some(inherited) } else { None };
+        }
+        
+        match value {
+            Some(ref mut enabled) => {
+                ui.checkbox(enabled, label);
+            }
+            None => {
+                ui.add_enabled(false, egui::Checkbox::new(&mut inherited.clone(), label));
+            }
+        }
+    });
+}
+
+pub fn template_ui(ui: &mut egui::Ui, template: &mut PresenceTemplate) {
+    ui.horizontal(|ui| {
+        ui.label("Details:");
+        ui.add(egui::TextEdit::singleline(&mut template.details).hint_text("CPU {cpu}% | {app}"));
+    });
+    ui.horizontal(|ui| {
+        ui.label("State:");
+        ui.add(egui::TextEdit::singleline(&mut template.state).hint_text("{window}"));
+    });
+    ui.horizontal(|ui| {
+        ui.label("Large Image:");
+        optional_text_edit(ui, &mut template.large_image, "default");
+        ui.label("Large Text:");
+        optional_text_edit(ui, &mut template.large_text, "MultiRichPresence");
+    });
+}
+
+/// Editor for an optional template; unchecking falls back to the inherited layout.
+pub fn optional_template_ui(ui: &mut egui::Ui, template: &mut Option<PresenceTemplate>, label: &str) {
+    let mut enabled = template.is_some();
+    if ui.checkbox(&mut enabled, label).changed() {
+        *template = if enabled { Some(PresenceTemplate::default()) } else { None };
+    }
+    
+    if let Some(ref mut template) = template {
+        template_ui(ui, template);
+        ui.small(TEMPLATE_FIELDS_HELP);
+    }
+}
+
+pub fn rules_ui(ui: &mut egui::Ui, rules: &mut Vec<Rule>, profile_names: &[String], patterns: &RulePatterns) {
+    let mut to_remove = None;
+    
+    for (i, rule) in rules.iter_mut().enumerate() {
+        ui.push_id(i, |ui| {
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut rule.enabled, "");
+                ui.add(egui::TextEdit::singleline(&mut rule.name).desired_width(140.0));
+                if ui.button("Remove").clicked() {
+                    to_remove = Some(i);
+                }
+            });
+            
+            ui.horizontal(|ui| {
+                ui.label("Process:");
+                optional_text_edit(ui, &mut rule.process, "any");
+                ui.label("Window:");
+                optional_text_edit(ui, &mut rule.window, "regex");
+            });
+            if let Some(error) = rule.window.as_deref().and_then(|pattern| patterns.error(pattern)) {
+                ui.colored_label(egui::Color32::RED, format!("Invalid window pattern, the rule never matches: {}", error));
+            }
+            
+            ui.horizontal(|ui| {
+                ui.label("Then:");
+                let selected = match rule.action {
+                    RuleAction::Profile(_) => "Use profile",
+                    RuleAction::Template(_) => "Use template",
+                    RuleAction::Hide => "Hide presence",
+                };
+                egui::ComboBox::from_id_source("rule_action")
+                    .selected_text(selected)
+                    .show_ui(ui, |ui| {
+                        if ui.selectable_label(matches!(rule.action, RuleAction::Hide), "Hide presence").clicked() {
+                            rule.action = RuleAction::Hide;
+                        }
+                        if ui.selectable_label(matches!(rule.action, RuleAction::Profile(_)), "Use profile").clicked()
+                            && !matches!(rule.action, RuleAction::Profile(_))
+                        {
+                            rule.action = RuleAction::Profile(profile_names.first().cloned().unwrap_or_default());
+                        }
+                        if ui.selectable_label(matches!(rule.action, RuleAction::Template(_)), "Use template").clicked()
+                            && !matches!(rule.action, RuleAction::Template(_))
+                        {
+                            rule.action = RuleAction::Template(PresenceTemplate::default());
+                        }
+                    });
+                
+                if let RuleAction::Profile(ref mut profile) = rule.action {
+                    egui::ComboBox::from_id_source("rule_profile")
+                        .selected_text(profile.as_str())
+                        .show_ui(ui, |ui| {
+                            for name in profile_names {
+                                ui.selectable_value(profile, name.clone(), name);
+                            }
+                        });
+                }
+            });
+            
+            if let RuleAction::Template(ref mut template) = rule.action {
+                template_ui(ui, template);
+            }
+            
+            ui.separator();
+        });
+    }
+    
+    if let Some(index) = to_remove {
+        rules.remove(index);
+    }
+    
+    if ui.button("Add Rule").clicked() {
+        rules.push(Rule::default());
+    }
+}
+
+/// Edits a list as comma-separated text.
+pub fn comma_list_edit(ui: &mut egui::Ui, values: &mut Vec<String>, hint: &str) {
+    let mut text = values.join(", ");
+    if ui.add(egui::TextEdit::singleline(&mut text).hint_text(hint).desired_width(200.0)).changed() {
+        *values = text
+            .split(',')
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+            .collect();
+    }
+}
+
+/// Key/value rows with add and remove buttons.
+pub fn map_edit(ui: &mut egui::Ui, map: &mut BTreeMap<String, String>, key_hint: &str, value_hint: &str) {
+    let mut to_remove = None;
+    let mut renamed = None;
+    
+    for (i, (key, value)) in map.iter_mut().enumerate() {
+        ui.push_id(i, |ui| {
+            ui.horizontal(|ui| {
+                let mut new_key = key.clone();
+                if ui.add(egui::TextEdit::singleline(&mut new_key).hint_text(key_hint).desired_width(100.0)).changed() {
+                    renamed = Some((key.clone(), new_key));
+                }
+                ui.add(egui::TextEdit::singleline(value).hint_text(value_hint).desired_width(200.0));
+                if ui.button("Remove").clicked() {
+                    to_remove = Some(key.clone());
+                }
+            });
+        });
+    }
+    
+    if let Some((old, new)) = renamed {
+        if !map.contains_key(&new) {
+            if let Some(value) = map.remove(&old) {
+                map.insert(new, value);
+            }
+        }
+    }
+    
+    if let Some(key) = to_remove {
+        map.remove(&key);
+    }
+    
+    if ui.button("Add").clicked() {
+        let key = (1..).map(|n| format!("field{}", n)).find(|key| !map.contains_key(key)).unwrap_or_default();
+        map.insert(key, String::new());
+    }
+}
+
+fn format_combo(ui: &mut egui::Ui, format: &mut SinkFormat) {
+    egui::ComboBox::from_id_source("sink_format")
+        .selected_text(match format {
+            SinkFormat::Text => "Text",
+            SinkFormat::Json => "JSON",
+        })
+        .show_ui(ui, |ui| {
+            ui.selectable_value(format, SinkFormat::Text, "Text");
+            ui.selectable_value(format, SinkFormat::Json, "JSON");
+        });
+}
+
+pub fn sinks_ui(ui: &mut egui::Ui, sinks: &mut Vec<SinkConfig>) {
+    let mut to_remove = None;
+    
+    for (i, sink) in sinks.iter_mut().enumerate() {
+        ui.push_id(i, |ui| {
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut sink.enabled, "");
+                ui.add(egui::TextEdit::singleline(&mut sink.name).desired_width(140.0));
+                
+                let selected = match sink.kind {
+                    SinkKind::File { .. } => "File",
+                    SinkKind::Stdout { .. } => "Stdout",
+                    SinkKind::Webhook { .. } => "Webhook",
+                    SinkKind::Mqtt(_) => "MQTT",
+                };
+                egui::ComboBox::from_id_source("sink_kind")
+                    .selected_text(selected)
+                    .show_ui(ui, |ui| {
+                        if ui.selectable_label(matches!(sink.kind, SinkKind::File { .. }), "File").clicked()
+                            && !matches!(sink.kind, SinkKind::File { .. })
+                        {
+                            sink.kind = SinkKind::File {
+                                path: PathBuf::new(),
+                                format: SinkFormat::Text,
+                            };
+                        }
+                        if ui.selectable_label(matches!(sink.kind, SinkKind::Stdout { .. }), "Stdout").clicked()
+                            && !matches!(sink.kind, SinkKind::Stdout { .. })
+                        {
+                            sink.kind = SinkKind::Stdout { format: SinkFormat::Text };
+                        }
+                        if ui.selectable_label(matches!(sink.kind, SinkKind::Webhook { .. }), "Webhook").clicked()
+                            && !matches!(sink.kind, SinkKind::Webhook { .. })
+                        {
+                            sink.kind = SinkKind::Webhook {
+                                url: String::new(),
+                                headers: BTreeMap::new(),
+                            };
+                        }
+                        if ui.selectable_label(matches!(sink.kind, SinkKind::Mqtt(_)), "MQTT").clicked()
+                            && !matches!(sink.kind, SinkKind::Mqtt(_))
+                        {
+                            sink.kind = SinkKind::Mqtt(MqttConfig::default());
+                        }
+                    });
+                
+                if ui.button("Remove").clicked() {
+                    to_remove = Some(i);
+                }
+            });
+            
+            match sink.kind {
+                SinkKind::File { ref mut path, ref mut format } => {
+                    ui.horizontal(|ui| {
+                        ui.label("Path:");
+                        let mut text = path.display().to_string();
+                        if ui.add(egui::TextEdit::singleline(&mut text).desired_width(260.0)).changed() {
+                            *path = PathBuf::from(text);
+                        }
+                        format_combo(ui, format);
+                    });
+                }
+                SinkKind::Stdout { ref mut format } => {
+                    ui.horizontal(|ui| {
+                        ui.label("Format:");
+                        format_combo(ui, format);
+                    });
+                }
+                SinkKind::Webhook { ref mut url, ref mut headers } => {
+                    ui.horizontal(|ui| {
+                        ui.label("URL:");
+                        ui.add(egui::TextEdit::singleline(url).hint_text("https://").desired_width(260.0));
+                    });
+                    ui.collapsing("Headers", |ui| {
+                        map_edit(ui, headers, "Header", "Value");
+                    });
+                }
+                SinkKind::Mqtt(ref mut mqtt) => {
+                    ui.horizontal(|ui| {
+                        ui.label("Broker:");
+                        ui.add(egui::TextEdit::singleline(&mut mqtt.host).hint_text("localhost").desired_width(180.0));
+                        ui.add(egui::DragValue::new(&mut mqtt.port).clamp_range(1..=65535));
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Client ID:");
+                        ui.text_edit_singleline(&mut mqtt.client_id);
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Username:");
+                        optional_text_edit(ui, &mut mqtt.username, "none");
+                        ui.label("Password:");
+                        let mut password = mqtt.password.clone().unwrap_or_default();
+                        if ui.add(egui::TextEdit::singleline(&mut password).password(true).desired_width(120.0)).changed() {
+                            mqtt.password = if password.is_empty() { None } else { Some(password) };
+                        }
+                    });
+                    ui.collapsing("Topics", |ui| {
+                        egui::Grid::new("mqtt_topics").num_columns(2).show(ui, |ui| {
+                            ui.label("Presence:");
+                            ui.text_edit_singleline(&mut mqtt.presence_topic);
+                            ui.end_row();
+                            ui.label("Stats:");
+                            ui.text_edit_singleline(&mut mqtt.stats_topic);
+                            ui.end_row();
+                            ui.label("Availability:");
+                            ui.text_edit_singleline(&mut mqtt.availability_topic);
+                            ui.end_row();
+                        });
+                    });
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut mqtt.retain, "Retain messages");
+                        ui.checkbox(&mut mqtt.discovery, "Home Assistant discovery");
+                        if mqtt.discovery {
+                            ui.label("Prefix:");
+                            ui.add(egui::TextEdit::singleline(&mut mqtt.discovery_prefix).desired_width(120.0));
+                        }
+                    });
+                }
+            }
+            
+            ui.horizontal(|ui| {
+                ui.label("Profiles:");
+                comma_list_edit(ui, &mut sink.profiles, "all profiles");
+            });
+            ui.horizontal(|ui| {
+                ui.label("Extra blacklisted words:");
+                comma_list_edit(ui, &mut sink.blacklisted_words, "none");
+            });
+            
+            ui.collapsing("Output Mapping", |ui| {
+                ui.label(SINK_FIELDS_HELP);
+                ui.label("Text:");
+                let mut text = sink.output.text.clone().unwrap_or_default();
+                let edit = egui::TextEdit::multiline(&mut text).hint_text("{details}\n{state}").desired_rows(2);
+                if ui.add(edit).changed() {
+                    sink.output.text = if text.is_empty() { None } else { Some(text) };
+                }
+                ui.label("JSON fields (whole activity when empty):");
+                map_edit(ui, &mut sink.output.fields, "Key", "Template");
+            });
+            
+            ui.separator();
+        });
+    }
+    
+    if let Some(index) = to_remove {
+        sinks.remove(index);
+    }
+    
+    if ui.button("Add Sink").clicked() {
+        let name = format!("Sink {}", sinks.len() + 1);
+        sinks.push(SinkConfig::new(&name, SinkKind::Stdout { format: SinkFormat::Text }));
+    }
+}
+
+pub fn media_ui(ui: &mut egui::Ui, media: &mut MediaConfig) {
+    ui.checkbox(&mut media.enabled, "Watch MPRIS media players");
+    ui.add_enabled_ui(media.enabled, |ui| {
+        ui.checkbox(&mut media.listening, "Show \"Listening to\" while music plays");
+        ui.horizontal(|ui| {
+            ui.label("Ignored players:");
+            comma_list_edit(ui, &mut media.ignored_players, "e.g. firefox, chromium");
+        });
+    });
+    ui.small("Media players are found over D-Bus, so this only works on Linux.");
+}
+
+pub fn git_ui(ui: &mut egui::Ui, git: &mut GitConfig) {
+    ui.checkbox(&mut git.enabled, "Show the git repository being worked in");
+    ui.add_enabled_ui(git.enabled, |ui| {
+        ui.horizontal(|ui| {
+            ui.label("Processes:");
+            comma_list_edit(ui, &mut git.processes, "editors and shells");
+        });
+        ui.checkbox(&mut git.private_by_default, "Treat unlisted repositories as private");
+        
+        ui.label("Repositories (name or absolute path):");
+        let mut to_remove = None;
+        for (i, entry) in git.repos.iter_mut().enumerate() {
+            ui.push_id(i, |ui| {
+                ui.horizontal(|ui| {
+                    ui.add(egui::TextEdit::singleline(&mut entry.repo).desired_width(260.0));
+                    ui.checkbox(&mut entry.private, "Private");
+                    if ui.button("Remove").clicked() {
+                        to_remove = Some(i);
+                    }
+                });
+            });
+        }
+        
+        if let Some(index) = to_remove {
+            git.repos.remove(index);
+        }
+        
+        if ui.button("Add Repository").clicked() {
+            git.repos.push(RepoPrivacy {
+                repo: String::new(),
+                private: false,
+            });
+        }
+    });
+    ui.small("Private repositories are shown as \"a private project\". Needs git and /proc (Linux).");
+}
+
+pub fn editor_ui(ui: &mut egui::Ui, editor: &mut EditorConfig) {
+    ui.checkbox(&mut editor.enabled, "Show the file being edited");
+    ui.add_enabled_ui(editor.enabled, |ui| {
+        ui.horizontal(|ui| {
+            ui.label("Plugin updates win over window titles for");
+            ui.add(egui::DragValue::new(&mut editor.plugin_timeout_seconds).clamp_range(5..=3600).suffix(" s"));
+        });
+        
+        ui.label("Window title patterns (regex with a file and an optional project group):");
+        let mut to_remove = None;
+        for (i, pattern) in editor.patterns.iter_mut().enumerate() {
+            ui.push_id(i, |ui| {
+                ui.horizontal(|ui| {
+                    ui.add(egui::TextEdit::singleline(&mut pattern.editor).desired_width(90.0));
+                    ui.add(egui::TextEdit::singleline(&mut pattern.title).code_editor().desired_width(360.0));
+                    if ui.button("Remove").clicked() {
+                        to_remove = Some(i);
+                    }
+                });
+            });
+        }
+        if let Some(index) = to_remove {
+            editor.patterns.remove(index);
+        }
+        if ui.button("Add Pattern").clicked() {
+            editor.patterns.push(EditorPattern {
+                editor: "Editor".to_string(),
+                title: r"^(?P<file>.+?) - My Editor$".to_string(),
+            });
+        }
+        
+        ui.collapsing("Languages", |ui| {
+            ui.label("Extension or file name, language name and asset key. Common languages are built in.");
+            let mut to_remove = None;
+            let mut renamed = None;
+            for (extension, language) in editor.languages.iter_mut() {
+                ui.horizontal(|ui| {
+                    let mut new_extension = extension.clone();
+                    if ui.add(egui::TextEdit::singleline(&mut new_extension).desired_width(80.0)).changed() {
+                        renamed = Some((extension.clone(), new_extension.to_lowercase()));
+                    }
+                    ui.add(egui::TextEdit::singleline(&mut language.name).desired_width(120.0));
+                    ui.add(egui::TextEdit::singleline(&mut language.asset).desired_width(120.0));
+                    if ui.button("Remove").clicked() {
+                        to_remove = Some(extension.clone());
+                    }
+                });
+            }
+            if let Some((old, new)) = renamed {
+                if !editor.languages.contains_key(&new) {
+                    if let Some(language) = editor.languages.remove(&old) {
+                        editor.languages.insert(new, language);
+                    }
+                }
+            }
+            if let Some(extension) = to_remove {
+                editor.languages.remove(&extension);
+            }
+            if ui.button("Add Language").clicked() {
+                let key = (1..).map(|n| format!("ext{}", n)).find(|key| !editor.languages.contains_key(key)).unwrap_or_default();
+                editor.languages.insert(
+                    key,
+                    Language {
+                        name: String::new(),
+                        asset: String::new(),
+                    },
+                );
+            }
+        });
+    });
+    ui.small("Editor plugins can report the current file with the set_editor control method or `mrp editor set`.");
+}
+
+/// Battery, temperature, load, disk and network providers.
+pub fn sensors_ui(ui: &mut egui::Ui, providers: &mut ProvidersConfig) {
+    ui.checkbox(&mut providers.battery.enabled, "Battery charge and charging state");
+    ui.checkbox(&mut providers.load.enabled, "Load average and swap");
+    
+    ui.checkbox(&mut providers.thermal.enabled, "Temperatures");
+    ui.add_enabled_ui(providers.thermal.enabled, |ui| {
+        ui.horizontal(|ui| {
+            ui.label("Sensors:");
+            comma_list_edit(ui, &mut providers.thermal.sensors, "all, or e.g. package, tctl");
+        });
+    });
+    
+    ui.checkbox(&mut providers.disk.enabled, "Disk usage");
+    ui.add_enabled_ui(providers.disk.enabled, |ui| {
+        ui.horizontal(|ui| {
+            ui.label("Mount points:");
+            comma_list_edit(ui, &mut providers.disk.mounts, "e.g. /, /home");
+        });
+    });
+    
+    ui.checkbox(&mut providers.network.enabled, "Network throughput");
+    ui.add_enabled_ui(providers.network.enabled, |ui| {
+        ui.horizontal(|ui| {
+            ui.label("Interfaces:");
+            comma_list_edit(ui, &mut providers.network.interfaces, "all physical interfaces");
+        });
+    });
+    ui.small("Battery, load and network are read from /proc and /sys, so they only work on Linux.");
+}
+
+pub fn containers_ui(ui: &mut egui::Ui, containers: &mut ContainersConfig) {
+    ui.checkbox(&mut containers.enabled, "Show running Docker or Podman containers");
+    ui.add_enabled_ui(containers.enabled, |ui| {
+        ui.horizontal(|ui| {
+            ui.label("Socket:");
+            let mut socket = containers.socket.as_ref().map(|path| path.display().to_string()).unwrap_or_default();
+            let response = ui.add(egui::TextEdit::singleline(&mut socket).hint_text("found automatically").desired_width(260.0));
+            if response.changed() {
+                containers.socket = Some(socket.trim()).filter(|socket| !socket.is_empty()).map(std::path::PathBuf::from);
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Hidden:");
+            comma_list_edit(ui, &mut containers.hidden, "names, images or projects, * as wildcard");
+        });
+    });
+    ui.small("Uses the Docker-compatible API socket, e.g. /var/run/docker.sock or Podman's user socket.");
+}
+
+/// Command editors with the latest result or error of each running command.
+pub fn commands_ui(ui: &mut egui::Ui, commands: &mut Vec<CommandSource>, statuses: &[CommandStatus]) {
+    let mut to_remove = None;
+    
+    for (i, source) in commands.iter_mut().enumerate() {
+        ui.push_id(i, |ui| {
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut source.enabled, "");
+                ui.add(egui::TextEdit::singleline(&mut source.name).hint_text("name").desired_width(90.0));
+                ui.add(egui::TextEdit::singleline(&mut source.command).hint_text("program").code_editor().desired_width(220.0));
+                egui::ComboBox::from_id_source("command_output")
+                    .selected_text(match source.output {
+                        CommandOutput::Text => "Text",
+                        CommandOutput::Json => "JSON",
+                    })
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut source.output, CommandOutput::Text, "Text");
+                        ui.selectable_value(&mut source.output, CommandOutput::Json, "JSON");
+                    });
+                if ui.button("Remove").clicked() {
+                    to_remove = Some(i);
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Arguments (one per line):");
+                let mut args = source.args.join("\n");
+                if ui.add(egui::TextEdit::multiline(&mut args).desired_rows(1).desired_width(220.0)).changed() {
+                    source.args = args.lines().map(str::to_string).collect();
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Every");
+                ui.add(egui::DragValue::new(&mut source.interval_seconds).clamp_range(1..=86_400).suffix(" s"));
+                ui.label("timeout");
+                ui.add(egui::DragValue::new(&mut source.timeout_seconds).clamp_range(1..=600).suffix(" s"));
+                ui.checkbox(&mut source.shell, "Run through the shell");
+            });
+            if source.shell {
+                ui.colored_label(egui::Color32::YELLOW, "The command line is interpreted by the shell; quote arguments carefully.");
+            }
+            
+            match statuses.iter().find(|status| status.name == source.name) {
+                Some(status) => {
+                    if let Some(ref error) = status.error {
+                        ui.colored_label(egui::Color32::RED, format!("Last run failed: {}", error));
+                    }
+                    let fields: Vec<String> = status.fields.iter().map(|(name, value)| format!("{{{}}} = {}", name, value)).collect();
+                    if !fields.is_empty() {
+                        ui.small(fields.join("  "));
+                    }
+                }
+                None if source.enabled => {
+                    ui.small("Not run yet");
+                }
+                None => {}
+            }
+            ui.separator();
+        });
+    }
+    
+    if let Some(index) = to_remove {
+        commands.remove(index);
+    }
+    
+    if ui.button("Add Command").clicked() {
+        commands.push(CommandSource::new(&format!("command{}", commands.len() + 1), ""));
+    }
+    ui.small("Text output becomes {name}; JSON objects become {name.key}. Failed runs keep the last good fields.");
+}
+
+pub fn plugins_ui(ui: &mut egui::Ui, plugins: &mut Vec<PluginConfig>, statuses: &[PluginStatus]) {
+    let mut to_remove = None;
+    
+    for (i, plugin) in plugins.iter_mut().enumerate() {
+        ui.push_id(i, |ui| {
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut plugin.enabled, "");
+                ui.add(egui::TextEdit::singleline(&mut plugin.name).hint_text("name").desired_width(90.0));
+                let mut path = plugin.path.display().to_string();
+                if ui.add(egui::TextEdit::singleline(&mut path).hint_text("plugin.wasm").desired_width(220.0)).changed() {
+                    plugin.path = PathBuf::from(path);
+                }
+                if ui.button("Remove").clicked() {
+                    to_remove = Some(i);
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Readable files (one per line):");
+                let mut files = plugin.grants.files.iter().map(|file| file.display().to_string()).collect::<Vec<_>>().join("\n");
+                if ui.add(egui::TextEdit::multiline(&mut files).desired_rows(1).desired_width(220.0)).changed() {
+                    plugin.grants.files = files.lines().map(str::trim).filter(|line| !line.is_empty()).map(PathBuf::from).collect();
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut plugin.grants.process_list, "May read the process list");
+                ui.label("Fuel");
+                ui.add(egui::DragValue::new(&mut plugin.fuel).clamp_range(1..=1_000_000_000).speed(10_000));
+                ui.label("memory");
+                ui.add(egui::DragValue::new(&mut plugin.memory_mb).clamp_range(1..=1024).suffix(" MB"));
+            });
+            
+            if let Some(status) = statuses.iter().find(|status| status.name == plugin.name) {
+                if let Some(ref manifest) = status.manifest {
+                    ui.small(format!("Provides {} every {} s", manifest.fields.join(", "), manifest.interval_seconds));
+                    let missing: Vec<&str> = manifest
+                        .capabilities
+                        .iter()
+                        .filter(|capability| !plugin.grants.allows(**capability))
+                        .map(|capability| match capability {
+                            Capability::ReadFile => "reading files",
+                            Capability::ProcessList => "the process list",
+                        })
+                        .collect();
+                    if !missing.is_empty() {
+                        ui.colored_label(egui::Color32::YELLOW, format!("Asks for {}, which is not granted", missing.join(" and ")));
+                    }
+                }
+                if let Some(ref error) = status.error {
+                    ui.colored_label(egui::Color32::RED, error);
+                }
+                let fields: Vec<String> = status.fields.iter().map(|(name, value)| format!("{{{}}} = {}", name, value)).collect();
+                if !fields.is_empty() {
+                    ui.small(fields.join("  "));
+                }
+            }
+            ui.separator();
+        });
+    }
+    
+    if let Some(index) = to_remove {
+        plugins.remove(index);
+    }
+    
+    if ui.button("Add Plugin").clicked() {
+        plugins.push(PluginConfig::new(&format!("plugin{}", plugins.len() + 1), ""));
+    }
+    ui.small("Plugins run without WASI and only get the access granted here. Their fields become {name.field}.");
+}
+
+pub fn history_ui(ui: &mut egui::Ui, history: &mut HistoryConfig) {
+    ui.checkbox(&mut history.enabled, "Record activity history on this computer");
+    ui.add_enabled_ui(history.enabled, |ui| {
+        ui.horizontal(|ui| {
+            ui.label("Keep for");
+            ui.add(egui::DragValue::new(&mut history.retention_days).clamp_range(0..=3650).suffix(" days"));
+            ui.small("(0 keeps everything)");
+        });
+        ui.horizontal(|ui| {
+            ui.label("Start a new session after a gap of");
+            ui.add(egui::DragValue::new(&mut history.merge_gap_seconds).clamp_range(1..=3600).suffix(" s"));
+        });
+        ui.checkbox(&mut history.record_windows, "Record window titles");
+    });
+    ui.small("Blacklisted words are filtered before anything is stored, and nothing is recorded while presence is hidden.");
+}
+
+/// Bindings are recorded by clicking one and pressing the keys. `states` are the live
+/// bindings and `note` says how they are registered, or why they are not.
+pub fn hotkeys_ui(
+    ui: &mut egui::Ui,
+    hotkeys: &mut HotkeysConfig,
+    recording: &mut Option<HotkeyAction>,
+    states: &BTreeMap<HotkeyAction, BindingState>,
+    note: Option<&str>,
+) {
+    ui.checkbox(&mut hotkeys.enabled, "Enable global hotkeys");
+    if !hotkeys.enabled {
+        *recording = None;
+        return;
+    }
+    if let Some(note) = note {
+        ui.small(note);
+    }
+    
+    let conflicts = hotkeys.conflicts();
+    egui::Grid::new("hotkeys").num_columns(3).show(ui, |ui| {
+        for action in HotkeyAction::ALL {
+            ui.label(action.description());
+            
+            let binding = hotkeys.binding_mut(action);
+            if *recording == Some(action) {
+                let pressed = ui.input(|input| {
+                    input.events.iter().find_map(|event| match event {
+                        egui::Event::Key { key, pressed: true, modifiers, .. } => Some((*key, *modifiers)),
+                        _ => None,
+                    })
+                });
+                match pressed {
+                    Some((egui::Key::Escape, _)) => *recording = None,
+                    Some((egui::Key::Backspace, _)) => {
+                        binding.clear();
+                        *recording = None;
+                    }
+                    Some((key, modifiers)) => {
+                        let mut text = String::new();
+                        for (held, name) in [(modifiers.ctrl, "Ctrl+"), (modifiers.alt, "Alt+"), (modifiers.shift, "Shift+")] {
+                            if held {
+                                text.push_str(name);
+                            }
+                        }
+                        text.push_str(key.name());
+                        // Anything else keeps waiting, e.g. a letter without Ctrl or Alt
+                        if let Ok(hotkey) = text.parse::<Hotkey>() {
+                            *binding = hotkey.to_string();
+                            *recording = None;
+                        }
+                    }
+                    None => {}
+                }
+            }
+            
+            let label = match *recording == Some(action) {
+                true => "Press keys…".to_string(),
+                false if binding.is_empty() => "Not set".to_string(),
+                false => binding.clone(),
+            };
+            if ui.button(label).on_hover_text("Click, then press the new keys. Escape cancels, Backspace clears.").clicked() {
+                *recording = if *recording == Some(action) { None } else { Some(action) };
+            }
+            
+            let conflict = conflicts.iter().find_map(|(first, second)| match action {
+                action if action == *first => Some(*second),
+                action if action == *second => Some(*first),
+                _ => None,
+            });
+            let invalid = match binding.is_empty() {
+                true => None,
+                false => binding.parse::<Hotkey>().err(),
+            };
+            if let Some(e) = invalid {
+                ui.colored_label(egui::Color32::RED, e);
+            } else if let Some(other) = conflict {
+                ui.colored_label(egui::Color32::RED, format!("Same keys as \"{}\"", other.description()));
+            } else {
+                match states.get(&action) {
+                    Some(BindingState::Active(trigger)) => ui.colored_label(egui::Color32::GREEN, format!("Active ({})", trigger)),
+                    Some(BindingState::Conflict(reason)) => ui.colored_label(egui::Color32::RED, reason),
+                    Some(BindingState::Pending) => ui.label("Waiting for the desktop…"),
+                    None => ui.label(""),
+                };
+            }
+            ui.end_row();
+        }
+    });
+}
+
+/// `visible` is whether a tray host currently shows the icon.
+pub fn tray_ui(ui: &mut egui::Ui, tray: &mut TrayConfig, visible: bool) {
+    ui.checkbox(&mut tray.enabled, "Show an icon in the system tray");
+    ui.add_enabled_ui(tray.enabled, |ui| {
+        ui.checkbox(&mut tray.close_to_tray, "Closing the window hides it to the tray");
+    });
+    if tray.enabled && !visible {
+        ui.small("No tray host is running, so closing the window quits.");
+    }
+}
+
+fn report_duration(seconds: i64) -> String {
+    template::format_duration(seconds.max(0) as u64)
+}
+
+/// A bar chart in hours with `labels` along the category axis.
+fn hours_chart(ui: &mut egui::Ui, id: &str, labels: Vec<String>, bars: Vec<Bar>, horizontal: bool) {
+    let height = if horizontal { 24.0 * labels.len().max(3) as f32 } else { 140.0 };
+    let category = move |mark: egui_plot::GridMark, _: usize, _: &std::ops::RangeInclusive<f64>| {
+        if mark.value.fract() != 0.0 || mark.value < 0.0 {
+            return String::new();
+        }
+        labels.get(mark.value as usize).cloned().unwrap_or_default()
+    };
+    let hours = |mark: egui_plot::GridMark, _: usize, _: &std::ops::RangeInclusive<f64>| format!("{}h", mark.value);
+    
+    let mut chart = BarChart::new(bars)
+        .width(0.7)
+        .element_formatter(Box::new(|bar, _| format!("{}: {}", bar.name, report_duration((bar.value * 3600.0) as i64))));
+    let mut plot = Plot::new(id)
+        .height(height)
+        .allow_zoom(false)
+        .allow_drag(false)
+        .allow_scroll(false)
+        .allow_boxed_zoom(false)
+        .show_x(false)
+        .show_y(false)
+        .include_x(0.0)
+        .include_y(0.0);
+    if horizontal {
+        chart = chart.horizontal();
+        plot = plot.x_axis_formatter(hours).y_axis_formatter(category);
+    } else {
+        plot = plot.x_axis_formatter(category).y_axis_formatter(hours);
+    }
+    plot.show(ui, |plot_ui| plot_ui.bar_chart(chart));
+}
+
+/// Time per day and in the top apps as charts, followed by weeks, projects and focus streaks.
+pub fn report_ui(ui: &mut egui::Ui, report: &Report) {
+    ui.label(format!("{} to {}: {} tracked", report.from, report.to, report_duration(report.total_seconds)));
+    if report.total_seconds == 0 {
+        ui.label("Nothing was recorded on these days.");
+        return;
+    }
+    let hours = |seconds: i64| seconds as f64 / 3600.0;
+    
+    ui.heading("Per Day");
+    // Month and day are enough along the axis; hovering a bar shows the full date
+    let labels = report.days.iter().map(|day| day.name.get(5..).unwrap_or(&day.name).to_string()).collect();
+    let bars = report
+        .days
+        .iter()
+        .enumerate()
+        .map(|(i, day)| Bar::new(i as f64, hours(day.seconds)).name(&day.name))
+        .collect();
+    hours_chart(ui, "report_days", labels, bars, false);
+    
+    if report.weeks.len() > 1 {
+        ui.horizontal_wrapped(|ui| {
+            for week in &report.weeks {
+                ui.label(format!("{}: {}", week.name, report_duration(week.seconds)));
+            }
+        });
+    }
+    
+    ui.heading("Top Apps");
+    // The most used app at the top
+    let apps: Vec<_> = report.apps.iter().take(8).rev().collect();
+    let labels = apps.iter().map(|app| app.name.clone()).collect();
+    let bars = apps
+        .iter()
+        .enumerate()
+        .map(|(i, app)| Bar::new(i as f64, hours(app.seconds)).name(&app.name))
+        .collect();
+    hours_chart(ui, "report_apps", labels, bars, true);
+    
+    if !report.projects.is_empty() {
+        ui.heading("Projects");
+        egui::Grid::new("report_projects").striped(true).show(ui, |ui| {
+            for project in &report.projects {
+                ui.label(&project.name);
+                ui.label(report_duration(project.seconds));
+                ui.end_row();
+            }
+        });
+    }
+    
+    ui.heading("Focus Streaks");
+    if report.focus_streaks.is_empty() {
+        ui.label(format!("No stretch of {} minutes or more in a single app.", report::FOCUS_MINIMUM_SECONDS / 60));
+    }
+    egui::Grid::new("report_streaks").striped(true).show(ui, |ui| {
+        for streak in &report.focus_streaks {
+            let (start, end) = (streak.started_at.with_timezone(&chrono::Local), streak.ended_at.with_timezone(&chrono::Local));
+            ui.label(format!("{} {}–{}", start.format("%a %d %b"), start.format("%H:%M"), end.format("%H:%M")));
+            ui.label(&streak.app);
+            ui.label(report_duration(streak.seconds));
+            ui.end_row();
+        }
+    });
+}
+
+/// Minutes from `now` to `at`; negative for the past.
+fn minutes_ago(at: chrono::DateTime<chrono::Local>, now: chrono::DateTime<chrono::Local>) -> f64 {
+    (at - now).num_milliseconds() as f64 / 60_000.0
+}
+
+fn stats_tooltip(sample: &StatsSample) -> String {
+    format!(
+        "{}\nCPU {:.1}%\nMemory {:.1}% ({} MB)\n{} processes",
+        sample.at.format("%H:%M:%S"),
+        sample.cpu_usage,
+        sample.memory_usage,
+        sample.memory_used / 1024 / 1024,
+        sample.process_count
+    )
+}
+
+/// Picks the value a live graph shows from a sample.
+type StatsValue = fn(&StatsSample) -> f64;
+
+/// CPU, memory and process count over the last `SampleHistory::minutes`. Hovering any
+/// graph shows the exact values of the nearest sample.
+pub fn stats_graphs_ui(ui: &mut egui::Ui, samples: &SampleHistory) {
+    if samples.system().is_empty() {
+        ui.label("Waiting for the first samples...");
+        return;
+    }
+    
+    let now = chrono::Local::now();
+    let minutes = samples.minutes() as f64;
+    let system: Vec<StatsSample> = samples.system().iter().copied().collect();
+    let graphs: [(&str, StatsValue, Option<f64>); 3] = [
+        ("CPU %", |sample| sample.cpu_usage as f64, Some(100.0)),
+        ("Memory %", |sample| sample.memory_usage, Some(100.0)),
+        ("Processes", |sample| sample.process_count as f64, None),
+    ];
+    
+    for (name, value, maximum) in graphs {
+        ui.label(name);
+        let points: PlotPoints = system.iter().map(|sample| [minutes_ago(sample.at, now), value(sample)]).collect();
+        let hovered = system.clone();
+        let mut plot = Plot::new(("stats_graph", name))
+            .height(90.0)
+            .allow_zoom(false)
+            .allow_drag(false)
+            .allow_scroll(false)
+            .allow_boxed_zoom(false)
+            .include_x(-minutes)
+            .include_x(0.0)
+            .include_y(0.0)
+            .link_cursor("stats_graphs", true, false)
+            .x_axis_formatter(|mark, _, _| format!("{}m", mark.value))
+            .label_formatter(move |_, point| {
+                let at = now + chrono::Duration::milliseconds((point.x * 60_000.0) as i64);
+                hovered
+                    .iter()
+                    .min_by_key(|sample| (sample.at - at).num_milliseconds().abs())
+                    .map(stats_tooltip)
+                    .unwrap_or_default()
+            });
+        if let Some(maximum) = maximum {
+            plot = plot.include_y(maximum);
+        }
+        plot.show(ui, |plot_ui| plot_ui.line(Line::new(points).name(name)));
+    }
+}
+
+/// A small CPU graph of one process over the graph window; hovering shows the exact values.
+pub fn sparkline(ui: &mut egui::Ui, samples: &RingBuffer<ProcessSample>, minutes: u32) {
+    let (rect, response) = ui.allocate_exact_size(egui::vec2(80.0, 16.0), egui::Sense::hover());
+    let now = chrono::Local::now();
+    let window = minutes.max(1) as f64;
+    // Processes can use more than one core, so scale to the busiest sample
+    let peak = samples.iter().map(|sample| sample.cpu_usage).fold(1.0f32, f32::max);
+    
+    let position = |sample: &ProcessSample| {
+        let x = rect.right() + (minutes_ago(sample.at, now) / window) as f32 * rect.width();
+        let y = rect.bottom() - sample.cpu_usage / peak * rect.height();
+        egui::pos2(x.max(rect.left()), y)
+    };
+    let points: Vec<egui::Pos2> = samples.iter().map(position).collect();
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+    let stroke = egui::Stroke::new(1.0, ui.visuals().selection.bg_fill);
+    match points.as_slice() {
+        [point] => {
+            painter.circle_filled(*point, 1.5, stroke.color);
+        }
+        points => {
+            painter.add(egui::Shape::line(points.to_vec(), stroke));
+        }
+    }
+    
+    response.on_hover_ui_at_pointer(|ui| {
+        let pointer = ui.ctx().pointer_hover_pos().map(|pos| pos.x).unwrap_or(rect.right());
+        let nearest = samples.iter().min_by(|a, b| {
+            (position(a).x - pointer).abs().total_cmp(&(position(b).x - pointer).abs())
+        });
+        if let Some(sample) = nearest {
+            ui.label(format!(
+                "{}: {:.1}% CPU, {} MB",
+                sample.at.format("%H:%M:%S"),
+                sample.cpu_usage,
+                sample.memory_usage / 1024 / 1024
+            ));
+        }
+        ui.label(format!("Peak {:.1}% CPU over {} samples", peak, samples.len()));
+    });
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProcessColumn {
+    Name,
+    Pid,
+    Exe,
+    Cpu,
+    Memory,
+    Started,
+    User,
+}
+
+impl ProcessColumn {
+    const ALL: [ProcessColumn; 7] = [
+        ProcessColumn::Name,
+        ProcessColumn::Pid,
+        ProcessColumn::Exe,
+        ProcessColumn::Cpu,
+        ProcessColumn::Memory,
+        ProcessColumn::Started,
+        ProcessColumn::User,
+    ];
+    
+    fn title(self) -> &'static str {
+        match self {
+            ProcessColumn::Name => "Name",
+            ProcessColumn::Pid => "PID",
+            ProcessColumn::Exe => "Executable",
+            ProcessColumn::Cpu => "CPU",
+            ProcessColumn::Memory => "Memory",
+            ProcessColumn::Started => "Started",
+            ProcessColumn::User => "User",
+        }
+    }
+    
+    fn compare(self, a: &ProcessEntry, b: &ProcessEntry) -> std::cmp::Ordering {
+        match self {
+            ProcessColumn::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+            ProcessColumn::Pid => a.pid.cmp(&b.pid),
+            ProcessColumn::Exe => a.exe.cmp(&b.exe),
+            ProcessColumn::Cpu => a.cpu_usage.total_cmp(&b.cpu_usage),
+            ProcessColumn::Memory => a.memory_usage.cmp(&b.memory_usage),
+            ProcessColumn::Started => a.start_time.cmp(&b.start_time),
+            ProcessColumn::User => a.user.cmp(&b.user),
+        }
+    }
+}
+
+/// Search, sorting and pending edits of the process browser.
+pub struct ProcessBrowser {
+    pub search: String,
+    pub sort: ProcessColumn,
+    pub descending: bool,
+    /// Process being given a display name, and the name typed so far.
+    renaming: Option<(String, String)>,
+    /// What the last action did.
+    notice: Option<String>,
+}
+
+impl Default for ProcessBrowser {
+    fn default() -> Self {
+        Self {
+            search: String::new(),
+            sort: ProcessColumn::Cpu,
+            descending: true,
+            renaming: None,
+            notice: None,
+        }
+    }
+}
+
+enum ProcessAction {
+    Hide(String),
+    Unhide(String),
+    Rename(String),
+    CreateRule(String),
+}
+
+fn process_actions(ui: &mut egui::Ui, process: &ProcessEntry, filters: &ActivityFilters, action: &mut Option<ProcessAction>) {
+    let name = &process.name;
+    let blacklisted = filters.blacklisted_processes.iter().any(|listed| listed.eq_ignore_ascii_case(name));
+    
+    if blacklisted {
+        if ui.button("Show this process again").clicked() {
+            *action = Some(ProcessAction::Unhide(name.clone()));
+            ui.close_menu();
+        }
+    } else if ui.button("Hide this process").clicked() {
+        *action = Some(ProcessAction::Hide(name.clone()));
+        ui.close_menu();
+    }
+    if ui.button("Always show as…").clicked() {
+        *action = Some(ProcessAction::Rename(name.clone()));
+        ui.close_menu();
+    }
+    if ui.button("Create rule from this").clicked() {
+        *action = Some(ProcessAction::CreateRule(name.clone()));
+        ui.close_menu();
+    }
+}
+
+/// A sortable, searchable table of every running process. Hiding and display names edit
+/// `filters`; a rule created from a process is returned for the caller to add.
+pub fn process_browser_ui(
+    ui: &mut egui::Ui,
+    browser: &mut ProcessBrowser,
+    processes: &[ProcessEntry],
+    filters: &mut ActivityFilters,
+    rules_location: &str,
+) -> Option<Rule> {
+    let mut new_rule = None;
+    
+    ui.horizontal(|ui| {
+        ui.label("Search:");
+        ui.add(egui::TextEdit::singleline(&mut browser.search).hint_text("name, PID, executable or user").desired_width(220.0));
+        if ui.button("Clear").clicked() {
+            browser.search.clear();
+        }
+    });
+    
+    if let Some((ref process, ref mut display)) = browser.renaming {
+        let mut done = None;
+        ui.horizontal(|ui| {
+            ui.label(format!("Always show {} as", process));
+            let response = ui.add(egui::TextEdit::singleline(display).desired_width(160.0));
+            if ui.button("Save").clicked() || (response.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter))) {
+                done = Some(true);
+            }
+            if ui.button("Cancel").clicked() {
+                done = Some(false);
+            }
+        });
+        
+        match done {
+            Some(true) => {
+                let display = display.trim().to_string();
+                filters.display_names.retain(|name, _| !name.eq_ignore_ascii_case(process));
+                browser.notice = Some(if display.is_empty() || display == *process {
+                    format!("{} is shown under its own name again", process)
+                } else {
+                    filters.display_names.insert(process.clone(), display.clone());
+                    format!("{} is now shown as {}", process, display)
+                });
+                browser.renaming = None;
+            }
+            Some(false) => browser.renaming = None,
+            None => {}
+        }
+    }
+    
+    if let Some(ref notice) = browser.notice {
+        ui.small(notice);
+    }
+    
+    let search = browser.search.trim().to_lowercase();
+    let mut rows: Vec<&ProcessEntry> = processes
+        .iter()
+        .filter(|process| {
+            search.is_empty()
+                || process.name.to_lowercase().contains(&search)
+                || process.pid.to_string() == search
+                || process.exe.as_deref().is_some_and(|exe| exe.to_lowercase().contains(&search))
+                || process.user.as_deref().is_some_and(|user| user.to_lowercase().contains(&search))
+        })
+        .collect();
+    rows.sort_by(|a, b| {
+        let order = browser.sort.compare(a, b);
+        if browser.descending { order.reverse() } else { order }
+    });
+    ui.label(format!("{} of {} processes; right-click a name for actions", rows.len(), processes.len()));
+    
+    let mut action = None;
+    egui::ScrollArea::both().auto_shrink([false, false]).show(ui, |ui| {
+        egui::Grid::new("process_browser").striped(true).num_columns(8).show(ui, |ui| {
+            for column in ProcessColumn::ALL {
+                let arrow = match (browser.sort == column, browser.descending) {
+                    (false, _) => "",
+                    (true, true) => " ⏷",
+                    (true, false) => " ⏶",
+                };
+                if ui.selectable_label(browser.sort == column, format!("{}{}", column.title(), arrow)).clicked() {
+                    if browser.sort == column {
+                        browser.descending = !browser.descending;
+                    } else {
+                        browser.sort = column;
+                        // Busiest, biggest and newest first; text columns alphabetically
+                        browser.descending = matches!(column, ProcessColumn::Cpu | ProcessColumn::Memory | ProcessColumn::Started);
+                    }
+                }
+            }
+            ui.label("");
+            ui.end_row();
+            
+            for process in rows {
+                let hidden = filters.hides(&process.name);
+                let display = filters.display_name(&process.name);
+                let mut name = egui::RichText::new(if display == process.name {
+                    process.name.clone()
+                } else {
+                    format!("{} → {}", process.name, display)
+                });
+                if hidden {
+                    name = name.weak().strikethrough();
+                }
+                
+                ui.label(name).on_hover_text(if hidden { "Hidden from presence" } else { "Right-click for actions" }).context_menu(|ui| {
+                    process_actions(ui, process, filters, &mut action);
+                });
+                ui.label(process.pid.to_string());
+                ui.label(process.exe.as_deref().unwrap_or("-"));
+                ui.label(format!("{:.1}%", process.cpu_usage));
+                ui.label(template::format_bytes(process.memory_usage));
+                let started = chrono::DateTime::from_timestamp(process.start_time as i64, 0)
+                    .map(|started| started.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+                    .unwrap_or_default();
+                ui.label(started);
+                ui.label(process.user.as_deref().unwrap_or("-"));
+                ui.push_id(process.pid, |ui| {
+                    ui.menu_button("⋯", |ui| process_actions(ui, process, filters, &mut action));
+                });
+                ui.end_row();
+            }
+        });
+    });
+    
+    match action {
+        Some(ProcessAction::Hide(name)) => {
+            browser.notice = Some(format!("{} is now hidden from your presence", name));
+            filters.blacklisted_processes.push(name);
+        }
+        Some(ProcessAction::Unhide(name)) => {
+            filters.blacklisted_processes.retain(|listed| !listed.eq_ignore_ascii_case(&name));
+            browser.notice = Some(match filters.hides(&name) {
+                true => format!("{} is removed from the blacklist but is still hidden as a system process", name),
+                false => format!("{} is shown again", name),
+            });
+        }
+        Some(ProcessAction::Rename(name)) => {
+            let display = filters.display_name(&name).to_string();
+            browser.renaming = Some((name, display));
+        }
+        Some(ProcessAction::CreateRule(name)) => {
+            // Rules see display names
+            let process = filters.display_name(&name).to_string();
+            browser.notice = Some(format!("Added a disabled rule for {} under {}; choose what it does and enable it there", process, rules_location));
+            new_rule = Some(Rule {
+                name: process.clone(),
+                enabled: false,
+                process: Some(process),
+                ..Rule::default()
+            });
+        }
+        None => {}
+    }
+    
+    new_rule
+}
+
+pub fn scripts_ui(ui: &mut egui::Ui, scripting: &mut ScriptingConfig, scripts: &[Script]) {
+    let mut to_remove = None;
+    
+    for (i, path) in scripting.scripts.iter_mut().enumerate() {
+        ui.push_id(i, |ui| {
+            ui.horizontal(|ui| {
+                let mut text = path.display().to_string();
+                if ui.add(egui::TextEdit::singleline(&mut text).hint_text("presence.rhai").desired_width(280.0)).changed() {
+                    *path = PathBuf::from(text);
+                }
+                if ui.button("Remove").clicked() {
+                    to_remove = Some(i);
+                }
+            });
+            // The host only reflects the paths after the next refresh
+            if let Some(error) = scripts.get(i).and_then(|script| script.error()) {
+                ui.colored_label(egui::Color32::RED, error);
+            }
+        });
+    }
+    
+    if let Some(index) = to_remove {
+        scripting.scripts.remove(index);
+    }
+    
+    if ui.button("Add Script").clicked() {
+        scripting.scripts.push(PathBuf::new());
+    }
+    
+    ui.horizontal(|ui| {
+        ui.label("Limits:");
+        ui.add(egui::DragValue::new(&mut scripting.max_operations).clamp_range(1..=10_000_000).suffix(" operations"));
+        ui.add(egui::DragValue::new(&mut scripting.timeout_millis).clamp_range(1..=1000).suffix(" ms"));
+    });
+    ui.small("Scripts run when no rule matches and see `data`, `fields` and `profile`. Return #{ profile: \"Work\" } or #{ details: \"...\", state: \"...\" }; files are reloaded when they change.");
+}
+
+pub fn terminal_ui(ui: &mut egui::Ui, terminal: &mut TerminalConfig) {
+    ui.checkbox(&mut terminal.enabled, "Show the command running in a terminal");
+    ui.add_enabled_ui(terminal.enabled, |ui| {
+        ui.horizontal(|ui| {
+            ui.label("Terminal emulators:");
+            comma_list_edit(ui, &mut terminal.emulators, "e.g. alacritty, kitty");
+        });
+        ui.checkbox(&mut terminal.redact_arguments, "Redact arguments (show only the command and subcommands)");
+        ui.horizontal(|ui| {
+            ui.label("Hidden commands:");
+            comma_list_edit(ui, &mut terminal.hidden_commands, "never shown");
+        });
+    });
+    ui.small("The foreground job is found through the terminal's TTY in /proc, so this only works on Linux.");
+}
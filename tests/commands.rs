@@ -0,0 +1,135 @@
+#![cfg(unix)]
+
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use multi_rich_presence::activity::CommandStatus;
+use multi_rich_presence::config::Config;
+use multi_rich_presence::provider::{parse_output, CommandOutput, CommandProvider, CommandSource};
+
+fn source(name: &str, command: &str, args: &[&str]) -> CommandSource {
+    CommandSource {
+        args: args.iter().map(|arg| arg.to_string()).collect(),
+        ..CommandSource::new(name, command)
+    }
+}
+
+fn fields(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+    pairs.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect()
+}
+
+/// Polls the provider until `done` accepts the status of its only command.
+fn wait_for(provider: &CommandProvider, done: impl Fn(&CommandStatus) -> bool) -> CommandStatus {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    loop {
+        if let Some(status) = provider.statuses().into_iter().next().filter(|status| done(status)) {
+            return status;
+        }
+        assert!(Instant::now() < deadline, "command status did not change in time");
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
+
+#[test]
+fn text_output_uses_the_first_line() {
+    let echo = source("vpn", "printf", &["\\n  connected (wg0)  \\nsecond line\\n"]);
+    assert_eq!(echo.run().unwrap(), fields(&[("vpn", "connected (wg0)")]));
+}
+
+#[test]
+fn json_output_becomes_namespaced_fields() {
+    let output = r#"{"ticket": {"key": "WEB-42", "points": 3}, "labels": ["ui", "bug"], "blocked": false, "owner": null}"#;
+    assert_eq!(
+        parse_output("jira", CommandOutput::Json, output).unwrap(),
+        fields(&[
+            ("jira.blocked", "false"),
+            ("jira.labels.0", "ui"),
+            ("jira.labels.1", "bug"),
+            ("jira.owner", ""),
+            ("jira.ticket.key", "WEB-42"),
+            ("jira.ticket.points", "3"),
+        ])
+    );
+    assert_eq!(parse_output("temp", CommandOutput::Json, "21.5").unwrap(), fields(&[("temp", "21.5")]));
+    assert!(parse_output("jira", CommandOutput::Json, "not json").unwrap_err().contains("invalid JSON"));
+}
+
+#[test]
+fn commands_do_not_run_through_a_shell_unless_enabled() {
+    // Shell syntax in arguments is passed through literally
+    let literal = source("echo", "echo", &["$HOME;", "`id`", "|", "cat"]);
+    assert_eq!(literal.run().unwrap()["echo"], "$HOME; `id` | cat");
+    
+    // A command line is not split into a program and arguments
+    let line = source("echo", "echo hello | tr a-z A-Z", &[]);
+    assert!(line.run().unwrap_err().starts_with("cannot start"));
+    
+    let shell = CommandSource {
+        shell: true,
+        ..source("echo", "echo \"$1-$2\" | tr a-z A-Z", &["one", "two"])
+    };
+    assert_eq!(shell.run().unwrap()["echo"], "ONE-TWO");
+}
+
+#[test]
+fn failures_report_the_exit_status_and_timeouts() {
+    let failing = CommandSource {
+        shell: true,
+        ..source("vpn", "echo 'not connected' >&2; exit 3", &[])
+    };
+    assert_eq!(failing.run().unwrap_err(), "exited with status 3: not connected");
+    
+    let slow = CommandSource {
+        timeout_seconds: 1,
+        ..source("slow", "sleep", &["10"])
+    };
+    let started = Instant::now();
+    assert_eq!(slow.run().unwrap_err(), "timed out after 1 s");
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
+
+#[test]
+fn provider_keeps_the_last_good_fields_when_a_run_fails() {
+    let dir = tempfile::tempdir().unwrap();
+    let cached = dir.path().join("weather.json");
+    std::fs::write(&cached, r#"{"summary": "Sunny", "celsius": 21}"#).unwrap();
+    
+    let weather = CommandSource {
+        interval_seconds: 1,
+        output: CommandOutput::Json,
+        ..source("weather", "cat", &[cached.to_str().unwrap()])
+    };
+    let provider = CommandProvider::new(&[weather]);
+    
+    let status = wait_for(&provider, |status| status.last_run.is_some());
+    assert_eq!(status.error, None);
+    assert_eq!(status.fields, fields(&[("weather.celsius", "21"), ("weather.summary", "Sunny")]));
+    
+    std::fs::remove_file(&cached).unwrap();
+    let status = wait_for(&provider, |status| status.error.is_some());
+    assert!(status.error.unwrap().starts_with("exited with status 1"));
+    assert_eq!(status.fields["weather.summary"], "Sunny");
+    
+    std::fs::write(&cached, r#"{"summary": "Rain", "celsius": 12}"#).unwrap();
+    let status = wait_for(&provider, |status| status.error.is_none());
+    assert_eq!(status.fields["weather.summary"], "Rain");
+}
+
+#[test]
+fn validation_rejects_bad_command_sources() {
+    let mut config = Config::default();
+    config.providers.commands = vec![
+        source("jira ticket", "jira-current", &[]),
+        source("vpn", "", &[]),
+        CommandSource {
+            interval_seconds: 0,
+            ..source("vpn", "vpn-status", &[])
+        },
+    ];
+    
+    let problems = config.validate();
+    assert!(problems.iter().any(|problem| problem.message.contains("commands[0] needs a name")));
+    assert!(problems.iter().any(|problem| problem.message.contains("\"vpn\" has nothing to run")));
+    assert!(problems.iter().any(|problem| problem.message.contains("\"vpn\" is defined more than once")));
+    assert!(problems.iter().any(|problem| problem.message.contains("\"vpn\" needs an interval")));
+}
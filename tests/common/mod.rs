@@ -0,0 +1,27 @@
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard, OnceLock};
+
+use multi_rich_presence::config;
+
+/// Every test shares one set of XDG directories, so they run one at a time.
+pub fn environment() -> MutexGuard<'static, PathBuf> {
+    static ENVIRONMENT: OnceLock<Mutex<PathBuf>> = OnceLock::new();
+    
+    let root = ENVIRONMENT.get_or_init(|| {
+        let root = tempfile::tempdir().unwrap().keep();
+        for (name, dir) in [
+            ("XDG_CONFIG_HOME", "config"),
+            ("XDG_DATA_HOME", "data"),
+            ("XDG_RUNTIME_DIR", "runtime"),
+        ] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+            std::env::set_var(name, root.join(dir));
+        }
+        Mutex::new(root)
+    });
+    
+    let guard = root.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let _ = std::fs::remove_file(config::config_path().unwrap());
+    let _ = std::fs::remove_file(config::data_dir().unwrap().join("privacy.json"));
+    guard
+}
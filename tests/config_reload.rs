@@ -0,0 +1,268 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use multi_rich_presence::config::{self, Config};
+use multi_rich_presence::engine::PresenceEngine;
+use multi_rich_presence::profile::Profile;
+use multi_rich_presence::schedule::{ScheduleAction, ScheduleBlock};
+
+fn wait_for(what: &str, condition: impl Fn() -> bool) {
+    let started = Instant::now();
+    while !condition() {
+        assert!(started.elapsed() < Duration::from_secs(5), "timed out waiting for {}", what);
+        std::thread::sleep(Duration::from_millis(20));
+    }
+}
+
+/// Saves `text` the way many editors do: write a new file, then rename it over the old one.
+fn write_config(text: &str) {
+    let path = config::config_path().unwrap();
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    let temporary = path.with_extension("json.tmp");
+    std::fs::write(&temporary, text).unwrap();
+    std::fs::rename(&temporary, &path).unwrap();
+}
+
+/// The default config with `edit` applied, as JSON.
+fn config_text(edit: impl FnOnce(&mut Config)) -> String {
+    let mut config = Config::default();
+    edit(&mut config);
+    serde_json::to_string_pretty(&config).unwrap()
+}
+
+#[test]
+fn syntax_and_type_errors_carry_their_position() {
+    let errors = Config::parse("{\n  \"show_time\": true,\n  \"graph_minutes\": \"ten\"\n}").unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].position.map(|(line, _)| line), Some(3));
+    assert!(errors[0].message.contains("invalid type"), "{}", errors[0].message);
+    assert!(!errors[0].message.contains(" at line "), "{}", errors[0].message);
+    assert!(errors[0].to_string().starts_with("line 3, column "), "{}", errors[0]);
+    
+    let errors = Config::parse("{\n  \"show_time\": true,\n}").unwrap_err();
+    assert_eq!(errors[0].position, Some((3, 1)));
+}
+
+#[test]
+fn validation_problems_point_at_the_setting() {
+    let mut value = serde_json::to_value(Config::default()).unwrap();
+    value["graph_minutes"] = 0.into();
+    value["profiles"] = serde_json::json!([{ "name": "Work" }, { "name": "" }]);
+    value["hotkeys"]["next_profile"] = "P".into();
+    let text = serde_json::to_string_pretty(&value).unwrap();
+    let line_of = |needle: &str| text.lines().position(|line| line.contains(needle)).unwrap() + 1;
+    
+    let errors = Config::parse(&text).unwrap_err();
+    let position = |setting: &str| errors.iter().find(|e| e.message.starts_with(setting)).unwrap().position;
+    assert_eq!(position("graph_minutes"), Some((line_of("\"graph_minutes\": 0"), 20)));
+    assert_eq!(position("profiles[1]").map(|(line, _)| line), Some(line_of("\"name\": \"\"")));
+    assert_eq!(position("hotkeys.next_profile").map(|(line, _)| line), Some(line_of("\"next_profile\": \"P\"")));
+    
+    // Settings nested in sections are found by their full path
+    let mut value = serde_json::to_value(Config::default()).unwrap();
+    value["providers"]["commands"] = serde_json::json!([
+        { "name": "weather", "command": "curl wttr.in" },
+        { "name": "no spaces", "command": "date" },
+    ]);
+    value["scripting"]["scripts"] = serde_json::json!(["/home/me/presence.rhai", ""]);
+    let text = serde_json::to_string_pretty(&value).unwrap();
+    let line_of = |needle: &str| text.lines().position(|line| line.contains(needle)).unwrap() + 1;
+    let errors = Config::parse(&text).unwrap_err();
+    let position = |setting: &str| errors.iter().find(|e| e.message.starts_with(setting)).unwrap().position;
+    assert_eq!(position("providers.commands[1]").map(|(line, _)| line), Some(line_of("\"name\": \"no spaces\"")));
+    assert_eq!(position("scripting.scripts[1]"), Some((line_of("presence.rhai") + 1, 7)));
+
+}
+
+#[test]
+fn problems_about_named_entries_point_at_the_entry() {
+    let mut value = serde_json::to_value(Config::default()).unwrap();
+    value["profiles"] = serde_json::json!([
+        { "name": "Work" },
+        { "name": "Work" },
+        { "name": "Gaming", "inherits": "Play" },
+    ]);
+    value["rules"] = serde_json::json!([{ "name": "ide", "window": "(", "action": "hide" }]);
+    value["schedule"]["blocks"] = serde_json::json!([{
+        "name": "evenings",
+        "days": ["Mon"],
+        "start": "18:00:00",
+        "end": "22:00:00",
+        "action": { "profile": "Evening" },
+    }]);
+    value["sinks"] = serde_json::json!([{ "name": "obs", "type": "webhook", "url": "localhost:8080" }]);
+    value["providers"]["commands"] = serde_json::json!([{ "name": "vpn", "command": "vpn-status", "timeout_seconds": 0 }]);
+    value["providers"]["editor"]["patterns"] = serde_json::json!([{ "editor": "Kate", "title": "(?P<name>.+) — Kate" }]);
+    let text = serde_json::to_string_pretty(&value).unwrap();
+    let line_of = |needle: &str| text.lines().position(|line| line.contains(needle)).unwrap() + 1;
+    
+    let errors = Config::parse(&text).unwrap_err();
+    let line = |message: &str| {
+        let error = errors.iter().find(|e| e.message.contains(message)).unwrap();
+        error.position.map(|(line, _)| line)
+    };
+    // The second definition is the duplicate
+    assert_eq!(line("\"Work\" is defined more than once"), Some(line_of("\"Work\"") + 3));
+    assert_eq!(line("inherits from unknown profile"), Some(line_of("\"inherits\": \"Play\"")));
+    assert_eq!(line("base rule \"ide\""), Some(line_of("\"window\": \"(\"")));
+    assert_eq!(line("schedule block \"evenings\""), Some(line_of("\"profile\": \"Evening\"")));
+    assert_eq!(line("sink \"obs\""), Some(line_of("\"url\": \"localhost:8080\"")));
+    assert_eq!(line("command \"vpn\" needs an interval"), Some(line_of("\"timeout_seconds\": 0")));
+    assert_eq!(line("editor pattern for \"Kate\""), Some(line_of("(?P<name>.+) — Kate")));
+}
+
+#[test]
+fn rejected_files_keep_the_last_good_config() {
+    let _guard = common::environment();
+    let mut engine = PresenceEngine::new(Config::default());
+    
+    write_config(&config_text(|config| {
+        config.graph_minutes = 30;
+        config.custom_messages = vec!["Shipping".to_string()];
+    }));
+    engine.reload_config().unwrap();
+    assert_eq!(engine.config.graph_minutes, 30);
+    assert_eq!(engine.samples.lock().unwrap().minutes(), 30);
+    
+    write_config(&config_text(|config| {
+        config.graph_minutes = 0;
+        config.custom_messages = Vec::new();
+    }));
+    let error = engine.reload_config().unwrap_err().to_string();
+    assert!(error.starts_with("line ") && error.contains(": graph_minutes must be"), "{}", error);
+    assert_eq!(engine.config.graph_minutes, 30);
+    assert_eq!(engine.config.custom_messages, ["Shipping"]);
+    assert_eq!(engine.status()["config_errors"][0], error);
+    
+    write_config(&config_text(|config| config.graph_minutes = 45));
+    engine.reload_config().unwrap();
+    assert_eq!(engine.config.graph_minutes, 45);
+    assert!(engine.config_errors.is_empty());
+    
+    // The same config again changes nothing
+    let same = engine.config.clone();
+    assert!(!engine.apply_config(same));
+}
+
+#[test]
+fn reloading_the_engines_own_save_changes_nothing() {
+    let _guard = common::environment();
+    let mut engine = PresenceEngine::new(Config::default());
+    engine.config.graph_minutes = 30;
+    engine.save_config().unwrap();
+    
+    let path = config::config_path().unwrap();
+    // Written through a temporary file that is renamed into place
+    assert!(!path.with_extension("json.saving").exists());
+    assert_eq!(Config::parse(&std::fs::read_to_string(&path).unwrap()).unwrap().graph_minutes, 30);
+    
+    // Settings changed since the save are not reverted by the reload the save triggers
+    engine.config.graph_minutes = 40;
+    engine.reload_config().unwrap();
+    assert_eq!(engine.config.graph_minutes, 40);
+    
+    write_config(&config_text(|config| config.graph_minutes = 50));
+    engine.reload_config().unwrap();
+    assert_eq!(engine.config.graph_minutes, 50);
+}
+
+#[test]
+fn rejected_files_are_never_overwritten() {
+    let _guard = common::environment();
+    let mut engine = PresenceEngine::new(Config::default());
+    engine.config.profiles = vec![Profile::new("Work")];
+    engine.save_config().unwrap();
+    
+    let broken = config_text(|config| config.graph_minutes = 0);
+    write_config(&broken);
+    assert!(engine.reload_config().is_err());
+    
+    assert!(engine.save_config().unwrap_err().to_string().contains("fix the file"));
+    engine.switch_profile(Some("Work".to_string())).unwrap();
+    assert_eq!(engine.settings.profile.as_deref(), Some("Work"));
+    engine.shutdown();
+    assert_eq!(std::fs::read_to_string(config::config_path().unwrap()).unwrap(), broken);
+}
+
+#[test]
+fn saving_refuses_a_config_the_next_start_would_reject() {
+    let _guard = common::environment();
+    let mut engine = PresenceEngine::new(Config::default());
+    engine.save_config().unwrap();
+    let saved = std::fs::read_to_string(config::config_path().unwrap()).unwrap();
+    
+    engine.config.schedule.timezone = Some("Mars/Olympus_Mons".to_string());
+    let error = engine.save_config().unwrap_err().to_string();
+    assert!(error.contains("not a known IANA timezone"), "{}", error);
+    assert_eq!(std::fs::read_to_string(config::config_path().unwrap()).unwrap(), saved);
+    
+    // A new schedule block starts without a profile, which keeps the active one
+    engine.config.schedule.timezone = None;
+    engine.config.schedule.blocks.push(ScheduleBlock {
+        action: ScheduleAction::Profile(String::new()),
+        ..ScheduleBlock::default()
+    });
+    assert_eq!(engine.config.validate(), Vec::new());
+    engine.save_config().unwrap();
+    assert_eq!(Config::load().unwrap().schedule.blocks.len(), 1);
+}
+
+#[test]
+fn startup_reports_a_rejected_file_and_leaves_it_alone() {
+    let _guard = common::environment();
+    let broken = config_text(|config| {
+        config.graph_minutes = 0;
+        config.profiles = vec![Profile::new("Work")];
+    });
+    write_config(&broken);
+    
+    let mut engine = PresenceEngine::load(Some("Work".to_string()));
+    assert_eq!(engine.config.graph_minutes, Config::default().graph_minutes);
+    assert_eq!(engine.config_errors.len(), 1);
+    let error = engine.config_errors[0].to_string();
+    assert!(error.starts_with("line ") && error.contains(": graph_minutes must be"), "{}", error);
+    engine.shutdown();
+    assert_eq!(std::fs::read_to_string(config::config_path().unwrap()).unwrap(), broken);
+    
+    // A valid file is used, and the profile from the command line is saved
+    write_config(&config_text(|config| {
+        config.graph_minutes = 30;
+        config.profiles = vec![Profile::new("Work")];
+    }));
+    let engine = PresenceEngine::load(Some("Work".to_string()));
+    assert!(engine.config_errors.is_empty());
+    assert_eq!(engine.config.graph_minutes, 30);
+    assert_eq!(Config::load().unwrap().active_profile.as_deref(), Some("Work"));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn edits_to_the_file_are_applied_while_watching() {
+    use multi_rich_presence::watch::ConfigWatcher;
+    
+    let _guard = common::environment();
+    let engine = Arc::new(Mutex::new(PresenceEngine::new(Config::default())));
+    let watcher = ConfigWatcher::start(&engine).unwrap();
+    assert_eq!(watcher.path(), config::config_path().unwrap());
+    
+    write_config(&config_text(|config| {
+        config.graph_minutes = 20;
+        config.custom_messages = vec!["Reviewing".to_string()];
+    }));
+    wait_for("the edit", || engine.lock().unwrap().config.graph_minutes == 20);
+    assert_eq!(engine.lock().unwrap().config.custom_messages, ["Reviewing"]);
+    
+    // Written in place rather than renamed
+    std::fs::write(config::config_path().unwrap(), "{\"graph_minutes\": 20,").unwrap();
+    wait_for("the rejection", || !engine.lock().unwrap().config_errors.is_empty());
+    assert_eq!(engine.lock().unwrap().config.custom_messages, ["Reviewing"]);
+    
+    // Other files in the directory are ignored
+    std::fs::write(config::config_path().unwrap().with_file_name("notes.txt"), "hello").unwrap();
+    drop(watcher);
+    write_config(&config_text(|config| config.graph_minutes = 25));
+    std::thread::sleep(Duration::from_millis(300));
+    assert_eq!(engine.lock().unwrap().config.graph_minutes, 20);
+}
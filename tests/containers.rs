@@ -0,0 +1,190 @@
+#![cfg(unix)]
+
+use serde_json::json;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::UnixListener;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+
+use multi_rich_presence::activity::ContainerInfo;
+use multi_rich_presence::provider::{ContainersConfig, ContainersProvider};
+
+/// Serves one canned HTTP response per connection on a Unix socket, like the Docker
+/// API would, and reports the request lines it received.
+fn stub_api(dir: &Path, responses: Vec<String>) -> (PathBuf, mpsc::Receiver<String>) {
+    let socket = dir.join("docker.sock");
+    let listener = UnixListener::bind(&socket).unwrap();
+    let (requests, received) = mpsc::channel();
+    
+    thread::spawn(move || {
+        for response in responses {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            loop {
+                let mut header = String::new();
+                if reader.read_line(&mut header).unwrap() == 0 || header.trim().is_empty() {
+                    break;
+                }
+            }
+            requests.send(request_line.trim().to_string()).unwrap();
+            reader.get_mut().write_all(response.as_bytes()).unwrap();
+        }
+    });
+    
+    (socket, received)
+}
+
+fn containers_json() -> String {
+    json!([
+        {
+            "Id": "c1",
+            "Names": ["/shop-postgres-1"],
+            "Image": "postgres:16",
+            "State": "running",
+            "Labels": { "com.docker.compose.project": "shop" }
+        },
+        {
+            "Id": "c2",
+            "Names": ["/shop-redis-1"],
+            "Image": "redis:7-alpine",
+            "State": "running",
+            "Labels": { "com.docker.compose.project": "shop" }
+        },
+        {
+            "Id": "c3",
+            "Names": ["/buildx_buildkit_builder0"],
+            "Image": "moby/buildkit:buildx-stable-1",
+            "State": "running",
+            "Labels": null
+        },
+        {
+            "Id": "c4",
+            "Names": ["/registry"],
+            "Image": "localhost:5000/tools/registry",
+            "State": "running",
+            "Labels": {}
+        }
+    ])
+    .to_string()
+}
+
+fn with_length(body: &str) -> String {
+    format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+        body.len(),
+        body
+    )
+}
+
+fn chunked(body: &str) -> String {
+    let (first, second) = body.split_at(body.len() / 2);
+    format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nTransfer-Encoding: chunked\r\n\r\n{:x}\r\n{}\r\n{:x};ext=1\r\n{}\r\n0\r\n\r\n",
+        first.len(),
+        first,
+        second.len(),
+        second
+    )
+}
+
+fn config(socket: PathBuf, hidden: &[&str]) -> ContainersConfig {
+    ContainersConfig {
+        enabled: true,
+        socket: Some(socket),
+        hidden: hidden.iter().map(|entry| entry.to_string()).collect(),
+    }
+}
+
+#[test]
+fn lists_running_containers_with_compose_projects() {
+    let dir = tempfile::tempdir().unwrap();
+    let (socket, requests) = stub_api(dir.path(), vec![with_length(&containers_json()), chunked(&containers_json())]);
+    let provider = ContainersProvider::new(config(socket, &[]));
+    
+    let containers = provider.current().unwrap();
+    assert_eq!(requests.recv().unwrap(), "GET /containers/json HTTP/1.1");
+    assert_eq!(containers.len(), 4);
+    assert_eq!(
+        containers[0],
+        ContainerInfo {
+            name: "shop-postgres-1".to_string(),
+            image: "postgres:16".to_string(),
+            project: Some("shop".to_string()),
+        }
+    );
+    assert_eq!(containers[2].project, None);
+    
+    // Chunked responses, as sent for longer lists, decode to the same containers
+    assert_eq!(provider.current().unwrap(), containers);
+}
+
+#[test]
+fn hidden_entries_match_names_images_and_projects() {
+    let dir = tempfile::tempdir().unwrap();
+    let responses = vec![with_length(&containers_json()), with_length(&containers_json())];
+    let (socket, _requests) = stub_api(dir.path(), responses);
+    
+    let provider = ContainersProvider::new(config(socket.clone(), &["buildx_*", "redis", "localhost:5000/tools/registry"]));
+    let names: Vec<String> = provider.current().unwrap().into_iter().map(|container| container.name).collect();
+    assert_eq!(names, ["shop-postgres-1"]);
+    
+    let provider = ContainersProvider::new(config(socket, &["shop"]));
+    let names: Vec<String> = provider.current().unwrap().into_iter().map(|container| container.name).collect();
+    assert_eq!(names, ["buildx_buildkit_builder0", "registry"]);
+}
+
+#[test]
+fn api_errors_are_reported() {
+    let dir = tempfile::tempdir().unwrap();
+    let (socket, _requests) = stub_api(
+        dir.path(),
+        vec!["HTTP/1.1 500 Internal Server Error\r\nContent-Length: 0\r\n\r\n".to_string()],
+    );
+    
+    let error = ContainersProvider::new(config(socket, &[])).current().unwrap_err();
+    assert!(error.to_string().contains("500"), "{}", error);
+    
+    let missing = ContainersProvider::new(config(dir.path().join("missing.sock"), &[]));
+    assert!(missing.current().unwrap_err().to_string().contains("cannot connect"));
+}
+
+#[test]
+fn template_fields_summarize_containers() {
+    use chrono::{Local, TimeZone};
+    use multi_rich_presence::activity::{ActivityData, SystemStats};
+    use multi_rich_presence::template;
+    
+    let container = |name: &str, image: &str, project: Option<&str>| ContainerInfo {
+        name: name.to_string(),
+        image: image.to_string(),
+        project: project.map(str::to_string),
+    };
+    let data = ActivityData {
+        system_stats: SystemStats {
+            cpu_usage: 12.0,
+            memory_usage: 40.0,
+            process_count: 200,
+            ..Default::default()
+        },
+        current_time: Local.timestamp_opt(1_700_000_000, 0).unwrap(),
+        containers: Some(vec![
+            container("postgres", "postgres:16", Some("shop")),
+            container("redis", "redis:7", Some("shop")),
+            container("worker", "shop-worker", Some("shop")),
+            container("mailpit", "axllent/mailpit", Some("mail")),
+            container("redis-cache", "redis:7", None),
+        ]),
+        ..Default::default()
+    };
+    
+    let fields = template::activity_fields(&data);
+    assert_eq!(
+        template::render("Running {containers} containers: {container_names}", &fields),
+        "Running 5 containers: postgres, redis, worker…"
+    );
+    assert_eq!(fields["container_images"], "postgres:16, redis:7, shop-worker…");
+    assert_eq!(fields["compose_projects"], "mail, shop");
+}
@@ -0,0 +1,304 @@
+#![cfg(unix)]
+
+mod common;
+
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use multi_rich_presence::config::{self, Config};
+use multi_rich_presence::control::{self, ControlClient, ControlServer, RpcError};
+use multi_rich_presence::engine::PresenceEngine;
+use multi_rich_presence::privacy;
+use multi_rich_presence::profile::Profile;
+
+struct Harness {
+    _guard: MutexGuard<'static, PathBuf>,
+    engine: Arc<Mutex<PresenceEngine>>,
+    server: ControlServer,
+}
+
+impl Harness {
+    fn start() -> Harness {
+        let guard = common::environment();
+        
+        let config = Config {
+            update_interval_seconds: 1,
+            profiles: vec![Profile::new("work")],
+            ..Default::default()
+        };
+        config.save().unwrap();
+        
+        let engine = Arc::new(Mutex::new(PresenceEngine::new(config)));
+        let server = ControlServer::start(&control::socket_path(), Arc::clone(&engine)).unwrap();
+        
+        Harness {
+            _guard: guard,
+            engine,
+            server,
+        }
+    }
+    
+    fn client(&self) -> ControlClient {
+        ControlClient::connect(self.server.path()).unwrap()
+    }
+    
+    fn raw(&self, line: &str) -> Value {
+        let mut stream = UnixStream::connect(self.server.path()).unwrap();
+        stream.write_all(line.as_bytes()).unwrap();
+        stream.write_all(b"\n").unwrap();
+        
+        let mut response = String::new();
+        BufReader::new(stream).read_line(&mut response).unwrap();
+        serde_json::from_str(&response).unwrap()
+    }
+}
+
+fn rpc_error(error: Box<dyn std::error::Error>) -> RpcError {
+    error.downcast_ref::<RpcError>().expect("expected a JSON-RPC error").clone()
+}
+
+#[test]
+fn socket_lives_in_runtime_dir_with_owner_only_permissions() {
+    let harness = Harness::start();
+    let path = harness.server.path();
+    
+    assert!(path.starts_with(std::env::var("XDG_RUNTIME_DIR").unwrap()));
+    
+    let file_mode = std::fs::metadata(path).unwrap().permissions().mode();
+    assert_eq!(file_mode & 0o777, 0o600);
+    
+    let dir_mode = std::fs::metadata(path.parent().unwrap()).unwrap().permissions().mode();
+    assert_eq!(dir_mode & 0o777, 0o700);
+}
+
+#[test]
+fn second_server_on_same_path_is_refused() {
+    let harness = Harness::start();
+    
+    let error = ControlServer::start(harness.server.path(), Arc::clone(&harness.engine))
+        .err()
+        .expect("second server should not start");
+    assert_eq!(error.kind(), std::io::ErrorKind::AddrInUse);
+}
+
+#[test]
+fn stale_socket_file_is_replaced() {
+    let harness = Harness::start();
+    let path = harness.server.path().to_path_buf();
+    drop(harness.server);
+    
+    std::fs::write(&path, b"").unwrap();
+    let server = ControlServer::start(&path, Arc::clone(&harness.engine)).unwrap();
+    
+    let mut client = ControlClient::connect(server.path()).unwrap();
+    assert!(client.call("get_connection", Value::Null).is_ok());
+}
+
+#[test]
+fn set_and_clear_message() {
+    let harness = Harness::start();
+    let mut client = harness.client();
+    
+    let status = client.call("set_message", json!({ "message": "Reviewing PR #123" })).unwrap();
+    assert_eq!(status["message"], "Reviewing PR #123");
+    assert_eq!(harness.engine.lock().unwrap().custom_message, "Reviewing PR #123");
+    
+    let status = client.call("clear_message", Value::Null).unwrap();
+    assert_eq!(status["message"], "");
+}
+
+#[test]
+fn get_activity_returns_filtered_composed_presence() {
+    let harness = Harness::start();
+    let mut client = harness.client();
+    
+    client.call("set_message", json!({ "message": "my secret project" })).unwrap();
+    
+    // The first monitoring sample arrives after one update interval
+    let deadline = Instant::now() + Duration::from_secs(15);
+    let activity = loop {
+        let response = client.call("get_activity", Value::Null).unwrap();
+        if !response["activity"].is_null() {
+            break response["activity"].clone();
+        }
+        assert!(Instant::now() < deadline, "no activity composed in time");
+        std::thread::sleep(Duration::from_millis(200));
+    };
+    
+    assert_eq!(activity["details"], "my [FILTERED] project");
+    assert_eq!(activity["large_image"], "default");
+}
+
+#[test]
+fn use_profile_switches_and_persists() {
+    let harness = Harness::start();
+    let mut client = harness.client();
+    
+    let status = client.call("use_profile", json!({ "profile": "work" })).unwrap();
+    assert_eq!(status["profile"], "work");
+    assert_eq!(Config::load().unwrap().active_profile.as_deref(), Some("work"));
+    
+    let profiles = client.call("list_profiles", Value::Null).unwrap();
+    assert_eq!(profiles["profiles"], json!(["work"]));
+    assert_eq!(profiles["active"], "work");
+    
+    let status = client.call("use_profile", json!({ "profile": null })).unwrap();
+    assert!(status["profile"].is_null());
+}
+
+#[test]
+fn unknown_profile_is_an_application_error() {
+    let harness = Harness::start();
+    let mut client = harness.client();
+    
+    let error = rpc_error(client.call("use_profile", json!({ "profile": "nope" })).unwrap_err());
+    assert_eq!(error.code, control::APPLICATION_ERROR);
+    assert!(error.message.contains("nope"));
+}
+
+#[test]
+fn pause_and_resume() {
+    let harness = Harness::start();
+    let mut client = harness.client();
+    
+    let status = client.call("pause", json!({ "duration_secs": 1800 })).unwrap();
+    assert_eq!(status["paused"], true);
+    assert!(!status["resume_at"].is_null());
+    
+    let activity = client.call("get_activity", Value::Null).unwrap();
+    assert_eq!(activity["hidden"], true);
+    assert!(activity["activity"].is_null());
+    
+    let status = client.call("resume", Value::Null).unwrap();
+    assert_eq!(status["paused"], false);
+    assert!(status["resume_at"].is_null());
+}
+
+#[test]
+fn oversized_pause_durations_are_rejected() {
+    let harness = Harness::start();
+    let mut client = harness.client();
+    
+    for secs in [10_000_000_000_000u64, u64::MAX] {
+        let error = rpc_error(client.call("pause", json!({ "duration_secs": secs })).unwrap_err());
+        assert_eq!(error.code, control::INVALID_PARAMS);
+    }
+    
+    // The engine is still usable
+    let status = client.call("get_status", Value::Null).unwrap();
+    assert_eq!(status["paused"], false);
+}
+
+#[test]
+fn pause_durations_parse_up_to_a_year() {
+    assert_eq!(privacy::parse_duration("1h30m"), Ok(5400));
+    assert_eq!(privacy::parse_duration("365d"), Ok(privacy::MAX_PAUSE_SECS));
+    
+    for too_long in ["366d", "100000000d", "99999999999999999999s", "18446744073709551615s1s"] {
+        let error = privacy::parse_duration(too_long).unwrap_err();
+        assert!(error.contains("longer than 365 days"), "{}: {}", too_long, error);
+    }
+}
+
+#[test]
+fn pause_without_duration_persists_until_resumed() {
+    let harness = Harness::start();
+    let mut client = harness.client();
+    
+    client.call("pause", Value::Null).unwrap();
+    
+    let state = std::fs::read_to_string(config::data_dir().unwrap().join("privacy.json")).unwrap();
+    let state: Value = serde_json::from_str(&state).unwrap();
+    assert_eq!(state["hidden"], true);
+    assert!(state["resume_at"].is_null());
+}
+
+#[test]
+fn get_connection_reports_discord_state() {
+    let harness = Harness::start();
+    let mut client = harness.client();
+    
+    // No Discord client is listening in the test runtime dir
+    let connection = client.call("get_connection", Value::Null).unwrap();
+    assert_eq!(connection["connected"], false);
+    assert!(connection["status"].as_str().unwrap().starts_with("Connection failed"));
+}
+
+#[test]
+fn reload_config_applies_valid_and_rejects_invalid_files() {
+    let harness = Harness::start();
+    let mut client = harness.client();
+    
+    let config = Config {
+        profiles: vec![Profile::new("streaming")],
+        ..Default::default()
+    };
+    config.save().unwrap();
+    
+    client.call("reload_config", Value::Null).unwrap();
+    let profiles = client.call("list_profiles", Value::Null).unwrap();
+    assert_eq!(profiles["profiles"], json!(["streaming"]));
+    
+    std::fs::write(config::config_path().unwrap(), "{ not json").unwrap();
+    let error = rpc_error(client.call("reload_config", Value::Null).unwrap_err());
+    assert_eq!(error.code, control::APPLICATION_ERROR);
+    
+    // The last good config stays in effect
+    let profiles = client.call("list_profiles", Value::Null).unwrap();
+    assert_eq!(profiles["profiles"], json!(["streaming"]));
+}
+
+#[test]
+fn protocol_errors() {
+    let harness = Harness::start();
+    
+    let response = harness.raw("{ not json");
+    assert_eq!(response["error"]["code"], control::PARSE_ERROR);
+    
+    let response = harness.raw(r#"{"jsonrpc":"2.0","id":7}"#);
+    assert_eq!(response["error"]["code"], control::INVALID_REQUEST);
+    
+    let response = harness.raw(r#"{"jsonrpc":"1.0","id":8,"method":"get_status"}"#);
+    assert_eq!(response["error"]["code"], control::INVALID_REQUEST);
+    assert_eq!(response["id"], 8);
+    
+    let response = harness.raw(r#"{"jsonrpc":"2.0","id":9,"method":"launch_rockets"}"#);
+    assert_eq!(response["error"]["code"], control::METHOD_NOT_FOUND);
+    assert_eq!(response["id"], 9);
+    
+    let response = harness.raw(r#"{"jsonrpc":"2.0","id":10,"method":"set_message","params":{}}"#);
+    assert_eq!(response["error"]["code"], control::INVALID_PARAMS);
+}
+
+#[test]
+fn one_connection_serves_many_requests() {
+    let harness = Harness::start();
+    let mut client = harness.client();
+    
+    for i in 0..5 {
+        let status = client.call("set_message", json!({ "message": format!("message {}", i) })).unwrap();
+        assert_eq!(status["message"], format!("message {}", i));
+    }
+}
+
+#[test]
+fn set_activity_keeps_lines_that_are_not_given() {
+    let harness = Harness::start();
+    let mut client = harness.client();
+    
+    client
+        .call("set_activity", json!({ "details": "Reviewing PR #123", "state": "In review" }))
+        .unwrap();
+    let status = client.call("set_activity", json!({ "state": "Approved" })).unwrap();
+    assert_eq!(status["message"], "Reviewing PR #123");
+    assert_eq!(status["state"], "Approved");
+    
+    let status = client.call("clear_activity", Value::Null).unwrap();
+    assert_eq!(status["message"], "");
+    assert_eq!(status["state"], "");
+}
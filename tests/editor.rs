@@ -0,0 +1,207 @@
+mod common;
+
+use chrono::{Local, TimeZone};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use multi_rich_presence::activity::{ActivityData, EditorInfo, SystemStats};
+use multi_rich_presence::config::Config;
+use multi_rich_presence::engine::PresenceEngine;
+use multi_rich_presence::presence;
+use multi_rich_presence::provider::{
+    self, EditorConfig, EditorPattern, EditorProvider, EditorUpdate, Language, Provider, PushedEditor, TitleParser,
+};
+
+fn activity_data(window: Option<&str>) -> ActivityData {
+    ActivityData {
+        system_stats: SystemStats {
+            cpu_usage: 97.0,
+            memory_usage: 40.0,
+            memory_total: 16 * 1024 * 1024 * 1024,
+            memory_used: 4 * 1024 * 1024 * 1024,
+            process_count: 200,
+            ..Default::default()
+        },
+        current_time: Local.timestamp_opt(1_700_000_000, 0).unwrap(),
+        active_window: window.map(str::to_string),
+        ..Default::default()
+    }
+}
+
+fn parsed(title: &str) -> (String, String, Option<String>, Option<String>) {
+    let info = TitleParser::new(&EditorConfig::default()).parse(title).unwrap_or_else(|| panic!("no match for {:?}", title));
+    (info.editor, info.file, info.project, info.language)
+}
+
+fn some(text: &str) -> Option<String> {
+    Some(text.to_string())
+}
+
+#[test]
+fn parses_editor_window_titles() {
+    assert_eq!(
+        parsed("● main.rs - multi-rich-presence - Visual Studio Code"),
+        ("VS Code".to_string(), "main.rs".to_string(), some("multi-rich-presence"), some("Rust"))
+    );
+    assert_eq!(
+        parsed("multi-rich-presence – engine.py"),
+        ("JetBrains".to_string(), "engine.py".to_string(), some("multi-rich-presence"), some("Python"))
+    );
+    assert_eq!(
+        parsed("shop [~/src/shop] – .../src/App.tsx – WebStorm"),
+        ("JetBrains".to_string(), "App.tsx".to_string(), some("shop"), some("React"))
+    );
+    assert_eq!(
+        parsed("init.lua + (~/.config/nvim) - NVIM"),
+        ("Neovim".to_string(), "init.lua".to_string(), some("~/.config/nvim"), some("Lua"))
+    );
+    assert_eq!(
+        parsed("Makefile - GNU Emacs at laptop"),
+        ("Emacs".to_string(), "Makefile".to_string(), None, some("Makefile"))
+    );
+    
+    assert_eq!(TitleParser::new(&EditorConfig::default()).parse("Inbox — Mozilla Firefox"), None);
+    assert_eq!(TitleParser::new(&EditorConfig::default()).parse("Song – Artist"), None);
+}
+
+#[test]
+fn maps_languages_with_overrides() {
+    let overrides = BTreeMap::from([(
+        "rs".to_string(),
+        Language {
+            name: "Rust (nightly)".to_string(),
+            asset: "ferris".to_string(),
+        },
+    )]);
+    
+    assert_eq!(provider::language_for("lib.rs", &BTreeMap::new()).unwrap().asset, "rust");
+    assert_eq!(provider::language_for("lib.rs", &overrides).unwrap().name, "Rust (nightly)");
+    assert_eq!(provider::language_for("Dockerfile", &BTreeMap::new()).unwrap().name, "Docker");
+    assert_eq!(provider::language_for("notes.xyz", &BTreeMap::new()), None);
+}
+
+#[test]
+fn plugin_updates_win_over_window_titles_until_they_expire() {
+    let pushed = Arc::new(Mutex::new(None));
+    let config = EditorConfig {
+        enabled: true,
+        ..Default::default()
+    };
+    let mut provider = EditorProvider::new(config.clone(), Arc::clone(&pushed));
+    
+    let mut data = activity_data(Some("main.rs - project - Visual Studio Code"));
+    provider.sample(&mut data).unwrap();
+    assert_eq!(data.editor.as_ref().unwrap().file, "main.rs");
+    
+    let update = EditorUpdate {
+        editor: some("Neovim"),
+        file: "/home/me/src/project/src/lib.rs".to_string(),
+        project: some("project"),
+        language: None,
+    };
+    *pushed.lock().unwrap() = Some(PushedEditor {
+        info: update.resolve(&config),
+        received: Instant::now(),
+    });
+    provider.sample(&mut data).unwrap();
+    assert_eq!(
+        data.editor,
+        Some(EditorInfo {
+            editor: "Neovim".to_string(),
+            file: "lib.rs".to_string(),
+            project: some("project"),
+            language: some("Rust"),
+            language_asset: some("rust"),
+            from_plugin: true,
+        })
+    );
+    
+    // Stale plugin updates fall back to the window title
+    pushed.lock().unwrap().as_mut().unwrap().received = Instant::now() - Duration::from_secs(600);
+    provider.sample(&mut data).unwrap();
+    assert_eq!(data.editor.as_ref().unwrap().file, "main.rs");
+}
+
+#[test]
+fn built_in_layout_shows_file_and_language_with_the_blacklist_applied() {
+    let config = EditorConfig {
+        enabled: true,
+        ..Default::default()
+    };
+    let mut data = activity_data(None);
+    data.editor = Some(
+        EditorUpdate {
+            file: "secret.rs".to_string(),
+            ..Default::default()
+        }
+        .resolve(&config),
+    );
+    
+    let word_filter = presence::create_word_filter(&["secret".to_string()]);
+    let settings = Config::default().base_settings();
+    let composed = presence::compose(&data, &settings, None, None, None, &word_filter);
+    
+    let state = composed.state.unwrap();
+    assert!(state.ends_with("Editing [FILTERED].rs"), "{}", state);
+    assert_eq!(composed.large_text, "Language: Rust");
+    assert_eq!(composed.large_image, "rust");
+}
+
+#[test]
+fn control_methods_push_editor_updates() {
+    let _guard = common::environment();
+    let config = Config {
+        update_interval_seconds: 1,
+        ..Default::default()
+    };
+    let engine = Arc::new(Mutex::new(PresenceEngine::new(config)));
+    PresenceEngine::spawn_ticker(&engine);
+    
+    let update = EditorUpdate {
+        file: "private.md".to_string(),
+        ..Default::default()
+    };
+    let error = engine.lock().unwrap().set_editor(Some(update.clone())).unwrap_err();
+    assert!(error.contains("disabled"));
+    
+    engine.lock().unwrap().config.providers.editor.enabled = true;
+    engine.lock().unwrap().set_editor(Some(update)).unwrap();
+    
+    let deadline = Instant::now() + Duration::from_secs(15);
+    let state = loop {
+        if let Some(activity) = engine.lock().unwrap().composed_activity() {
+            let state = activity.state.unwrap_or_default();
+            if state.contains("Editing") {
+                break state;
+            }
+        }
+        assert!(Instant::now() < deadline, "editor update not shown in time");
+        std::thread::sleep(Duration::from_millis(200));
+    };
+    assert!(state.ends_with("Editing [FILTERED].md"), "{}", state);
+    
+    engine.lock().unwrap().set_editor(None).unwrap();
+    let state = engine.lock().unwrap().composed_activity().unwrap().state.unwrap_or_default();
+    assert!(!state.contains("Editing"));
+    
+    let empty = EditorUpdate::default();
+    assert!(engine.lock().unwrap().set_editor(Some(empty)).is_err());
+}
+
+#[test]
+fn validation_rejects_patterns_without_a_file_group() {
+    let mut config = Config::default();
+    config.providers.editor.patterns.push(EditorPattern {
+        editor: "Broken".to_string(),
+        title: "^(?P<name>.+) - Broken$".to_string(),
+    });
+    config.providers.editor.patterns.push(EditorPattern {
+        editor: "Invalid".to_string(),
+        title: "(".to_string(),
+    });
+    
+    let problems = config.validate();
+    assert!(problems.iter().any(|problem| problem.message.contains("\"Broken\" has no (?P<file>...) group")));
+    assert!(problems.iter().any(|problem| problem.message.contains("\"Invalid\" is not a valid regex")));
+}
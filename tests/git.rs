@@ -0,0 +1,175 @@
+#![cfg(target_os = "linux")]
+
+use std::fs;
+use std::os::unix::fs::symlink;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use multi_rich_presence::provider::{GitConfig, GitProvider, RepoPrivacy, PRIVATE_PROJECT};
+
+fn git(repo: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .status()
+        .unwrap();
+    assert!(status.success(), "git {:?} failed", args);
+}
+
+/// A repository with one commit on `feature/login`.
+fn repository(parent: &Path, name: &str) -> PathBuf {
+    let repo = parent.join(name);
+    fs::create_dir_all(repo.join("src")).unwrap();
+    git(&repo, &["init", "-q", "-b", "feature/login"]);
+    fs::write(repo.join("src/main.rs"), "fn main() {}\n").unwrap();
+    git(&repo, &["add", "."]);
+    git(&repo, &["commit", "-q", "-m", "Add login form"]);
+    repo
+}
+
+/// Adds `/proc/<pid>` with the bits the provider reads.
+fn fake_process(proc_root: &Path, pid: u32, name: &str, ppid: u32, start_time: u64, cwd: &Path) {
+    let dir = proc_root.join(pid.to_string());
+    fs::create_dir_all(&dir).unwrap();
+    // Fields 5 to 21 are irrelevant here; field 22 is the start time
+    let fields = vec!["0"; 17].join(" ");
+    fs::write(dir.join("stat"), format!("{} ({}) S {} {} {} 0 0", pid, name, ppid, fields, start_time)).unwrap();
+    symlink(cwd, dir.join("cwd")).unwrap();
+}
+
+fn public(name: &str) -> GitConfig {
+    GitConfig {
+        enabled: true,
+        repos: vec![RepoPrivacy {
+            repo: name.to_string(),
+            private: false,
+        }],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn reports_repository_of_the_newest_editor_or_shell_in_the_focused_window() {
+    let dir = tempfile::tempdir().unwrap();
+    let proc_root = dir.path().join("proc");
+    let older = repository(dir.path(), "older-project");
+    let repo = repository(dir.path(), "multi-rich-presence");
+    let other = repository(dir.path(), "other-window");
+    
+    // A terminal window whose shells run in two repositories
+    fake_process(&proc_root, 10, "kitty", 1, 50, dir.path());
+    fake_process(&proc_root, 11, "zsh", 10, 100, &older);
+    fake_process(&proc_root, 12, "zsh", 10, 400, dir.path());
+    fake_process(&proc_root, 13, "nvim", 12, 500, &repo.join("src"));
+    // Not an editor or shell
+    fake_process(&proc_root, 14, "rustc", 13, 900, &other);
+    // Newer, but in a window without focus
+    fake_process(&proc_root, 20, "alacritty", 1, 950, dir.path());
+    fake_process(&proc_root, 21, "bash", 20, 960, &other);
+    
+    let mut config = public("multi-rich-presence");
+    for public in [&older, &other] {
+        config.repos.push(RepoPrivacy {
+            repo: public.display().to_string(),
+            private: false,
+        });
+    }
+    let mut provider = GitProvider::with_proc_root(config.clone(), &proc_root);
+    
+    let git_info = provider.current(Some(10)).unwrap();
+    assert_eq!(git_info.repo, "multi-rich-presence");
+    assert_eq!(git_info.branch.as_deref(), Some("feature/login"));
+    assert_eq!(git_info.commit.as_deref(), Some("Add login form"));
+    assert!(!git_info.dirty);
+    assert!(!git_info.private);
+    assert_eq!(provider.current(Some(20)).unwrap().repo, "other-window");
+    
+    // Once the editor is gone the older shell's repository is used
+    fs::remove_dir_all(proc_root.join("13")).unwrap();
+    assert_eq!(provider.current(Some(10)).unwrap().repo, "older-project");
+    assert_eq!(provider.current(None), None);
+}
+
+#[test]
+fn focused_editor_uses_its_own_directory() {
+    let dir = tempfile::tempdir().unwrap();
+    let proc_root = dir.path().join("proc");
+    let repo = repository(dir.path(), "project");
+    fake_process(&proc_root, 30, "code", 1, 1, &repo.join("src"));
+    
+    assert_eq!(GitProvider::with_proc_root(public("project"), &proc_root).current(Some(30)).unwrap().repo, "project");
+}
+
+#[test]
+fn cached_status_is_refreshed_when_git_state_changes() {
+    let dir = tempfile::tempdir().unwrap();
+    let proc_root = dir.path().join("proc");
+    let repo = repository(dir.path(), "project");
+    fake_process(&proc_root, 40, "code", 1, 1, &repo);
+    let mut provider = GitProvider::with_proc_root(public("project"), &proc_root);
+    assert!(!provider.current(Some(40)).unwrap().dirty);
+    
+    // Staging rewrites the index, which invalidates the cached status
+    fs::write(repo.join("src/main.rs"), "fn main() { todo!() }\n").unwrap();
+    git(&repo, &["add", "."]);
+    assert!(provider.current(Some(40)).unwrap().dirty);
+    
+    git(&repo, &["commit", "-q", "-m", "Stub out main"]);
+    let git_info = provider.current(Some(40)).unwrap();
+    assert!(!git_info.dirty);
+    assert_eq!(git_info.commit.as_deref(), Some("Stub out main"));
+    
+    git(&repo, &["checkout", "-q", "-b", "fix/crash"]);
+    assert_eq!(provider.current(Some(40)).unwrap().branch.as_deref(), Some("fix/crash"));
+}
+
+#[test]
+fn private_repositories_are_anonymized() {
+    let dir = tempfile::tempdir().unwrap();
+    let proc_root = dir.path().join("proc");
+    let repo = repository(dir.path(), "acme-secret-launch");
+    fake_process(&proc_root, 20, "code", 1, 1, &repo);
+    
+    // Unlisted repositories are private by default
+    let config = GitConfig {
+        enabled: true,
+        ..Default::default()
+    };
+    let git_info = GitProvider::with_proc_root(config, &proc_root).current(Some(20)).unwrap();
+    assert_eq!(git_info.repo, PRIVATE_PROJECT);
+    assert_eq!(git_info.branch, None);
+    assert_eq!(git_info.commit, None);
+    assert!(git_info.private);
+    
+    // Explicitly private repositories stay private when the default is public
+    let config = GitConfig {
+        enabled: true,
+        private_by_default: false,
+        repos: vec![RepoPrivacy {
+            repo: "acme-secret-launch".to_string(),
+            private: true,
+        }],
+        ..Default::default()
+    };
+    assert_eq!(GitProvider::with_proc_root(config, &proc_root).current(Some(20)).unwrap().repo, PRIVATE_PROJECT);
+    
+    let config = GitConfig {
+        private_by_default: false,
+        ..public("something-else")
+    };
+    assert_eq!(GitProvider::with_proc_root(config, &proc_root).current(Some(20)).unwrap().repo, "acme-secret-launch");
+}
+
+#[test]
+fn no_repository_outside_the_focused_process_tree() {
+    let dir = tempfile::tempdir().unwrap();
+    let proc_root = dir.path().join("proc");
+    let repo = repository(dir.path(), "project");
+    fake_process(&proc_root, 30, "firefox", 1, 1, dir.path());
+    fake_process(&proc_root, 31, "zsh", 1, 2, &repo);
+    
+    assert_eq!(GitProvider::with_proc_root(public("project"), &proc_root).current(Some(30)), None);
+    assert_eq!(GitProvider::with_proc_root(public("project"), dir.path().join("missing")).current(Some(30)), None);
+}
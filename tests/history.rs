@@ -0,0 +1,216 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use rusqlite::Connection;
+use std::path::Path;
+
+use multi_rich_presence::config::Config;
+use multi_rich_presence::history::{History, HistoryConfig, HistorySample};
+use multi_rich_presence::presence::{self, ComposedActivity};
+
+fn config(dir: &Path) -> HistoryConfig {
+    HistoryConfig {
+        enabled: true,
+        path: Some(dir.join("history.sqlite3")),
+        ..HistoryConfig::default()
+    }
+}
+
+fn at(minutes: i64) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 3, 4, 9, 0, 0).unwrap() + Duration::minutes(minutes)
+}
+
+fn activity(details: &str) -> ComposedActivity {
+    ComposedActivity {
+        details: Some(details.to_string()),
+        state: Some("CPU 12%".to_string()),
+        ..ComposedActivity::default()
+    }
+}
+
+fn sample<'a>(activity: &'a ComposedActivity, app: &'a str, window: &'a str) -> HistorySample<'a> {
+    HistorySample {
+        presence: Some(activity),
+        profile: Some("Work"),
+        source: None,
+        app: Some(app),
+        window: Some(window),
+        project: None,
+    }
+}
+
+#[test]
+fn matching_samples_are_merged_into_sessions() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut history = History::open(&config(dir.path())).unwrap();
+    let filter = presence::create_word_filter(&[]);
+    let coding = activity("Coding");
+    let reading = activity("Reading");
+    
+    for minute in [0, 1, 2] {
+        history.record(at(minute), &sample(&coding, "code", "main.rs"), &filter).unwrap();
+    }
+    history.record(at(3), &sample(&coding, "code", "lib.rs"), &filter).unwrap();
+    history.record(at(4), &sample(&reading, "firefox", "Docs"), &filter).unwrap();
+    // Longer than the two-minute merge gap
+    history.record(at(10), &sample(&reading, "firefox", "Docs"), &filter).unwrap();
+    
+    let presence = history.presence_sessions(at(0), at(60)).unwrap();
+    let spans: Vec<(Option<&str>, i64)> = presence
+        .iter()
+        .map(|session| (session.details.as_deref(), session.duration().num_minutes()))
+        .collect();
+    assert_eq!(spans, [(Some("Coding"), 4), (Some("Reading"), 0), (Some("Reading"), 0)]);
+    assert_eq!(presence[0].profile.as_deref(), Some("Work"));
+    
+    let apps = history.app_sessions(at(0), at(60)).unwrap();
+    let spans: Vec<(&str, Option<&str>, i64)> = apps
+        .iter()
+        .map(|session| (session.app.as_str(), session.window.as_deref(), session.duration().num_minutes()))
+        .collect();
+    assert_eq!(
+        spans,
+        [
+            ("code", Some("main.rs"), 3),
+            ("code", Some("lib.rs"), 1),
+            ("firefox", Some("Docs"), 0),
+            ("firefox", Some("Docs"), 0),
+        ]
+    );
+    
+    // Only sessions overlapping the range are returned
+    let later = history.app_sessions(at(4), at(5)).unwrap();
+    assert_eq!(later.len(), 2);
+}
+
+#[test]
+fn blacklisted_words_never_reach_the_database() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = config(dir.path());
+    let mut history = History::open(&config).unwrap();
+    let filter = presence::create_word_filter(&["secret".to_string(), "payroll".to_string()]);
+    
+    let leaky = ComposedActivity {
+        details: Some("Editing payroll.xlsx".to_string()),
+        state: None,
+        ..ComposedActivity::default()
+    };
+    let sample = HistorySample {
+        source: Some("rule: secret project"),
+        ..sample(&leaky, "secret-tool", "secret plans - Editor")
+    };
+    history.record(at(0), &sample, &filter).unwrap();
+    
+    let apps = history.app_sessions(at(0), at(1)).unwrap();
+    assert_eq!(apps[0].app, "[FILTERED]-tool");
+    assert_eq!(apps[0].window.as_deref(), Some("[FILTERED] plans - Editor"));
+    let presence = history.presence_sessions(at(0), at(1)).unwrap();
+    assert_eq!(presence[0].details.as_deref(), Some("Editing [FILTERED].xlsx"));
+    assert_eq!(presence[0].state, None);
+    assert_eq!(presence[0].source.as_deref(), Some("rule: [FILTERED] project"));
+    
+    // Nothing in the file itself mentions the words
+    drop(history);
+    let bytes = std::fs::read(config.path.as_ref().unwrap()).unwrap();
+    let wal = std::fs::read(dir.path().join("history.sqlite3-wal")).unwrap_or_default();
+    for word in ["secret", "payroll"] {
+        let found = [&bytes, &wal].iter().any(|file| file.windows(word.len()).any(|window| window == word.as_bytes()));
+        assert!(!found, "{} was stored", word);
+    }
+}
+
+#[test]
+fn window_titles_can_be_left_out() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = HistoryConfig {
+        record_windows: false,
+        ..config(dir.path())
+    };
+    let mut history = History::open(&config).unwrap();
+    let coding = activity("Coding");
+    
+    history.record(at(0), &sample(&coding, "code", "main.rs"), &presence::create_word_filter(&[])).unwrap();
+    let apps = history.app_sessions(at(0), at(1)).unwrap();
+    assert_eq!(apps[0].window, None);
+}
+
+#[test]
+fn pauses_and_restarts_start_new_sessions() {
+    let dir = tempfile::tempdir().unwrap();
+    let filter = presence::create_word_filter(&[]);
+    let coding = activity("Coding");
+    
+    let mut history = History::open(&config(dir.path())).unwrap();
+    history.record(at(0), &sample(&coding, "code", "main.rs"), &filter).unwrap();
+    history.pause();
+    history.record(at(1), &sample(&coding, "code", "main.rs"), &filter).unwrap();
+    history.record(at(2), &sample(&coding, "code", "main.rs"), &filter).unwrap();
+    drop(history);
+    
+    let mut history = History::open(&config(dir.path())).unwrap();
+    history.record(at(3), &sample(&coding, "code", "main.rs"), &filter).unwrap();
+    
+    let starts: Vec<DateTime<Utc>> = history.app_sessions(at(0), at(60)).unwrap().iter().map(|session| session.started_at).collect();
+    assert_eq!(starts, [at(0), at(1), at(3)]);
+}
+
+#[test]
+fn old_sessions_are_pruned_after_the_retention_period() {
+    let dir = tempfile::tempdir().unwrap();
+    let filter = presence::create_word_filter(&[]);
+    let coding = activity("Coding");
+    let long_ago = at(0) - Duration::days(40);
+    
+    let config = HistoryConfig {
+        retention_days: 30,
+        ..config(dir.path())
+    };
+    let mut history = History::open(&config).unwrap();
+    history.record(long_ago, &sample(&coding, "code", "old.rs"), &filter).unwrap();
+    history.record(at(0), &sample(&coding, "code", "new.rs"), &filter).unwrap();
+    
+    let windows: Vec<Option<String>> = history.app_sessions(long_ago, at(1)).unwrap().into_iter().map(|session| session.window).collect();
+    assert_eq!(windows, [Some("new.rs".to_string())]);
+    assert_eq!(history.presence_sessions(long_ago, at(1)).unwrap().len(), 1);
+    
+    // Zero keeps everything
+    let config = HistoryConfig {
+        retention_days: 0,
+        ..config
+    };
+    let mut history = History::open(&config).unwrap();
+    history.record(long_ago, &sample(&coding, "code", "old.rs"), &filter).unwrap();
+    assert_eq!(history.prune(at(0)).unwrap(), 0);
+    
+    history.clear().unwrap();
+    let rows: i64 = Connection::open(config.path.as_ref().unwrap())
+        .unwrap()
+        .query_row("SELECT COUNT(*) FROM app_sessions", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(rows, 0);
+}
+
+#[test]
+fn projects_are_recorded_in_older_databases() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = config(dir.path());
+    // The app_sessions table as it was before projects were recorded
+    Connection::open(config.path.as_ref().unwrap())
+        .unwrap()
+        .execute_batch("CREATE TABLE app_sessions (id INTEGER PRIMARY KEY, started_at INTEGER NOT NULL, ended_at INTEGER NOT NULL, app TEXT NOT NULL, window TEXT);")
+        .unwrap();
+    
+    let mut history = History::open(&config).unwrap();
+    let filter = presence::create_word_filter(&[]);
+    let coding = activity("Coding");
+    history.record(at(0), &HistorySample { project: Some("mrp"), ..sample(&coding, "code", "main.rs") }, &filter).unwrap();
+    history.record(at(1), &HistorySample { project: Some("website"), ..sample(&coding, "code", "main.rs") }, &filter).unwrap();
+    
+    let projects: Vec<Option<String>> = history.app_sessions(at(0), at(2)).unwrap().into_iter().map(|session| session.project).collect();
+    assert_eq!(projects, [Some("mrp".to_string()), Some("website".to_string())]);
+}
+
+#[test]
+fn validation_rejects_a_zero_merge_gap() {
+    let mut config = Config::default();
+    config.history.merge_gap_seconds = 0;
+    assert!(config.validate().iter().any(|problem| problem.message.contains("merge_gap_seconds")));
+}
@@ -0,0 +1,270 @@
+mod common;
+
+use multi_rich_presence::config::Config;
+use multi_rich_presence::hotkeys::{self, Hotkey, HotkeyAction, HotkeysConfig};
+use multi_rich_presence::profile::Profile;
+
+#[test]
+fn hotkeys_parse_into_a_canonical_form() {
+    let hotkey: Hotkey = "alt + ctrl+h".parse().unwrap();
+    assert_eq!(hotkey.to_string(), "Ctrl+Alt+H");
+    assert_eq!(hotkey.keysym(), 'h' as u32);
+    assert_eq!(hotkey.x11_modifiers(), 4 | 8);
+    assert_eq!(hotkey.portal_trigger(), "CTRL+ALT+h");
+    
+    let hotkey: Hotkey = "Super+Shift+pageup".parse().unwrap();
+    assert_eq!(hotkey.to_string(), "Shift+Super+PageUp");
+    assert_eq!(hotkey.portal_trigger(), "SHIFT+LOGO+Page_Up");
+    
+    // Function keys need no modifier
+    assert_eq!("f9".parse::<Hotkey>().unwrap().keysym(), 0xffc6);
+    
+    for invalid in ["", "Ctrl+", "Shift+A", "H", "Ctrl+Hyper+A", "Ctrl+Alt+Foo", "Ctrl+F25"] {
+        assert!(invalid.parse::<Hotkey>().is_err(), "{}", invalid);
+    }
+}
+
+#[test]
+fn repeated_bindings_are_conflicts() {
+    let mut config = Config::default();
+    config.hotkeys.next_message = "ctrl+alt+H".to_string();
+    
+    assert_eq!(config.hotkeys.conflicts(), [(HotkeyAction::TogglePrivacy, HotkeyAction::NextMessage)]);
+    // Only the first action gets the keys
+    let actions: Vec<HotkeyAction> = config.hotkeys.hotkeys().into_iter().map(|(action, _)| action).collect();
+    assert_eq!(actions, [HotkeyAction::TogglePrivacy, HotkeyAction::NextProfile]);
+    assert!(config.validate().iter().any(|problem| problem.message.contains("hotkeys.next_message")));
+    
+    // Unassigned bindings are fine, unparseable ones are not
+    config.hotkeys.next_message = String::new();
+    assert!(config.validate().is_empty());
+    config.hotkeys.next_profile = "P".to_string();
+    assert!(config.validate().iter().any(|problem| problem.message.contains("hotkeys.next_profile")));
+}
+
+#[test]
+fn profiles_and_messages_cycle_back_to_the_start() {
+    let mut config = Config {
+        profiles: vec![Profile::new("Work"), Profile::new("Gaming")],
+        ..Config::default()
+    };
+    let mut seen = Vec::new();
+    for _ in 0..3 {
+        config.active_profile = hotkeys::next_profile(&config);
+        seen.push(config.active_profile.clone());
+    }
+    assert_eq!(seen, [Some("Work".to_string()), Some("Gaming".to_string()), None]);
+    
+    let messages = vec!["Shipping".to_string(), String::new(), "Reviewing".to_string()];
+    assert_eq!(hotkeys::next_message(&messages, ""), "Shipping");
+    assert_eq!(hotkeys::next_message(&messages, "Shipping"), "Reviewing");
+    assert_eq!(hotkeys::next_message(&messages, "Reviewing"), "");
+    // A message typed by hand is followed by no message
+    assert_eq!(hotkeys::next_message(&messages, "Lunch"), "");
+}
+
+#[test]
+fn configs_without_hotkeys_load_with_them_off() {
+    let config: HotkeysConfig = serde_json::from_str(r#"{"next_profile": "F8"}"#).unwrap();
+    assert!(!config.enabled);
+    assert_eq!(config.next_profile, "F8");
+    assert_eq!(config.toggle_privacy, "Ctrl+Alt+H");
+}
+
+#[cfg(target_os = "linux")]
+mod portal {
+    use std::collections::HashMap;
+    use std::io::{BufRead, BufReader};
+    use std::process::{Child, Command, Stdio};
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+    use zbus::message::Header;
+    use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value};
+    
+    use multi_rich_presence::config::Config;
+    use multi_rich_presence::engine::PresenceEngine;
+    use multi_rich_presence::hotkeys::{BindingState, HotkeyAction, Hotkeys, HotkeysConfig};
+    use multi_rich_presence::profile::Profile;
+    
+    const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
+    
+    /// A private session bus so tests never talk to the desktop's real portal.
+    struct Bus {
+        daemon: Child,
+        address: String,
+    }
+    
+    impl Bus {
+        /// `None` when `dbus-daemon` is not installed.
+        fn start() -> Option<Bus> {
+            let mut daemon = match Command::new("dbus-daemon")
+                .args(["--session", "--nofork", "--print-address"])
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .spawn()
+            {
+                Ok(daemon) => daemon,
+                Err(e) => {
+                    eprintln!("skipping: could not start dbus-daemon: {}", e);
+                    return None;
+                }
+            };
+            
+            let mut address = String::new();
+            BufReader::new(daemon.stdout.take().unwrap()).read_line(&mut address).unwrap();
+            Some(Bus {
+                daemon,
+                address: address.trim().to_string(),
+            })
+        }
+    }
+    
+    impl Drop for Bus {
+        fn drop(&mut self) {
+            let _ = self.daemon.kill();
+            let _ = self.daemon.wait();
+        }
+    }
+    
+    /// Records the requested triggers and assigns every shortcut except `taken`.
+    struct FakePortal {
+        requested: Arc<Mutex<Vec<(String, String)>>>,
+        session: Arc<Mutex<Option<String>>>,
+        taken: &'static str,
+    }
+    
+    fn string(value: &OwnedValue) -> String {
+        String::try_from(value.try_clone().unwrap()).unwrap()
+    }
+    
+    /// The request object the caller is listening on, from its `handle_token`.
+    fn request_path(header: &Header<'_>, options: &HashMap<String, OwnedValue>) -> String {
+        let sender = header.sender().unwrap().trim_start_matches(':').replace('.', "_");
+        format!("{}/request/{}/{}", PORTAL_PATH, sender, string(&options["handle_token"]))
+    }
+    
+    #[zbus::interface(name = "org.freedesktop.portal.GlobalShortcuts")]
+    impl FakePortal {
+        async fn create_session(
+            &self,
+            options: HashMap<String, OwnedValue>,
+            #[zbus(header)] header: Header<'_>,
+            #[zbus(connection)] connection: &zbus::Connection,
+        ) -> OwnedObjectPath {
+            let request = request_path(&header, &options);
+            let session = format!("{}/session/caller/{}", PORTAL_PATH, string(&options["session_handle_token"]));
+            *self.session.lock().unwrap() = Some(session.clone());
+            
+            let results = HashMap::from([("session_handle", Value::from(session))]);
+            connection
+                .emit_signal(header.sender().map(|sender| sender.as_str()), request.as_str(), "org.freedesktop.portal.Request", "Response", &(0u32, results))
+                .await
+                .unwrap();
+            OwnedObjectPath::try_from(request).unwrap()
+        }
+        
+        async fn bind_shortcuts(
+            &self,
+            _session: OwnedObjectPath,
+            shortcuts: Vec<(String, HashMap<String, OwnedValue>)>,
+            _parent_window: String,
+            options: HashMap<String, OwnedValue>,
+            #[zbus(header)] header: Header<'_>,
+            #[zbus(connection)] connection: &zbus::Connection,
+        ) -> OwnedObjectPath {
+            let mut assigned = Vec::new();
+            for (id, properties) in shortcuts {
+                let trigger = string(&properties["preferred_trigger"]);
+                self.requested.lock().unwrap().push((id.clone(), trigger.clone()));
+                if id != self.taken {
+                    assigned.push((id, HashMap::from([("trigger_description", Value::from(format!("{} (desktop)", trigger)))])));
+                }
+            }
+            
+            let request = request_path(&header, &options);
+            let results = HashMap::from([("shortcuts", Value::from(assigned))]);
+            connection
+                .emit_signal(header.sender().map(|sender| sender.as_str()), request.as_str(), "org.freedesktop.portal.Request", "Response", &(0u32, results))
+                .await
+                .unwrap();
+            OwnedObjectPath::try_from(request).unwrap()
+        }
+    }
+    
+    fn wait_for(what: &str, condition: impl Fn() -> bool) {
+        let started = Instant::now();
+        while !condition() {
+            assert!(started.elapsed() < Duration::from_secs(5), "timed out waiting for {}", what);
+            std::thread::sleep(Duration::from_millis(20));
+        }
+    }
+    
+    #[test]
+    fn shortcuts_are_bound_through_the_portal_and_act_on_the_engine() {
+        let _guard = super::common::environment();
+        let Some(bus) = Bus::start() else { return };
+        let requested = Arc::new(Mutex::new(Vec::new()));
+        let session = Arc::new(Mutex::new(None));
+        let portal = zbus::blocking::connection::Builder::address(bus.address.as_str())
+            .unwrap()
+            .name("org.freedesktop.portal.Desktop")
+            .unwrap()
+            .serve_at(
+                PORTAL_PATH,
+                FakePortal {
+                    requested: Arc::clone(&requested),
+                    session: Arc::clone(&session),
+                    taken: "next_message",
+                },
+            )
+            .unwrap()
+            .build()
+            .unwrap();
+        
+        let engine = Arc::new(Mutex::new(PresenceEngine::new(Config {
+            profiles: vec![Profile::new("Work")],
+            ..Config::default()
+        })));
+        let config = HotkeysConfig {
+            enabled: true,
+            ..HotkeysConfig::default()
+        };
+        let hotkeys = Hotkeys::start_portal_at(&bus.address, &config, &engine).unwrap();
+        assert_eq!(hotkeys.backend(), "portal");
+        
+        wait_for("the bindings", || !hotkeys.states().values().any(|state| *state == BindingState::Pending));
+        let states = hotkeys.states();
+        assert_eq!(states[&HotkeyAction::TogglePrivacy], BindingState::Active("CTRL+ALT+h (desktop)".to_string()));
+        assert!(matches!(states[&HotkeyAction::NextMessage], BindingState::Conflict(_)));
+        assert_eq!(
+            *requested.lock().unwrap(),
+            [
+                ("toggle_privacy".to_string(), "CTRL+ALT+h".to_string()),
+                ("next_profile".to_string(), "CTRL+ALT+p".to_string()),
+                ("next_message".to_string(), "CTRL+ALT+m".to_string()),
+            ]
+        );
+        
+        let session = session.lock().unwrap().clone().unwrap();
+        let activate = |id: &str| {
+            let options: HashMap<&str, Value> = HashMap::new();
+            let body = (ObjectPath::try_from(session.as_str()).unwrap(), id, 0u64, options);
+            portal
+                .emit_signal(None::<&str>, PORTAL_PATH, "org.freedesktop.portal.GlobalShortcuts", "Activated", &body)
+                .unwrap();
+        };
+        
+        activate("next_profile");
+        wait_for("the profile switch", || engine.lock().unwrap().config.active_profile.as_deref() == Some("Work"));
+        activate("toggle_privacy");
+        wait_for("presence to be hidden", || engine.lock().unwrap().privacy.hidden);
+        activate("toggle_privacy");
+        wait_for("presence to resume", || !engine.lock().unwrap().privacy.hidden);
+        
+        // Dropping the hotkeys ends the session
+        drop(hotkeys);
+        activate("next_profile");
+        std::thread::sleep(Duration::from_millis(200));
+        assert_eq!(engine.lock().unwrap().config.active_profile.as_deref(), Some("Work"));
+    }
+}
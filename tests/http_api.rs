@@ -0,0 +1,353 @@
+mod common;
+
+use serde_json::{json, Value};
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::{Message, WebSocket};
+
+use multi_rich_presence::activity::{ActivityData, GitInfo, ProcessInfo, TerminalInfo};
+use multi_rich_presence::api::ApiServer;
+use multi_rich_presence::config::{ActivityFilters, ApiConfig, Config};
+use multi_rich_presence::engine::PresenceEngine;
+use multi_rich_presence::presence;
+use multi_rich_presence::profile::Profile;
+use multi_rich_presence::template::PresenceTemplate;
+
+const TOKEN: &str = "test-token";
+
+struct Harness {
+    _guard: MutexGuard<'static, PathBuf>,
+    engine: Arc<Mutex<PresenceEngine>>,
+    server: ApiServer,
+}
+
+impl Harness {
+    fn start() -> Harness {
+        let guard = common::environment();
+        
+        let config = Config {
+            update_interval_seconds: 1,
+            profiles: vec![Profile::new("work")],
+            api: ApiConfig {
+                enabled: true,
+                port: 0,
+                token: TOKEN.to_string(),
+            },
+            ..Default::default()
+        };
+        config.save().unwrap();
+        
+        let engine = Arc::new(Mutex::new(PresenceEngine::new(config)));
+        let server = ApiServer::start(0, Arc::clone(&engine)).unwrap();
+        
+        Harness {
+            _guard: guard,
+            engine,
+            server,
+        }
+    }
+    
+    fn url(&self, path: &str) -> String {
+        format!("http://{}{}", self.server.addr(), path)
+    }
+    
+    fn get(&self, path: &str) -> Result<Value, u16> {
+        let response = ureq::get(&self.url(path))
+            .set("Authorization", &format!("Bearer {}", TOKEN))
+            .call();
+        into_json(response)
+    }
+    
+    fn post(&self, path: &str, body: Value) -> Result<Value, u16> {
+        let response = ureq::post(&self.url(path))
+            .set("Authorization", &format!("Bearer {}", TOKEN))
+            .send_json(body);
+        into_json(response)
+    }
+    
+    fn events(&self) -> WebSocket<MaybeTlsStream<TcpStream>> {
+        let url = format!("ws://{}/events?token={}", self.server.addr(), TOKEN);
+        let (socket, _) = tungstenite::connect(url).unwrap();
+        if let MaybeTlsStream::Plain(ref stream) = socket.get_ref() {
+            stream.set_read_timeout(Some(Duration::from_secs(15))).unwrap();
+        }
+        socket
+    }
+    
+    /// Polls `/activity` until the first monitoring sample has been composed.
+    fn wait_for_activity(&self) -> Value {
+        let deadline = Instant::now() + Duration::from_secs(15);
+        loop {
+            let response = self.get("/activity").unwrap();
+            if !response["activity"].is_null() {
+                return response;
+            }
+            assert!(Instant::now() < deadline, "no activity composed in time");
+            std::thread::sleep(Duration::from_millis(200));
+        }
+    }
+}
+
+fn into_json(response: Result<ureq::Response, ureq::Error>) -> Result<Value, u16> {
+    match response {
+        Ok(response) => Ok(response.into_json().unwrap()),
+        Err(ureq::Error::Status(code, _)) => Err(code),
+        Err(e) => panic!("request failed: {}", e),
+    }
+}
+
+fn next_event(socket: &mut WebSocket<MaybeTlsStream<TcpStream>>) -> Value {
+    loop {
+        match socket.read().unwrap() {
+            Message::Text(text) => return serde_json::from_str(&text).unwrap(),
+            Message::Ping(_) | Message::Pong(_) => continue,
+            other => panic!("unexpected message: {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn requests_need_the_bearer_token() {
+    let harness = Harness::start();
+    
+    let status = |request: ureq::Request| match request.call() {
+        Ok(response) => response.status(),
+        Err(ureq::Error::Status(code, _)) => code,
+        Err(e) => panic!("request failed: {}", e),
+    };
+    
+    assert_eq!(status(ureq::get(&harness.url("/activity"))), 401);
+    assert_eq!(status(ureq::get(&harness.url("/activity")).set("Authorization", "Bearer wrong")), 401);
+    assert_eq!(status(ureq::get(&harness.url("/activity?token=wrong"))), 401);
+    assert_eq!(status(ureq::get(&harness.url("/events"))), 401);
+    assert_eq!(status(ureq::get(&harness.url(&format!("/activity?token={}", TOKEN)))), 200);
+    
+    // The token is read from the live config, so changing it takes effect immediately
+    harness.engine.lock().unwrap().config.api.token = "rotated".to_string();
+    assert_eq!(harness.get("/activity"), Err(401));
+}
+
+#[test]
+fn server_refuses_to_start_without_a_token() {
+    let harness = Harness::start();
+    harness.engine.lock().unwrap().config.api.token.clear();
+    
+    let error = ApiServer::start(0, Arc::clone(&harness.engine)).err().expect("server should not start");
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn get_activity_returns_presence_and_activity_data() {
+    let harness = Harness::start();
+    
+    harness.post("/message", json!({ "message": "my secret project" })).unwrap();
+    let response = harness.wait_for_activity();
+    
+    assert_eq!(response["activity"]["details"], "my [FILTERED] project");
+    assert_eq!(response["hidden"], false);
+    assert!(response["data"]["system_stats"]["process_count"].as_u64().unwrap() > 0);
+    assert!(response["data"]["top_processes"].is_array());
+}
+
+#[test]
+fn activity_data_goes_through_the_blacklists() {
+    let process = |name: &str| ProcessInfo {
+        name: name.to_string(),
+        pid: 1,
+        cpu_usage: 1.0,
+        memory_usage: 0,
+        start_time: 0,
+    };
+    let data = ActivityData {
+        top_processes: vec![process("secret-tool"), process("ssh")],
+        git: Some(GitInfo {
+            repo: "secret-plans".to_string(),
+            branch: Some("main".to_string()),
+            dirty: false,
+            commit: Some("Draft the secret launch".to_string()),
+            private: false,
+        }),
+        terminal: Some(TerminalInfo {
+            terminal: "alacritty".to_string(),
+            command: "ssh".to_string(),
+            argv: vec!["ssh".to_string(), "host".to_string()],
+        }),
+        ..Default::default()
+    };
+    let filters = ActivityFilters {
+        blacklisted_processes: vec!["SSH".to_string()],
+        ..Default::default()
+    };
+    
+    let filtered = data.filtered(&presence::create_word_filter(&["secret".to_string()]), &filters);
+    let names: Vec<&str> = filtered.top_processes.iter().map(|process| process.name.as_str()).collect();
+    assert_eq!(names, ["[FILTERED]-tool"]);
+    let git = filtered.git.unwrap();
+    assert_eq!(git.repo, "[FILTERED]-plans");
+    assert_eq!(git.commit.as_deref(), Some("Draft the [FILTERED] launch"));
+    assert_eq!(filtered.terminal, None);
+}
+
+#[test]
+fn activity_data_is_withheld_while_hidden() {
+    let harness = Harness::start();
+    harness.wait_for_activity();
+    
+    harness.engine.lock().unwrap().hide_presence(None);
+    
+    let response = harness.get("/activity").unwrap();
+    assert_eq!(response["hidden"], true);
+    assert!(response["activity"].is_null());
+    assert!(response["data"].is_null());
+}
+
+#[test]
+fn post_message_and_profile() {
+    let harness = Harness::start();
+    
+    let status = harness.post("/message", json!({ "message": "Reviewing PR #123" })).unwrap();
+    assert_eq!(status["message"], "Reviewing PR #123");
+    
+    let status = harness.post("/profile", json!({ "profile": "work" })).unwrap();
+    assert_eq!(status["profile"], "work");
+    assert_eq!(Config::load().unwrap().active_profile.as_deref(), Some("work"));
+    
+    let status = harness.post("/profile", json!({ "profile": null })).unwrap();
+    assert!(status["profile"].is_null());
+    
+    assert_eq!(harness.post("/profile", json!({ "profile": "nope" })), Err(400));
+    assert_eq!(harness.post("/message", json!({ "text": "wrong field" })), Err(400));
+}
+
+#[test]
+fn unknown_routes_and_methods() {
+    let harness = Harness::start();
+    
+    assert_eq!(harness.get("/nope"), Err(404));
+    assert_eq!(harness.get("/message"), Err(405));
+    assert_eq!(harness.post("/activity", json!({})), Err(405));
+}
+
+#[test]
+fn websocket_streams_presence_changes() {
+    let harness = Harness::start();
+    harness.wait_for_activity();
+    
+    let mut events = harness.events();
+    
+    // A snapshot of the current state arrives first
+    let connection = next_event(&mut events);
+    assert_eq!(connection["type"], "connection");
+    assert_eq!(connection["connected"], false);
+    let presence = next_event(&mut events);
+    assert_eq!(presence["type"], "presence");
+    
+    harness.post("/message", json!({ "message": "Streaming" })).unwrap();
+    let presence = loop {
+        let event = next_event(&mut events);
+        if event["type"] == "presence" && event["activity"]["details"] == "Streaming" {
+            break event;
+        }
+    };
+    assert!(presence["hidden_reason"].is_null());
+    
+    harness.engine.lock().unwrap().hide_presence(None);
+    let hidden = loop {
+        let event = next_event(&mut events);
+        if event["type"] == "presence" && event["activity"].is_null() {
+            break event;
+        }
+    };
+    assert_eq!(hidden["type"], "presence");
+}
+
+/// Reads one sample from a Prometheus text exposition.
+fn sample(metrics: &str, name: &str) -> f64 {
+    metrics
+        .lines()
+        .find_map(|line| line.strip_prefix(name)?.strip_prefix(' '))
+        .unwrap_or_else(|| panic!("{} missing from:\n{}", name, metrics))
+        .parse()
+        .unwrap()
+}
+
+#[test]
+fn metrics_endpoint_reports_gauges_and_counters() {
+    let harness = Harness::start();
+    harness.wait_for_activity();
+    
+    let scrape = || {
+        ureq::get(&harness.url("/metrics"))
+            .set("Authorization", &format!("Bearer {}", TOKEN))
+            .call()
+            .unwrap()
+            .into_string()
+            .unwrap()
+    };
+    
+    // Discord is not running, so every delivery attempt fails
+    harness.engine.lock().unwrap().set_custom_message("my secret project");
+    let metrics = scrape();
+    assert!(sample(&metrics, "mrp_process_count") > 0.0);
+    assert!(sample(&metrics, "mrp_memory_total_bytes") > 0.0);
+    assert_eq!(sample(&metrics, "mrp_discord_connected"), 0.0);
+    assert!(sample(&metrics, "mrp_presence_updates_total{result=\"failed\"}") >= 1.0);
+    assert!(sample(&metrics, "mrp_reconnect_attempts_total") >= 1.0);
+    assert!(sample(&metrics, "mrp_provider_latency_seconds_count{provider=\"system\"}") >= 1.0);
+    assert!(metrics.contains("# TYPE mrp_presence_updates_total counter"));
+    
+    harness.engine.lock().unwrap().hide_presence(None);
+    harness.engine.lock().unwrap().push_update();
+    let metrics = scrape();
+    assert_eq!(sample(&metrics, "mrp_presence_hidden"), 1.0);
+    assert!(sample(&metrics, "mrp_presence_updates_total{result=\"suppressed\"}") >= 1.0);
+    
+    let unauthorized = ureq::get(&harness.url("/metrics")).call();
+    assert!(matches!(unauthorized, Err(ureq::Error::Status(401, _))));
+}
+
+#[test]
+fn filter_hits_count_replaced_words_that_are_shown() {
+    let word_filter = presence::create_word_filter(&["secret".to_string()]);
+    let settings = Config::default().base_settings();
+    let data = ActivityData {
+        active_window: Some("secret plans".to_string()),
+        ..Default::default()
+    };
+    
+    // Marker text typed by the user is not a hit
+    let composed = presence::compose(&data, &settings, None, Some("[FILTERED] and secret"), None, &word_filter);
+    assert_eq!(composed.details.as_deref(), Some("[FILTERED] and [FILTERED]"));
+    assert_eq!(composed.filter_hits, 1);
+    
+    // Fields the template does not use are not hits either
+    let mut template = PresenceTemplate {
+        details: "CPU {cpu}%".to_string(),
+        ..Default::default()
+    };
+    assert_eq!(presence::compose(&data, &settings, Some(&template), None, None, &word_filter).filter_hits, 0);
+    template.state = "In { window }".to_string();
+    assert_eq!(presence::compose(&data, &settings, Some(&template), None, None, &word_filter).filter_hits, 1);
+}
+
+#[test]
+fn a_stopped_server_frees_its_port_for_a_restart() {
+    let harness = Harness::start();
+    let port = harness.server.addr().port();
+    let engine = Arc::clone(&harness.engine);
+    drop(harness.server);
+    
+    // Settings reloads restart the server, possibly on the same port
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let server = loop {
+        match ApiServer::start(port, Arc::clone(&engine)) {
+            Ok(server) => break server,
+            Err(e) => assert!(Instant::now() < deadline, "port {} was not freed: {}", port, e),
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    };
+    assert_eq!(server.addr().port(), port);
+}
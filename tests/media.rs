@@ -0,0 +1,274 @@
+#![cfg(target_os = "linux")]
+
+use chrono::{Local, TimeZone};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use zbus::zvariant::{OwnedValue, Value};
+
+use multi_rich_presence::activity::{ActivityData, MediaInfo, PlaybackStatus, SystemStats};
+use multi_rich_presence::config::Config;
+use multi_rich_presence::presence::{self, ActivityKind};
+use multi_rich_presence::provider::{MediaConfig, MediaProvider, Provider};
+use multi_rich_presence::template;
+
+/// A private session bus so tests never see the desktop's real players.
+struct Bus {
+    daemon: Child,
+    address: String,
+}
+
+impl Bus {
+    /// `None` when `dbus-daemon` is not installed.
+    fn start() -> Option<Bus> {
+        let mut daemon = match Command::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(daemon) => daemon,
+            Err(e) => {
+                eprintln!("skipping: could not start dbus-daemon: {}", e);
+                return None;
+            }
+        };
+        
+        let mut address = String::new();
+        BufReader::new(daemon.stdout.take().unwrap()).read_line(&mut address).unwrap();
+        Some(Bus {
+            daemon,
+            address: address.trim().to_string(),
+        })
+    }
+}
+
+impl Drop for Bus {
+    fn drop(&mut self) {
+        let _ = self.daemon.kill();
+        let _ = self.daemon.wait();
+    }
+}
+
+#[derive(Clone)]
+struct Track {
+    status: &'static str,
+    title: &'static str,
+    artists: Vec<&'static str>,
+    album: &'static str,
+    length_micros: i64,
+    position_micros: i64,
+}
+
+struct FakePlayer {
+    track: Arc<Mutex<Track>>,
+}
+
+#[zbus::interface(name = "org.mpris.MediaPlayer2.Player")]
+impl FakePlayer {
+    #[zbus(property)]
+    fn playback_status(&self) -> String {
+        self.track.lock().unwrap().status.to_string()
+    }
+    
+    #[zbus(property)]
+    fn metadata(&self) -> HashMap<String, OwnedValue> {
+        let track = self.track.lock().unwrap().clone();
+        let value = |value: Value| OwnedValue::try_from(value).unwrap();
+        HashMap::from([
+            ("xesam:title".to_string(), value(Value::from(track.title))),
+            ("xesam:artist".to_string(), value(Value::from(track.artists))),
+            ("xesam:album".to_string(), value(Value::from(track.album))),
+            ("mpris:length".to_string(), value(Value::from(track.length_micros))),
+        ])
+    }
+    
+    #[zbus(property)]
+    fn position(&self) -> i64 {
+        self.track.lock().unwrap().position_micros
+    }
+}
+
+struct FakeRoot {
+    identity: &'static str,
+}
+
+#[zbus::interface(name = "org.mpris.MediaPlayer2")]
+impl FakeRoot {
+    #[zbus(property)]
+    fn identity(&self) -> String {
+        self.identity.to_string()
+    }
+}
+
+/// Registers a player as `org.mpris.MediaPlayer2.<id>`; it goes away when dropped.
+fn spawn_player(bus: &Bus, id: &str, identity: &'static str, track: Track) -> (zbus::blocking::Connection, Arc<Mutex<Track>>) {
+    let track = Arc::new(Mutex::new(track));
+    let connection = zbus::blocking::connection::Builder::address(bus.address.as_str())
+        .unwrap()
+        .name(format!("org.mpris.MediaPlayer2.{}", id))
+        .unwrap()
+        .serve_at("/org/mpris/MediaPlayer2", FakeRoot { identity })
+        .unwrap()
+        .serve_at("/org/mpris/MediaPlayer2", FakePlayer { track: Arc::clone(&track) })
+        .unwrap()
+        .build()
+        .unwrap();
+    (connection, track)
+}
+
+fn song() -> Track {
+    Track {
+        status: "Playing",
+        title: "Teardrop",
+        artists: vec!["Massive Attack"],
+        album: "Mezzanine",
+        length_micros: 330_000_000,
+        position_micros: 30_000_000,
+    }
+}
+
+fn media_config() -> MediaConfig {
+    MediaConfig {
+        enabled: true,
+        ..Default::default()
+    }
+}
+
+fn activity_data(media: Option<MediaInfo>) -> ActivityData {
+    ActivityData {
+        system_stats: SystemStats {
+            cpu_usage: 5.0,
+            memory_usage: 40.0,
+            memory_total: 16 * 1024 * 1024 * 1024,
+            memory_used: 4 * 1024 * 1024 * 1024,
+            process_count: 200,
+            ..Default::default()
+        },
+        current_time: Local.timestamp_opt(1_700_000_000, 0).unwrap(),
+        media,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn reads_the_playing_track() {
+    let Some(bus) = Bus::start() else { return };
+    let mut provider = MediaProvider::with_address(media_config(), &bus.address);
+    assert_eq!(provider.current().unwrap(), None);
+    
+    let _player = spawn_player(&bus, "spotify", "Spotify", song());
+    
+    let mut data = activity_data(None);
+    provider.sample(&mut data).unwrap();
+    assert_eq!(
+        data.media,
+        Some(MediaInfo {
+            player: "Spotify".to_string(),
+            title: "Teardrop".to_string(),
+            artist: Some("Massive Attack".to_string()),
+            album: Some("Mezzanine".to_string()),
+            position_seconds: Some(30.0),
+            length_seconds: Some(330.0),
+            status: PlaybackStatus::Playing,
+        })
+    );
+}
+
+#[test]
+fn prefers_playing_players_and_skips_ignored_and_stopped_ones() {
+    let Some(bus) = Bus::start() else { return };
+    
+    let (_paused, _) = spawn_player(&bus, "mpv", "mpv", Track { status: "Paused", title: "Podcast", ..song() });
+    let (_browser, _) = spawn_player(&bus, "firefox.instance42", "Firefox", Track { title: "Some video", ..song() });
+    let (_stopped, _) = spawn_player(&bus, "rhythmbox", "Rhythmbox", Track { status: "Stopped", ..song() });
+    let (_playing, track) = spawn_player(
+        &bus,
+        "vlc",
+        "VLC",
+        Track {
+            title: "Angel",
+            artists: vec!["Massive Attack", "Horace Andy"],
+            ..song()
+        },
+    );
+    
+    let config = MediaConfig {
+        ignored_players: vec!["Firefox".to_string()],
+        ..media_config()
+    };
+    let mut provider = MediaProvider::with_address(config, &bus.address);
+    
+    let media = provider.current().unwrap().unwrap();
+    assert_eq!(media.player, "VLC");
+    assert_eq!(media.title, "Angel");
+    assert_eq!(media.artist.as_deref(), Some("Massive Attack, Horace Andy"));
+    
+    // With nothing playing, a paused player is reported
+    track.lock().unwrap().status = "Stopped";
+    let media = provider.current().unwrap().unwrap();
+    assert_eq!(media.title, "Podcast");
+    assert_eq!(media.status, PlaybackStatus::Paused);
+}
+
+#[test]
+fn listening_presence_shows_the_track_with_remaining_time() {
+    let settings = Config::default().base_settings();
+    let word_filter = presence::create_word_filter(&["private".to_string()]);
+    let media = MediaInfo {
+        player: "Spotify".to_string(),
+        title: "My private playlist".to_string(),
+        artist: Some("Massive Attack".to_string()),
+        album: Some("Mezzanine".to_string()),
+        position_seconds: Some(30.0),
+        length_seconds: Some(330.0),
+        status: PlaybackStatus::Playing,
+    };
+    let data = activity_data(Some(media.clone()));
+    
+    let composed = presence::compose(&data, &settings, None, None, None, &word_filter);
+    assert_eq!(composed.kind, ActivityKind::Listening);
+    assert_eq!(composed.details.as_deref(), Some("My [FILTERED] playlist"));
+    assert_eq!(composed.state.as_deref(), Some("by Massive Attack"));
+    assert_eq!(composed.large_text, "Mezzanine");
+    assert_eq!(composed.start_timestamp, Some(1_700_000_000 - 30));
+    assert_eq!(composed.end_timestamp, Some(1_700_000_000 + 300));
+    
+    // A custom message means the user is doing something else
+    let composed = presence::compose(&data, &settings, None, Some("Reviewing PRs"), None, &word_filter);
+    assert_eq!(composed.kind, ActivityKind::Playing);
+    assert_eq!(composed.end_timestamp, None);
+    
+    // Paused music and disabled listening keep the normal presence
+    let paused = activity_data(Some(MediaInfo {
+        status: PlaybackStatus::Paused,
+        ..media
+    }));
+    assert_eq!(presence::compose(&paused, &settings, None, None, None, &word_filter).kind, ActivityKind::Playing);
+    
+    let mut config = Config::default();
+    config.providers.media.listening = false;
+    let composed = presence::compose(&data, &config.base_settings(), None, None, None, &word_filter);
+    assert_eq!(composed.kind, ActivityKind::Playing);
+}
+
+#[test]
+fn media_fields_are_available_to_templates() {
+    let data = activity_data(Some(MediaInfo {
+        player: "mpv".to_string(),
+        title: "Teardrop".to_string(),
+        artist: None,
+        album: None,
+        position_seconds: Some(65.4),
+        length_seconds: Some(330.0),
+        status: PlaybackStatus::Paused,
+    }));
+    
+    let fields = template::activity_fields(&data);
+    assert_eq!(
+        template::render("{media_title} [{media_position}/{media_length}] {media_status} in {media_player}", &fields),
+        "Teardrop [1:05/5:30] Paused in mpv"
+    );
+    assert_eq!(fields["media_artist"], "");
+}
@@ -0,0 +1,229 @@
+use bytes::BytesMut;
+use rumqttc::mqttbytes::v4::{self, ConnAck, ConnectReturnCode, Packet, PingResp, PubAck};
+use rumqttc::mqttbytes::Error as PacketError;
+use serde_json::{json, Value};
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver};
+use std::time::Duration;
+
+use multi_rich_presence::activity::SystemStats;
+use multi_rich_presence::presence::ComposedActivity;
+use multi_rich_presence::sink::{MqttConfig, MqttSink, PresenceSink, SinkOutput};
+
+/// A single-connection broker that acknowledges everything and reports what it received.
+struct Broker {
+    port: u16,
+    packets: Receiver<Packet>,
+}
+
+impl Broker {
+    fn start() -> Broker {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (sender, packets) = mpsc::channel();
+        
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buffer = BytesMut::new();
+            loop {
+                let packet = match v4::read(&mut buffer, 1024 * 1024) {
+                    Ok(packet) => packet,
+                    Err(PacketError::InsufficientBytes(_)) => {
+                        let mut chunk = [0u8; 4096];
+                        match stream.read(&mut chunk) {
+                            Ok(0) | Err(_) => return,
+                            Ok(n) => buffer.extend_from_slice(&chunk[..n]),
+                        }
+                        continue;
+                    }
+                    Err(e) => panic!("malformed packet: {:?}", e),
+                };
+                
+                reply(&mut stream, &packet);
+                if sender.send(packet).is_err() {
+                    return;
+                }
+            }
+        });
+        
+        Broker { port, packets }
+    }
+    
+    fn next(&self) -> Packet {
+        self.packets
+            .recv_timeout(Duration::from_secs(10))
+            .expect("no packet received in time")
+    }
+    
+    /// Skips pings and returns the next publish as (topic, payload, retain).
+    fn next_publish(&self) -> (String, String, bool) {
+        loop {
+            match self.next() {
+                Packet::Publish(publish) => {
+                    return (
+                        publish.topic,
+                        String::from_utf8(publish.payload.to_vec()).unwrap(),
+                        publish.retain,
+                    )
+                }
+                Packet::PingReq => continue,
+                other => panic!("expected a publish, got {:?}", other),
+            }
+        }
+    }
+}
+
+fn reply(stream: &mut TcpStream, packet: &Packet) {
+    let mut out = BytesMut::new();
+    match packet {
+        Packet::Connect(_) => ConnAck::new(ConnectReturnCode::Success, false).write(&mut out),
+        Packet::Publish(publish) if publish.pkid != 0 => PubAck::new(publish.pkid).write(&mut out),
+        Packet::PingReq => PingResp.write(&mut out),
+        _ => return,
+    }
+    .unwrap();
+    stream.write_all(&out).unwrap();
+}
+
+fn config(port: u16) -> MqttConfig {
+    MqttConfig {
+        host: "127.0.0.1".to_string(),
+        port,
+        client_id: "desk pc".to_string(),
+        ..Default::default()
+    }
+}
+
+fn activity(details: &str, state: &str) -> ComposedActivity {
+    ComposedActivity {
+        details: Some(details.to_string()),
+        state: Some(state.to_string()),
+        large_image: "default".to_string(),
+        large_text: "MultiRichPresence".to_string(),
+        start_timestamp: None,
+        ..Default::default()
+    }
+}
+
+fn parse(payload: &str) -> Value {
+    serde_json::from_str(payload).unwrap()
+}
+
+#[test]
+fn connects_with_last_will_and_announces_availability() {
+    let broker = Broker::start();
+    let mut sink = MqttSink::new(&config(broker.port), SinkOutput::default()).unwrap();
+    
+    let connect = match broker.next() {
+        Packet::Connect(connect) => connect,
+        other => panic!("expected connect, got {:?}", other),
+    };
+    assert_eq!(connect.client_id, "desk pc");
+    let will = connect.last_will.expect("no last will set");
+    assert_eq!(will.topic, "multi-rich-presence/availability");
+    assert_eq!(&will.message[..], b"offline");
+    assert!(will.retain);
+    
+    assert_eq!(
+        broker.next_publish(),
+        ("multi-rich-presence/availability".to_string(), "online".to_string(), true)
+    );
+    
+    // A clean shutdown marks the sink offline itself, since the broker skips the will
+    sink.close();
+    assert_eq!(
+        broker.next_publish(),
+        ("multi-rich-presence/availability".to_string(), "offline".to_string(), true)
+    );
+    loop {
+        match broker.next() {
+            Packet::Disconnect => break,
+            Packet::PingReq => continue,
+            other => panic!("expected disconnect, got {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn publishes_retained_presence_and_stats() {
+    let broker = Broker::start();
+    let mut sink = MqttSink::new(&config(broker.port), SinkOutput::default()).unwrap();
+    broker.next();
+    broker.next_publish();
+    
+    sink.update(Some(&activity("Coding", "main.rs"))).unwrap();
+    let (topic, payload, retain) = broker.next_publish();
+    assert_eq!(topic, "multi-rich-presence/presence");
+    assert!(retain);
+    let presence = parse(&payload);
+    assert_eq!(presence["details"], "Coding");
+    assert_eq!(presence["state"], "main.rs");
+    
+    let stats = SystemStats {
+        cpu_usage: 12.5,
+        memory_usage: 40.0,
+        memory_used: 4,
+        memory_total: 10,
+        process_count: 321,
+        uptime: 99,
+    };
+    sink.update_stats(&stats).unwrap();
+    let (topic, payload, _) = broker.next_publish();
+    assert_eq!(topic, "multi-rich-presence/stats");
+    assert_eq!(parse(&payload)["process_count"], 321);
+    
+    // Hidden presence is published as null so retained state gets replaced
+    sink.update(None).unwrap();
+    let (topic, payload, _) = broker.next_publish();
+    assert_eq!(topic, "multi-rich-presence/presence");
+    assert_eq!(parse(&payload), Value::Null);
+}
+
+#[test]
+fn publishes_home_assistant_discovery() {
+    let broker = Broker::start();
+    let config = MqttConfig {
+        discovery: true,
+        ..config(broker.port)
+    };
+    let _sink = MqttSink::new(&config, SinkOutput::default()).unwrap();
+    broker.next();
+    broker.next_publish();
+    
+    let mut discovered = Vec::new();
+    for _ in 0..5 {
+        let (topic, payload, retain) = broker.next_publish();
+        assert!(retain);
+        discovered.push((topic, parse(&payload)));
+    }
+    
+    let topics: Vec<&str> = discovered.iter().map(|(topic, _)| topic.as_str()).collect();
+    assert_eq!(
+        topics,
+        [
+            "homeassistant/sensor/desk_pc/details/config",
+            "homeassistant/sensor/desk_pc/state/config",
+            "homeassistant/sensor/desk_pc/cpu/config",
+            "homeassistant/sensor/desk_pc/memory/config",
+            "homeassistant/sensor/desk_pc/process_count/config",
+        ]
+    );
+    
+    let cpu = &discovered[2].1;
+    assert_eq!(cpu["unique_id"], "desk_pc_cpu");
+    assert_eq!(cpu["state_topic"], "multi-rich-presence/stats");
+    assert_eq!(cpu["unit_of_measurement"], "%");
+    assert_eq!(cpu["availability_topic"], "multi-rich-presence/availability");
+    assert_eq!(cpu["device"]["identifiers"], json!(["desk_pc"]));
+    assert_eq!(discovered[0].1["state_topic"], "multi-rich-presence/presence");
+}
+
+#[test]
+fn rejects_empty_host() {
+    let config = MqttConfig {
+        host: " ".to_string(),
+        ..Default::default()
+    };
+    assert!(MqttSink::new(&config, SinkOutput::default()).is_err());
+}
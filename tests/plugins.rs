@@ -0,0 +1,241 @@
+use chrono::{Local, TimeZone};
+use std::path::{Path, PathBuf};
+
+use multi_rich_presence::activity::{ActivityData, ProcessInfo, SystemStats};
+use multi_rich_presence::config::Config;
+use multi_rich_presence::provider::{Plugin, PluginConfig, PluginGrants, PluginProvider, Provider};
+use multi_rich_presence::template;
+
+const MANIFEST: &str = r#"{"fields": ["ticket", "count", "status", "0"], "interval_seconds": 1, "capabilities": ["read_file", "process_list"]}"#;
+
+/// Escapes text for a WAT data string.
+fn quoted(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// A plugin whose manifest is at 0 and `text` at 1024. `poll` runs in `mrp_poll` and
+/// leaves a length in `$len`: the snapshot is then the buffer at 4096, or the text at
+/// 2048 when the length is negative.
+fn plugin_wat(text: &str, poll: &str) -> String {
+    let fallback = r#"{"status": "denied"}"#;
+    format!(
+        r#"(module
+            (import "mrp" "read_file" (func $read_file (param i32 i32 i32 i32) (result i32)))
+            (import "mrp" "process_list" (func $process_list (param i32 i32) (result i32)))
+            (memory (export "memory") 1)
+            (data (i32.const 0) {manifest})
+            (data (i32.const 1024) {text})
+            (data (i32.const 2048) {fallback})
+            (func $pack (param $ptr i32) (param $len i32) (result i64)
+                (i64.or
+                    (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32))
+                    (i64.extend_i32_u (local.get $len))))
+            (func (export "mrp_manifest") (result i64)
+                (call $pack (i32.const 0) (i32.const {manifest_len})))
+            (func (export "mrp_poll") (result i64)
+                (local $len i32)
+                {poll}
+                (if (result i64) (i32.lt_s (local.get $len) (i32.const 0))
+                    (then (call $pack (i32.const 2048) (i32.const {fallback_len})))
+                    (else (call $pack (i32.const 4096) (local.get $len))))))"#,
+        manifest = quoted(MANIFEST),
+        manifest_len = MANIFEST.len(),
+        text = quoted(text),
+        fallback = quoted(fallback),
+        fallback_len = fallback.len(),
+        poll = poll,
+    )
+}
+
+/// A poll that copies `text` to the buffer, as if it had built the snapshot itself.
+fn returns_text(text: &str) -> String {
+    format!(
+        "(memory.copy (i32.const 4096) (i32.const 1024) (i32.const {len})) (local.set $len (i32.const {len}))",
+        len = text.len()
+    )
+}
+
+fn reads_file(path: &Path) -> String {
+    format!(
+        "(local.set $len (call $read_file (i32.const 1024) (i32.const {}) (i32.const 4096) (i32.const 4096)))",
+        path.display().to_string().len()
+    )
+}
+
+const READS_PROCESSES: &str = "(local.set $len (call $process_list (i32.const 4096) (i32.const 4096)))";
+
+fn write_plugin(dir: &Path, name: &str, wat: &str) -> PathBuf {
+    let path = dir.join(format!("{}.wasm", name));
+    std::fs::write(&path, wat::parse_str(wat).unwrap()).unwrap();
+    path
+}
+
+fn process(name: &str) -> ProcessInfo {
+    ProcessInfo {
+        name: name.to_string(),
+        pid: 4242,
+        cpu_usage: 87.5,
+        memory_usage: 0,
+        start_time: 0,
+    }
+}
+
+fn data() -> ActivityData {
+    ActivityData {
+        system_stats: SystemStats {
+            cpu_usage: 12.0,
+            memory_usage: 40.0,
+            process_count: 200,
+            ..Default::default()
+        },
+        current_time: Local.timestamp_opt(1_700_000_000, 0).unwrap(),
+        top_processes: vec![process("cargo")],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn snapshots_become_declared_namespaced_fields() {
+    let dir = tempfile::tempdir().unwrap();
+    let snapshot = r#"{"ticket": {"key": "WEB-42"}, "count": 3, "undeclared": "dropped"}"#;
+    let path = write_plugin(dir.path(), "jira", &plugin_wat(snapshot, &returns_text(snapshot)));
+    
+    let mut provider = PluginProvider::new(&[PluginConfig::new("jira", path)]);
+    let mut data = data();
+    provider.sample(&mut data).unwrap();
+    
+    let status = &data.plugins[0];
+    assert_eq!(status.error, None);
+    assert_eq!(status.manifest.as_ref().unwrap().interval_seconds, 1);
+    let names: Vec<&str> = status.fields.keys().map(String::as_str).collect();
+    assert_eq!(names, ["jira.count", "jira.ticket.key"]);
+    
+    let fields = template::activity_fields(&data);
+    assert_eq!(template::render("{jira.ticket.key} ({jira.count})", &fields), "WEB-42 (3)");
+}
+
+#[test]
+fn files_are_only_readable_when_granted() {
+    let dir = tempfile::tempdir().unwrap();
+    let allowed = dir.path().join("allowed");
+    std::fs::create_dir(&allowed).unwrap();
+    std::fs::write(allowed.join("build.json"), r#"{"status": "green"}"#).unwrap();
+    std::fs::write(dir.path().join("secret.json"), r#"{"status": "leaked"}"#).unwrap();
+    
+    let status = |file: &Path, grants: &[&Path]| {
+        let path = write_plugin(dir.path(), "ci", &plugin_wat(&file.display().to_string(), &reads_file(file)));
+        let config = PluginConfig {
+            grants: PluginGrants {
+                files: grants.iter().map(|grant| grant.to_path_buf()).collect(),
+                process_list: false,
+            },
+            ..PluginConfig::new("ci", path)
+        };
+        let fields = Plugin::load(&config).unwrap().poll(&[]).unwrap();
+        fields["ci.status"].clone()
+    };
+    
+    let build = allowed.join("build.json");
+    assert_eq!(status(&build, &[&allowed]), "green");
+    assert_eq!(status(&build, &[&build]), "green");
+    assert_eq!(status(&build, &[]), "denied");
+    assert_eq!(status(&allowed.join("../secret.json"), &[&allowed]), "denied");
+    assert_eq!(status(&allowed.join("missing.json"), &[&allowed]), "denied");
+}
+
+#[test]
+fn the_process_list_needs_a_grant() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = write_plugin(dir.path(), "top", &plugin_wat("", READS_PROCESSES));
+    
+    let mut config = PluginConfig::new("top", path);
+    let fields = Plugin::load(&config).unwrap().poll(&[process("cargo")]).unwrap();
+    assert_eq!(fields["top.status"], "denied");
+    
+    config.grants.process_list = true;
+    let fields = Plugin::load(&config).unwrap().poll(&[process("cargo")]).unwrap();
+    assert_eq!(fields["top.0.name"], "cargo");
+    assert_eq!(fields["top.0.pid"], "4242");
+}
+
+#[test]
+fn runaway_plugins_run_out_of_fuel() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = write_plugin(dir.path(), "spin", &plugin_wat("", "(loop $forever (br $forever))"));
+    
+    let config = PluginConfig {
+        fuel: 100_000,
+        ..PluginConfig::new("spin", path)
+    };
+    let mut plugin = Plugin::load(&config).unwrap();
+    let error = plugin.poll(&[]).unwrap_err();
+    assert!(error.contains("ran out of fuel after 100000 instructions"), "{}", error);
+    
+    // The provider reports the failure instead of giving up on the plugin
+    let mut provider = PluginProvider::new(&[config]);
+    let mut data = data();
+    provider.sample(&mut data).unwrap();
+    assert!(data.plugins[0].error.as_deref().unwrap().contains("fuel"));
+    assert!(data.plugins[0].fields.is_empty());
+}
+
+#[test]
+fn loading_rejects_wasi_bad_modules_and_manifests() {
+    let dir = tempfile::tempdir().unwrap();
+    let load = |name: &str, wat: &str| {
+        let path = write_plugin(dir.path(), name, wat);
+        Plugin::load(&PluginConfig::new(name, path)).err().unwrap()
+    };
+    
+    let error = load(
+        "wasi",
+        r#"(module (import "wasi_snapshot_preview1" "fd_write" (func (param i32 i32 i32 i32) (result i32))))"#,
+    );
+    assert_eq!(error, "imports wasi_snapshot_preview1::fd_write, which plugins cannot use");
+    
+    let error = load("bare", r#"(module (memory (export "memory") 1))"#);
+    assert_eq!(error, "does not export mrp_manifest() -> i64");
+    
+    let error = load(
+        "manifest",
+        r#"(module (memory (export "memory") 1) (data (i32.const 0) "{}")
+            (func (export "mrp_manifest") (result i64) (i64.const 2)))"#,
+    );
+    assert!(error.starts_with("invalid manifest"), "{}", error);
+    
+    // More initial memory than the plugin may use
+    let path = write_plugin(dir.path(), "huge", r#"(module (memory (export "memory") 64))"#);
+    let config = PluginConfig {
+        memory_mb: 1,
+        ..PluginConfig::new("huge", path)
+    };
+    assert!(Plugin::load(&config).is_err());
+    
+    std::fs::write(dir.path().join("text.wasm"), "not wasm").unwrap();
+    let error = Plugin::load(&PluginConfig::new("text", dir.path().join("text.wasm"))).err().unwrap();
+    assert!(error.starts_with("invalid module"), "{}", error);
+}
+
+#[test]
+fn validation_rejects_bad_plugin_configs() {
+    let mut config = Config::default();
+    config.providers.plugins = vec![
+        PluginConfig::new("jira ticket", "jira.wasm"),
+        PluginConfig::new("ci", ""),
+        PluginConfig {
+            fuel: 0,
+            grants: PluginGrants {
+                files: vec![PathBuf::from("relative/build.json")],
+                process_list: false,
+            },
+            ..PluginConfig::new("ci", "ci.wasm")
+        },
+    ];
+    
+    let problems = config.validate();
+    assert!(problems.iter().any(|problem| problem.message.contains("plugins[0] needs a name")));
+    assert!(problems.iter().any(|problem| problem.message.contains("\"ci\" has no .wasm file")));
+    assert!(problems.iter().any(|problem| problem.message.contains("\"ci\" is defined more than once")));
+    assert!(problems.iter().any(|problem| problem.message.contains("\"ci\" needs fuel")));
+    assert!(problems.iter().any(|problem| problem.message.contains("not an absolute path")));
+}
@@ -0,0 +1,82 @@
+use multi_rich_presence::config::{ActivityFilters, Config};
+use multi_rich_presence::profile::Profile;
+use multi_rich_presence::rules::Rule;
+
+#[test]
+fn blacklisted_processes_are_always_hidden() {
+    let mut filters = ActivityFilters {
+        hide_system_processes: false,
+        blacklisted_processes: vec!["Steam.exe".to_string()],
+        ..ActivityFilters::default()
+    };
+    
+    assert!(filters.hides("steam.exe"));
+    assert!(!filters.hides("dwm.exe"));
+    assert!(!filters.hides("cargo"));
+    
+    // The built-in system names only with the toggle
+    filters.hide_system_processes = true;
+    assert!(filters.hides("DWM.EXE"));
+}
+
+#[test]
+fn display_names_replace_process_names() {
+    let mut filters = ActivityFilters::default();
+    filters.display_names.insert("javaw.exe".to_string(), "Minecraft".to_string());
+    
+    assert_eq!(filters.display_name("JavaW.exe"), "Minecraft");
+    assert_eq!(filters.display_name("cargo"), "cargo");
+}
+
+#[test]
+fn filters_are_edited_where_the_profile_takes_them_from() {
+    let mut config = Config::default();
+    let mut gaming = Profile::new("Gaming");
+    gaming.activity_filters = Some(ActivityFilters::default());
+    let mut streaming = Profile::new("Streaming");
+    streaming.inherits = Some("Gaming".to_string());
+    config.profiles = vec![gaming, Profile::new("Work"), streaming];
+    
+    // Inherited from Gaming
+    config.activity_filters_mut(Some("Streaming")).blacklisted_processes.push("obs".to_string());
+    // Work has no override, so the base filters apply
+    config.activity_filters_mut(Some("Work")).blacklisted_processes.push("slack".to_string());
+    
+    assert!(config.settings_for(Some("Streaming")).activity_filters.hides("obs"));
+    assert!(!config.settings_for(Some("Streaming")).activity_filters.hides("slack"));
+    assert!(config.settings_for(None).activity_filters.hides("slack"));
+    assert!(config.settings_for(Some("Work")).activity_filters.hides("slack"));
+}
+
+#[test]
+fn new_rules_go_to_the_active_profile() {
+    let mut config = Config {
+        profiles: vec![Profile::new("Gaming")],
+        ..Config::default()
+    };
+    let rule = |name: &str| Rule {
+        name: name.to_string(),
+        ..Rule::default()
+    };
+    
+    config.rules_mut(Some("Gaming")).push(rule("steam"));
+    config.rules_mut(Some("Missing")).push(rule("unknown profile"));
+    config.rules_mut(None).push(rule("base"));
+    
+    let names = |rules: &[Rule]| rules.iter().map(|rule| rule.name.clone()).collect::<Vec<_>>();
+    assert_eq!(names(&config.profiles[0].rules), ["steam"]);
+    assert_eq!(names(&config.rules), ["unknown profile", "base"]);
+    // The profile's own rules come before the inherited ones
+    assert_eq!(names(&config.settings_for(Some("Gaming")).rules), ["steam", "unknown profile", "base"]);
+}
+
+#[test]
+fn configs_without_display_names_still_load() {
+    let filters: ActivityFilters = serde_json::from_str(
+        r#"{"hide_system_processes": true, "hide_background_apps": true, "minimum_cpu_usage": 0.1, "blacklisted_processes": ["csrss.exe"]}"#,
+    )
+    .unwrap();
+    
+    assert!(filters.display_names.is_empty());
+    assert!(filters.hides("csrss.exe"));
+}
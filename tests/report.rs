@@ -0,0 +1,107 @@
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate, TimeZone, Utc};
+
+use multi_rich_presence::history::AppSession;
+use multi_rich_presence::report::{Report, Total};
+
+fn date(day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2024, 3, day).unwrap()
+}
+
+/// Minutes after midnight UTC on 2024-03-`day`.
+fn at(day: u32, minutes: i64) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 3, day, 0, 0, 0).unwrap() + Duration::minutes(minutes)
+}
+
+fn session(app: &str, project: Option<&str>, started_at: DateTime<Utc>, minutes: i64) -> AppSession {
+    AppSession {
+        started_at,
+        ended_at: started_at + Duration::minutes(minutes),
+        app: app.to_string(),
+        window: None,
+        project: project.map(str::to_string),
+    }
+}
+
+fn totals(totals: &[Total]) -> Vec<(&str, i64)> {
+    totals.iter().map(|total| (total.name.as_str(), total.seconds / 60)).collect()
+}
+
+#[test]
+fn time_is_totalled_per_day_week_app_and_project() {
+    // Sunday 2024-03-10 ends ISO week 10
+    let sessions = [
+        session("code", Some("mrp"), at(9, 9 * 60), 90),
+        session("firefox", None, at(9, 11 * 60), 30),
+        // Runs past midnight into Monday, the first day of week 11
+        session("code", Some("website"), at(10, 23 * 60), 120),
+        session("firefox", None, at(11, 10 * 60), 45),
+    ];
+    let report = Report::build(&sessions, date(9), date(12), &Utc);
+    
+    assert_eq!(report.total_seconds / 60, 285);
+    assert_eq!(totals(&report.days), [("2024-03-09", 120), ("2024-03-10", 60), ("2024-03-11", 105), ("2024-03-12", 0)]);
+    assert_eq!(totals(&report.weeks), [("2024-W10", 180), ("2024-W11", 105)]);
+    assert_eq!(totals(&report.apps), [("code", 210), ("firefox", 75)]);
+    assert_eq!(totals(&report.projects), [("website", 120), ("mrp", 90)]);
+}
+
+#[test]
+fn sessions_are_clipped_to_the_range_in_the_given_time_zone() {
+    let sessions = [
+        session("code", None, at(8, 22 * 60), 240),
+        session("steam", None, at(12, 60), 60),
+    ];
+    
+    // In UTC only the part after midnight on the 9th counts
+    let report = Report::build(&sessions, date(9), date(9), &Utc);
+    assert_eq!(totals(&report.days), [("2024-03-09", 120)]);
+    
+    // Two hours east, the session starts at midnight local time and ends at 04:00
+    let east = FixedOffset::east_opt(2 * 3600).unwrap();
+    let report = Report::build(&sessions, date(9), date(9), &east);
+    assert_eq!(totals(&report.days), [("2024-03-09", 240)]);
+    assert_eq!(totals(&report.apps), [("code", 240)]);
+}
+
+#[test]
+fn focus_streaks_tolerate_short_interruptions() {
+    let sessions = [
+        session("code", None, at(9, 0), 20),
+        // A quick look at the docs does not break the streak
+        session("firefox", None, at(9, 20), 1),
+        session("code", None, at(9, 21), 15),
+        // A long break does
+        session("slack", None, at(9, 36), 10),
+        session("code", None, at(9, 46), 20),
+        session("blender", None, at(9, 66), 30),
+    ];
+    let report = Report::build(&sessions, date(9), date(9), &Utc);
+    
+    let streaks: Vec<(&str, DateTime<Utc>, DateTime<Utc>, i64)> = report
+        .focus_streaks
+        .iter()
+        .map(|streak| (streak.app.as_str(), streak.started_at, streak.ended_at, streak.seconds / 60))
+        .collect();
+    assert_eq!(streaks, [("code", at(9, 0), at(9, 36), 35), ("blender", at(9, 66), at(9, 96), 30)]);
+}
+
+#[test]
+fn csv_export_lists_every_total_and_streak() {
+    let sessions = [session("Code, Insiders", Some("say \"hi\""), at(9, 0), 30)];
+    let report = Report::build(&sessions, date(9), date(9), &Utc);
+    
+    assert_eq!(
+        report.to_csv(),
+        "kind,name,seconds,start,end\n\
+         day,2024-03-09,1800,,\n\
+         week,2024-W10,1800,,\n\
+         app,\"Code, Insiders\",1800,,\n\
+         project,\"say \"\"hi\"\"\",1800,,\n\
+         streak,\"Code, Insiders\",1800,2024-03-09T00:00:00+00:00,2024-03-09T00:30:00+00:00\n"
+    );
+    
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["from"], "2024-03-09");
+    assert_eq!(json["apps"][0]["name"], "Code, Insiders");
+    assert_eq!(json["focus_streaks"][0]["seconds"], 1800);
+}
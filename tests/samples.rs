@@ -0,0 +1,87 @@
+use chrono::{DateTime, Duration, Local, TimeZone};
+
+use multi_rich_presence::activity::{ActivityData, ProcessInfo, SystemStats};
+use multi_rich_presence::config::Config;
+use multi_rich_presence::samples::{RingBuffer, SampleHistory};
+
+fn at(seconds: i64) -> DateTime<Local> {
+    Local.timestamp_opt(1_700_000_000, 0).unwrap() + Duration::seconds(seconds)
+}
+
+fn process(name: &str, pid: u32, cpu_usage: f32) -> ProcessInfo {
+    ProcessInfo {
+        name: name.to_string(),
+        pid,
+        cpu_usage,
+        memory_usage: 64 * 1024 * 1024,
+        start_time: 0,
+    }
+}
+
+fn data(seconds: i64, cpu_usage: f32, top_processes: Vec<ProcessInfo>) -> ActivityData {
+    ActivityData {
+        system_stats: SystemStats {
+            cpu_usage,
+            memory_usage: 40.0,
+            process_count: 200,
+            ..Default::default()
+        },
+        current_time: at(seconds),
+        top_processes,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn ring_buffers_drop_the_oldest_item_when_full() {
+    let mut buffer = RingBuffer::new(3);
+    for i in 0..5 {
+        buffer.push(i);
+    }
+    
+    assert_eq!(buffer.iter().copied().collect::<Vec<_>>(), [2, 3, 4]);
+    assert_eq!(buffer.last(), Some(&4));
+}
+
+#[test]
+fn samples_older_than_the_window_are_dropped() {
+    let mut samples = SampleHistory::new(1);
+    for (seconds, cpu) in [(0, 10.0), (30, 20.0), (60, 30.0), (75, 40.0)] {
+        samples.record(&data(seconds, cpu, Vec::new()));
+    }
+    
+    let cpu: Vec<f32> = samples.system().iter().map(|sample| sample.cpu_usage).collect();
+    assert_eq!(cpu, [20.0, 30.0, 40.0]);
+    
+    // A shorter window applies immediately
+    samples.set_minutes(0);
+    assert_eq!(samples.system().len(), 1);
+}
+
+#[test]
+fn processes_keep_their_own_series_until_they_leave_the_window() {
+    let mut samples = SampleHistory::new(2);
+    samples.record(&data(0, 10.0, vec![process("cargo", 1, 90.0), process("code", 2, 5.0)]));
+    samples.record(&data(60, 10.0, vec![process("cargo", 1, 150.0)]));
+    
+    let cargo: Vec<f32> = samples.process(1).unwrap().iter().map(|sample| sample.cpu_usage).collect();
+    assert_eq!(cargo, [90.0, 150.0]);
+    assert_eq!(samples.process(2).unwrap().len(), 1);
+    
+    // Not a top process for the whole window
+    samples.record(&data(150, 10.0, vec![process("cargo", 1, 100.0)]));
+    assert!(samples.process(2).is_none());
+    assert_eq!(samples.process(1).unwrap().len(), 2);
+}
+
+#[test]
+fn validation_rejects_out_of_range_graph_windows() {
+    for minutes in [0, 24 * 60 + 1] {
+        let config = Config {
+            graph_minutes: minutes,
+            ..Config::default()
+        };
+        assert!(config.validate().iter().any(|problem| problem.message.contains("graph_minutes")), "{}", minutes);
+    }
+    assert!(Config::default().validate().is_empty());
+}
@@ -0,0 +1,93 @@
+use chrono::{NaiveTime, TimeZone, Utc, Weekday};
+
+use multi_rich_presence::schedule::{Schedule, ScheduleAction, ScheduleBlock};
+
+fn at(hour: u32, minute: u32) -> NaiveTime {
+    NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+}
+
+fn block(name: &str, days: &[Weekday], start: NaiveTime, end: NaiveTime) -> ScheduleBlock {
+    ScheduleBlock {
+        name: name.to_string(),
+        days: days.to_vec(),
+        start,
+        end,
+        action: ScheduleAction::Profile(name.to_string()),
+    }
+}
+
+#[test]
+fn blocks_past_midnight_continue_into_the_next_weekday() {
+    let night = block("night", &[Weekday::Fri], at(22, 0), at(2, 0));
+    
+    assert!(night.covers(Weekday::Fri, at(22, 0)));
+    assert!(night.covers(Weekday::Fri, at(23, 59)));
+    assert!(night.covers(Weekday::Sat, at(0, 0)));
+    assert!(night.covers(Weekday::Sat, at(1, 59)));
+    assert!(!night.covers(Weekday::Sat, at(2, 0)));
+    assert!(!night.covers(Weekday::Fri, at(21, 59)));
+    // Only the part after midnight runs on the following day
+    assert!(!night.covers(Weekday::Sat, at(22, 30)));
+    assert!(!night.covers(Weekday::Fri, at(1, 0)));
+    
+    // The wrap from Sunday lands on Monday
+    let sunday = block("sunday", &[Weekday::Sun], at(23, 0), at(1, 0));
+    assert!(sunday.covers(Weekday::Mon, at(0, 30)));
+    assert!(!sunday.covers(Weekday::Sun, at(0, 30)));
+}
+
+#[test]
+fn equal_start_and_end_cover_the_whole_day() {
+    let weekend = block("weekend", &[Weekday::Sat, Weekday::Sun], at(0, 0), at(0, 0));
+    
+    assert!(weekend.covers(Weekday::Sat, at(0, 0)));
+    assert!(weekend.covers(Weekday::Sun, at(23, 59)));
+    assert!(!weekend.covers(Weekday::Mon, at(12, 0)));
+    
+    let noon = block("noon", &[Weekday::Wed], at(12, 0), at(12, 0));
+    assert!(noon.covers(Weekday::Wed, at(3, 0)));
+    assert!(!noon.covers(Weekday::Thu, at(3, 0)));
+}
+
+#[test]
+fn weekday_and_time_come_from_the_configured_timezone() {
+    let mut schedule = Schedule {
+        enabled: true,
+        timezone: Some("Pacific/Auckland".to_string()),
+        blocks: vec![block("morning", &[Weekday::Tue], at(8, 0), at(10, 0))],
+    };
+    // Monday 20:00 UTC is Tuesday 09:00 in Auckland and Monday 13:00 in Los Angeles
+    let now = Utc.with_ymd_and_hms(2026, 10, 19, 20, 0, 0).unwrap();
+    
+    let localized = schedule.localize(now);
+    assert_eq!((localized.weekday, localized.time), (Weekday::Tue, at(9, 0)));
+    assert_eq!(schedule.active_block(now).map(|(index, _)| index), Some(0));
+    
+    schedule.timezone = Some("America/Los_Angeles".to_string());
+    assert_eq!(schedule.localize(now).weekday, Weekday::Mon);
+    assert!(schedule.active_block(now).is_none());
+    
+    schedule.enabled = false;
+    schedule.timezone = Some("Pacific/Auckland".to_string());
+    assert!(schedule.active_block(now).is_none());
+}
+
+#[test]
+fn the_first_matching_block_wins() {
+    let schedule = Schedule {
+        enabled: true,
+        timezone: Some("UTC".to_string()),
+        blocks: vec![
+            block("lunch", &[Weekday::Mon], at(12, 0), at(13, 0)),
+            block("work", &[Weekday::Mon], at(9, 0), at(17, 0)),
+            block("all day", &[Weekday::Mon], at(0, 0), at(0, 0)),
+        ],
+    };
+    let monday = |hour: u32| Utc.with_ymd_and_hms(2026, 10, 19, hour, 30, 0).unwrap();
+    let name = |hour: u32| schedule.active_block(monday(hour)).map(|(_, block)| block.name.clone());
+    
+    assert_eq!(name(12).as_deref(), Some("lunch"));
+    assert_eq!(name(10).as_deref(), Some("work"));
+    assert_eq!(name(20).as_deref(), Some("all day"));
+    assert_eq!(schedule.block_at(Weekday::Mon, 12).map(|(index, _)| index), Some(0));
+}
@@ -0,0 +1,201 @@
+mod common;
+
+use chrono::{Local, TimeZone};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use multi_rich_presence::activity::{ActivityData, ProcessInfo, SystemStats};
+use multi_rich_presence::config::Config;
+use multi_rich_presence::engine::PresenceEngine;
+use multi_rich_presence::script::{ScriptHost, ScriptOutcome, ScriptingConfig};
+use multi_rich_presence::template::PresenceTemplate;
+
+fn data(cpu_usage: f32, app: &str) -> ActivityData {
+    ActivityData {
+        system_stats: SystemStats {
+            cpu_usage,
+            memory_usage: 40.0,
+            process_count: 200,
+            ..Default::default()
+        },
+        current_time: Local.timestamp_opt(1_700_000_000, 0).unwrap(),
+        top_processes: vec![ProcessInfo {
+            name: app.to_string(),
+            pid: 4242,
+            cpu_usage,
+            memory_usage: 0,
+            start_time: 0,
+        }],
+        ..Default::default()
+    }
+}
+
+fn write_script(dir: &Path, name: &str, source: &str) -> PathBuf {
+    let path = dir.join(name);
+    std::fs::write(&path, source).unwrap();
+    path
+}
+
+fn host(dir: &Path, scripts: &[&str]) -> ScriptHost {
+    let config = ScriptingConfig {
+        scripts: scripts.iter().map(PathBuf::from).collect(),
+        ..ScriptingConfig::default()
+    };
+    ScriptHost::new(&config, Some(dir))
+}
+
+fn known(name: &str) -> bool {
+    ["Work", "Gaming"].contains(&name)
+}
+
+#[test]
+fn scripts_choose_a_profile_from_the_snapshot() {
+    let dir = tempfile::tempdir().unwrap();
+    write_script(
+        dir.path(),
+        "profile.rhai",
+        r#"
+            if fields.app == "steam" && data.system_stats.cpu_usage > 50.0 {
+                #{ profile: "Gaming" }
+            }
+        "#,
+    );
+    let mut host = host(dir.path(), &["profile.rhai"]);
+    
+    assert_eq!(
+        host.evaluate(&data(80.0, "steam"), None, known),
+        Some(("profile.rhai".to_string(), ScriptOutcome::Profile("Gaming".to_string())))
+    );
+    assert_eq!(host.evaluate(&data(10.0, "steam"), None, known), None);
+    assert_eq!(host.scripts()[0].error(), None);
+}
+
+#[test]
+fn scripts_return_activity_fields_and_the_first_decision_wins() {
+    let dir = tempfile::tempdir().unwrap();
+    write_script(dir.path(), "quiet.rhai", "if profile == \"Work\" { () }");
+    write_script(
+        dir.path(),
+        "cargo.rhai",
+        r#"
+            let crates = 12;
+            #{ details: `Compiling (${crates} crates)`, state: "in " + fields.app }
+        "#,
+    );
+    let mut host = host(dir.path(), &["quiet.rhai", "cargo.rhai"]);
+    
+    let (script, outcome) = host.evaluate(&data(95.0, "cargo"), Some("Work"), known).unwrap();
+    assert_eq!(script, "cargo.rhai");
+    assert_eq!(
+        outcome,
+        ScriptOutcome::Template(PresenceTemplate {
+            details: "Compiling (12 crates)".to_string(),
+            state: "in cargo".to_string(),
+            ..PresenceTemplate::default()
+        })
+    );
+}
+
+#[test]
+fn runaway_scripts_are_stopped_and_reported() {
+    let dir = tempfile::tempdir().unwrap();
+    write_script(dir.path(), "loop.rhai", "loop { }");
+    write_script(dir.path(), "fallback.rhai", "#{ details: \"still here\" }");
+    let mut host = host(dir.path(), &["loop.rhai", "fallback.rhai"]);
+    
+    let (script, _) = host.evaluate(&data(0.0, "bash"), None, known).unwrap();
+    assert_eq!(script, "fallback.rhai");
+    let error = host.scripts()[0].error().unwrap().to_string();
+    assert!(error.contains("operations"), "{}", error);
+    
+    // A tight time limit stops the script before the operation limit does
+    let config = ScriptingConfig {
+        scripts: vec![PathBuf::from("loop.rhai")],
+        max_operations: u64::MAX,
+        timeout_millis: 20,
+    };
+    let mut host = ScriptHost::new(&config, Some(dir.path()));
+    assert_eq!(host.evaluate(&data(0.0, "bash"), None, known), None);
+    let error = host.scripts()[0].error().unwrap().to_string();
+    assert!(error.contains("time limit exceeded"), "{}", error);
+}
+
+#[test]
+fn bad_scripts_and_results_become_errors() {
+    let dir = tempfile::tempdir().unwrap();
+    write_script(dir.path(), "syntax.rhai", "let x = ;");
+    write_script(dir.path(), "unknown.rhai", "#{ profile: \"Sleeping\" }");
+    write_script(dir.path(), "keys.rhai", "#{ title: \"oops\" }");
+    write_script(dir.path(), "number.rhai", "42");
+    write_script(dir.path(), "files.rhai", "import \"other\" as other;");
+    let mut host = host(dir.path(), &["syntax.rhai", "unknown.rhai", "keys.rhai", "number.rhai", "files.rhai", "missing.rhai"]);
+    
+    assert_eq!(host.evaluate(&data(0.0, "bash"), None, known), None);
+    let errors: Vec<String> = host.scripts().iter().map(|script| script.error().unwrap().to_string()).collect();
+    assert!(errors[0].contains("line 1, position"), "{}", errors[0]);
+    assert!(errors[1].contains("\"Sleeping\" does not exist"), "{}", errors[1]);
+    assert!(errors[2].contains("unknown key \"title\""), "{}", errors[2]);
+    assert!(errors[3].contains("must return nothing or a map"), "{}", errors[3]);
+    assert!(errors[4].contains("other"), "{}", errors[4]);
+    assert!(errors[5].contains("cannot read"), "{}", errors[5]);
+}
+
+#[test]
+fn changed_files_are_reloaded() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = write_script(dir.path(), "reload.rhai", "#{ details: \"first\" }");
+    let mut host = host(dir.path(), &["reload.rhai"]);
+    let details = |host: &mut ScriptHost| match host.evaluate(&data(0.0, "bash"), None, known) {
+        Some((_, ScriptOutcome::Template(template))) => template.details,
+        other => panic!("unexpected outcome {:?}", other),
+    };
+    assert_eq!(details(&mut host), "first");
+    
+    // Move the modification time on explicitly in case the file system is coarse
+    let touch = |source: &str, seconds: u64| {
+        std::fs::write(&path, source).unwrap();
+        let modified = SystemTime::now() + Duration::from_secs(seconds);
+        File::options().write(true).open(&path).unwrap().set_modified(modified).unwrap();
+    };
+    
+    touch("#{ details: \"second\" }", 10);
+    assert_eq!(details(&mut host), "second");
+    
+    // A broken edit keeps the last good version running and shows the error
+    touch("#{ details: ", 20);
+    assert_eq!(details(&mut host), "second");
+    assert!(host.scripts()[0].error().is_some());
+}
+
+#[test]
+fn validation_rejects_bad_scripting_settings() {
+    let mut config = Config::default();
+    config.scripting.scripts = vec![PathBuf::new()];
+    config.scripting.timeout_millis = 0;
+    
+    let problems = config.validate();
+    assert!(problems.iter().any(|problem| problem.message.contains("scripts[0] has no file path")));
+    assert!(problems.iter().any(|problem| problem.message.contains("timeout_millis of at least 1")));
+}
+
+#[test]
+fn script_lines_go_through_the_word_filter() {
+    let _guard = common::environment();
+    let dir = tempfile::tempdir().unwrap();
+    let script = write_script(dir.path(), "launch.rhai", "#{ details: \"Working on \" + fields.app, state: \"Acme\" }");
+    
+    let mut config = Config {
+        blacklisted_words: vec!["acme".to_string()],
+        ..Config::default()
+    };
+    config.scripting.scripts = vec![script];
+    let mut engine = PresenceEngine::new(config);
+    *engine.activity_data.lock().unwrap() = Some(data(10.0, "acme-launch"));
+    engine.refresh_settings();
+    
+    let composed = engine.composed_activity().unwrap();
+    assert_eq!(composed.details.as_deref(), Some("Working on [FILTERED]-launch"));
+    assert_eq!(composed.state.as_deref(), Some("[FILTERED]"));
+    assert_eq!(composed.filter_hits, 2);
+}
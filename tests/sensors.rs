@@ -0,0 +1,263 @@
+#![cfg(target_os = "linux")]
+
+use chrono::{Local, TimeZone};
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use multi_rich_presence::activity::{
+    ActivityData, BatteryInfo, BatteryStatus, DiskUsage, LoadInfo, NetworkThroughput, SystemStats, Temperature,
+};
+use multi_rich_presence::provider::{BatteryProvider, LoadProvider, NetworkConfig, NetworkProvider, ThermalConfig};
+use multi_rich_presence::template::{self, format_bytes, format_duration};
+
+/// Writes `files` as `(path, contents)` below `root`.
+fn tree(root: &Path, files: &[(&str, &str)]) {
+    for (path, contents) in files {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, format!("{}\n", contents)).unwrap();
+    }
+}
+
+fn activity_data() -> ActivityData {
+    ActivityData {
+        system_stats: SystemStats {
+            cpu_usage: 12.0,
+            memory_usage: 40.0,
+            memory_total: 16 * 1024 * 1024 * 1024,
+            memory_used: 4 * 1024 * 1024 * 1024,
+            uptime: 3 * 86_400 + 4 * 3_600 + 5 * 60,
+            process_count: 200,
+        },
+        current_time: Local.timestamp_opt(1_700_000_000, 0).unwrap(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn batteries_are_combined_by_energy() {
+    let dir = tempfile::tempdir().unwrap();
+    tree(
+        dir.path(),
+        &[
+            ("class/power_supply/AC/type", "Mains"),
+            ("class/power_supply/AC/online", "1"),
+            ("class/power_supply/BAT0/type", "Battery"),
+            ("class/power_supply/BAT0/status", "Charging"),
+            ("class/power_supply/BAT0/energy_now", "20000000"),
+            ("class/power_supply/BAT0/energy_full", "40000000"),
+            ("class/power_supply/BAT0/capacity", "50"),
+            ("class/power_supply/BAT1/type", "Battery"),
+            ("class/power_supply/BAT1/status", "Full"),
+            ("class/power_supply/BAT1/energy_now", "20000000"),
+            ("class/power_supply/BAT1/energy_full", "20000000"),
+            ("class/power_supply/BAT1/capacity", "100"),
+            // A wireless mouse is not a system battery
+            ("class/power_supply/hidpp_battery_0/type", "Battery"),
+            ("class/power_supply/hidpp_battery_0/scope", "Device"),
+            ("class/power_supply/hidpp_battery_0/capacity", "5"),
+            ("class/power_supply/hidpp_battery_0/status", "Discharging"),
+        ],
+    );
+    
+    let battery = BatteryProvider::with_sysfs_root(dir.path()).current().unwrap();
+    assert_eq!(battery.status, BatteryStatus::Charging);
+    assert!((battery.percent - 66.67).abs() < 0.01, "{}", battery.percent);
+}
+
+#[test]
+fn battery_falls_back_to_capacity() {
+    let dir = tempfile::tempdir().unwrap();
+    tree(
+        dir.path(),
+        &[
+            ("class/power_supply/BAT0/type", "Battery"),
+            ("class/power_supply/BAT0/status", "Not charging"),
+            ("class/power_supply/BAT0/capacity", "80"),
+        ],
+    );
+    
+    let battery = BatteryProvider::with_sysfs_root(dir.path()).current().unwrap();
+    assert_eq!(
+        battery,
+        BatteryInfo {
+            percent: 80.0,
+            status: BatteryStatus::NotCharging,
+        }
+    );
+    
+    // Desktops have no battery at all
+    let empty = tempfile::tempdir().unwrap();
+    assert_eq!(BatteryProvider::with_sysfs_root(empty.path()).current(), None);
+}
+
+#[test]
+fn reads_load_average_and_swap() {
+    let dir = tempfile::tempdir().unwrap();
+    tree(
+        dir.path(),
+        &[
+            ("loadavg", "0.52 1.25 2.00 2/1200 31337"),
+            (
+                "meminfo",
+                "MemTotal:       16318480 kB\nSwapCached:            0 kB\nSwapTotal:       8388604 kB\nSwapFree:        6291452 kB",
+            ),
+        ],
+    );
+    
+    let load = LoadProvider::with_proc_root(dir.path()).current().unwrap();
+    assert_eq!(
+        load,
+        LoadInfo {
+            one: 0.52,
+            five: 1.25,
+            fifteen: 2.0,
+            swap_total: 8388604 * 1024,
+            swap_used: (8388604 - 6291452) * 1024,
+        }
+    );
+    
+    tree(dir.path(), &[("loadavg", "")]);
+    assert!(LoadProvider::with_proc_root(dir.path()).current().is_err());
+}
+
+#[test]
+fn network_throughput_between_samples() {
+    let dir = tempfile::tempdir().unwrap();
+    let counters = |root: &Path, rx: u64, tx: u64| {
+        tree(
+            root,
+            &[
+                ("class/net/wlan0/device/uevent", ""),
+                ("class/net/wlan0/statistics/rx_bytes", &rx.to_string()),
+                ("class/net/wlan0/statistics/tx_bytes", &tx.to_string()),
+                ("class/net/lo/statistics/rx_bytes", "999999"),
+                ("class/net/lo/statistics/tx_bytes", "999999"),
+                ("class/net/docker0/statistics/rx_bytes", "0"),
+                ("class/net/docker0/statistics/tx_bytes", "0"),
+            ],
+        );
+    };
+    
+    counters(dir.path(), 1_000_000, 50_000);
+    let mut provider = NetworkProvider::with_sysfs_root(NetworkConfig::default(), dir.path());
+    let start = Instant::now();
+    assert!(provider.measure(start).is_empty());
+    
+    counters(dir.path(), 3_000_000, 60_000);
+    assert_eq!(
+        provider.measure(start + Duration::from_secs(2)),
+        vec![NetworkThroughput {
+            interface: "wlan0".to_string(),
+            received_per_second: 1_000_000,
+            sent_per_second: 5_000,
+        }]
+    );
+    
+    // Listed interfaces are reported even without a device, and a counter reset is not negative
+    let config = NetworkConfig {
+        enabled: true,
+        interfaces: vec!["lo".to_string(), "wlan0".to_string()],
+    };
+    let mut provider = NetworkProvider::with_sysfs_root(config, dir.path());
+    provider.measure(start);
+    counters(dir.path(), 0, 0);
+    let throughput = provider.measure(start + Duration::from_secs(1));
+    let interfaces: Vec<&str> = throughput.iter().map(|network| network.interface.as_str()).collect();
+    assert_eq!(interfaces, ["lo", "wlan0"]);
+    assert_eq!(throughput[1].received_per_second, 0);
+}
+
+#[test]
+fn thermal_sensors_match_labels_ignoring_case() {
+    let config = ThermalConfig {
+        enabled: true,
+        sensors: vec!["package".to_string()],
+    };
+    assert!(config.includes("coretemp Package id 0"));
+    assert!(!config.includes("nvme Composite"));
+    assert!(ThermalConfig::default().includes("nvme Composite"));
+}
+
+#[test]
+fn template_fields_use_units() {
+    let mut data = activity_data();
+    data.battery = Some(BatteryInfo {
+        percent: 66.6,
+        status: BatteryStatus::Discharging,
+    });
+    data.temperatures = vec![
+        Temperature {
+            label: "nvme Composite".to_string(),
+            celsius: 41.2,
+        },
+        Temperature {
+            label: "coretemp Package id 0".to_string(),
+            celsius: 71.6,
+        },
+    ];
+    data.load = Some(LoadInfo {
+        one: 0.5,
+        five: 1.25,
+        fifteen: 2.0,
+        swap_total: 8 * 1024 * 1024 * 1024,
+        swap_used: 1024 * 1024 * 1024,
+    });
+    data.disks = vec![
+        DiskUsage {
+            mount: "/".to_string(),
+            total: 500 * 1024 * 1024 * 1024,
+            used: 125 * 1024 * 1024 * 1024,
+        },
+        DiskUsage {
+            mount: "/home".to_string(),
+            total: 2 * 1024 * 1024 * 1024 * 1024,
+            used: 1024 * 1024 * 1024 * 1024,
+        },
+    ];
+    data.networks = vec![
+        NetworkThroughput {
+            interface: "eth0".to_string(),
+            received_per_second: 1536,
+            sent_per_second: 100,
+        },
+        NetworkThroughput {
+            interface: "wlan0".to_string(),
+            received_per_second: 2 * 1024 * 1024,
+            sent_per_second: 0,
+        },
+    ];
+    
+    let fields = template::activity_fields(&data);
+    let field = |name: &str| fields.get(name).map(String::as_str).unwrap_or_else(|| panic!("no field {}", name));
+    
+    assert_eq!(field("uptime"), "3d 4h");
+    assert_eq!(field("battery"), "67");
+    assert_eq!(field("battery_status"), "Discharging");
+    assert_eq!(field("temperature"), "72°C");
+    assert_eq!(field("temperature:nvme Composite"), "41°C");
+    assert_eq!(field("load"), "0.50");
+    assert_eq!(field("load_15"), "2.00");
+    assert_eq!(field("swap"), "12.5");
+    assert_eq!(field("swap_used"), "1.0 GB");
+    assert_eq!(field("disk"), "25.0");
+    assert_eq!(field("disk_total"), "500.0 GB");
+    assert_eq!(field("disk:/home"), "50.0");
+    assert_eq!(field("disk_used:/home"), "1.0 TB");
+    assert_eq!(field("net_down:eth0"), "1.5 KB/s");
+    assert_eq!(field("net_up:eth0"), "100 B/s");
+    assert_eq!(field("net_down"), "2.0 MB/s");
+    
+    let rendered = template::render("{battery}% {battery_status} | {temperature} | up {uptime}", &fields);
+    assert_eq!(rendered, "67% Discharging | 72°C | up 3d 4h");
+}
+
+#[test]
+fn durations_and_sizes_format_compactly() {
+    assert_eq!(format_duration(59), "0m");
+    assert_eq!(format_duration(2 * 3600 + 15 * 60), "2h 15m");
+    assert_eq!(format_bytes(0), "0 B");
+    assert_eq!(format_bytes(1023), "1023 B");
+    assert_eq!(format_bytes(1024 * 1024 * 3 / 2), "1.5 MB");
+}
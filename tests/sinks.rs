@@ -0,0 +1,208 @@
+mod common;
+
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use multi_rich_presence::config::Config;
+use multi_rich_presence::engine::PresenceEngine;
+use multi_rich_presence::presence::ComposedActivity;
+use multi_rich_presence::sink::{FileSink, PresenceSink, SinkConfig, SinkFormat, SinkKind, SinkOutput, SinkSlot, WebhookSink};
+
+fn activity(details: &str, state: &str) -> ComposedActivity {
+    ComposedActivity {
+        details: Some(details.to_string()),
+        state: Some(state.to_string()),
+        large_image: "default".to_string(),
+        large_text: "MultiRichPresence".to_string(),
+        start_timestamp: Some(1_700_000_000),
+        ..Default::default()
+    }
+}
+
+fn read(path: &Path) -> String {
+    std::fs::read_to_string(path).unwrap()
+}
+
+/// A local HTTP server standing in for a webhook receiver.
+struct StandIn {
+    server: tiny_http::Server,
+}
+
+struct Received {
+    body: Value,
+    headers: BTreeMap<String, String>,
+}
+
+impl StandIn {
+    fn start() -> StandIn {
+        StandIn {
+            server: tiny_http::Server::http("127.0.0.1:0").unwrap(),
+        }
+    }
+    
+    fn url(&self) -> String {
+        format!("http://{}/hook", self.server.server_addr().to_ip().unwrap())
+    }
+    
+    fn receive(&self) -> Received {
+        let mut request = self
+            .server
+            .recv_timeout(Duration::from_secs(10))
+            .unwrap()
+            .expect("webhook was not called in time");
+        
+        assert_eq!(*request.method(), tiny_http::Method::Post);
+        assert_eq!(request.url(), "/hook");
+        
+        let mut body = String::new();
+        request.as_reader().read_to_string(&mut body).unwrap();
+        let headers = request
+            .headers()
+            .iter()
+            .map(|header| (header.field.as_str().as_str().to_lowercase(), header.value.as_str().to_string()))
+            .collect();
+        request.respond(tiny_http::Response::empty(204)).unwrap();
+        
+        Received {
+            body: serde_json::from_str(&body).unwrap(),
+            headers,
+        }
+    }
+}
+
+#[test]
+fn webhook_posts_mapped_json_with_headers() {
+    let stand_in = StandIn::start();
+    let output = SinkOutput {
+        text: None,
+        fields: BTreeMap::from([
+            ("title".to_string(), "{details}".to_string()),
+            ("subtitle".to_string(), "{state} since {start_timestamp}".to_string()),
+        ]),
+    };
+    let headers = BTreeMap::from([("X-Api-Key".to_string(), "abc123".to_string())]);
+    let mut sink = WebhookSink::new(&stand_in.url(), headers, output).unwrap();
+    
+    sink.update(Some(&activity("Reviewing PR #123", "In review"))).unwrap();
+    let received = stand_in.receive();
+    assert_eq!(
+        received.body,
+        json!({ "title": "Reviewing PR #123", "subtitle": "In review since 1700000000" })
+    );
+    assert_eq!(received.headers["x-api-key"], "abc123");
+    assert!(received.headers["content-type"].starts_with("application/json"));
+    
+    // Hidden presence is posted as null
+    sink.update(None).unwrap();
+    assert_eq!(stand_in.receive().body, Value::Null);
+}
+
+#[test]
+fn webhook_without_mapping_posts_the_whole_activity() {
+    let stand_in = StandIn::start();
+    let mut sink = WebhookSink::new(&stand_in.url(), BTreeMap::new(), SinkOutput::default()).unwrap();
+    
+    sink.update(Some(&activity("Coding", "main.rs"))).unwrap();
+    let body = stand_in.receive().body;
+    assert_eq!(body["details"], "Coding");
+    assert_eq!(body["state"], "main.rs");
+    assert_eq!(body["large_image"], "default");
+}
+
+#[test]
+fn webhook_rejects_non_http_urls() {
+    assert!(WebhookSink::new("ftp://example.com", BTreeMap::new(), SinkOutput::default()).is_err());
+}
+
+#[test]
+fn file_sink_writes_text_and_json() {
+    let dir = tempfile::tempdir().unwrap();
+    
+    let text_path = dir.path().join("obs/presence.txt");
+    let mut text = FileSink::new(text_path.clone(), SinkFormat::Text, SinkOutput::default());
+    text.update(Some(&activity("Coding", "main.rs"))).unwrap();
+    assert_eq!(read(&text_path), "Coding\nmain.rs");
+    text.update(None).unwrap();
+    assert_eq!(read(&text_path), "");
+    
+    let json_path = dir.path().join("presence.json");
+    let output = SinkOutput {
+        text: None,
+        fields: BTreeMap::from([("now".to_string(), "{details} ({state})".to_string())]),
+    };
+    let mut json_sink = FileSink::new(json_path.clone(), SinkFormat::Json, output);
+    json_sink.update(Some(&activity("Coding", "main.rs"))).unwrap();
+    let written: Value = serde_json::from_str(&read(&json_path)).unwrap();
+    assert_eq!(written, json!({ "now": "Coding (main.rs)" }));
+    
+    // No temporary file is left next to the output
+    assert!(!dir.path().join("presence.json.tmp").exists());
+}
+
+#[test]
+fn slot_applies_its_own_filter_profiles_and_skips_unchanged_updates() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("presence.txt");
+    
+    let mut config = SinkConfig::new(
+        "obs",
+        SinkKind::File {
+            path: path.clone(),
+            format: SinkFormat::Text,
+        },
+    );
+    config.profiles = vec!["streaming".to_string()];
+    config.blacklisted_words = vec!["acme".to_string()];
+    config.output.text = Some("{details}".to_string());
+    let mut slot = SinkSlot::new(config).unwrap();
+    
+    let launch = activity("Acme launch prep", "");
+    slot.publish(Some(&launch), Some("streaming")).unwrap();
+    assert_eq!(read(&path), "[FILTERED] launch prep");
+    
+    // An unchanged presence is not written again
+    std::fs::remove_file(&path).unwrap();
+    slot.publish(Some(&launch), Some("streaming")).unwrap();
+    assert!(!path.exists());
+    
+    // Other profiles clear the sink
+    slot.publish(Some(&launch), Some("work")).unwrap();
+    assert_eq!(read(&path), "");
+    slot.publish(Some(&launch), None).unwrap();
+    assert_eq!(read(&path), "");
+}
+
+#[test]
+fn engine_publishes_to_configured_sinks() {
+    let _guard = common::environment();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("presence.txt");
+    
+    let config = Config {
+        update_interval_seconds: 1,
+        sinks: vec![SinkConfig::new(
+            "file",
+            SinkKind::File {
+                path: path.clone(),
+                format: SinkFormat::Text,
+            },
+        )],
+        ..Default::default()
+    };
+    let engine = Arc::new(Mutex::new(PresenceEngine::new(config)));
+    PresenceEngine::spawn_ticker(&engine);
+    
+    engine.lock().unwrap().set_custom_activity("my secret project", "testing");
+    
+    let deadline = Instant::now() + Duration::from_secs(15);
+    while std::fs::read_to_string(&path).unwrap_or_default() != "my [FILTERED] project\ntesting" {
+        assert!(Instant::now() < deadline, "sink was not written in time");
+        std::thread::sleep(Duration::from_millis(200));
+    }
+    
+    engine.lock().unwrap().hide_presence(None);
+    assert_eq!(read(&path), "");
+}
@@ -0,0 +1,150 @@
+#![cfg(target_os = "linux")]
+
+use std::fs;
+use std::path::Path;
+
+use multi_rich_presence::provider::{sanitize_argv, TerminalConfig, TerminalProvider, REDACTED};
+
+/// One fake process: pid, name, parent, process group, foreground group of its TTY
+/// (-1 without a TTY), start time and command line.
+type Row<'a> = (u32, &'a str, u32, u32, i64, u64, &'a [&'a str]);
+
+fn add(proc_root: &Path, rows: &[Row]) {
+    for &(pid, name, ppid, pgrp, tpgid, start_time, argv) in rows {
+        let dir = proc_root.join(pid.to_string());
+        fs::create_dir_all(&dir).unwrap();
+        let tty = if tpgid > 0 { 34816 + pgrp } else { 0 };
+        // Fields 9 to 21 are irrelevant here; field 22 is the start time
+        let fields = vec!["0"; 13].join(" ");
+        let stat = format!("{} ({}) S {} {} {} {} {} {} {} 0 0", pid, name, ppid, pgrp, pgrp, tty, tpgid, fields, start_time);
+        fs::write(dir.join("stat"), stat).unwrap();
+        let cmdline: String = argv.iter().map(|arg| format!("{}\0", arg)).collect();
+        fs::write(dir.join("cmdline"), cmdline).unwrap();
+    }
+}
+
+fn argv(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+fn enabled() -> TerminalConfig {
+    TerminalConfig {
+        enabled: true,
+        ..Default::default()
+    }
+}
+
+/// An alacritty window with an idle shell on pts/1 and `cargo build` in the foreground of pts/2,
+/// plus a gnome-terminal window whose shell runs `command`.
+fn terminals(proc_root: &Path, command: &[&str], command_start: u64) {
+    add(
+        proc_root,
+        &[
+            (100, "alacritty", 1, 100, -1, 10, &["alacritty"]),
+            (101, "zsh", 100, 101, 101, 11, &["-zsh"]),
+            (102, "zsh", 100, 102, 110, 12, &["-zsh"]),
+            (110, "cargo", 102, 110, 110, 50, &["/home/me/.cargo/bin/cargo", "build", "--release", "--features", "token=abc"]),
+            // rustc shares cargo's process group but is not the group leader
+            (111, "rustc", 110, 110, 110, 60, &["rustc", "--crate-name", "app"]),
+            (200, "gnome-terminal-", 1, 200, -1, 20, &["gnome-terminal-server"]),
+            (201, "bash", 200, 201, 210, 21, &["bash"]),
+            (210, command[0], 201, 210, 210, command_start, command),
+            // A shell outside any terminal emulator, e.g. under sshd, is ignored
+            (300, "sshd", 1, 300, -1, 5, &["sshd"]),
+            (301, "bash", 300, 301, 310, 90, &["bash"]),
+            (310, "vim", 301, 310, 310, 99, &["vim", "notes.txt"]),
+        ],
+    );
+}
+
+#[test]
+fn redaction_keeps_only_the_program_and_subcommands() {
+    let config = TerminalConfig::default();
+    
+    assert_eq!(
+        sanitize_argv(&argv(&["/usr/bin/cargo", "build", "--release"]), &config),
+        Some(argv(&["cargo", "build", REDACTED]))
+    );
+    assert_eq!(
+        sanitize_argv(&argv(&["git", "remote", "add", "origin", "https://token@example.com/repo.git"]), &config),
+        Some(argv(&["git", "remote", "add", REDACTED]))
+    );
+    assert_eq!(sanitize_argv(&argv(&["htop"]), &config), Some(argv(&["htop"])));
+    assert_eq!(sanitize_argv(&argv(&["ssh", "user@10.0.0.5"]), &config), Some(argv(&["ssh", REDACTED])));
+    assert_eq!(sanitize_argv(&argv(&["-zsh"]), &config), Some(argv(&["zsh"])));
+    
+    let full = TerminalConfig {
+        redact_arguments: false,
+        ..Default::default()
+    };
+    assert_eq!(
+        sanitize_argv(&argv(&["ssh", "user@10.0.0.5"]), &full),
+        Some(argv(&["ssh", "user@10.0.0.5"]))
+    );
+}
+
+#[test]
+fn sensitive_commands_are_hidden_even_behind_wrappers() {
+    let config = TerminalConfig {
+        redact_arguments: false,
+        ..Default::default()
+    };
+    
+    assert_eq!(sanitize_argv(&argv(&["pass", "show", "bank"]), &config), None);
+    assert_eq!(sanitize_argv(&argv(&["/usr/bin/gpg", "--decrypt", "file.gpg"]), &config), None);
+    assert_eq!(sanitize_argv(&argv(&["sudo", "/usr/bin/passwd", "me"]), &config), None);
+    assert_eq!(sanitize_argv(&argv(&[]), &config), None);
+}
+
+#[test]
+fn reports_the_newest_foreground_job_of_the_focused_terminal() {
+    let dir = tempfile::tempdir().unwrap();
+    terminals(dir.path(), &["htop"], 70);
+    let provider = TerminalProvider::with_proc_root(enabled(), dir.path());
+    
+    // htop in gnome-terminal is newer, but alacritty has focus
+    let info = provider.current(Some(100)).unwrap();
+    assert_eq!(info.terminal, "alacritty");
+    assert_eq!(info.command, "cargo");
+    assert_eq!(info.command_line(), format!("cargo build {}", REDACTED));
+    
+    // The truncated-name gnome-terminal-server still counts as an emulator
+    let dir = tempfile::tempdir().unwrap();
+    terminals(dir.path(), &["ssh", "prod-db"], 40);
+    let info = TerminalProvider::with_proc_root(enabled(), dir.path()).current(Some(200)).unwrap();
+    assert_eq!(info.terminal, "gnome-terminal-server");
+    assert_eq!(info.argv, argv(&["ssh", "prod-db"]));
+}
+
+#[test]
+fn nothing_is_reported_while_another_application_has_focus() {
+    let dir = tempfile::tempdir().unwrap();
+    terminals(dir.path(), &["htop"], 70);
+    add(dir.path(), &[(400, "firefox", 1, 400, -1, 30, &["firefox"])]);
+    let provider = TerminalProvider::with_proc_root(enabled(), dir.path());
+    
+    assert_eq!(provider.current(Some(400)), None);
+    // Focus on a shell's job rather than an emulator, an unknown process, or no focus at all
+    assert_eq!(provider.current(Some(310)), None);
+    assert_eq!(provider.current(Some(999)), None);
+    assert_eq!(provider.current(None), None);
+}
+
+#[test]
+fn hidden_newest_job_hides_terminal_activity() {
+    let dir = tempfile::tempdir().unwrap();
+    terminals(dir.path(), &["gopass", "show", "email"], 70);
+    
+    assert_eq!(TerminalProvider::with_proc_root(enabled(), dir.path()).current(Some(200)), None);
+}
+
+#[test]
+fn idle_shells_report_nothing() {
+    let dir = tempfile::tempdir().unwrap();
+    add(
+        dir.path(),
+        &[(100, "kitty", 1, 100, -1, 10, &["kitty"]), (101, "fish", 100, 101, 101, 11, &["fish"])],
+    );
+    
+    assert_eq!(TerminalProvider::with_proc_root(enabled(), dir.path()).current(Some(100)), None);
+}
@@ -0,0 +1,271 @@
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use zbus::blocking::Connection;
+use zbus::zvariant::{OwnedValue, Value};
+
+use multi_rich_presence::config::Config;
+use multi_rich_presence::engine::PresenceEngine;
+use multi_rich_presence::profile::Profile;
+use multi_rich_presence::tray::{self, MenuAction, MenuToggle, Tray, TrayState, TrayStatus, WindowAction};
+
+type Pixmap = (i32, i32, Vec<u8>);
+type ToolTip = (String, Vec<Pixmap>, String, String);
+/// A dbusmenu layout node: id, properties and children.
+type Layout = (i32, HashMap<String, OwnedValue>, Vec<OwnedValue>);
+
+/// A private session bus so tests never register with the desktop's real tray.
+struct Bus {
+    daemon: Child,
+    address: String,
+}
+
+impl Bus {
+    /// `None` when `dbus-daemon` is not installed.
+    fn start() -> Option<Bus> {
+        let mut daemon = match Command::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(daemon) => daemon,
+            Err(e) => {
+                eprintln!("skipping: could not start dbus-daemon: {}", e);
+                return None;
+            }
+        };
+        
+        let mut address = String::new();
+        BufReader::new(daemon.stdout.take().unwrap()).read_line(&mut address).unwrap();
+        Some(Bus {
+            daemon,
+            address: address.trim().to_string(),
+        })
+    }
+    
+    fn connect(&self) -> Connection {
+        zbus::blocking::connection::Builder::address(self.address.as_str()).unwrap().build().unwrap()
+    }
+}
+
+impl Drop for Bus {
+    fn drop(&mut self) {
+        let _ = self.daemon.kill();
+        let _ = self.daemon.wait();
+    }
+}
+
+/// Stands in for the desktop's tray host.
+struct FakeWatcher {
+    items: Arc<Mutex<Vec<String>>>,
+}
+
+#[zbus::interface(name = "org.kde.StatusNotifierWatcher")]
+impl FakeWatcher {
+    fn register_status_notifier_item(&self, service: String) {
+        self.items.lock().unwrap().push(service);
+    }
+}
+
+fn start_watcher(bus: &Bus, items: &Arc<Mutex<Vec<String>>>) -> Connection {
+    zbus::blocking::connection::Builder::address(bus.address.as_str())
+        .unwrap()
+        .name("org.kde.StatusNotifierWatcher")
+        .unwrap()
+        .serve_at("/StatusNotifierWatcher", FakeWatcher { items: Arc::clone(items) })
+        .unwrap()
+        .build()
+        .unwrap()
+}
+
+fn wait_for(what: &str, condition: impl Fn() -> bool) {
+    let started = Instant::now();
+    while !condition() {
+        assert!(started.elapsed() < Duration::from_secs(5), "timed out waiting for {}", what);
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
+
+fn state() -> TrayState {
+    TrayState {
+        status: TrayStatus::Connected,
+        profiles: vec!["Work".to_string(), "Gaming".to_string()],
+        active_profile: Some("Gaming".to_string()),
+        messages: vec!["Shipping".to_string(), "Reviewing".to_string()],
+        message: "Reviewing".to_string(),
+        tooltip: "Connected: Gaming".to_string(),
+    }
+}
+
+fn engine() -> Arc<Mutex<PresenceEngine>> {
+    let config = Config {
+        profiles: vec![Profile::new("Work")],
+        custom_messages: vec!["Shipping".to_string()],
+        ..Config::default()
+    };
+    Arc::new(Mutex::new(PresenceEngine::new(config)))
+}
+
+#[test]
+fn the_menu_marks_the_current_profile_and_message() {
+    let menu = tray::menu(&state());
+    let labels: Vec<&str> = menu.children.iter().map(|item| item.label.as_str()).collect();
+    assert_eq!(labels, ["Pause Presence", "", "Profile", "Custom Message", "", "Open Window", "Quit"]);
+    assert_eq!(menu.children[0].toggle, Some(MenuToggle::Checkmark(false)));
+    
+    let checked = |submenu: &tray::MenuItem| -> Vec<String> {
+        submenu
+            .children
+            .iter()
+            .filter(|item| item.toggle == Some(MenuToggle::Radio(true)))
+            .map(|item| item.label.clone())
+            .collect()
+    };
+    assert_eq!(checked(&menu.children[2]), ["Gaming"]);
+    assert_eq!(checked(&menu.children[3]), ["Reviewing"]);
+    assert_eq!(menu.children[2].children[0].action, Some(MenuAction::Profile(None)));
+    
+    // Every item can be found by its id
+    let mut ids = Vec::new();
+    let mut pending = vec![&menu];
+    while let Some(item) = pending.pop() {
+        assert_eq!(menu.find(item.id), Some(item));
+        ids.push(item.id);
+        pending.extend(&item.children);
+    }
+    let count = ids.len();
+    ids.sort();
+    ids.dedup();
+    assert_eq!(ids.len(), count);
+}
+
+#[test]
+fn the_icon_color_follows_the_status() {
+    let connected = tray::icon(TrayStatus::Connected, 22);
+    assert_eq!(connected.len(), 22 * 22 * 4);
+    // Transparent corner, opaque center
+    assert_eq!(connected[0], 0);
+    let center = (11 * 22 + 11) * 4;
+    assert_eq!(connected[center], 255);
+    
+    let paused = tray::icon(TrayStatus::Paused, 22);
+    assert_ne!(connected[center + 1..center + 4], paused[center + 1..center + 4]);
+}
+
+#[test]
+fn the_item_registers_and_serves_its_menu() {
+    let _guard = common::environment();
+    let Some(bus) = Bus::start() else { return };
+    let items = Arc::new(Mutex::new(Vec::new()));
+    let _watcher = start_watcher(&bus, &items);
+    
+    let engine = engine();
+    let windows = Arc::new(Mutex::new(Vec::new()));
+    let recorded = Arc::clone(&windows);
+    let tray = Tray::start_at(
+        &bus.address,
+        &engine,
+        Box::new(move |action| recorded.lock().unwrap().push(action)),
+    )
+    .unwrap();
+    
+    let name = format!("org.kde.StatusNotifierItem-{}-1", std::process::id());
+    wait_for("registration", || tray.is_visible());
+    assert_eq!(*items.lock().unwrap(), std::slice::from_ref(&name));
+    
+    let connection = bus.connect();
+    let item = zbus::blocking::proxy::Builder::<zbus::blocking::Proxy>::new(&connection)
+        .destination(name.as_str())
+        .unwrap()
+        .path("/StatusNotifierItem")
+        .unwrap()
+        .interface("org.kde.StatusNotifierItem")
+        .unwrap()
+        .cache_properties(zbus::proxy::CacheProperties::No)
+        .build()
+        .unwrap();
+    let pixmaps: Vec<Pixmap> = item.get_property("IconPixmap").unwrap();
+    assert!(pixmaps.iter().all(|(width, height, pixels)| pixels.len() == (width * height * 4) as usize));
+    let tooltip: ToolTip = item.get_property("ToolTip").unwrap();
+    assert!(tooltip.3.ends_with(": Base"), "{}", tooltip.3);
+    
+    let menu = zbus::blocking::Proxy::new(&connection, name.as_str(), "/MenuBar", "com.canonical.dbusmenu").unwrap();
+    let (_, (root, _, children)): (u32, Layout) = menu.call("GetLayout", &(0i32, -1i32, Vec::<String>::new())).unwrap();
+    assert_eq!(root, 0);
+    assert_eq!(children.len(), 7);
+    let (pause_id, properties, _): Layout = children[0].try_clone().unwrap().try_into().unwrap();
+    assert_eq!(String::try_from(properties["label"].try_clone().unwrap()).unwrap(), "Pause Presence");
+    assert_eq!(i32::try_from(&properties["toggle-state"]).unwrap(), 0);
+    
+    // Clicking pause hides the presence, and the menu follows
+    let _: () = menu.call("Event", &(pause_id, "clicked", Value::from(0i32), 0u32)).unwrap();
+    assert!(engine.lock().unwrap().privacy.hidden);
+    wait_for("the paused tooltip", || {
+        let tooltip: ToolTip = item.get_property("ToolTip").unwrap();
+        tooltip.3 == "Presence paused"
+    });
+    let toggled: OwnedValue = menu.call("GetProperty", &(pause_id, "toggle-state")).unwrap();
+    assert_eq!(i32::try_from(&toggled).unwrap(), 1);
+    
+    // Window actions go to the callback
+    let _: () = item.call("Activate", &(0i32, 0i32)).unwrap();
+    assert_eq!(*windows.lock().unwrap(), [WindowAction::Open]);
+}
+
+#[test]
+fn the_item_registers_again_when_the_tray_host_restarts() {
+    let _guard = common::environment();
+    let Some(bus) = Bus::start() else { return };
+    let engine = engine();
+    let tray = Tray::start_at(&bus.address, &engine, Box::new(|_| {})).unwrap();
+    
+    // No tray host yet
+    std::thread::sleep(Duration::from_millis(700));
+    assert!(!tray.is_visible());
+    
+    let items = Arc::new(Mutex::new(Vec::new()));
+    let watcher = start_watcher(&bus, &items);
+    wait_for("registration", || tray.is_visible());
+    drop(watcher);
+    wait_for("the host to go away", || !tray.is_visible());
+    
+    let _watcher = start_watcher(&bus, &items);
+    wait_for("registration with the new host", || items.lock().unwrap().len() == 2);
+}
+
+#[test]
+fn menu_actions_change_the_engine() {
+    let _guard = common::environment();
+    let engine = engine();
+    let windows = Arc::new(Mutex::new(Vec::new()));
+    let recorded = Arc::clone(&windows);
+    let on_window: tray::WindowCallback = Box::new(move |action| recorded.lock().unwrap().push(action));
+    
+    tray::apply(&MenuAction::Profile(Some("Work".to_string())), &engine, &on_window);
+    tray::apply(&MenuAction::Message("Shipping".to_string()), &engine, &on_window);
+    tray::apply(&MenuAction::TogglePause, &engine, &on_window);
+    {
+        let engine = engine.lock().unwrap();
+        assert_eq!(engine.config.active_profile.as_deref(), Some("Work"));
+        assert_eq!(engine.custom_message, "Shipping");
+        assert!(engine.privacy.hidden);
+        assert_eq!(TrayState::from_engine(&engine).status, TrayStatus::Paused);
+    }
+    
+    tray::apply(&MenuAction::TogglePause, &engine, &on_window);
+    assert!(!engine.lock().unwrap().privacy.hidden);
+    
+    // Quit is called with the engine unlocked
+    let on_window: tray::WindowCallback = {
+        let engine = Arc::clone(&engine);
+        Box::new(move |_| assert!(engine.try_lock().is_ok()))
+    };
+    tray::apply(&MenuAction::Window(WindowAction::Quit), &engine, &on_window);
+}